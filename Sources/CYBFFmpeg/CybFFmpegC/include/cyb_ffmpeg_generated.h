@@ -14,6 +14,51 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+// Identity (RGB / GBR)
+#define RGB 0
+
+// ITU-R BT.709
+#define BT709 1
+
+// Unspecified
+#define UNSPECIFIED 2
+
+// ITU-R BT.601 625-line (PAL)
+#define BT470BG 5
+
+// ITU-R BT.601 525-line (NTSC)
+#define SMPTE170M 6
+
+// ITU-R BT.2020 non-constant luminance
+#define BT2020_NCL 9
+
+// ITU-R BT.2020 constant luminance
+#define BT2020_CL 10
+
+// ITU-R BT.2020
+#define BT2020 9
+
+// Display P3 (SMPTE EG 432-1)
+#define SMPTE432 12
+
+// Linear light
+#define LINEAR 8
+
+// sRGB (IEC 61966-2-1)
+#define IEC61966_2_1 13
+
+// ITU-R BT.2020 10-bit
+#define BT2020_10 14
+
+// SMPTE ST 2084 (PQ)
+#define SMPTE2084 16
+
+// ARIB STD-B67 (HLG)
+#define ARIB_STD_B67 18
+
+// End time used for events whose duration is not known yet
+#define OPEN_END_US INT64_MAX
+
 typedef enum CybResult {
     CYB_RESULT_SUCCESS = 0,
     CYB_RESULT_ERROR_FILE_NOT_FOUND = 1,
@@ -24,21 +69,53 @@ typedef enum CybResult {
     CYB_RESULT_ERROR_MEMORY = 6,
     CYB_RESULT_ERROR_INVALID_HANDLE = 7,
     CYB_RESULT_ERROR_NOT_PREPARED = 8,
+    CYB_RESULT_ERROR_CANCELLED = 9,
     CYB_RESULT_ERROR_UNKNOWN = 99,
 } CybResult;
 
+// Opaque attachment handle (owns the data)
+typedef struct CybAttachmentHandle CybAttachmentHandle;
+
 // Opaque audio frame handle (owns the data)
 typedef struct CybAudioFrameHandle CybAudioFrameHandle;
 
 // Opaque decoder handle
 typedef struct CybDecoderHandle CybDecoderHandle;
 
+// Opaque encoder handle
+typedef struct CybEncoderHandle CybEncoderHandle;
+
 // Opaque frame handle (owns the data)
 typedef struct CybFrameHandle CybFrameHandle;
 
+// Opaque loudness report handle (owns the time series)
+typedef struct CybLoudnessHandle CybLoudnessHandle;
+
 // Opaque media info handle
 typedef struct CybMediaInfoHandle CybMediaInfoHandle;
 
+// Opaque packet handle (owns the data)
+typedef struct CybPacketHandle CybPacketHandle;
+
+// Opaque scene cut list handle (owns the data)
+typedef struct CybSceneListHandle CybSceneListHandle;
+
+// Opaque subtitle event list handle (owns the data)
+typedef struct CybSubtitleListHandle CybSubtitleListHandle;
+
+// Opaque waveform handle (owns the buckets)
+typedef struct CybWaveformHandle CybWaveformHandle;
+
+typedef struct Option_CybExportProgressFn Option_CybExportProgressFn;
+
+typedef struct Option_CybLoudnessProgressFn Option_CybLoudnessProgressFn;
+
+typedef struct Option_CybSceneProgressFn Option_CybSceneProgressFn;
+
+typedef struct Option_CybThumbnailFn Option_CybThumbnailFn;
+
+typedef struct Option_CybWaveformProgressFn Option_CybWaveformProgressFn;
+
 typedef struct CybCacheConfig {
     uint32_t l1_capacity;
     uint32_t l2_capacity;
@@ -46,13 +123,84 @@ typedef struct CybCacheConfig {
     bool enable_prefetch;
 } CybCacheConfig;
 
+// Stream selection for FFI (unknown codes fail decoder creation)
+typedef struct CybStreamSelector {
+    // 0=Best, 1=Index, 2=Language, 3=Disposition
+    uint8_t kind;
+    // Stream index (kind 1)
+    int32_t index;
+    // Language code, e.g. "eng" (kind 2)
+    const char *language;
+    // 0=Default, 1=Original, 2=Dub, 3=Commentary, 4=Forced,
+    // 5=HearingImpaired, 6=VisualImpaired (kind 3)
+    uint8_t disposition;
+} CybStreamSelector;
+
+// Audio output format for FFI
+typedef struct CybAudioOutputConfig {
+    // Output sample rate in Hz (0 = source rate)
+    uint32_t sample_rate;
+    // Output channel count (0 = source channel count)
+    uint32_t channels;
+    // FFmpeg channel mask (0 = default layout for the channel count)
+    uint64_t channel_layout;
+    // 0=Float32, 1=Int16, 2=Int32
+    uint8_t sample_format;
+    // Planar (one block per channel) instead of interleaved output
+    bool planar;
+    // 0=Default, 1=ITU (LFE dropped), 2=ITU with LFE
+    uint8_t downmix;
+} CybAudioOutputConfig;
+
 typedef struct CybDecoderConfig {
     bool prefer_hardware_decoding;
     struct CybCacheConfig cache_config;
     uint32_t thread_count;
+    // 0=BGRA, 1=NV12, 2=YUV420P, 3=RGBA, 4=P010, 5=YUV422P10, 6=YUV444P16, 7=RGBA64, 8=RGB48, 9=native
     uint8_t output_pixel_format;
+    struct CybStreamSelector video_stream;
+    struct CybStreamSelector audio_stream;
+    struct CybStreamSelector subtitle_stream;
+    struct CybAudioOutputConfig audio_output;
+    // Output size; 0x0 keeps the source size, one 0 dimension keeps the aspect ratio
+    uint32_t output_width;
+    uint32_t output_height;
+    uint8_t scale_mode;
+    uint8_t scaling_algorithm;
+    // Matrix coefficients of YUV output (see CybColorInfo; 0 or 2=unspecified keeps the source
+    // matrix, as the identity matrix 0 does not apply to YUV output)
+    uint8_t output_color_matrix;
+    // Range of YUV output (0=source, 1=limited, 2=full)
+    uint8_t output_color_range;
+    // HDR→SDR tone mapping operator (0=none, 1=Hable, 2=BT.2390)
+    uint8_t tone_map_operator;
+    // Peak luminance of the SDR target in cd/m² (typically 100)
+    float tone_map_target_peak;
+    // Rotate frames upright and correct non-square pixels during conversion
+    bool apply_orientation;
+    // Deinterlacing of interlaced frames (0=none, 1=bob at double rate, 2=yadif)
+    uint8_t deinterlace_mode;
+    // libavfilter graph for video frames, e.g. "crop=1280:720" (null = none)
+    const char *video_filter;
+    // libavfilter graph for audio frames, e.g. "volume=0.5" (null = none)
+    const char *audio_filter;
 } CybDecoderConfig;
 
+// Caller-supplied I/O callbacks.
+//
+// Callbacks may be invoked from decoder and prefetch threads but never
+// concurrently.
+typedef struct CybIoCallbacks {
+    // User data passed to every callback
+    void *opaque;
+    // Copy up to `size` bytes into `buf`; return bytes read, 0 at end of data, negative on error
+    int32_t (*read)(void *opaque, uint8_t *buf, int32_t size);
+    // Seek with `whence` 0 (SET), 1 (CUR) or 2 (END); return new position, negative on error
+    int64_t (*seek)(void *opaque, int64_t offset, int32_t whence);
+    // Called once when `opaque` is no longer used (optional)
+    void (*release)(void *opaque);
+} CybIoCallbacks;
+
 typedef struct CybCacheStats {
     uint32_t l1_entries;
     uint32_t l2_entries;
@@ -64,6 +212,54 @@ typedef struct CybCacheStats {
     uint64_t memory_usage_bytes;
 } CybCacheStats;
 
+// Color description for FFI (ISO/IEC 23091-4 code points, as used by FFmpeg)
+typedef struct CybColorInfo {
+    // Matrix coefficients (0=RGB, 1=BT.709, 2=unspecified, 6=BT.601, 9=BT.2020 NCL, ...)
+    uint8_t matrix;
+    // Color primaries (1=BT.709, 2=unspecified, 9=BT.2020, 12=Display P3, ...)
+    uint8_t primaries;
+    // Transfer characteristic (1=BT.709, 2=unspecified, 16=PQ, 18=HLG, ...)
+    uint8_t transfer;
+    // Range (0=unspecified, 1=limited, 2=full)
+    uint8_t range;
+    // Chroma location (0=unspecified, 1=left, 2=center, 3=top left, 4=top, 5=bottom left, 6=bottom)
+    uint8_t chroma_location;
+} CybColorInfo;
+
+// HDR metadata for FFI
+typedef struct CybHdrMetadata {
+    bool has_mastering_display;
+    // Red, green, blue primaries as CIE 1931 xy (rx, ry, gx, gy, bx, by)
+    float display_primaries[6];
+    // White point as CIE 1931 xy
+    float white_point[2];
+    // Mastering display luminance range in cd/m²
+    float min_luminance;
+    float max_luminance;
+    bool has_content_light_level;
+    // Maximum content / frame-average light level in cd/m²
+    uint32_t max_cll;
+    uint32_t max_fall;
+    // HDR10+ dynamic metadata present
+    bool has_hdr10_plus;
+    // Dolby Vision configuration or RPU present
+    bool has_dolby_vision;
+} CybHdrMetadata;
+
+// SMPTE timecode for FFI
+typedef struct CybTimecode {
+    bool is_valid;
+    uint8_t hours;
+    uint8_t minutes;
+    uint8_t seconds;
+    uint16_t frames;
+    // Drop-frame counting (displayed as HH:MM:SS;FF)
+    bool drop_frame;
+    // Frame rate the timecode counts at
+    int32_t rate_num;
+    int32_t rate_den;
+} CybTimecode;
+
 // Video frame data for FFI
 typedef struct CybVideoFrame {
     // Raw pixel data pointer
@@ -84,8 +280,30 @@ typedef struct CybVideoFrame {
     bool is_keyframe;
     // Sequential frame number
     int64_t frame_number;
-    // Pixel format (0=BGRA, 1=NV12, 2=YUV420P)
+    // Pixel format (0=BGRA, 1=NV12, 2=YUV420P, 3=RGBA, 4=P010, 5=YUV422P10, 6=YUV444P16, 7=RGBA64, 8=RGB48)
     uint8_t pixel_format;
+    // Number of planes in `data`
+    uint32_t plane_count;
+    // Byte offset of each plane within `data` (unused entries are 0)
+    uintptr_t plane_offsets[3];
+    // Bytes per row of each plane (unused entries are 0)
+    uint32_t plane_strides[3];
+    // Color description of the pixel data
+    struct CybColorInfo color;
+    // HDR metadata of the source frame
+    struct CybHdrMetadata hdr;
+    // Clockwise rotation still needed for upright display (0 once applied)
+    uint32_t rotation;
+    // Whether the decoded frame was interlaced (still set after deinterlacing)
+    bool is_interlaced;
+    // Whether the top field is displayed first
+    bool top_field_first;
+    // SMPTE timecode (is_valid = false if the source carries none)
+    struct CybTimecode timecode;
+    // A/53 closed caption data, 3-byte cc_data triplets (null if none)
+    const uint8_t *caption_data;
+    // Closed caption data size in bytes
+    uintptr_t caption_data_size;
 } CybVideoFrame;
 
 // Media info for FFI
@@ -94,6 +312,11 @@ typedef struct CybMediaInfo {
     const char *container_format;
     int32_t video_track_count;
     int32_t audio_track_count;
+    int32_t subtitle_track_count;
+    // Start timecode (is_valid = false if the media has none)
+    struct CybTimecode start_timecode;
+    int32_t chapter_count;
+    int32_t attachment_count;
 } CybMediaInfo;
 
 // Video track info for FFI
@@ -106,6 +329,24 @@ typedef struct CybVideoTrack {
     double frame_rate;
     int64_t bit_rate;
     bool is_hardware_decodable;
+    struct CybColorInfo color;
+    struct CybHdrMetadata hdr;
+    // Clockwise rotation needed for upright display (0, 90, 180 or 270)
+    int32_t rotation;
+    // Sample aspect ratio (1:1 when unknown)
+    int32_t sar_num;
+    int32_t sar_den;
+    // Dimensions after SAR correction and rotation
+    int32_t display_width;
+    int32_t display_height;
+    // 0=unknown, 1=progressive, 2=top first, 3=bottom first,
+    // 4=top coded/bottom displayed first, 5=bottom coded/top displayed first
+    uint8_t field_order;
+    // Total number of frames
+    int64_t frame_count;
+    // Codec extradata (null if none)
+    const uint8_t *extradata;
+    uintptr_t extradata_size;
 } CybVideoTrack;
 
 // Audio track info for FFI
@@ -115,13 +356,78 @@ typedef struct CybAudioTrack {
     const char *codec_long_name;
     int32_t sample_rate;
     int32_t channels;
+    // Channel layout description, e.g. "5.1(side)" (null if unknown)
+    const char *channel_layout;
     int64_t bit_rate;
+    // Codec extradata (null if none)
+    const uint8_t *extradata;
+    uintptr_t extradata_size;
 } CybAudioTrack;
 
+// Subtitle track info for FFI
+typedef struct CybSubtitleTrack {
+    int32_t index;
+    const char *codec_name;
+    const char *codec_long_name;
+    // Language code (null if unknown)
+    const char *language_code;
+    // Track title (null if unknown)
+    const char *title;
+    bool is_bitmap;
+    bool is_default;
+    bool is_forced;
+    // Codec extradata (null if none)
+    const uint8_t *extradata;
+    uintptr_t extradata_size;
+} CybSubtitleTrack;
+
+// Chapter info for FFI
+typedef struct CybChapter {
+    int64_t id;
+    // Chapter title (null if untitled)
+    const char *title;
+    int64_t start_us;
+    int64_t end_us;
+    // Start and end in time_base units
+    int64_t start;
+    int64_t end;
+    int32_t time_base_num;
+    int32_t time_base_den;
+} CybChapter;
+
+// Attachment info for FFI
+typedef struct CybAttachment {
+    // Stream index
+    int32_t index;
+    // 0=Picture (cover art), 1=File
+    uint8_t kind;
+    const char *codec_name;
+    // File name (null if unknown)
+    const char *filename;
+    // MIME type (null if unknown)
+    const char *mime_type;
+    // Description (null if none)
+    const char *title;
+    // Picture type, e.g. "Cover (front)" (null if none)
+    const char *picture_type;
+    // Data size in bytes
+    uintptr_t size;
+    // Picture dimensions (0 for files)
+    int32_t width;
+    int32_t height;
+    bool is_font;
+} CybAttachment;
+
 // Audio frame data for FFI
 typedef struct CybAudioFrame {
-    // Raw sample data pointer (interleaved float32)
-    const float *data;
+    // Raw sample data pointer (layout given by `sample_format` and `is_planar`)
+    const uint8_t *data;
+    // Sample data size in bytes
+    uintptr_t data_size;
+    // 0=Float32, 1=Int16, 2=Int32
+    uint8_t sample_format;
+    // Channels stored one after another instead of interleaved
+    bool is_planar;
     // Number of samples per channel
     uint32_t sample_count;
     // Number of audio channels
@@ -136,6 +442,229 @@ typedef struct CybAudioFrame {
     int64_t frame_number;
 } CybAudioFrame;
 
+// Attachment contents for FFI
+typedef struct CybAttachmentData {
+    // Raw bytes (the encoded image for pictures)
+    const uint8_t *data;
+    uintptr_t data_size;
+    // File name (null if unknown)
+    const char *filename;
+    // MIME type (null if unknown)
+    const char *mime_type;
+} CybAttachmentData;
+
+// Subtitle event data for FFI
+typedef struct CybSubtitleEvent {
+    // Container stream index
+    int32_t stream_index;
+    // Start time in microseconds
+    int64_t start_us;
+    // End time in microseconds (INT64_MAX if not known yet)
+    int64_t end_us;
+    // Payload kind (0=Text, 1=ASS, 2=Bitmap)
+    uint8_t kind;
+    // Plain text (UTF-8, empty for bitmaps)
+    const char *text;
+    // Raw ASS dialogue line (null unless kind is ASS)
+    const char *ass;
+    // Bitmap left position
+    int32_t bitmap_x;
+    // Bitmap top position
+    int32_t bitmap_y;
+    // Bitmap width (0 if no bitmap)
+    uint32_t bitmap_width;
+    // Bitmap height (0 if no bitmap)
+    uint32_t bitmap_height;
+    // Bitmap stride in bytes
+    uint32_t bitmap_stride;
+    // RGBA bitmap data (null if no bitmap)
+    const uint8_t *bitmap_data;
+    // Bitmap data size in bytes
+    uintptr_t bitmap_data_size;
+} CybSubtitleEvent;
+
+// Compressed packet for FFI
+typedef struct CybPacket {
+    int32_t stream_index;
+    // Timestamps in stream time base units (valid if has_pts / has_dts)
+    int64_t pts;
+    int64_t dts;
+    bool has_pts;
+    bool has_dts;
+    // Duration in time base units (0 if unknown)
+    int64_t duration;
+    int32_t time_base_num;
+    int32_t time_base_den;
+    // Timestamps and duration in microseconds
+    int64_t pts_us;
+    int64_t dts_us;
+    int64_t duration_us;
+    bool is_keyframe;
+    bool is_corrupt;
+    // Byte position in the file (-1 if unknown)
+    int64_t position;
+    // Packet payload
+    const uint8_t *data;
+    uintptr_t data_size;
+} CybPacket;
+
+// Lossless export options for FFI
+typedef struct CybExportOptions {
+    // In point in microseconds (snaps back to the preceding video keyframe)
+    int64_t start_us;
+    // Out point in microseconds (negative = end of media)
+    int64_t end_us;
+    // Stream indices to copy (null or 0 count = all supported tracks)
+    const int32_t *tracks;
+    uintptr_t track_count;
+    // Container: 0 = MP4, 1 = MOV, 2 = MKV, negative = from the file extension
+    int32_t format;
+    bool copy_metadata;
+    bool copy_chapters;
+} CybExportOptions;
+
+// Result of a finished export
+typedef struct CybExportSummary {
+    // Exported range in source time, after keyframe snapping
+    int64_t start_us;
+    int64_t end_us;
+    int32_t track_count;
+    uint64_t packet_count;
+    uint64_t byte_count;
+} CybExportSummary;
+
+// Thumbnail strip options for FFI
+typedef struct CybThumbnailOptions {
+    // Range in microseconds (negative end = end of media)
+    int64_t start_us;
+    int64_t end_us;
+    // Number of evenly spaced thumbnails (ignored when interval_us > 0)
+    uint32_t count;
+    // Distance between thumbnails in microseconds (0 = derived from count)
+    int64_t interval_us;
+    // Thumbnail size (0 on one axis = keep aspect ratio)
+    uint32_t width;
+    uint32_t height;
+    uint8_t scale_mode;
+    // Decode the exact frame at each time instead of the nearest keyframe
+    bool exact;
+} CybThumbnailOptions;
+
+// Waveform options for FFI
+typedef struct CybWaveformOptions {
+    // Range in microseconds (negative end = end of media)
+    int64_t start_us;
+    int64_t end_us;
+    // Samples per channel summarized by one bucket
+    uint32_t samples_per_bucket;
+    // Audio stream index (negative = active audio track)
+    int32_t track;
+} CybWaveformOptions;
+
+// Waveform description for FFI
+typedef struct CybWaveformInfo {
+    uint32_t sample_rate;
+    uint32_t channels;
+    uint32_t samples_per_bucket;
+    int32_t bucket_count;
+    // Time of the first bucket
+    int64_t start_us;
+    double bucket_duration_us;
+} CybWaveformInfo;
+
+// Level summary of one bucket of samples (-1.0 to 1.0)
+typedef struct CybWaveformBucket {
+    float min;
+    float max;
+    float rms;
+} CybWaveformBucket;
+
+// Loudness measurement options for FFI
+typedef struct CybLoudnessOptions {
+    // Range in microseconds (negative end = end of media)
+    int64_t start_us;
+    int64_t end_us;
+    // Audio stream index (negative = active audio track)
+    int32_t track;
+} CybLoudnessOptions;
+
+// Loudness of a whole range (-INFINITY for silence)
+typedef struct CybLoudnessSummary {
+    double integrated_lufs;
+    double loudness_range_lu;
+    double true_peak_dbtp;
+    double sample_peak_dbfs;
+    double max_momentary_lufs;
+    double max_short_term_lufs;
+    // Number of points in the time series
+    int32_t point_count;
+} CybLoudnessSummary;
+
+// Momentary and short-term loudness at one point in time
+// (-INFINITY for silence or a window not yet filled)
+typedef struct CybLoudnessPoint {
+    int64_t time_us;
+    double momentary_lufs;
+    double short_term_lufs;
+} CybLoudnessPoint;
+
+// Scene detection options for FFI
+typedef struct CybSceneDetectionOptions {
+    // Range in microseconds (negative end = end of media)
+    int64_t start_us;
+    int64_t end_us;
+    // 0 = histogram, 1 = luma difference
+    uint8_t method;
+    // Difference (0.0-1.0) from which two frames are a cut
+    double threshold;
+    // Shortest shot in microseconds
+    int64_t min_shot_us;
+    // Analysis size (0x0 = decoded size)
+    uint32_t width;
+    uint32_t height;
+    // 0 = fit, 1 = fill, 2 = stretch
+    uint8_t scale_mode;
+} CybSceneDetectionOptions;
+
+// A detected cut for FFI
+typedef struct CybSceneCut {
+    // Time of the first frame of the new shot (its pts_us)
+    int64_t time_us;
+    int64_t frame_number;
+    double score;
+    double confidence;
+} CybSceneCut;
+
+// Encoder configuration for FFI
+typedef struct CybEncoderConfig {
+    bool has_video;
+    uint8_t video_codec;
+    uint32_t width;
+    uint32_t height;
+    int32_t frame_rate_num;
+    int32_t frame_rate_den;
+    // Bits per second (0 = codec default / quantizer)
+    uint64_t video_bit_rate;
+    // Quantizer for MPEG-4 and MJPEG (1-31; 0 = use the bit rate, or the
+    // default quantizer when no bit rate is set)
+    uint32_t quality;
+    // Keyframe interval in frames (0 = codec default)
+    uint32_t gop_size;
+    uint8_t prores_profile;
+    uint8_t scaling_algorithm;
+    // Pixel format of the written frames, same values as
+    // CybDecoderConfig.output_pixel_format (0 = BGRA)
+    uint8_t input_pixel_format;
+    bool has_audio;
+    uint8_t audio_codec;
+    uint32_t sample_rate;
+    uint32_t channels;
+    // Bits per second (0 = codec default)
+    uint64_t audio_bit_rate;
+    // Container: 0 = from the file extension, 1 = MP4, 2 = MOV, 3 = MKV
+    int32_t format;
+} CybEncoderConfig;
+
 // Get last error message
  const char *cyb_get_last_error(void) ;
 
@@ -152,6 +681,22 @@ struct CybDecoderHandle *cyb_decoder_create(const char *path,
                                             const struct CybDecoderConfig *config)
 ;
 
+// Create decoder for media held in memory (the data is copied)
+
+struct CybDecoderHandle *cyb_decoder_create_from_memory(const uint8_t *data,
+                                                        uintptr_t size,
+                                                        const struct CybDecoderConfig *config)
+;
+
+// Create decoder reading through caller-supplied I/O callbacks
+//
+// `release` (if set) is called once the decoder and all prefetch workers
+// are done with `opaque`.
+
+struct CybDecoderHandle *cyb_decoder_create_with_io(const struct CybIoCallbacks *callbacks,
+                                                    const struct CybDecoderConfig *config)
+;
+
 // Prepare decoder
  enum CybResult cyb_decoder_prepare(struct CybDecoderHandle *handle) ;
 
@@ -191,6 +736,34 @@ enum CybResult cyb_decoder_seek_precise(struct CybDecoderHandle *handle,
                                         struct CybFrameHandle **out_frame)
 ;
 
+// Seek precisely to the start of a chapter
+
+enum CybResult cyb_decoder_seek_to_chapter(struct CybDecoderHandle *handle,
+                                           int32_t index,
+                                           struct CybFrameHandle **out_frame)
+;
+
+// Seek precisely to a frame number (0-based); out_frame is null if there is no such frame
+
+enum CybResult cyb_decoder_seek_to_frame(struct CybDecoderHandle *handle,
+                                         int64_t frame_number,
+                                         struct CybFrameHandle **out_frame)
+;
+
+// Get a frame by number (0-based), from the cache or by seeking
+
+enum CybResult cyb_decoder_get_frame_by_index(struct CybDecoderHandle *handle,
+                                              int64_t frame_number,
+                                              struct CybFrameHandle **out_frame)
+;
+
+// Seek precisely to the frame labelled with a SMPTE timecode ("HH:MM:SS:FF" or "HH:MM:SS;FF")
+
+enum CybResult cyb_decoder_seek_to_timecode(struct CybDecoderHandle *handle,
+                                            const char *timecode,
+                                            struct CybFrameHandle **out_frame)
+;
+
 // Prime audio decoder after seek.
 // Call this after seek and before reading audio frames to ensure
 // audio packets are pre-loaded into the queue for immediate decoding.
@@ -224,6 +797,26 @@ enum CybResult cyb_decoder_get_frame_at(struct CybDecoderHandle *handle,
                                         struct CybFrameHandle **out_frame)
 ;
 
+// Get frame at specific time, scaled to the given size (bypasses the frame cache)
+
+enum CybResult cyb_decoder_get_frame_at_size(struct CybDecoderHandle *handle,
+                                             int64_t time_us,
+                                             int64_t tolerance_us,
+                                             uint32_t width,
+                                             uint32_t height,
+                                             uint8_t scale_mode,
+                                             struct CybFrameHandle **out_frame)
+;
+
+// Get frame at specific time, run through a libavfilter graph (bypasses the frame cache)
+
+enum CybResult cyb_decoder_get_frame_with_filter(struct CybDecoderHandle *handle,
+                                                 int64_t time_us,
+                                                 int64_t tolerance_us,
+                                                 const char *filter,
+                                                 struct CybFrameHandle **out_frame)
+;
+
 // Get next frame in sequence
 
 enum CybResult cyb_decoder_get_next_frame(struct CybDecoderHandle *handle,
@@ -265,6 +858,27 @@ enum CybResult cyb_media_info_get_audio_track(const struct CybMediaInfoHandle *i
                                               struct CybAudioTrack *out_track)
 ;
 
+// Get subtitle track info
+
+enum CybResult cyb_media_info_get_subtitle_track(const struct CybMediaInfoHandle *info_handle,
+                                                 int32_t index,
+                                                 struct CybSubtitleTrack *out_track)
+;
+
+// Get chapter info
+
+enum CybResult cyb_media_info_get_chapter(const struct CybMediaInfoHandle *info_handle,
+                                          int32_t index,
+                                          struct CybChapter *out_chapter)
+;
+
+// Get attachment info
+
+enum CybResult cyb_media_info_get_attachment(const struct CybMediaInfoHandle *info_handle,
+                                             int32_t index,
+                                             struct CybAttachment *out_attachment)
+;
+
 // Release media info handle
  void cyb_media_info_release(struct CybMediaInfoHandle *info_handle) ;
 
@@ -295,4 +909,295 @@ void cyb_audio_frame_get_data(const struct CybAudioFrameHandle *frame_handle,
 // Get audio channel count
  uint32_t cyb_decoder_get_audio_channels(const struct CybDecoderHandle *handle) ;
 
+// Switch the active audio track (container stream index)
+
+enum CybResult cyb_decoder_select_audio_track(struct CybDecoderHandle *handle,
+                                              int32_t stream_index)
+;
+
+// Replace the audio filter graph, e.g. "volume=0.5,atempo=1.25" (null = no filtering)
+ enum CybResult cyb_decoder_set_audio_filter(struct CybDecoderHandle *handle, const char *filter) ;
+
+// Get the stream index of the active video track (-1 if none)
+ int32_t cyb_decoder_get_active_video_track(const struct CybDecoderHandle *handle) ;
+
+// Switch the active subtitle track (container stream index)
+
+enum CybResult cyb_decoder_select_subtitle_track(struct CybDecoderHandle *handle,
+                                                 int32_t stream_index)
+;
+
+// Get the stream index of the active subtitle track (-1 if none)
+ int32_t cyb_decoder_get_active_subtitle_track(const struct CybDecoderHandle *handle) ;
+
+// Get the stream index of the active audio track (-1 if none)
+ int32_t cyb_decoder_get_active_audio_track(const struct CybDecoderHandle *handle) ;
+
+// Decode the cover art (front cover if tagged, else the first attached picture)
+//
+// `out_frame` is set to null if the media has no attached picture.
+
+enum CybResult cyb_decoder_get_cover_art(const struct CybDecoderHandle *handle,
+                                         struct CybFrameHandle **out_frame)
+;
+
+// Decode the attached picture of a stream
+
+enum CybResult cyb_decoder_get_attached_picture(const struct CybDecoderHandle *handle,
+                                                int32_t stream_index,
+                                                struct CybFrameHandle **out_frame)
+;
+
+// Get the raw contents of an attachment or attached picture stream
+
+enum CybResult cyb_decoder_get_attachment(const struct CybDecoderHandle *handle,
+                                          int32_t stream_index,
+                                          struct CybAttachmentHandle **out_attachment)
+;
+
+// Get attachment contents from handle
+
+void cyb_attachment_get_data(const struct CybAttachmentHandle *attachment_handle,
+                             struct CybAttachmentData *out_data)
+;
+
+// Release attachment handle
+ void cyb_attachment_release(struct CybAttachmentHandle *attachment_handle) ;
+
+// Get subtitle events active at a specific time
+
+enum CybResult cyb_decoder_get_subtitles_at(const struct CybDecoderHandle *handle,
+                                            int64_t time_us,
+                                            struct CybSubtitleListHandle **out_list)
+;
+
+// Decode the whole active subtitle track up front
+ enum CybResult cyb_decoder_load_subtitles(struct CybDecoderHandle *handle) ;
+
+// Get number of events in a subtitle list
+ int32_t cyb_subtitle_list_get_count(const struct CybSubtitleListHandle *list) ;
+
+// Get subtitle event from a list
+
+enum CybResult cyb_subtitle_list_get_event(const struct CybSubtitleListHandle *list,
+                                           int32_t index,
+                                           struct CybSubtitleEvent *out_event)
+;
+
+// Release subtitle list handle
+ void cyb_subtitle_list_release(struct CybSubtitleListHandle *list) ;
+
+// Read the next compressed packet of any stream, without decoding
+//
+// `out_packet` is set to null at the end of the input. Packets read this way
+// are not decoded; seek before resuming playback.
+
+enum CybResult cyb_decoder_read_packet(struct CybDecoderHandle *handle,
+                                       struct CybPacketHandle **out_packet)
+;
+
+// Get packet data from handle
+
+void cyb_packet_get_data(const struct CybPacketHandle *packet_handle,
+                         struct CybPacket *out_packet)
+;
+
+// Release packet handle
+ void cyb_packet_release(struct CybPacketHandle *packet_handle) ;
+
+// Export a time range without re-encoding (stream copy) to MP4, MOV or MKV
+//
+// Blocks until the export finishes, and holds the decoder handle meanwhile:
+// export from a separate handle to keep playing. `progress` is called on the
+// calling thread; returning false cancels with CYB_RESULT_ERROR_CANCELLED and
+// removes the partial file.
+
+enum CybResult cyb_decoder_export(struct CybDecoderHandle *handle,
+                                  const char *path,
+                                  const struct CybExportOptions *options,
+                                  struct Option_CybExportProgressFn progress,
+                                  void *user_data,
+                                  struct CybExportSummary *out_summary)
+;
+
+// Get closed caption events of a channel active at a specific time
+//
+// `channel` is 1-4 for CEA-608 CC1-CC4 and 101-163 for CEA-708 services 1-63.
+// Events are returned as a subtitle list (kind Text).
+
+enum CybResult cyb_decoder_get_captions_at(const struct CybDecoderHandle *handle,
+                                           int32_t channel,
+                                           int64_t time_us,
+                                           struct CybSubtitleListHandle **out_list)
+;
+
+// Get the caption channels that produced events so far
+//
+// Writes up to `max_count` channel ids to `out_channels` and returns the
+// total number of channels.
+
+int32_t cyb_decoder_get_caption_channels(const struct CybDecoderHandle *handle,
+                                         int32_t *out_channels,
+                                         int32_t max_count)
+;
+
+// Generate evenly spaced thumbnails, streaming each one to `callback` as it
+// completes (bypasses the frame cache and keeps the playhead)
+//
+// Blocks until done and holds the decoder handle meanwhile. `callback` is
+// called on the calling thread; returning false cancels with
+// CYB_RESULT_ERROR_CANCELLED. `out_count` (optional) receives the number of
+// thumbnails delivered.
+
+enum CybResult cyb_decoder_generate_thumbnails(struct CybDecoderHandle *handle,
+                                               const struct CybThumbnailOptions *options,
+                                               struct Option_CybThumbnailFn callback,
+                                               void *user_data,
+                                               int32_t *out_count)
+;
+
+// Generate thumbnails and composite them into one contact sheet image
+//
+// `columns` = 0 picks a near-square grid; `spacing` is the gap in pixels.
+// The sheet is packed RGB (the configured format, or BGRA).
+
+enum CybResult cyb_decoder_get_contact_sheet(struct CybDecoderHandle *handle,
+                                             const struct CybThumbnailOptions *options,
+                                             uint32_t columns,
+                                             uint32_t spacing,
+                                             struct CybFrameHandle **out_frame)
+;
+
+// Compute per-channel min/max/RMS waveform buckets of an audio track
+//
+// Blocks until done and holds the decoder handle meanwhile. `progress`
+// (optional) streams partial results on the calling thread; returning false
+// cancels with CYB_RESULT_ERROR_CANCELLED. Release the result with
+// cyb_waveform_release.
+
+enum CybResult cyb_decoder_generate_waveform(struct CybDecoderHandle *handle,
+                                             const struct CybWaveformOptions *options,
+                                             struct Option_CybWaveformProgressFn progress,
+                                             void *user_data,
+                                             struct CybWaveformHandle **out_waveform)
+;
+
+// Get the description of a waveform
+
+enum CybResult cyb_waveform_get_info(const struct CybWaveformHandle *waveform_handle,
+                                     struct CybWaveformInfo *out_info)
+;
+
+// Copy up to `max_count` buckets of a channel, starting at `first_bucket`
+//
+// Returns the number of buckets copied.
+
+int32_t cyb_waveform_get_buckets(const struct CybWaveformHandle *waveform_handle,
+                                 int32_t channel,
+                                 int32_t first_bucket,
+                                 struct CybWaveformBucket *out_buckets,
+                                 int32_t max_count)
+;
+
+// Save a waveform to a file (compact binary format)
+
+enum CybResult cyb_waveform_save(const struct CybWaveformHandle *waveform_handle,
+                                 const char *path)
+;
+
+// Load a waveform saved with cyb_waveform_save
+ enum CybResult cyb_waveform_load(const char *path, struct CybWaveformHandle **out_waveform) ;
+
+// Release waveform
+ void cyb_waveform_release(struct CybWaveformHandle *waveform_handle) ;
+
+// Measure the loudness (EBU R128) of an audio track
+//
+// Blocks until done and holds the decoder handle meanwhile. `progress`
+// (optional) streams the time series on the calling thread; returning false
+// cancels with CYB_RESULT_ERROR_CANCELLED. Release the result with
+// cyb_loudness_release.
+
+enum CybResult cyb_decoder_measure_loudness(struct CybDecoderHandle *handle,
+                                            const struct CybLoudnessOptions *options,
+                                            struct Option_CybLoudnessProgressFn progress,
+                                            void *user_data,
+                                            struct CybLoudnessHandle **out_report)
+;
+
+// Get the summary of a loudness report
+
+enum CybResult cyb_loudness_get_summary(const struct CybLoudnessHandle *report_handle,
+                                        struct CybLoudnessSummary *out_summary)
+;
+
+// Copy up to `max_count` points of the time series, starting at `first_point`
+//
+// Returns the number of points copied.
+
+int32_t cyb_loudness_get_points(const struct CybLoudnessHandle *report_handle,
+                                int32_t first_point,
+                                struct CybLoudnessPoint *out_points,
+                                int32_t max_count)
+;
+
+// Release loudness report
+ void cyb_loudness_release(struct CybLoudnessHandle *report_handle) ;
+
+// Find the cuts between shots of the active video track
+//
+// Blocks until done and holds the decoder handle meanwhile. `progress`
+// (optional) streams the cuts on the calling thread; returning false
+// cancels with CYB_RESULT_ERROR_CANCELLED. Release the result with
+// cyb_scene_list_release.
+
+enum CybResult cyb_decoder_detect_scenes(struct CybDecoderHandle *handle,
+                                         const struct CybSceneDetectionOptions *options,
+                                         struct Option_CybSceneProgressFn progress,
+                                         void *user_data,
+                                         struct CybSceneListHandle **out_list)
+;
+
+// Get number of cuts in a scene list
+ int32_t cyb_scene_list_get_count(const struct CybSceneListHandle *list) ;
+
+// Get cut from a scene list
+
+enum CybResult cyb_scene_list_get_cut(const struct CybSceneListHandle *list,
+                                      int32_t index,
+                                      struct CybSceneCut *out_cut)
+;
+
+// Release scene list
+ void cyb_scene_list_release(struct CybSceneListHandle *list) ;
+
+// Create an encoder writing to `path` (the header is written immediately)
+//
+// Returns null on failure; the reason is available from cyb_get_last_error.
+
+struct CybEncoderHandle *cyb_encoder_create(const char *path,
+                                            const struct CybEncoderConfig *config)
+;
+
+// Encode a video frame (written at the configured frame rate; frames of
+// another size or pixel format are converted)
+
+enum CybResult cyb_encoder_write_video_frame(struct CybEncoderHandle *handle,
+                                             const struct CybFrameHandle *frame_handle)
+;
+
+// Encode an audio frame (sample rate and channel count must match the config)
+
+enum CybResult cyb_encoder_write_audio_frame(struct CybEncoderHandle *handle,
+                                             const struct CybAudioFrameHandle *frame_handle)
+;
+
+// Flush the encoders and finalize the file
+//
+// Must be called before cyb_encoder_destroy, or the file is incomplete.
+ enum CybResult cyb_encoder_finish(struct CybEncoderHandle *handle) ;
+
+// Destroy encoder
+ void cyb_encoder_destroy(struct CybEncoderHandle *handle) ;
+
 #endif /* CYB_FFMPEG_H */
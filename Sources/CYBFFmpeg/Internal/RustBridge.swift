@@ -320,6 +320,8 @@ internal final class RustBridge: @unchecked Sendable {
             throw FFmpegError.invalidHandle
         case CYB_RESULT_ERROR_NOT_PREPARED:
             throw FFmpegError.notPrepared
+        case CYB_RESULT_ERROR_CANCELLED:
+            throw FFmpegError.cancelled
         default:
             throw FFmpegError.unknown(Int32(result.rawValue))
         }
@@ -399,7 +401,7 @@ internal final class RustBridge: @unchecked Sendable {
             ),
             sampleRate: Int(cyb.sample_rate),
             channels: Int(cyb.channels),
-            channelLayout: nil,
+            channelLayout: cyb.channel_layout.map { String(cString: $0) },
             bitRate: cyb.bit_rate > 0 ? cyb.bit_rate : nil,
             languageCode: nil
         )
@@ -440,8 +442,10 @@ internal final class RustBridge: @unchecked Sendable {
         let totalSamples = Int(cybFrame.sample_count) * Int(cybFrame.channels)
         var samples: [Float] = []
 
-        if let dataPtr = cybFrame.data, totalSamples > 0 {
-            samples = Array(UnsafeBufferPointer(start: dataPtr, count: totalSamples))
+        // The bridge always requests interleaved Float32 output
+        if let dataPtr = cybFrame.data, totalSamples > 0, cybFrame.sample_format == 0 {
+            let floatPtr = UnsafeRawPointer(dataPtr).assumingMemoryBound(to: Float.self)
+            samples = Array(UnsafeBufferPointer(start: floatPtr, count: totalSamples))
         }
 
         return FFmpegAudioFrame(
@@ -464,11 +468,40 @@ extension DecoderConfiguration {
             prefer_hardware_decoding: preferHardwareDecoding,
             cache_config: cacheConfiguration.toCybConfig(),
             thread_count: UInt32(threadCount),
-            output_pixel_format: outputPixelFormat.toCybFormat()
+            output_pixel_format: outputPixelFormat.toCybFormat(),
+            video_stream: CybStreamSelector.best,
+            audio_stream: CybStreamSelector.best,
+            subtitle_stream: CybStreamSelector.best,
+            audio_output: CybAudioOutputConfig.interleavedStereoFloat,
+            output_width: 0,
+            output_height: 0,
+            scale_mode: 0,
+            scaling_algorithm: 1,
+            output_color_matrix: 0,
+            output_color_range: 0,
+            tone_map_operator: 0,
+            tone_map_target_peak: 100,
+            apply_orientation: false,
+            deinterlace_mode: 0,
+            video_filter: nil,
+            audio_filter: nil
         )
     }
 }
 
+extension CybStreamSelector {
+    /// Let FFmpeg pick the best stream
+    static let best = CybStreamSelector(kind: 0, index: -1, language: nil, disposition: 0)
+}
+
+extension CybAudioOutputConfig {
+    /// 48 kHz interleaved stereo Float32 (matches FFmpegAudioFrame.samples)
+    static let interleavedStereoFloat = CybAudioOutputConfig(
+        sample_rate: 48000, channels: 2, channel_layout: 0, sample_format: 0, planar: false,
+        downmix: 0
+    )
+}
+
 extension CacheConfiguration {
     func toCybConfig() -> CybCacheConfig {
         CybCacheConfig(
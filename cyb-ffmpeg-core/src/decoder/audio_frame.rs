@@ -18,12 +18,111 @@ impl Default for SampleFormat {
     }
 }
 
+impl SampleFormat {
+    /// Size of one sample in bytes
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            SampleFormat::Float32 => 4,
+            SampleFormat::Int16 => 2,
+            SampleFormat::Int32 => 4,
+        }
+    }
+}
+
+/// Typed audio sample data
+#[derive(Debug, Clone, PartialEq)]
+pub enum AudioSamples {
+    /// 32-bit float samples
+    Float32(Vec<f32>),
+    /// 16-bit signed integer samples
+    Int16(Vec<i16>),
+    /// 32-bit signed integer samples
+    Int32(Vec<i32>),
+}
+
+impl AudioSamples {
+    /// Create empty sample data of the given format
+    pub fn empty(format: SampleFormat) -> Self {
+        match format {
+            SampleFormat::Float32 => AudioSamples::Float32(Vec::new()),
+            SampleFormat::Int16 => AudioSamples::Int16(Vec::new()),
+            SampleFormat::Int32 => AudioSamples::Int32(Vec::new()),
+        }
+    }
+
+    /// Sample format of the data
+    pub fn format(&self) -> SampleFormat {
+        match self {
+            AudioSamples::Float32(_) => SampleFormat::Float32,
+            AudioSamples::Int16(_) => SampleFormat::Int16,
+            AudioSamples::Int32(_) => SampleFormat::Int32,
+        }
+    }
+
+    /// Number of samples (all channels)
+    pub fn len(&self) -> usize {
+        match self {
+            AudioSamples::Float32(v) => v.len(),
+            AudioSamples::Int16(v) => v.len(),
+            AudioSamples::Int32(v) => v.len(),
+        }
+    }
+
+    /// Check if there are no samples
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Size in bytes
+    pub fn byte_len(&self) -> usize {
+        self.len() * self.format().bytes_per_sample()
+    }
+
+    /// Pointer to the raw sample bytes
+    pub fn as_ptr(&self) -> *const u8 {
+        match self {
+            AudioSamples::Float32(v) => v.as_ptr() as *const u8,
+            AudioSamples::Int16(v) => v.as_ptr() as *const u8,
+            AudioSamples::Int32(v) => v.as_ptr() as *const u8,
+        }
+    }
+
+    /// Float samples (None for integer formats)
+    pub fn as_f32(&self) -> Option<&[f32]> {
+        match self {
+            AudioSamples::Float32(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Sample `i` scaled to -1.0..1.0, whatever the format
+    ///
+    /// # Panics
+    /// If `i` is out of range.
+    pub fn sample_f64(&self, i: usize) -> f64 {
+        match self {
+            AudioSamples::Float32(v) => v[i] as f64,
+            AudioSamples::Int16(v) => v[i] as f64 / 32768.0,
+            AudioSamples::Int32(v) => v[i] as f64 / 2_147_483_648.0,
+        }
+    }
+
+    /// Sample `i` scaled to -1.0..1.0 as `f32`, see [`Self::sample_f64`]
+    pub fn sample_f32(&self, i: usize) -> f32 {
+        self.sample_f64(i) as f32
+    }
+}
+
 /// Decoded audio frame
 #[derive(Clone)]
 pub struct AudioFrame {
-    /// Interleaved audio sample data
-    /// Format: [L0, R0, L1, R1, ...] for stereo
-    pub data: Vec<f32>,
+    /// Audio sample data
+    /// Interleaved: [L0, R0, L1, R1, ...] for stereo
+    /// Planar: [L0, L1, ..., R0, R1, ...] (one block of `sample_count` per channel)
+    pub data: AudioSamples,
+
+    /// Whether `data` is planar (channel after channel) instead of interleaved
+    pub planar: bool,
 
     /// Number of samples per channel
     pub sample_count: u32,
@@ -47,7 +146,8 @@ pub struct AudioFrame {
 impl AudioFrame {
     /// Create a new audio frame
     pub fn new(
-        data: Vec<f32>,
+        data: AudioSamples,
+        planar: bool,
         sample_count: u32,
         channels: u32,
         sample_rate: u32,
@@ -57,6 +157,7 @@ impl AudioFrame {
     ) -> Self {
         Self {
             data,
+            planar,
             sample_count,
             channels,
             sample_rate,
@@ -66,13 +167,18 @@ impl AudioFrame {
         }
     }
 
+    /// Sample format of the data
+    pub fn sample_format(&self) -> SampleFormat {
+        self.data.format()
+    }
+
     /// Get data size in bytes
     pub fn data_size(&self) -> usize {
-        self.data.len() * std::mem::size_of::<f32>()
+        self.data.byte_len()
     }
 
     /// Get data pointer
-    pub fn data_ptr(&self) -> *const f32 {
+    pub fn data_ptr(&self) -> *const u8 {
         self.data.as_ptr()
     }
 
@@ -105,7 +211,8 @@ impl AudioFrame {
         let total_samples = (sample_count * channels) as usize;
         let duration_us = Self::calculate_duration_us(sample_count, sample_rate);
         Self {
-            data: vec![0.0f32; total_samples],
+            data: AudioSamples::Float32(vec![0.0f32; total_samples]),
+            planar: false,
             sample_count,
             channels,
             sample_rate,
@@ -121,6 +228,8 @@ impl std::fmt::Debug for AudioFrame {
         f.debug_struct("AudioFrame")
             .field("sample_count", &self.sample_count)
             .field("channels", &self.channels)
+            .field("sample_format", &self.sample_format())
+            .field("planar", &self.planar)
             .field("sample_rate", &self.sample_rate)
             .field("pts_us", &self.pts_us)
             .field("duration_us", &self.duration_us)
@@ -156,4 +265,23 @@ mod tests {
         // 2048 samples * 4 bytes per f32 = 8192 bytes
         assert_eq!(frame.data_size(), 8192);
     }
+
+    #[test]
+    fn test_integer_samples() {
+        let data = AudioSamples::Int16(vec![0i16; 2048]);
+        let frame = AudioFrame::new(data, true, 1024, 2, 48000, 0, 21333, 0);
+        assert_eq!(frame.sample_format(), SampleFormat::Int16);
+        assert_eq!(frame.data_size(), 4096);
+        assert!(frame.data.as_f32().is_none());
+    }
+
+    #[test]
+    fn test_scaled_samples() {
+        let int16 = AudioSamples::Int16(vec![-32768, 16384]);
+        assert_eq!((int16.sample_f32(0), int16.sample_f32(1)), (-1.0, 0.5));
+        let int32 = AudioSamples::Int32(vec![i32::MIN, 1 << 29]);
+        assert_eq!((int32.sample_f64(0), int32.sample_f64(1)), (-1.0, 0.25));
+        let float = AudioSamples::Float32(vec![0.1]);
+        assert_eq!(float.sample_f32(0), 0.1);
+    }
 }
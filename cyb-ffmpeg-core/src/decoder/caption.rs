@@ -0,0 +1,863 @@
+//! Closed captions carried in video frames
+//!
+//! A/53 caption data (frame side data of H.264, HEVC and MPEG-2 video) is a
+//! list of 3-byte cc_data triplets. Types 0 and 1 carry CEA-608 byte pairs for
+//! the two NTSC fields; types 2 and 3 assemble CEA-708 (DTVCC) packets. Both
+//! are decoded into plain text screens, and every change of a channel's
+//! screen starts a new timed text event.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::subtitle::{SubtitleBuffer, SubtitleEvent, SubtitleKind, OPEN_END_US};
+
+/// Closed caption channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CaptionChannel {
+    /// CEA-608 channel CC1-CC4
+    Cea608(u8),
+    /// CEA-708 service 1-63
+    Cea708(u8),
+}
+
+impl CaptionChannel {
+    /// Numeric id: 1-4 for CC1-CC4, 101-163 for CEA-708 services 1-63
+    pub fn id(&self) -> i32 {
+        match self {
+            CaptionChannel::Cea608(channel) => *channel as i32,
+            CaptionChannel::Cea708(service) => 100 + *service as i32,
+        }
+    }
+
+    /// Channel from its numeric id
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            1..=4 => Some(CaptionChannel::Cea608(id as u8)),
+            101..=163 => Some(CaptionChannel::Cea708((id - 100) as u8)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CaptionChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptionChannel::Cea608(channel) => write!(f, "CC{}", channel),
+            CaptionChannel::Cea708(service) => write!(f, "Service {}", service),
+        }
+    }
+}
+
+/// Caption decoder for one video stream, collecting events per channel
+pub(crate) struct CaptionDecoder {
+    /// Video stream the captions belong to
+    stream_index: i32,
+    /// CEA-608 state of NTSC fields 1 and 2
+    fields: [Cea608Field; 2],
+    /// DTVCC packet being assembled
+    packet: Vec<u8>,
+    /// CEA-708 services by number
+    services: BTreeMap<u8, Cea708Service>,
+    /// Decoded events by channel
+    buffers: BTreeMap<CaptionChannel, SubtitleBuffer>,
+    /// Screen text of each channel's current event
+    screens: BTreeMap<CaptionChannel, String>,
+}
+
+impl CaptionDecoder {
+    /// Create a decoder for captions of the given video stream
+    pub fn new(stream_index: i32) -> Self {
+        Self {
+            stream_index,
+            fields: Default::default(),
+            packet: Vec::new(),
+            services: BTreeMap::new(),
+            buffers: BTreeMap::new(),
+            screens: BTreeMap::new(),
+        }
+    }
+
+    /// Decode the cc_data triplets of a frame presented at `time_us`
+    pub fn decode(&mut self, cc_data: &[u8], time_us: i64) {
+        for triplet in cc_data.chunks_exact(3) {
+            let valid = triplet[0] & 0x04 != 0;
+            match triplet[0] & 0x03 {
+                field @ (0 | 1) if valid => {
+                    self.fields[field as usize].decode(triplet[1], triplet[2])
+                }
+                3 => {
+                    self.packet.clear();
+                    if valid {
+                        self.packet.extend_from_slice(&triplet[1..]);
+                    }
+                }
+                2 if valid && !self.packet.is_empty() => {
+                    self.packet.extend_from_slice(&triplet[1..]);
+                }
+                _ => {}
+            }
+
+            if let Some(&header) = self.packet.first() {
+                let size = match header & 0x3f {
+                    0 => 128,
+                    code => code as usize * 2,
+                };
+                if self.packet.len() >= size {
+                    let packet = std::mem::take(&mut self.packet);
+                    self.decode_packet(&packet[..size]);
+                }
+            }
+        }
+
+        self.update_events(time_us);
+    }
+
+    /// Split a DTVCC packet into service blocks
+    fn decode_packet(&mut self, packet: &[u8]) {
+        let mut i = 1;
+        while i < packet.len() {
+            let header = packet[i];
+            let mut service = header >> 5;
+            let size = (header & 0x1f) as usize;
+            i += 1;
+            if service == 7 {
+                match packet.get(i) {
+                    Some(extended) => service = extended & 0x3f,
+                    None => break,
+                }
+                i += 1;
+            }
+            if service == 0 || size == 0 {
+                break;
+            }
+
+            let end = (i + size).min(packet.len());
+            self.services
+                .entry(service)
+                .or_default()
+                .decode(&packet[i..end]);
+            i = end;
+        }
+    }
+
+    /// Turn changed screens into events starting at `time_us`
+    fn update_events(&mut self, time_us: i64) {
+        let mut changed = Vec::new();
+        for (field, state) in self.fields.iter_mut().enumerate() {
+            for (channel, screen) in state.channels.iter_mut().enumerate() {
+                if std::mem::take(&mut screen.dirty) {
+                    let number = (field * 2 + channel + 1) as u8;
+                    changed.push((CaptionChannel::Cea608(number), screen.text()));
+                }
+            }
+        }
+        for (&number, service) in self.services.iter_mut() {
+            if std::mem::take(&mut service.dirty) {
+                changed.push((CaptionChannel::Cea708(number), service.text()));
+            }
+        }
+
+        for (channel, text) in changed {
+            let screen = self.screens.entry(channel).or_default();
+            if *screen == text {
+                continue;
+            }
+            screen.clone_from(&text);
+
+            let buffer = self.buffers.entry(channel).or_default();
+            if text.is_empty() {
+                buffer.close_open_events(time_us);
+            } else {
+                buffer.insert(SubtitleEvent {
+                    stream_index: self.stream_index,
+                    start_us: time_us,
+                    end_us: OPEN_END_US,
+                    kind: SubtitleKind::Text,
+                    text,
+                    ass: None,
+                    bitmap: None,
+                });
+            }
+        }
+    }
+
+    /// Forget partially received captions (after a seek).
+    ///
+    /// Decoded events are kept.
+    pub fn reset(&mut self) {
+        self.fields = Default::default();
+        self.packet.clear();
+        self.services.clear();
+        self.screens.clear();
+    }
+
+    /// Channels that produced events, in id order
+    pub fn channels(&self) -> Vec<CaptionChannel> {
+        self.buffers.keys().copied().collect()
+    }
+
+    /// All events of a channel in start time order
+    pub fn events(&self, channel: CaptionChannel) -> &[SubtitleEvent] {
+        self.buffers
+            .get(&channel)
+            .map(|buffer| buffer.events())
+            .unwrap_or(&[])
+    }
+
+    /// Events of a channel visible at the given time
+    pub fn active_at(&self, channel: CaptionChannel, time_us: i64) -> Vec<SubtitleEvent> {
+        self.buffers
+            .get(&channel)
+            .map(|buffer| buffer.active_at(time_us))
+            .unwrap_or_default()
+    }
+}
+
+// =============================================================================
+// CEA-608
+// =============================================================================
+
+const ROWS: usize = 15;
+const COLUMNS: usize = 32;
+
+/// Row of a preamble address code, by first byte bits and second byte bit 5
+const PAC_ROWS: [Option<usize>; 16] = [
+    Some(10),
+    None,
+    Some(0),
+    Some(1),
+    Some(2),
+    Some(3),
+    Some(11),
+    Some(12),
+    Some(13),
+    Some(14),
+    Some(4),
+    Some(5),
+    Some(6),
+    Some(7),
+    Some(8),
+    Some(9),
+];
+
+/// Special characters (0x11/0x19 followed by 0x30-0x3f)
+const SPECIAL_CHARS: [char; 16] = [
+    '®', '°', '½', '¿', '™', '¢', '£', '♪', 'à', ' ', 'è', 'â', 'ê', 'î', 'ô', 'û',
+];
+
+/// Extended Spanish/French characters (0x12/0x1a followed by 0x20-0x3f)
+const EXTENDED_CHARS_1: [char; 32] = [
+    'Á', 'É', 'Ó', 'Ú', 'Ü', 'ü', '‘', '¡', '*', '’', '—', '©', '℠', '•', '“', '”', 'À', 'Â', 'Ç',
+    'È', 'Ê', 'Ë', 'ë', 'Î', 'Ï', 'ï', 'Ô', 'Ù', 'ù', 'Û', '«', '»',
+];
+
+/// Extended Portuguese/German/Danish characters (0x13/0x1b followed by 0x20-0x3f)
+const EXTENDED_CHARS_2: [char; 32] = [
+    'Ã', 'ã', 'Í', 'Ì', 'ì', 'Ò', 'ò', 'Õ', 'õ', '{', '}', '\\', '^', '_', '|', '~', 'Ä', 'ä', 'Ö',
+    'ö', 'ß', '¥', '¤', '│', 'Å', 'å', 'Ø', 'ø', '┌', '┐', '└', '┘',
+];
+
+/// Basic character, with the code points CEA-608 redefines
+fn basic_char(byte: u8) -> char {
+    match byte {
+        0x2a => 'á',
+        0x5c => 'é',
+        0x5e => 'í',
+        0x5f => 'ó',
+        0x60 => 'ú',
+        0x7b => 'ç',
+        0x7c => '÷',
+        0x7d => 'Ñ',
+        0x7e => 'ñ',
+        0x7f => '█',
+        _ => byte as char,
+    }
+}
+
+/// Caption display mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Text is built off screen and shown by end of caption
+    PopOn,
+    /// Text appears on the bottom rows and scrolls up
+    RollUp(usize),
+    /// Text appears on screen as it arrives
+    PaintOn,
+}
+
+type Memory = [[char; COLUMNS]; ROWS];
+
+/// Displayed and off-screen memory of one CEA-608 channel
+#[derive(Clone)]
+struct Cea608Screen {
+    mode: Mode,
+    displayed: Memory,
+    buffered: Memory,
+    row: usize,
+    column: usize,
+    /// Displayed memory changed since the last check
+    dirty: bool,
+}
+
+impl Default for Cea608Screen {
+    fn default() -> Self {
+        Self {
+            mode: Mode::PopOn,
+            displayed: [[' '; COLUMNS]; ROWS],
+            buffered: [[' '; COLUMNS]; ROWS],
+            row: ROWS - 1,
+            column: 0,
+            dirty: false,
+        }
+    }
+}
+
+impl Cea608Screen {
+    /// Memory written by characters in the current mode
+    fn target(&mut self) -> &mut Memory {
+        if self.mode == Mode::PopOn {
+            &mut self.buffered
+        } else {
+            self.dirty = true;
+            &mut self.displayed
+        }
+    }
+
+    fn write(&mut self, c: char) {
+        let (row, column) = (self.row, self.column);
+        self.target()[row][column] = c;
+        self.column = (column + 1).min(COLUMNS - 1);
+    }
+
+    fn backspace(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+            let (row, column) = (self.row, self.column);
+            self.target()[row][column] = ' ';
+        }
+    }
+
+    fn roll_up(&mut self, rows: usize) {
+        if self.mode != Mode::RollUp(rows) {
+            if !matches!(self.mode, Mode::RollUp(_)) {
+                self.displayed = [[' '; COLUMNS]; ROWS];
+                self.buffered = [[' '; COLUMNS]; ROWS];
+                self.row = ROWS - 1;
+            }
+            self.mode = Mode::RollUp(rows);
+            self.dirty = true;
+        }
+        self.column = 0;
+    }
+
+    fn carriage_return(&mut self) {
+        if let Mode::RollUp(rows) = self.mode {
+            let top = (self.row + 1).saturating_sub(rows);
+            for row in 0..ROWS {
+                self.displayed[row] = if row >= top && row < self.row {
+                    self.displayed[row + 1]
+                } else {
+                    [' '; COLUMNS]
+                };
+            }
+            self.dirty = true;
+        }
+        self.column = 0;
+    }
+
+    /// Move the cursor; roll-up captions move with their base row
+    fn set_row(&mut self, row: usize) {
+        if let Mode::RollUp(rows) = self.mode {
+            if row != self.row {
+                let old = self.displayed;
+                self.displayed = [[' '; COLUMNS]; ROWS];
+                for offset in 0..rows.min(row + 1).min(self.row + 1) {
+                    self.displayed[row - offset] = old[self.row - offset];
+                }
+                self.dirty = true;
+            }
+        }
+        self.row = row;
+    }
+
+    fn end_of_caption(&mut self) {
+        std::mem::swap(&mut self.displayed, &mut self.buffered);
+        self.mode = Mode::PopOn;
+        self.dirty = true;
+    }
+
+    /// Displayed rows, trimmed, top to bottom
+    fn text(&self) -> String {
+        self.displayed
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim().to_string())
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// CEA-608 state of one NTSC field (two data channels)
+#[derive(Clone, Default)]
+struct Cea608Field {
+    channels: [Cea608Screen; 2],
+    /// Data channel that receives characters
+    channel: usize,
+    /// Last control code, to drop its redundant repetition
+    last_control: Option<(u8, u8)>,
+    /// Inside an XDS (extended data services) packet
+    in_xds: bool,
+}
+
+impl Cea608Field {
+    fn decode(&mut self, b1: u8, b2: u8) {
+        let (b1, b2) = (b1 & 0x7f, b2 & 0x7f);
+        if b1 == 0 && b2 == 0 {
+            return;
+        }
+
+        // XDS packets interleave with captions on field 2
+        match b1 {
+            0x01..=0x0e => self.in_xds = true,
+            0x0f => self.in_xds = false,
+            0x10..=0x1f => self.in_xds = false,
+            _ => {}
+        }
+        if self.in_xds || b1 == 0x0f {
+            return;
+        }
+
+        if (0x10..=0x1f).contains(&b1) {
+            // Control codes are sent twice in a row
+            if self.last_control.take() == Some((b1, b2)) {
+                return;
+            }
+            self.last_control = Some((b1, b2));
+            self.channel = (b1 >> 3 & 1) as usize;
+            self.control(b1 & 0x17, b2);
+            return;
+        }
+
+        self.last_control = None;
+        let screen = &mut self.channels[self.channel];
+        for byte in [b1, b2] {
+            if byte >= 0x20 {
+                screen.write(basic_char(byte));
+            }
+        }
+    }
+
+    /// Handle a control code, with the channel bit cleared from `b1`
+    fn control(&mut self, b1: u8, b2: u8) {
+        let screen = &mut self.channels[self.channel];
+        match (b1, b2) {
+            // Preamble address code: row and indent
+            (0x10..=0x17, 0x40..=0x7f) => {
+                let index = ((b1 as usize & 0x07) << 1) | (b2 as usize >> 5 & 1);
+                if let Some(row) = PAC_ROWS[index] {
+                    screen.set_row(row);
+                    screen.column = if b2 & 0x10 != 0 {
+                        ((b2 as usize & 0x0e) >> 1) * 4
+                    } else {
+                        0
+                    };
+                }
+            }
+            // Mid-row style change, shown as a space
+            (0x11, 0x20..=0x2f) => screen.write(' '),
+            (0x11, 0x30..=0x3f) => screen.write(SPECIAL_CHARS[(b2 - 0x30) as usize]),
+            // Extended characters replace the basic fallback sent before them
+            (0x12, 0x20..=0x3f) => {
+                screen.backspace();
+                screen.write(EXTENDED_CHARS_1[(b2 - 0x20) as usize]);
+            }
+            (0x13, 0x20..=0x3f) => {
+                screen.backspace();
+                screen.write(EXTENDED_CHARS_2[(b2 - 0x20) as usize]);
+            }
+            (0x14 | 0x15, 0x20) => screen.mode = Mode::PopOn,
+            (0x14 | 0x15, 0x21) => screen.backspace(),
+            (0x14 | 0x15, 0x24) => {
+                let (row, column) = (screen.row, screen.column);
+                screen.target()[row][column..].fill(' ');
+            }
+            (0x14 | 0x15, 0x25..=0x27) => screen.roll_up((b2 - 0x23) as usize),
+            (0x14 | 0x15, 0x29) => screen.mode = Mode::PaintOn,
+            (0x14 | 0x15, 0x2c) => {
+                screen.displayed = [[' '; COLUMNS]; ROWS];
+                screen.dirty = true;
+            }
+            (0x14 | 0x15, 0x2d) => screen.carriage_return(),
+            (0x14 | 0x15, 0x2e) => screen.buffered = [[' '; COLUMNS]; ROWS],
+            (0x14 | 0x15, 0x2f) => screen.end_of_caption(),
+            // Tab offsets
+            (0x17, 0x21..=0x23) => {
+                screen.column = (screen.column + (b2 - 0x20) as usize).min(COLUMNS - 1)
+            }
+            _ => {}
+        }
+    }
+}
+
+// =============================================================================
+// CEA-708
+// =============================================================================
+
+/// Caption window of a CEA-708 service
+#[derive(Debug, Clone, Default)]
+struct Window {
+    defined: bool,
+    visible: bool,
+    rows: Vec<Vec<char>>,
+    row: usize,
+    column: usize,
+}
+
+impl Window {
+    fn clear(&mut self) {
+        for row in self.rows.iter_mut() {
+            row.clear();
+        }
+        self.row = 0;
+        self.column = 0;
+    }
+
+    fn write(&mut self, c: char) {
+        if let Some(row) = self.rows.get_mut(self.row) {
+            if row.len() <= self.column {
+                row.resize(self.column + 1, ' ');
+            }
+            row[self.column] = c;
+            self.column += 1;
+        }
+    }
+
+    fn carriage_return(&mut self) {
+        self.row += 1;
+        if self.row >= self.rows.len() {
+            // Scroll the text up
+            if !self.rows.is_empty() {
+                self.rows.remove(0);
+                self.rows.push(Vec::new());
+            }
+            self.row = self.rows.len().saturating_sub(1);
+        }
+        self.column = 0;
+    }
+}
+
+/// Windows and pen state of one CEA-708 service
+#[derive(Debug, Clone, Default)]
+struct Cea708Service {
+    windows: [Window; 8],
+    current: usize,
+    /// Visible text changed since the last check
+    dirty: bool,
+}
+
+impl Cea708Service {
+    /// Decode the data of a service block
+    fn decode(&mut self, data: &[u8]) {
+        let mut i = 0;
+        while i < data.len() {
+            let code = data[i];
+            i += 1;
+            let params = match code {
+                0x10 => {
+                    // EXT1: extended code sets
+                    let Some(&extended) = data.get(i) else {
+                        break;
+                    };
+                    i += 1;
+                    match extended {
+                        0x00..=0x07 => 0,
+                        0x08..=0x0f => 1,
+                        0x10..=0x17 => 2,
+                        0x18..=0x1f => 3,
+                        0x20..=0x7f => {
+                            if let Some(c) = g2_char(extended) {
+                                self.write(c);
+                            }
+                            0
+                        }
+                        0x80..=0x87 => 4,
+                        0x88..=0x8f => 5,
+                        0x90..=0x9f => 1 + data.get(i).map_or(0, |&b| (b & 0x3f) as usize),
+                        _ => 0,
+                    }
+                }
+                0x00..=0x1f => self.c0(code),
+                0x20..=0x7e => {
+                    self.write(code as char);
+                    0
+                }
+                0x7f => {
+                    self.write('♪');
+                    0
+                }
+                0x80..=0x9f => {
+                    let count = c1_parameter_count(code);
+                    match data.get(i..i + count) {
+                        Some(params) => self.c1(code, params),
+                        None => break,
+                    }
+                    count
+                }
+                _ => {
+                    self.write(code as char);
+                    0
+                }
+            };
+            i += params;
+        }
+    }
+
+    /// Handle a C0 control code; returns the number of parameter bytes
+    fn c0(&mut self, code: u8) -> usize {
+        let window = &mut self.windows[self.current];
+        match code {
+            // Backspace
+            0x08 if window.column > 0 => {
+                window.column -= 1;
+                let (row, column) = (window.row, window.column);
+                if let Some(c) = window.rows.get_mut(row).and_then(|r| r.get_mut(column)) {
+                    *c = ' ';
+                }
+            }
+            // Form feed
+            0x0c => window.clear(),
+            0x0d => window.carriage_return(),
+            // Horizontal carriage return
+            0x0e => {
+                if let Some(row) = window.rows.get_mut(window.row) {
+                    row.clear();
+                }
+                window.column = 0;
+            }
+            _ => {}
+        }
+        self.dirty |= window.visible;
+
+        match code {
+            0x11..=0x17 => 1,
+            0x18..=0x1f => 2,
+            _ => 0,
+        }
+    }
+
+    /// Handle a C1 window or pen command
+    fn c1(&mut self, code: u8, params: &[u8]) {
+        let selected = |bit: usize| params.first().is_some_and(|mask| mask >> bit & 1 != 0);
+        match code {
+            0x80..=0x87 => self.current = (code - 0x80) as usize,
+            // ClearWindows, DisplayWindows, HideWindows, ToggleWindows, DeleteWindows
+            0x88..=0x8c => {
+                for (id, window) in self.windows.iter_mut().enumerate() {
+                    if !selected(id) {
+                        continue;
+                    }
+                    let was_visible = window.visible && window.defined;
+                    match code {
+                        0x88 => window.clear(),
+                        0x89 => window.visible = true,
+                        0x8a => window.visible = false,
+                        0x8b => window.visible = !window.visible,
+                        _ => *window = Window::default(),
+                    }
+                    self.dirty |= was_visible || (window.visible && window.defined);
+                }
+            }
+            // Reset
+            0x8f => {
+                self.dirty |= self.windows.iter().any(|w| w.defined && w.visible);
+                self.windows = Default::default();
+            }
+            // SetPenLocation
+            0x92 => {
+                let window = &mut self.windows[self.current];
+                window.row = (params[0] & 0x0f) as usize;
+                window.column = (params[1] & 0x3f) as usize;
+            }
+            // DefineWindow
+            0x98..=0x9f => {
+                let id = (code - 0x98) as usize;
+                let window = &mut self.windows[id];
+                let was_visible = window.visible && window.defined;
+                if !window.defined {
+                    *window = Window {
+                        defined: true,
+                        ..Window::default()
+                    };
+                }
+                window.visible = params[0] & 0x20 != 0;
+                window
+                    .rows
+                    .resize((params[3] & 0x0f) as usize + 1, Vec::new());
+                window.row = window.row.min(window.rows.len() - 1);
+                self.current = id;
+                self.dirty |= was_visible || window.visible;
+            }
+            _ => {}
+        }
+    }
+
+    fn write(&mut self, c: char) {
+        let window = &mut self.windows[self.current];
+        if window.defined {
+            window.write(c);
+            self.dirty |= window.visible;
+        }
+    }
+
+    /// Text of the visible windows in window order
+    fn text(&self) -> String {
+        self.windows
+            .iter()
+            .filter(|window| window.defined && window.visible)
+            .flat_map(|window| window.rows.iter())
+            .map(|row| row.iter().collect::<String>().trim().to_string())
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Parameter bytes following a C1 command
+fn c1_parameter_count(code: u8) -> usize {
+    match code {
+        0x88..=0x8d => 1,
+        0x90 | 0x92 => 2,
+        0x91 => 3,
+        0x97 => 4,
+        0x98..=0x9f => 6,
+        _ => 0,
+    }
+}
+
+/// Character of the G2 set (reached through EXT1)
+fn g2_char(code: u8) -> Option<char> {
+    let c = match code {
+        0x20 => ' ',
+        0x21 => '\u{a0}',
+        0x25 => '…',
+        0x2a => 'Š',
+        0x2c => 'Œ',
+        0x30 => '█',
+        0x31 => '‘',
+        0x32 => '’',
+        0x33 => '“',
+        0x34 => '”',
+        0x35 => '•',
+        0x39 => '™',
+        0x3a => 'š',
+        0x3c => 'œ',
+        0x3d => '℠',
+        0x3f => 'Ÿ',
+        0x76 => '⅛',
+        0x77 => '⅜',
+        0x78 => '⅝',
+        0x79 => '⅞',
+        0x7a => '│',
+        0x7b => '┐',
+        0x7c => '└',
+        0x7d => '─',
+        0x7e => '┘',
+        0x7f => '┌',
+        _ => return None,
+    };
+    Some(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// cc_data triplets for CEA-608 field 1 byte pairs
+    fn field1(pairs: &[[u8; 2]]) -> Vec<u8> {
+        pairs.iter().flat_map(|p| [0xfc, p[0], p[1]]).collect()
+    }
+
+    /// Byte pairs of a text, padded with a null
+    fn text_pairs(text: &str) -> Vec<[u8; 2]> {
+        text.as_bytes()
+            .chunks(2)
+            .map(|c| [c[0], c.get(1).copied().unwrap_or(0)])
+            .collect()
+    }
+
+    #[test]
+    fn test_channel_ids() {
+        for channel in [CaptionChannel::Cea608(3), CaptionChannel::Cea708(12)] {
+            assert_eq!(CaptionChannel::from_id(channel.id()), Some(channel));
+        }
+        assert_eq!(CaptionChannel::from_id(5), None);
+        assert_eq!(CaptionChannel::Cea608(1).to_string(), "CC1");
+    }
+
+    #[test]
+    fn test_pop_on() {
+        let cc1 = CaptionChannel::Cea608(1);
+        let mut decoder = CaptionDecoder::new(0);
+
+        // RCL (sent twice), PAC row 15, text with an extended character
+        let mut pairs = vec![[0x14, 0x20], [0x14, 0x20], [0x14, 0x70]];
+        pairs.extend(text_pairs("Cafe"));
+        pairs.push([0x13, 0x21]);
+        decoder.decode(&field1(&pairs), 0);
+        assert!(decoder.events(cc1).is_empty());
+
+        // End of caption shows it
+        decoder.decode(&field1(&[[0x14, 0x2f], [0x14, 0x2f]]), 1_000_000);
+        // Erase displayed memory ends it
+        decoder.decode(&field1(&[[0x14, 0x2c]]), 3_000_000);
+
+        let events = decoder.events(cc1);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].text, "Cafã");
+        assert_eq!(
+            (events[0].start_us, events[0].end_us),
+            (1_000_000, 3_000_000)
+        );
+        assert_eq!(decoder.channels(), vec![cc1]);
+    }
+
+    #[test]
+    fn test_roll_up() {
+        let cc1 = CaptionChannel::Cea608(1);
+        let mut decoder = CaptionDecoder::new(0);
+
+        let mut pairs = vec![[0x14, 0x25]];
+        pairs.extend(text_pairs("ONE"));
+        decoder.decode(&field1(&pairs), 0);
+
+        let mut pairs = vec![[0x14, 0x2d]];
+        pairs.extend(text_pairs("TWO"));
+        decoder.decode(&field1(&pairs), 500_000);
+
+        assert_eq!(decoder.active_at(cc1, 100_000)[0].text, "ONE");
+        assert_eq!(decoder.active_at(cc1, 600_000)[0].text, "ONE\nTWO");
+    }
+
+    #[test]
+    fn test_cea708_service() {
+        let service = CaptionChannel::Cea708(1);
+        let mut decoder = CaptionDecoder::new(0);
+
+        // DefineWindow 0 (visible, 2 rows, 32 columns), then "Hi"
+        let block = [0x98, 0x20, 0, 0, 0x01, 0x1f, 0, b'H', b'i'];
+        let mut packet = vec![0x06, 1 << 5 | block.len() as u8];
+        packet.extend_from_slice(&block);
+        packet.resize(12, 0);
+
+        let mut cc_data = vec![0xff, packet[0], packet[1]];
+        for pair in packet[2..].chunks(2) {
+            cc_data.extend_from_slice(&[0xfe, pair[0], pair[1]]);
+        }
+        decoder.decode(&cc_data, 2_000_000);
+
+        let events = decoder.events(service);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].text, "Hi");
+        assert_eq!(events[0].start_us, 2_000_000);
+    }
+}
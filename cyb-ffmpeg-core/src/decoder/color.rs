@@ -0,0 +1,217 @@
+//! Color description types
+//!
+//! Matrix, primaries and transfer values are ISO/IEC 23091-4 (ITU-T H.273)
+//! code points, which FFmpeg uses for AVColorSpace, AVColorPrimaries and
+//! AVColorTransferCharacteristic.
+
+/// Matrix coefficient code points
+pub mod matrix {
+    /// Identity (RGB / GBR)
+    pub const RGB: u8 = 0;
+    /// ITU-R BT.709
+    pub const BT709: u8 = 1;
+    /// Unspecified
+    pub const UNSPECIFIED: u8 = 2;
+    /// ITU-R BT.601 625-line (PAL)
+    pub const BT470BG: u8 = 5;
+    /// ITU-R BT.601 525-line (NTSC)
+    pub const SMPTE170M: u8 = 6;
+    /// ITU-R BT.2020 non-constant luminance
+    pub const BT2020_NCL: u8 = 9;
+    /// ITU-R BT.2020 constant luminance
+    pub const BT2020_CL: u8 = 10;
+}
+
+/// Color primaries code points
+pub mod primaries {
+    /// ITU-R BT.709 / sRGB
+    pub const BT709: u8 = 1;
+    /// Unspecified
+    pub const UNSPECIFIED: u8 = 2;
+    /// ITU-R BT.601 625-line (PAL)
+    pub const BT470BG: u8 = 5;
+    /// ITU-R BT.601 525-line (NTSC)
+    pub const SMPTE170M: u8 = 6;
+    /// ITU-R BT.2020
+    pub const BT2020: u8 = 9;
+    /// Display P3 (SMPTE EG 432-1)
+    pub const SMPTE432: u8 = 12;
+}
+
+/// Transfer characteristic code points
+pub mod transfer {
+    /// ITU-R BT.709
+    pub const BT709: u8 = 1;
+    /// Unspecified
+    pub const UNSPECIFIED: u8 = 2;
+    /// Linear light
+    pub const LINEAR: u8 = 8;
+    /// sRGB (IEC 61966-2-1)
+    pub const IEC61966_2_1: u8 = 13;
+    /// ITU-R BT.2020 10-bit
+    pub const BT2020_10: u8 = 14;
+    /// SMPTE ST 2084 (PQ)
+    pub const SMPTE2084: u8 = 16;
+    /// ARIB STD-B67 (HLG)
+    pub const ARIB_STD_B67: u8 = 18;
+}
+
+/// Range of the encoded sample values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ColorRange {
+    /// Not signalled
+    Unspecified = 0,
+    /// Limited / "TV" range (16-235 for 8-bit luma)
+    Limited = 1,
+    /// Full / "PC" range (0-255 for 8-bit)
+    Full = 2,
+}
+
+impl Default for ColorRange {
+    fn default() -> Self {
+        Self::Unspecified
+    }
+}
+
+impl ColorRange {
+    /// Convert from FFmpeg's AVColorRange value
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            1 => ColorRange::Limited,
+            2 => ColorRange::Full,
+            _ => ColorRange::Unspecified,
+        }
+    }
+}
+
+/// Position of chroma samples relative to luma samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ChromaLocation {
+    /// Not signalled
+    Unspecified = 0,
+    /// Left (MPEG-2/4 4:2:0, H.264 default)
+    Left = 1,
+    /// Center (MPEG-1 4:2:0, JPEG)
+    Center = 2,
+    /// Top left (ITU-R BT.2020 4:2:0)
+    TopLeft = 3,
+    /// Top
+    Top = 4,
+    /// Bottom left
+    BottomLeft = 5,
+    /// Bottom
+    Bottom = 6,
+}
+
+impl Default for ChromaLocation {
+    fn default() -> Self {
+        Self::Unspecified
+    }
+}
+
+impl ChromaLocation {
+    /// Convert from FFmpeg's AVChromaLocation value
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            1 => ChromaLocation::Left,
+            2 => ChromaLocation::Center,
+            3 => ChromaLocation::TopLeft,
+            4 => ChromaLocation::Top,
+            5 => ChromaLocation::BottomLeft,
+            6 => ChromaLocation::Bottom,
+            _ => ChromaLocation::Unspecified,
+        }
+    }
+}
+
+/// Color description of a video stream or frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorInfo {
+    /// Matrix coefficients (see [`matrix`])
+    pub matrix: u8,
+
+    /// Color primaries (see [`primaries`])
+    pub primaries: u8,
+
+    /// Transfer characteristic (see [`transfer`])
+    pub transfer: u8,
+
+    /// Sample value range
+    pub range: ColorRange,
+
+    /// Chroma sample siting
+    pub chroma_location: ChromaLocation,
+}
+
+impl Default for ColorInfo {
+    fn default() -> Self {
+        Self {
+            matrix: matrix::UNSPECIFIED,
+            primaries: primaries::UNSPECIFIED,
+            transfer: transfer::UNSPECIFIED,
+            range: ColorRange::Unspecified,
+            chroma_location: ChromaLocation::Unspecified,
+        }
+    }
+}
+
+impl ColorInfo {
+    /// Check if no color property is signalled
+    pub fn is_unspecified(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check if the transfer characteristic is HDR (PQ or HLG)
+    pub fn is_hdr(&self) -> bool {
+        self.transfer == transfer::SMPTE2084 || self.transfer == transfer::ARIB_STD_B67
+    }
+
+    /// Color description of RGB data converted from this source
+    pub fn as_rgb(&self) -> Self {
+        Self {
+            matrix: matrix::RGB,
+            range: ColorRange::Full,
+            chroma_location: ChromaLocation::Unspecified,
+            ..*self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_is_unspecified() {
+        let color = ColorInfo::default();
+        assert!(color.is_unspecified());
+        assert_eq!(color.matrix, matrix::UNSPECIFIED);
+        assert_eq!(color.range, ColorRange::Unspecified);
+    }
+
+    #[test]
+    fn test_raw_conversion() {
+        assert_eq!(ColorRange::from_raw(1), ColorRange::Limited);
+        assert_eq!(ColorRange::from_raw(3), ColorRange::Unspecified);
+        assert_eq!(ChromaLocation::from_raw(3), ChromaLocation::TopLeft);
+        assert_eq!(ChromaLocation::from_raw(7), ChromaLocation::Unspecified);
+    }
+
+    #[test]
+    fn test_as_rgb() {
+        let hdr = ColorInfo {
+            matrix: matrix::BT2020_NCL,
+            primaries: primaries::BT2020,
+            transfer: transfer::SMPTE2084,
+            range: ColorRange::Limited,
+            chroma_location: ChromaLocation::TopLeft,
+        };
+        let rgb = hdr.as_rgb();
+        assert_eq!(rgb.matrix, matrix::RGB);
+        assert_eq!(rgb.range, ColorRange::Full);
+        assert_eq!(rgb.transfer, transfer::SMPTE2084);
+        assert_eq!(rgb.primaries, primaries::BT2020);
+    }
+}
@@ -1,5 +1,10 @@
 //! Decoder configuration
 
+use super::audio_frame::SampleFormat;
+use super::color::ColorRange;
+use super::deinterlace::DeinterlaceMode;
+use super::hdr::ToneMapConfig;
+
 /// Pixel format for output frames
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -10,6 +15,24 @@ pub enum PixelFormat {
     Nv12 = 1,
     /// YUV420P (12-bit, planar)
     Yuv420p = 2,
+    /// RGBA (32-bit)
+    Rgba = 3,
+    /// P010 (10-bit 4:2:0, semi-planar, 16-bit little-endian samples)
+    P010 = 4,
+    /// YUV422P10 (10-bit 4:2:2, planar, 16-bit little-endian samples)
+    Yuv422p10 = 5,
+    /// YUV444P16 (16-bit 4:4:4, planar, little-endian)
+    Yuv444p16 = 6,
+    /// RGBA64 (16 bits per component, little-endian)
+    Rgba64 = 7,
+    /// RGB48 (16 bits per component, little-endian)
+    Rgb48 = 8,
+    /// Keep the decoder's own format, or else the nearest supported one that
+    /// keeps its bit depth and chroma resolution (e.g. 8-bit 4:2:2 becomes
+    /// Yuv422p10 and 12-bit YUV Yuv444p16; float formats are reduced to 16
+    /// bits). Only valid in `DecoderConfig`; frames always report the
+    /// resolved format.
+    Native = 9,
 }
 
 impl Default for PixelFormat {
@@ -18,6 +41,366 @@ impl Default for PixelFormat {
     }
 }
 
+impl PixelFormat {
+    /// Number of planes in frame data
+    pub fn plane_count(&self) -> usize {
+        match self {
+            PixelFormat::Bgra | PixelFormat::Rgba | PixelFormat::Rgba64 | PixelFormat::Rgb48 => 1,
+            PixelFormat::Nv12 | PixelFormat::P010 => 2,
+            PixelFormat::Yuv420p | PixelFormat::Yuv422p10 | PixelFormat::Yuv444p16 => 3,
+            PixelFormat::Native => 0,
+        }
+    }
+
+    /// Bytes per pixel in the first plane
+    pub fn bytes_per_pixel(&self) -> u32 {
+        match self {
+            PixelFormat::Bgra | PixelFormat::Rgba => 4,
+            PixelFormat::Rgba64 => 8,
+            PixelFormat::Rgb48 => 6,
+            PixelFormat::Nv12 | PixelFormat::Yuv420p => 1,
+            PixelFormat::P010 | PixelFormat::Yuv422p10 | PixelFormat::Yuv444p16 => 2,
+            PixelFormat::Native => 0,
+        }
+    }
+
+    /// Check if the format stores RGB data
+    pub fn is_rgb(&self) -> bool {
+        matches!(
+            self,
+            PixelFormat::Bgra | PixelFormat::Rgba | PixelFormat::Rgba64 | PixelFormat::Rgb48
+        )
+    }
+
+    /// Bits per component
+    pub fn bit_depth(&self) -> u32 {
+        match self {
+            PixelFormat::Bgra | PixelFormat::Rgba | PixelFormat::Nv12 | PixelFormat::Yuv420p => 8,
+            PixelFormat::P010 | PixelFormat::Yuv422p10 => 10,
+            PixelFormat::Yuv444p16 | PixelFormat::Rgba64 | PixelFormat::Rgb48 => 16,
+            PixelFormat::Native => 0,
+        }
+    }
+
+    /// Row size in bytes and row count of a plane, given the first plane's stride.
+    ///
+    /// Frame data stores planes back to back in this layout.
+    pub fn plane_layout(&self, plane: usize, stride: u32, height: u32) -> Option<(u32, u32)> {
+        if plane >= self.plane_count() {
+            return None;
+        }
+        if plane == 0 {
+            return Some((stride, height));
+        }
+
+        // Samples of the YUV formats are 1 or 2 bytes
+        let sample_size = self.bytes_per_pixel();
+        let chroma_width = (stride / sample_size).div_ceil(2);
+        let layout = match self {
+            PixelFormat::Nv12 | PixelFormat::P010 => {
+                (chroma_width * 2 * sample_size, height.div_ceil(2))
+            }
+            PixelFormat::Yuv420p => (chroma_width * sample_size, height.div_ceil(2)),
+            PixelFormat::Yuv422p10 => (chroma_width * sample_size, height),
+            _ => (stride, height),
+        };
+        Some(layout)
+    }
+
+    /// Horizontal and vertical chroma subsampling shift of a plane
+    pub fn plane_subsampling(&self, plane: usize) -> (u32, u32) {
+        match self {
+            _ if plane == 0 => (0, 0),
+            PixelFormat::Nv12 | PixelFormat::P010 | PixelFormat::Yuv420p => (1, 1),
+            PixelFormat::Yuv422p10 => (1, 0),
+            _ => (0, 0),
+        }
+    }
+
+    /// Bytes per sample position in a plane (an interleaved chroma pair counts as one)
+    pub fn plane_unit_size(&self, plane: usize) -> u32 {
+        match self {
+            PixelFormat::Nv12 | PixelFormat::P010 if plane == 1 => self.bytes_per_pixel() * 2,
+            _ => self.bytes_per_pixel(),
+        }
+    }
+}
+
+/// How a frame is fitted into a requested output size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ScaleMode {
+    /// Keep aspect ratio and fit inside the target (output may be smaller on one axis)
+    Fit = 0,
+    /// Keep aspect ratio and cover the target, cropping the overflow
+    Fill = 1,
+    /// Scale to exactly the target size, ignoring aspect ratio
+    Stretch = 2,
+}
+
+impl Default for ScaleMode {
+    fn default() -> Self {
+        Self::Fit
+    }
+}
+
+/// Scaling algorithm used by swscale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ScalingAlgorithm {
+    /// Fast bilinear (lowest quality, fastest)
+    FastBilinear = 0,
+    /// Bilinear
+    Bilinear = 1,
+    /// Bicubic
+    Bicubic = 2,
+    /// Nearest neighbor
+    Point = 3,
+    /// Area averaging (good for downscaling)
+    Area = 4,
+    /// Lanczos (sharpest, slowest)
+    Lanczos = 5,
+    /// Natural bicubic spline
+    Spline = 6,
+}
+
+impl Default for ScalingAlgorithm {
+    fn default() -> Self {
+        Self::Bilinear
+    }
+}
+
+/// Requested output frame size (0x0 = source size)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputSize {
+    /// Target width (0 = derive from height, keeping aspect ratio)
+    pub width: u32,
+
+    /// Target height (0 = derive from width, keeping aspect ratio)
+    pub height: u32,
+
+    /// Aspect handling when both dimensions are set
+    pub mode: ScaleMode,
+}
+
+/// Source crop and output dimensions resolved from an `OutputSize`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScaleGeometry {
+    /// Left edge of the source region
+    pub crop_x: u32,
+    /// Top edge of the source region
+    pub crop_y: u32,
+    /// Width of the source region
+    pub crop_width: u32,
+    /// Height of the source region
+    pub crop_height: u32,
+    /// Output width
+    pub width: u32,
+    /// Output height
+    pub height: u32,
+}
+
+impl ScaleGeometry {
+    /// Swap the horizontal and vertical axes
+    pub fn transposed(&self) -> Self {
+        Self {
+            crop_x: self.crop_y,
+            crop_y: self.crop_x,
+            crop_width: self.crop_height,
+            crop_height: self.crop_width,
+            width: self.height,
+            height: self.width,
+        }
+    }
+}
+
+impl OutputSize {
+    /// Create a target size with the given aspect mode
+    pub fn new(width: u32, height: u32, mode: ScaleMode) -> Self {
+        Self {
+            width,
+            height,
+            mode,
+        }
+    }
+
+    /// Resolve the source region and output dimensions for a source size.
+    ///
+    /// Output dimensions and crop offsets are kept even so chroma-subsampled
+    /// formats stay aligned.
+    pub fn resolve(&self, source_width: u32, source_height: u32) -> ScaleGeometry {
+        let full = ScaleGeometry {
+            crop_x: 0,
+            crop_y: 0,
+            crop_width: source_width,
+            crop_height: source_height,
+            width: source_width,
+            height: source_height,
+        };
+        if source_width == 0 || source_height == 0 {
+            return full;
+        }
+
+        let even = |v: f64| ((v / 2.0).round() as u32 * 2).max(2);
+        let sw = source_width as f64;
+        let sh = source_height as f64;
+
+        match (self.width, self.height) {
+            (0, 0) => full,
+            (w, 0) => ScaleGeometry {
+                width: even(w as f64),
+                height: even(w as f64 * sh / sw),
+                ..full
+            },
+            (0, h) => ScaleGeometry {
+                width: even(h as f64 * sw / sh),
+                height: even(h as f64),
+                ..full
+            },
+            (w, h) => {
+                let (tw, th) = (w as f64, h as f64);
+                match self.mode {
+                    ScaleMode::Stretch => ScaleGeometry {
+                        width: even(tw),
+                        height: even(th),
+                        ..full
+                    },
+                    ScaleMode::Fit => {
+                        let scale = (tw / sw).min(th / sh);
+                        ScaleGeometry {
+                            width: even(sw * scale),
+                            height: even(sh * scale),
+                            ..full
+                        }
+                    }
+                    ScaleMode::Fill => {
+                        let scale = (tw / sw).max(th / sh);
+                        let crop_width = even(tw / scale).min(source_width);
+                        let crop_height = even(th / scale).min(source_height);
+                        ScaleGeometry {
+                            crop_x: ((source_width - crop_width) / 2) & !1,
+                            crop_y: ((source_height - crop_height) / 2) & !1,
+                            crop_width,
+                            crop_height,
+                            width: even(tw),
+                            height: even(th),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Stream disposition flags usable for track selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StreamDisposition {
+    /// Track flagged as default
+    Default = 0,
+    /// Original language track
+    Original = 1,
+    /// Dubbed track
+    Dub = 2,
+    /// Commentary track
+    Commentary = 3,
+    /// Forced track
+    Forced = 4,
+    /// Track for hearing impaired viewers
+    HearingImpaired = 5,
+    /// Audio description for visually impaired viewers
+    VisualImpaired = 6,
+}
+
+/// How a video or audio stream is chosen when the file is opened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamSelector {
+    /// Let FFmpeg pick the "best" stream
+    Best,
+    /// Absolute stream index in the container
+    Index(usize),
+    /// First stream tagged with this language (e.g., "eng", "jpn")
+    Language(String),
+    /// First stream carrying this disposition flag
+    Disposition(StreamDisposition),
+}
+
+impl Default for StreamSelector {
+    fn default() -> Self {
+        Self::Best
+    }
+}
+
+/// How multichannel audio is mixed down to fewer output channels.
+///
+/// The ITU modes apply the BS.775 gains unscaled when mixing down to mono or
+/// stereo, so loud integer output can clip; other output layouts use
+/// FFmpeg's rematrixing at the same levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DownmixMode {
+    /// FFmpeg's default rematrixing (scaled down for integer output so that
+    /// the sums cannot clip)
+    Default = 0,
+    /// ITU-R BS.775: center and surrounds at -3 dB, LFE dropped
+    Itu = 1,
+    /// ITU-R BS.775 with the LFE channel mixed in at -3 dB
+    ItuWithLfe = 2,
+}
+
+impl Default for DownmixMode {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+/// Audio output format produced by the resampler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioOutputConfig {
+    /// Output sample rate in Hz (0 = keep source rate)
+    pub sample_rate: u32,
+
+    /// Output channel count (0 = keep source channel count)
+    pub channels: u32,
+
+    /// Output channel layout as an FFmpeg channel mask (0 = default layout
+    /// for the channel count). Overrides `channels` when set.
+    pub channel_layout: u64,
+
+    /// Output sample format
+    pub sample_format: SampleFormat,
+
+    /// Output planar (one block per channel) instead of interleaved samples
+    pub planar: bool,
+
+    /// Downmix matrix used when the output has fewer channels than the source
+    pub downmix: DownmixMode,
+}
+
+impl Default for AudioOutputConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            channels: 2,
+            channel_layout: 0,
+            sample_format: SampleFormat::Float32,
+            planar: false,
+            downmix: DownmixMode::Default,
+        }
+    }
+}
+
+impl AudioOutputConfig {
+    /// Keep the source sample rate and all source channels (no rematrixing)
+    pub fn passthrough() -> Self {
+        Self {
+            sample_rate: 0,
+            channels: 0,
+            ..Self::default()
+        }
+    }
+}
+
 /// Decoder configuration
 #[derive(Debug, Clone)]
 pub struct DecoderConfig {
@@ -41,6 +424,45 @@ pub struct DecoderConfig {
 
     /// Output pixel format
     pub output_pixel_format: PixelFormat,
+
+    /// Output frame size (None = source size)
+    pub output_size: Option<OutputSize>,
+
+    /// Scaling algorithm for pixel format conversion and resizing
+    pub scaling_algorithm: ScalingAlgorithm,
+
+    /// Matrix coefficients of YUV output (`color::matrix` code point, None = source matrix)
+    pub output_color_matrix: Option<u8>,
+
+    /// Range of YUV output (None = source range; RGB output is always full range)
+    pub output_color_range: Option<ColorRange>,
+
+    /// HDR→SDR tone mapping (disabled by default)
+    pub tone_mapping: ToneMapConfig,
+
+    /// Rotate frames upright and correct non-square pixels during conversion
+    pub apply_orientation: bool,
+
+    /// Deinterlacing of frames flagged as interlaced (off by default)
+    pub deinterlace: DeinterlaceMode,
+
+    /// libavfilter graph applied to decoded video frames (e.g. `"crop=1280:720,hflip"`)
+    pub video_filter: Option<String>,
+
+    /// libavfilter graph applied to decoded audio frames (e.g. `"volume=0.5"`)
+    pub audio_filter: Option<String>,
+
+    /// Video stream selection
+    pub video_stream: StreamSelector,
+
+    /// Audio stream selection
+    pub audio_stream: StreamSelector,
+
+    /// Subtitle stream selection
+    pub subtitle_stream: StreamSelector,
+
+    /// Audio output format
+    pub audio_output: AudioOutputConfig,
 }
 
 impl Default for DecoderConfig {
@@ -53,6 +475,19 @@ impl Default for DecoderConfig {
             enable_prefetch: true,
             thread_count: 0,
             output_pixel_format: PixelFormat::Bgra,
+            output_size: None,
+            scaling_algorithm: ScalingAlgorithm::Bilinear,
+            output_color_matrix: None,
+            output_color_range: None,
+            tone_mapping: ToneMapConfig::default(),
+            apply_orientation: false,
+            deinterlace: DeinterlaceMode::None,
+            video_filter: None,
+            audio_filter: None,
+            video_stream: StreamSelector::Best,
+            audio_stream: StreamSelector::Best,
+            subtitle_stream: StreamSelector::Best,
+            audio_output: AudioOutputConfig::default(),
         }
     }
 }
@@ -68,6 +503,19 @@ impl DecoderConfig {
             enable_prefetch: true,
             thread_count: 0,
             output_pixel_format: PixelFormat::Bgra,
+            output_size: None,
+            scaling_algorithm: ScalingAlgorithm::Bilinear,
+            output_color_matrix: None,
+            output_color_range: None,
+            tone_mapping: ToneMapConfig::default(),
+            apply_orientation: false,
+            deinterlace: DeinterlaceMode::None,
+            video_filter: None,
+            audio_filter: None,
+            video_stream: StreamSelector::Best,
+            audio_stream: StreamSelector::Best,
+            subtitle_stream: StreamSelector::Best,
+            audio_output: AudioOutputConfig::default(),
         }
     }
 
@@ -81,6 +529,19 @@ impl DecoderConfig {
             enable_prefetch: false,
             thread_count: 2,
             output_pixel_format: PixelFormat::Nv12,
+            output_size: None,
+            scaling_algorithm: ScalingAlgorithm::Bilinear,
+            output_color_matrix: None,
+            output_color_range: None,
+            tone_mapping: ToneMapConfig::default(),
+            apply_orientation: false,
+            deinterlace: DeinterlaceMode::None,
+            video_filter: None,
+            audio_filter: None,
+            video_stream: StreamSelector::Best,
+            audio_stream: StreamSelector::Best,
+            subtitle_stream: StreamSelector::Best,
+            audio_output: AudioOutputConfig::default(),
         }
     }
 
@@ -94,6 +555,19 @@ impl DecoderConfig {
             enable_prefetch: true,
             thread_count: 0,
             output_pixel_format: PixelFormat::Bgra,
+            output_size: None,
+            scaling_algorithm: ScalingAlgorithm::Bilinear,
+            output_color_matrix: None,
+            output_color_range: None,
+            tone_mapping: ToneMapConfig::default(),
+            apply_orientation: false,
+            deinterlace: DeinterlaceMode::None,
+            video_filter: None,
+            audio_filter: None,
+            video_stream: StreamSelector::Best,
+            audio_stream: StreamSelector::Best,
+            subtitle_stream: StreamSelector::Best,
+            audio_output: AudioOutputConfig::default(),
         }
     }
 }
@@ -118,4 +592,44 @@ mod tests {
         let low = DecoderConfig::low_memory();
         assert!(!low.enable_prefetch);
     }
+
+    #[test]
+    fn test_default_stream_selection() {
+        let config = DecoderConfig::default();
+        assert_eq!(config.video_stream, StreamSelector::Best);
+        assert_eq!(config.audio_stream, StreamSelector::Best);
+    }
+
+    #[test]
+    fn test_output_size_resolve() {
+        // 16:9 into a square box
+        let fit = OutputSize::new(320, 320, ScaleMode::Fit).resolve(1920, 1080);
+        assert_eq!((fit.width, fit.height), (320, 180));
+        assert_eq!((fit.crop_width, fit.crop_height), (1920, 1080));
+
+        let fill = OutputSize::new(320, 320, ScaleMode::Fill).resolve(1920, 1080);
+        assert_eq!((fill.width, fill.height), (320, 320));
+        assert_eq!((fill.crop_x, fill.crop_y), (420, 0));
+        assert_eq!((fill.crop_width, fill.crop_height), (1080, 1080));
+
+        let stretch = OutputSize::new(320, 320, ScaleMode::Stretch).resolve(1920, 1080);
+        assert_eq!((stretch.width, stretch.height), (320, 320));
+
+        let by_width = OutputSize::new(640, 0, ScaleMode::Fit).resolve(1920, 1080);
+        assert_eq!((by_width.width, by_width.height), (640, 360));
+    }
+
+    #[test]
+    fn test_default_audio_output() {
+        let output = DecoderConfig::default().audio_output;
+        assert_eq!(output.sample_rate, 48000);
+        assert_eq!(output.channels, 2);
+        assert_eq!(output.sample_format, SampleFormat::Float32);
+        assert!(!output.planar);
+        assert_eq!(output.downmix, DownmixMode::Default);
+
+        let passthrough = AudioOutputConfig::passthrough();
+        assert_eq!(passthrough.channels, 0);
+        assert_eq!(passthrough.sample_rate, 0);
+    }
 }
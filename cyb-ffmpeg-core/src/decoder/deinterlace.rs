@@ -0,0 +1,406 @@
+//! Software deinterlacing
+//!
+//! Deinterlacers work plane by plane on raw sample rows, so they apply to
+//! planar and semi-planar YUV of any bit depth (8-bit or 16-bit samples).
+//! Interleaved chroma pairs are treated like independent samples, which is
+//! exact for vertical interpolation.
+
+/// Deinterlacing applied to frames flagged as interlaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DeinterlaceMode {
+    /// Deliver interlaced frames unchanged
+    None = 0,
+    /// Output each field as its own frame (double rate), interpolating the missing lines
+    Bob = 1,
+    /// Motion-adaptive, edge-directed interpolation at frame rate (yadif-style)
+    Yadif = 2,
+}
+
+impl Default for DeinterlaceMode {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// Field order of a video stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FieldOrder {
+    /// Not signalled
+    Unknown = 0,
+    /// Progressive frames
+    Progressive = 1,
+    /// Top field coded and displayed first
+    TopFirst = 2,
+    /// Bottom field coded and displayed first
+    BottomFirst = 3,
+    /// Top field coded first, bottom field displayed first
+    TopCodedBottomFirst = 4,
+    /// Bottom field coded first, top field displayed first
+    BottomCodedTopFirst = 5,
+}
+
+impl Default for FieldOrder {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
+impl FieldOrder {
+    /// Convert from FFmpeg's AVFieldOrder value
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            1 => FieldOrder::Progressive,
+            2 => FieldOrder::TopFirst,
+            3 => FieldOrder::BottomFirst,
+            4 => FieldOrder::TopCodedBottomFirst,
+            5 => FieldOrder::BottomCodedTopFirst,
+            _ => FieldOrder::Unknown,
+        }
+    }
+
+    /// Check if the stream is interlaced
+    pub fn is_interlaced(&self) -> bool {
+        !matches!(self, FieldOrder::Unknown | FieldOrder::Progressive)
+    }
+}
+
+/// Sample rows of a source plane
+#[derive(Clone, Copy)]
+pub(crate) struct Plane<'a> {
+    /// Sample data
+    pub data: &'a [u8],
+    /// Bytes per row
+    pub stride: usize,
+}
+
+/// Sample rows of a destination plane
+pub(crate) struct PlaneMut<'a> {
+    /// Sample data
+    pub data: &'a mut [u8],
+    /// Bytes per row
+    pub stride: usize,
+}
+
+/// Sample storage (8-bit, or 16-bit little endian)
+trait Sample {
+    const SIZE: usize;
+    fn load(data: &[u8], offset: usize) -> i32;
+    fn store(data: &mut [u8], offset: usize, value: i32);
+}
+
+struct U8;
+struct U16;
+
+impl Sample for U8 {
+    const SIZE: usize = 1;
+
+    #[inline]
+    fn load(data: &[u8], offset: usize) -> i32 {
+        data[offset] as i32
+    }
+
+    #[inline]
+    fn store(data: &mut [u8], offset: usize, value: i32) {
+        data[offset] = value as u8;
+    }
+}
+
+impl Sample for U16 {
+    const SIZE: usize = 2;
+
+    #[inline]
+    fn load(data: &[u8], offset: usize) -> i32 {
+        u16::from_le_bytes([data[offset], data[offset + 1]]) as i32
+    }
+
+    #[inline]
+    fn store(data: &mut [u8], offset: usize, value: i32) {
+        data[offset..offset + 2].copy_from_slice(&(value as u16).to_le_bytes());
+    }
+}
+
+impl Plane<'_> {
+    #[inline]
+    fn at<S: Sample>(&self, row: usize, column: usize) -> i32 {
+        S::load(self.data, row * self.stride + column * S::SIZE)
+    }
+
+    fn row(&self, row: usize, row_bytes: usize) -> &[u8] {
+        &self.data[row * self.stride..row * self.stride + row_bytes]
+    }
+}
+
+impl PlaneMut<'_> {
+    fn copy_row(&mut self, row: usize, source: &[u8]) {
+        let start = row * self.stride;
+        self.data[start..start + source.len()].copy_from_slice(source);
+    }
+}
+
+/// Rows of the kept field around missing row `y` (mirrored at the edges)
+fn neighbours(y: usize, rows: usize) -> (usize, usize) {
+    let above = if y > 0 { y - 1 } else { y + 1 };
+    let below = if y + 1 < rows { y + 1 } else { y - 1 };
+    (above, below)
+}
+
+/// Whether row `y` belongs to the kept field
+fn is_kept(y: usize, keep_top: bool) -> bool {
+    (y % 2 == 0) == keep_top
+}
+
+/// Keep one field and interpolate the other field's lines linearly (bob).
+///
+/// `keep_top` keeps the even rows; `row_bytes` is the used width of a row.
+pub(crate) fn bob_plane(
+    src: Plane,
+    dst: PlaneMut,
+    row_bytes: usize,
+    rows: usize,
+    sample_size: usize,
+    keep_top: bool,
+) {
+    if sample_size == 2 {
+        bob::<U16>(src, dst, row_bytes, rows, keep_top);
+    } else {
+        bob::<U8>(src, dst, row_bytes, rows, keep_top);
+    }
+}
+
+fn bob<S: Sample>(src: Plane, mut dst: PlaneMut, row_bytes: usize, rows: usize, keep_top: bool) {
+    let width = row_bytes / S::SIZE;
+    for y in 0..rows {
+        if rows < 2 || is_kept(y, keep_top) {
+            dst.copy_row(y, src.row(y, row_bytes));
+            continue;
+        }
+
+        let (above, below) = neighbours(y, rows);
+        for x in 0..width {
+            let value = (src.at::<S>(above, x) + src.at::<S>(below, x) + 1) >> 1;
+            S::store(dst.data, y * dst.stride + x * S::SIZE, value);
+        }
+    }
+}
+
+/// Keep one field and rebuild the other from the previous frame where the
+/// picture is static, falling back to edge-directed spatial interpolation
+/// where it moves (yadif-style, without a lookahead frame).
+///
+/// The kept field lies in time between the previous frame's second field and
+/// the current frame's second field, so their average is the temporal prediction.
+pub(crate) fn yadif_plane(
+    cur: Plane,
+    prev: Plane,
+    dst: PlaneMut,
+    row_bytes: usize,
+    rows: usize,
+    sample_size: usize,
+    keep_top: bool,
+) {
+    if sample_size == 2 {
+        yadif::<U16>(cur, prev, dst, row_bytes, rows, keep_top);
+    } else {
+        yadif::<U8>(cur, prev, dst, row_bytes, rows, keep_top);
+    }
+}
+
+fn yadif<S: Sample>(
+    cur: Plane,
+    prev: Plane,
+    mut dst: PlaneMut,
+    row_bytes: usize,
+    rows: usize,
+    keep_top: bool,
+) {
+    let width = row_bytes / S::SIZE;
+    if width == 0 {
+        return;
+    }
+    let column =
+        |x: usize, offset: isize| (x as isize + offset).clamp(0, width as isize - 1) as usize;
+
+    for y in 0..rows {
+        if rows < 3 || is_kept(y, keep_top) {
+            dst.copy_row(y, cur.row(y, row_bytes));
+            continue;
+        }
+
+        let (up, down) = neighbours(y, rows);
+        // Nearest rows of the missing field, for the spatial interlacing check
+        let up2 = if y >= 2 { y - 2 } else { y };
+        let down2 = if y + 2 < rows { y + 2 } else { y };
+
+        for x in 0..width {
+            let c = cur.at::<S>(up, x);
+            let e = cur.at::<S>(down, x);
+
+            // Temporal prediction and how much the picture moves
+            let before = prev.at::<S>(y, x);
+            let after = cur.at::<S>(y, x);
+            let d = (before + after) >> 1;
+            let temporal_diff0 = (before - after).abs();
+            let temporal_diff1 =
+                ((prev.at::<S>(up, x) - c).abs() + (prev.at::<S>(down, x) - e).abs()) >> 1;
+            let mut diff = (temporal_diff0 >> 1).max(temporal_diff1);
+
+            // Edge-directed spatial prediction
+            let mut spatial = (c + e) >> 1;
+            let mut score = (cur.at::<S>(up, column(x, -1)) - cur.at::<S>(down, column(x, -1)))
+                .abs()
+                + (c - e).abs()
+                + (cur.at::<S>(up, column(x, 1)) - cur.at::<S>(down, column(x, 1))).abs();
+            for direction in [-1isize, 1] {
+                let direction_score = (-1..=1)
+                    .map(|i| {
+                        (cur.at::<S>(up, column(x, i + direction))
+                            - cur.at::<S>(down, column(x, i - direction)))
+                        .abs()
+                    })
+                    .sum::<i32>();
+                if direction_score < score {
+                    score = direction_score;
+                    spatial = (cur.at::<S>(up, column(x, direction))
+                        + cur.at::<S>(down, column(x, -direction)))
+                        >> 1;
+                }
+            }
+
+            // Allow more spatial freedom where the missing field looks combed
+            let b = (prev.at::<S>(up2, x) + cur.at::<S>(up2, x)) >> 1;
+            let f = (prev.at::<S>(down2, x) + cur.at::<S>(down2, x)) >> 1;
+            let max = (d - e).max(d - c).max((b - c).min(f - e));
+            let min = (d - e).min(d - c).min((b - c).max(f - e));
+            diff = diff.max(min).max(-max);
+
+            let value = spatial.clamp(d - diff, d + diff);
+            S::store(dst.data, y * dst.stride + x * S::SIZE, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 8-bit plane with `width` samples per row, each row filled by `row_value`
+    fn plane(width: usize, rows: usize, row_value: impl Fn(usize) -> u8) -> Vec<u8> {
+        (0..rows).flat_map(|y| vec![row_value(y); width]).collect()
+    }
+
+    #[test]
+    fn test_field_order() {
+        assert_eq!(FieldOrder::from_raw(2), FieldOrder::TopFirst);
+        assert_eq!(FieldOrder::from_raw(9), FieldOrder::Unknown);
+        assert!(FieldOrder::BottomCodedTopFirst.is_interlaced());
+        assert!(!FieldOrder::Progressive.is_interlaced());
+    }
+
+    #[test]
+    fn test_bob() {
+        // Combed 8-bit plane: top field 200, bottom field 0
+        let src = plane(4, 4, |y| if y % 2 == 0 { 200 } else { 0 });
+        let mut dst = vec![0u8; 16];
+        let source = Plane {
+            data: &src,
+            stride: 4,
+        };
+        bob_plane(
+            source,
+            PlaneMut {
+                data: &mut dst,
+                stride: 4,
+            },
+            4,
+            4,
+            1,
+            true,
+        );
+        assert!(dst.iter().all(|&v| v == 200));
+
+        bob_plane(
+            source,
+            PlaneMut {
+                data: &mut dst,
+                stride: 4,
+            },
+            4,
+            4,
+            1,
+            false,
+        );
+        assert!(dst.iter().all(|&v| v == 0));
+
+        // 16-bit samples average as values, not bytes
+        let src: Vec<u8> = [0x0100u16, 0x0300, 0x0100]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let mut dst = vec![0u8; 6];
+        bob_plane(
+            Plane {
+                data: &src,
+                stride: 2,
+            },
+            PlaneMut {
+                data: &mut dst,
+                stride: 2,
+            },
+            2,
+            3,
+            2,
+            true,
+        );
+        assert_eq!(u16::from_le_bytes([dst[2], dst[3]]), 0x0100);
+    }
+
+    #[test]
+    fn test_yadif() {
+        // Static progressive content passes through unchanged
+        let ramp = plane(6, 7, |y| (y * 10) as u8);
+        let mut dst = vec![0u8; ramp.len()];
+        let frame = Plane {
+            data: &ramp,
+            stride: 6,
+        };
+        yadif_plane(
+            frame,
+            frame,
+            PlaneMut {
+                data: &mut dst,
+                stride: 6,
+            },
+            6,
+            7,
+            1,
+            true,
+        );
+        assert_eq!(dst, ramp);
+
+        // Motion: the stale bottom field is replaced by interpolation
+        let cur = plane(6, 8, |y| if y % 2 == 0 { 200 } else { 0 });
+        let prev = plane(6, 8, |_| 0);
+        let mut dst = vec![0u8; cur.len()];
+        yadif_plane(
+            Plane {
+                data: &cur,
+                stride: 6,
+            },
+            Plane {
+                data: &prev,
+                stride: 6,
+            },
+            PlaneMut {
+                data: &mut dst,
+                stride: 6,
+            },
+            6,
+            8,
+            1,
+            true,
+        );
+        assert!(dst.iter().all(|&v| v == 200));
+    }
+}
@@ -0,0 +1,283 @@
+//! Lossless export (stream copy) of a time range to a new file
+//!
+//! Packets are copied without re-encoding, so a cut can only start at a video
+//! keyframe: the in point snaps back to the keyframe that starts its GOP and
+//! the out point forward to the next keyframe. Timestamps of the copy start
+//! at zero.
+
+use std::path::Path;
+
+/// Container of an exported file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExportFormat {
+    /// MPEG-4 (.mp4, .m4v, .m4a)
+    Mp4 = 0,
+    /// QuickTime (.mov)
+    Mov = 1,
+    /// Matroska (.mkv, .mka)
+    Mkv = 2,
+}
+
+impl ExportFormat {
+    /// Guess the container from a file extension
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "mp4" | "m4v" | "m4a" => Some(Self::Mp4),
+            "mov" | "qt" => Some(Self::Mov),
+            "mkv" | "mka" | "mks" => Some(Self::Mkv),
+            _ => None,
+        }
+    }
+
+    /// FFmpeg muxer name
+    pub fn muxer_name(&self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Mov => "mov",
+            Self::Mkv => "matroska",
+        }
+    }
+
+    /// Whether the container is from the ISO base media family (MP4/MOV)
+    pub fn is_iso_bmff(&self) -> bool {
+        matches!(self, Self::Mp4 | Self::Mov)
+    }
+}
+
+/// What to export and how
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// In point in microseconds (snaps back to the preceding video keyframe)
+    pub start_us: i64,
+
+    /// Out point in microseconds (snaps forward to the next video keyframe;
+    /// None = end of media)
+    pub end_us: Option<i64>,
+
+    /// Stream indices to copy (empty = every video, audio and subtitle track
+    /// the container can hold)
+    pub tracks: Vec<usize>,
+
+    /// Output container (None = from the file extension)
+    pub format: Option<ExportFormat>,
+
+    /// Copy container and per-track metadata (title, language, ...)
+    pub copy_metadata: bool,
+
+    /// Copy the chapters overlapping the range
+    pub copy_chapters: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            start_us: 0,
+            end_us: None,
+            tracks: Vec::new(),
+            format: None,
+            copy_metadata: true,
+            copy_chapters: true,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Export the range from `start_us` to `end_us` with all tracks
+    pub fn range(start_us: i64, end_us: i64) -> Self {
+        Self {
+            start_us,
+            end_us: Some(end_us),
+            ..Self::default()
+        }
+    }
+}
+
+/// Outcome of a finished export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    /// Source time the copy starts at, after keyframe snapping
+    pub start_us: i64,
+
+    /// Source time the copy ends at, after keyframe snapping
+    pub end_us: i64,
+
+    /// Source stream indices that were copied, in output order
+    pub tracks: Vec<usize>,
+
+    /// Number of packets written
+    pub packet_count: u64,
+
+    /// Payload bytes written
+    pub byte_count: u64,
+}
+
+impl ExportSummary {
+    /// Duration of the exported file in microseconds
+    pub fn duration_us(&self) -> i64 {
+        self.end_us - self.start_us
+    }
+}
+
+/// Where the copied tracks end.
+///
+/// Video ends before the first keyframe at or after the out point and the
+/// other tracks end at that same cut. Unless the keyframe index already
+/// located it, their packets past the out point are held back until the
+/// video track reaches it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ExportCut {
+    start_us: i64,
+    out_point_us: i64,
+    cut_us: Option<i64>,
+}
+
+/// What to do with a packet of a copied track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CutAction {
+    /// Write it to the output
+    Copy,
+    /// Keep it until the cut is known
+    Hold,
+    /// The track is past the cut
+    End,
+}
+
+impl ExportCut {
+    /// Cut for the range `start_us..out_point_us`, where `keyframe_end_us` is
+    /// the keyframe the index found at or after the out point
+    pub(crate) fn new(
+        start_us: i64,
+        out_point_us: Option<i64>,
+        keyframe_end_us: Option<i64>,
+        has_video: bool,
+    ) -> Self {
+        let cut_us = match out_point_us {
+            Some(_) if has_video => keyframe_end_us,
+            out_point_us => Some(out_point_us.unwrap_or(i64::MAX)),
+        };
+        Self {
+            start_us,
+            out_point_us: out_point_us.unwrap_or(i64::MAX),
+            cut_us,
+        }
+    }
+
+    /// Action for a video packet; the first keyframe at or after the out
+    /// point ends the copy
+    pub(crate) fn video(&mut self, is_key: bool, timestamp_us: i64) -> CutAction {
+        if is_key && timestamp_us >= self.out_point_us && timestamp_us > self.start_us {
+            self.cut_us.get_or_insert(timestamp_us);
+            CutAction::End
+        } else {
+            CutAction::Copy
+        }
+    }
+
+    /// Action for a packet of any other track
+    pub(crate) fn other(&self, timestamp_us: i64) -> CutAction {
+        match self.cut_us {
+            Some(cut_us) if timestamp_us >= cut_us => CutAction::End,
+            Some(_) => CutAction::Copy,
+            None if timestamp_us >= self.out_point_us => CutAction::Hold,
+            None => CutAction::Copy,
+        }
+    }
+
+    /// Whether the cut is known, so held packets can be decided
+    pub(crate) fn is_known(&self) -> bool {
+        self.cut_us.is_some()
+    }
+
+    /// Video ended without a keyframe past the out point: keep everything
+    pub(crate) fn finish(&mut self) {
+        self.cut_us.get_or_insert(i64::MAX);
+    }
+}
+
+/// Fraction of the range `start_us..end_us` done at `position_us` (0.0-1.0)
+pub(crate) fn export_progress(position_us: i64, start_us: i64, end_us: i64) -> f64 {
+    if end_us <= start_us {
+        return 0.0;
+    }
+    ((position_us - start_us) as f64 / (end_us - start_us) as f64).clamp(0.0, 1.0)
+}
+
+/// Clip a chapter to the exported range and shift it to start at zero.
+///
+/// Returns None if the chapter lies outside the range.
+pub(crate) fn clip_chapter(
+    chapter_start_us: i64,
+    chapter_end_us: i64,
+    start_us: i64,
+    end_us: i64,
+) -> Option<(i64, i64)> {
+    let start = chapter_start_us.max(start_us);
+    let end = chapter_end_us.min(end_us);
+    (end > start).then_some((start - start_us, end - start_us))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ExportFormat::from_path("/tmp/cut.MP4"),
+            Some(ExportFormat::Mp4)
+        );
+        assert_eq!(ExportFormat::from_path("clip.mov"), Some(ExportFormat::Mov));
+        assert_eq!(ExportFormat::from_path("show.mkv"), Some(ExportFormat::Mkv));
+        assert_eq!(ExportFormat::from_path("video.avi"), None);
+        assert_eq!(ExportFormat::from_path("noextension"), None);
+        assert_eq!(ExportFormat::Mkv.muxer_name(), "matroska");
+        assert!(ExportFormat::Mov.is_iso_bmff());
+    }
+
+    #[test]
+    fn test_range_helpers() {
+        assert_eq!(export_progress(5_000_000, 2_000_000, 8_000_000), 0.5);
+        assert_eq!(export_progress(0, 2_000_000, 8_000_000), 0.0);
+        assert_eq!(export_progress(9_000_000, 2_000_000, 8_000_000), 1.0);
+
+        // Chapter 1-5 s in a 2-8 s export becomes 0-3 s
+        assert_eq!(
+            clip_chapter(1_000_000, 5_000_000, 2_000_000, 8_000_000),
+            Some((0, 3_000_000))
+        );
+        assert_eq!(
+            clip_chapter(8_000_000, 9_000_000, 2_000_000, 8_000_000),
+            None
+        );
+    }
+
+    #[test]
+    fn test_cut_without_index() {
+        // Out point at 5 s, next video keyframe at 6 s
+        let mut cut = ExportCut::new(0, Some(5_000_000), None, true);
+        assert_eq!(cut.other(4_900_000), CutAction::Copy);
+        assert_eq!(cut.other(5_100_000), CutAction::Hold);
+        assert_eq!(cut.video(false, 5_500_000), CutAction::Copy);
+        assert!(!cut.is_known());
+        assert_eq!(cut.video(true, 6_000_000), CutAction::End);
+        // Audio ends with video, not at the out point
+        assert_eq!(cut.other(5_100_000), CutAction::Copy);
+        assert_eq!(cut.other(6_000_000), CutAction::End);
+
+        // The index gives the same cut up front
+        let cut = ExportCut::new(0, Some(5_000_000), Some(6_000_000), true);
+        assert_eq!(cut.other(5_100_000), CutAction::Copy);
+        assert_eq!(cut.other(6_000_000), CutAction::End);
+
+        // Without video the out point is exact
+        let cut = ExportCut::new(0, Some(5_000_000), None, false);
+        assert_eq!(cut.other(5_000_000), CutAction::End);
+
+        // A stream ending before the next keyframe keeps the held packets
+        let mut cut = ExportCut::new(0, Some(5_000_000), None, true);
+        cut.finish();
+        assert_eq!(cut.other(5_100_000), CutAction::Copy);
+    }
+}
@@ -13,12 +13,37 @@ use ffmpeg_next::software::resampling::Context as ResamplerContext;
 use ffmpeg_next::software::scaling::{Context as ScalerContext, Flags as ScalerFlags};
 use ffmpeg_next::util::frame::audio::Audio as AudioFrameFFmpeg;
 use ffmpeg_next::util::frame::video::Video as VideoFrameFFmpeg;
-use ffmpeg_next::Rational;
-
-use super::audio_frame::AudioFrame;
-use super::config::{DecoderConfig, PixelFormat};
+use ffmpeg_next::{Rational, Rescale};
+
+use super::audio_frame::{AudioFrame, AudioSamples, SampleFormat};
+use super::caption::{CaptionChannel, CaptionDecoder};
+use super::color::{matrix, primaries, transfer, ChromaLocation, ColorInfo, ColorRange};
+use super::config::{
+    AudioOutputConfig, DecoderConfig, DownmixMode, OutputSize, PixelFormat, ScaleGeometry,
+    ScalingAlgorithm, StreamDisposition, StreamSelector,
+};
+use super::deinterlace::{bob_plane, yadif_plane, DeinterlaceMode, FieldOrder, Plane, PlaneMut};
+use super::export::{
+    clip_chapter, export_progress, CutAction, ExportCut, ExportFormat, ExportOptions, ExportSummary,
+};
+use super::filter::{FilterGraph, FilterInput};
 use super::frame::VideoFrame;
-use super::info::{AudioTrack, CodecInfo, MediaInfo, VideoTrack};
+use super::hdr::{ContentLightLevel, HdrMetadata, MasteringDisplay, ToneMapConfig, ToneMapper};
+use super::info::{
+    Attachment, AttachmentData, AttachmentKind, AudioTrack, Chapter, CodecInfo, MediaInfo,
+    SubtitleTrack, VideoTrack,
+};
+use super::loudness::{LoudnessMeter, LoudnessOptions, LoudnessPoint, LoudnessReport};
+use super::packet::Packet;
+use super::scene::{SceneCut, SceneDetectionOptions, SceneDetector};
+use super::source::{open_input, CustomIo, MediaSource};
+use super::subtitle::{
+    ass_dialogue_to_text, SubtitleBitmap, SubtitleBuffer, SubtitleEvent, SubtitleKind,
+    OPEN_END_US,
+};
+use super::thumbnail::{thumbnail_times, Thumbnail, ThumbnailOptions};
+use super::timecode::Timecode;
+use super::waveform::{Waveform, WaveformBuilder, WaveformOptions};
 use crate::error::{Error, Result};
 
 /// FFmpeg decoder context
@@ -32,29 +57,129 @@ pub struct FFmpegContext {
     /// Audio stream index
     audio_stream_index: Option<usize>,
 
+    /// Subtitle stream index
+    subtitle_stream_index: Option<usize>,
+
     /// Video decoder
     video_decoder: Option<ffmpeg::decoder::Video>,
 
     /// Audio decoder
     audio_decoder: Option<ffmpeg::decoder::Audio>,
 
-    /// Scaler for pixel format conversion
+    /// Subtitle decoder
+    subtitle_decoder: Option<ffmpeg::decoder::Subtitle>,
+
+    /// Whether the subtitle codec is ASS/SSA (raw dialogue is kept)
+    subtitle_is_ass: bool,
+
+    /// Decoded subtitle events for the active subtitle stream
+    subtitles: SubtitleBuffer,
+
+    /// Closed captions carried in the video stream
+    captions: CaptionDecoder,
+
+    /// Scaler for pixel format conversion and resizing (created on demand)
     scaler: Option<ScalerContext>,
 
+    /// Input/output parameters the current scaler was built for
+    scaler_setup: Option<ScalerSetup>,
+
+    /// Requested output size (None = source size)
+    output_size: Option<OutputSize>,
+
+    /// Scaling algorithm
+    scaling_algorithm: ScalingAlgorithm,
+
+    /// Requested matrix coefficients of YUV output (None = source)
+    output_color_matrix: Option<u8>,
+
+    /// Requested range of YUV output (None = source)
+    output_color_range: Option<ColorRange>,
+
+    /// HDR→SDR tone mapping configuration
+    tone_mapping: ToneMapConfig,
+
+    /// Tone mapper for the current HDR source (created on demand)
+    tone_mapper: Option<ToneMapper>,
+
+    /// Transfer, primaries and peak luminance the tone mapper was built for
+    tone_mapper_key: Option<(u8, u8, Option<u32>)>,
+
+    /// Scaler from the tone-mapped RGBA64 intermediate to the target format
+    tone_map_scaler: Option<ScalerContext>,
+
+    /// Parameters the tone-map scaler was built for
+    tone_map_scaler_setup: Option<ScalerSetup>,
+
+    /// Static HDR metadata of the video stream
+    video_hdr: HdrMetadata,
+
+    /// Rotate frames upright and correct non-square pixels
+    apply_orientation: bool,
+
+    /// Clockwise rotation of the video stream's display matrix
+    video_rotation: u32,
+
+    /// Sample aspect ratio of the video stream (0/1 = unknown)
+    video_sample_aspect_ratio: Rational,
+
+    /// Frame rate of the video stream (timecodes count at this rate)
+    video_frame_rate: Rational,
+
+    /// Start time of the video stream in microseconds
+    video_start_us: i64,
+
+    /// Timecode of the first video frame
+    start_timecode: Option<Timecode>,
+
+    /// Timecode of a decoded frame and its pts in microseconds; later frames count on from it
+    timecode_anchor: Option<(Timecode, i64)>,
+
+    /// Total number of video frames (0 = unknown)
+    video_frame_count: i64,
+
+    /// Deinterlacing of interlaced frames
+    deinterlace: DeinterlaceMode,
+
+    /// Previous interlaced frame, the temporal reference of the yadif deinterlacer
+    deinterlace_previous: Option<VideoFrameFFmpeg>,
+
+    /// Frames produced but not yet returned (second field of bob deinterlacing,
+    /// several outputs of the filter graph)
+    pending_frames: VecDeque<VideoFrame>,
+
+    /// libavfilter description applied to decoded video frames
+    video_filter: Option<String>,
+
+    /// Video filter graph (built on demand, dropped on seek)
+    video_filter_graph: Option<FilterGraph>,
+
+    /// libavfilter description applied to decoded audio frames
+    audio_filter: Option<String>,
+
+    /// Audio filter graph (built on demand, dropped on seek)
+    audio_filter_graph: Option<FilterGraph>,
+
+    /// Audio frames produced but not yet returned
+    pending_audio_frames: VecDeque<AudioFrame>,
+
     /// Resampler for audio format conversion
     resampler: Option<ResamplerContext>,
 
     /// Target pixel format
     target_format: PixelFormat,
 
-    /// Target audio sample rate (default: 48000 Hz)
+    /// Requested audio output format
+    audio_output: AudioOutputConfig,
+
+    /// Resolved audio output sample rate
     target_sample_rate: u32,
 
-    /// Target audio channels (default: 2 = stereo)
+    /// Resolved audio output channel count
     target_channels: u32,
 
-    /// Frame counter
-    frame_number: i64,
+    /// Resolved audio output channel layout
+    target_channel_layout: ffmpeg::channel_layout::ChannelLayout,
 
     /// Audio frame counter
     audio_frame_number: i64,
@@ -65,6 +190,9 @@ pub struct FFmpegContext {
     /// Time base for audio stream
     audio_time_base: Rational,
 
+    /// Time base for subtitle stream
+    subtitle_time_base: Rational,
+
     /// Video duration in microseconds
     duration_us: i64,
 
@@ -94,6 +222,45 @@ pub struct FFmpegContext {
 
     /// Keyframe index for fast seeking (built during prepare)
     keyframe_index: Option<KeyframeIndex>,
+
+    /// Custom I/O for memory/stream sources (declared last so it outlives `input`)
+    _custom_io: Option<CustomIo>,
+}
+
+/// Layout of AVMasteringDisplayMetadata (libavutil/mastering_display_metadata.h
+/// is not part of the generated bindings)
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawMasteringDisplay {
+    display_primaries: [[ffmpeg::ffi::AVRational; 2]; 3],
+    white_point: [ffmpeg::ffi::AVRational; 2],
+    min_luminance: ffmpeg::ffi::AVRational,
+    max_luminance: ffmpeg::ffi::AVRational,
+    has_primaries: std::os::raw::c_int,
+    has_luminance: std::os::raw::c_int,
+}
+
+/// Layout of AVContentLightMetadata
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RawContentLightLevel {
+    max_cll: std::os::raw::c_uint,
+    max_fall: std::os::raw::c_uint,
+}
+
+/// Parameters a scaler context was created with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ScalerSetup {
+    pub(crate) format: ffmpeg::format::Pixel,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) out_format: ffmpeg::format::Pixel,
+    pub(crate) out_width: u32,
+    pub(crate) out_height: u32,
+    pub(crate) src_matrix: u8,
+    pub(crate) src_full_range: bool,
+    pub(crate) dst_matrix: u8,
+    pub(crate) dst_full_range: bool,
 }
 
 /// Keyframe index for fast seeking
@@ -179,52 +346,83 @@ impl KeyframeIndex {
 impl FFmpegContext {
     /// Create a new FFmpeg context
     pub fn new<P: AsRef<Path>>(path: P, config: &DecoderConfig) -> Result<Self> {
-        let path_ref = path.as_ref();
+        Self::from_source(&MediaSource::from_path(path), config)
+    }
 
+    /// Create a new FFmpeg context reading from any media source
+    pub fn from_source(source: &MediaSource, config: &DecoderConfig) -> Result<Self> {
         // Initialize FFmpeg (safe to call multiple times)
         ffmpeg::init().map_err(|e| Error::FFmpeg {
             code: -1,
             message: format!("FFmpeg init failed: {}", e),
         })?;
 
-        // Open input file
-        let input = ffmpeg::format::input(path_ref).map_err(|e| {
-            if e.to_string().contains("No such file") {
-                Error::FileNotFound(path_ref.to_path_buf())
-            } else {
-                Error::InvalidFormat(e.to_string())
-            }
-        })?;
+        // Open input (file or custom I/O)
+        let (input, custom_io) = open_input(source)?;
 
-        log::debug!("Opened file: {:?}", path_ref);
+        log::debug!("Opened input: {}", source);
 
         // Find video stream
-        let video_stream_index = input
-            .streams()
-            .best(MediaType::Video)
-            .map(|s| s.index());
+        let video_stream_index =
+            Self::select_stream(&input, MediaType::Video, &config.video_stream)?;
 
         // Find audio stream
-        let audio_stream_index = input
-            .streams()
-            .best(MediaType::Audio)
-            .map(|s| s.index());
+        let audio_stream_index =
+            Self::select_stream(&input, MediaType::Audio, &config.audio_stream)?;
+
+        // Find subtitle stream
+        let subtitle_stream_index =
+            Self::select_stream(&input, MediaType::Subtitle, &config.subtitle_stream)?;
 
         let mut ctx = Self {
             input,
             video_stream_index,
             audio_stream_index,
+            subtitle_stream_index: None,
             video_decoder: None,
             audio_decoder: None,
+            subtitle_decoder: None,
+            subtitle_is_ass: false,
+            subtitles: SubtitleBuffer::new(),
+            captions: CaptionDecoder::new(video_stream_index.map_or(-1, |index| index as i32)),
             scaler: None,
+            scaler_setup: None,
+            output_size: config.output_size,
+            scaling_algorithm: config.scaling_algorithm,
+            output_color_matrix: config.output_color_matrix,
+            output_color_range: config.output_color_range,
+            tone_mapping: config.tone_mapping,
+            tone_mapper: None,
+            tone_mapper_key: None,
+            tone_map_scaler: None,
+            tone_map_scaler_setup: None,
+            video_hdr: HdrMetadata::default(),
+            apply_orientation: config.apply_orientation,
+            video_rotation: 0,
+            video_sample_aspect_ratio: Rational::new(0, 1),
+            video_frame_rate: Rational::new(24, 1),
+            video_start_us: 0,
+            start_timecode: None,
+            timecode_anchor: None,
+            video_frame_count: 0,
+            deinterlace: config.deinterlace,
+            deinterlace_previous: None,
+            pending_frames: VecDeque::new(),
+            video_filter: config.video_filter.clone(),
+            video_filter_graph: None,
+            audio_filter: config.audio_filter.clone(),
+            audio_filter_graph: None,
+            pending_audio_frames: VecDeque::new(),
             resampler: None,
             target_format: config.output_pixel_format,
-            target_sample_rate: 48000, // Standard audio sample rate
-            target_channels: 2,        // Stereo
-            frame_number: 0,
+            audio_output: config.audio_output,
+            target_sample_rate: 0,
+            target_channels: 0,
+            target_channel_layout: ffmpeg::channel_layout::ChannelLayout::STEREO,
             audio_frame_number: 0,
             video_time_base: Rational::new(1, 1000000),
             audio_time_base: Rational::new(1, 1000000),
+            subtitle_time_base: Rational::new(1, 1000000),
             duration_us: 0,
             frame_rate: 0.0,
             width: 0,
@@ -235,6 +433,7 @@ impl FFmpegContext {
             audio_packet_queue: VecDeque::with_capacity(64),
             video_packet_queue: VecDeque::with_capacity(32),
             keyframe_index: None,
+            _custom_io: custom_io,
         };
 
         // Initialize video decoder if we have a video stream
@@ -247,9 +446,138 @@ impl FFmpegContext {
             ctx.init_audio_decoder(stream_idx)?;
         }
 
+        // Subtitles are optional - a missing decoder must not prevent playback
+        if let Some(stream_idx) = subtitle_stream_index {
+            match ctx.init_subtitle_decoder(stream_idx) {
+                Ok(()) => ctx.subtitle_stream_index = Some(stream_idx),
+                Err(e) => log::warn!("Subtitle stream {} disabled: {:?}", stream_idx, e),
+            }
+        }
+
         Ok(ctx)
     }
 
+    /// Resolve a stream selector to a stream index of the given media type.
+    ///
+    /// Explicit indices must refer to a stream of the right type. Language and
+    /// disposition selectors fall back to the best stream when nothing matches.
+    fn select_stream(
+        input: &FormatContext,
+        medium: MediaType,
+        selector: &StreamSelector,
+    ) -> Result<Option<usize>> {
+        // Attached pictures are video streams too, but not tracks
+        let is_track = |s: &ffmpeg::format::stream::Stream| {
+            s.parameters().medium() == medium && !Self::is_attached_picture(s)
+        };
+        let best = || match input.streams().best(medium) {
+            Some(stream) if !is_track(&stream) => {
+                input.streams().find(|s| is_track(s)).map(|s| s.index())
+            }
+            stream => stream.map(|s| s.index()),
+        };
+
+        match selector {
+            StreamSelector::Best => Ok(best()),
+            StreamSelector::Index(index) => match input.stream(*index) {
+                Some(stream) if stream.parameters().medium() == medium => Ok(Some(*index)),
+                Some(_) => Err(Error::InvalidFormat(format!(
+                    "Stream {} is not a {:?} stream",
+                    index, medium
+                ))),
+                None => Err(Error::InvalidFormat(format!("Stream {} not found", index))),
+            },
+            StreamSelector::Language(language) => {
+                let found = input
+                    .streams()
+                    .filter(|s| is_track(s))
+                    .find(|s| {
+                        s.metadata()
+                            .get("language")
+                            .map(|l| l.eq_ignore_ascii_case(language))
+                            .unwrap_or(false)
+                    })
+                    .map(|s| s.index());
+
+                if found.is_none() {
+                    log::warn!(
+                        "No {:?} stream with language '{}', using best stream",
+                        medium,
+                        language
+                    );
+                }
+                Ok(found.or_else(best))
+            }
+            StreamSelector::Disposition(disposition) => {
+                let flag = Self::disposition_to_ffmpeg(*disposition);
+                let found = input
+                    .streams()
+                    .filter(|s| is_track(s))
+                    .find(|s| s.disposition().contains(flag))
+                    .map(|s| s.index());
+
+                if found.is_none() {
+                    log::warn!(
+                        "No {:?} stream with disposition {:?}, using best stream",
+                        medium,
+                        disposition
+                    );
+                }
+                Ok(found.or_else(best))
+            }
+        }
+    }
+
+    /// Convert our StreamDisposition to FFmpeg disposition flags
+    fn disposition_to_ffmpeg(disposition: StreamDisposition) -> ffmpeg::format::stream::Disposition {
+        use ffmpeg::format::stream::Disposition;
+
+        match disposition {
+            StreamDisposition::Default => Disposition::DEFAULT,
+            StreamDisposition::Original => Disposition::ORIGINAL,
+            StreamDisposition::Dub => Disposition::DUB,
+            StreamDisposition::Commentary => Disposition::COMMENT,
+            StreamDisposition::Forced => Disposition::FORCED,
+            StreamDisposition::HearingImpaired => Disposition::HEARING_IMPAIRED,
+            StreamDisposition::VisualImpaired => Disposition::VISUAL_IMPAIRED,
+        }
+    }
+
+    /// Switch the active audio stream without reopening the file.
+    ///
+    /// The new decoder picks up from the current demuxer position, so audio
+    /// stays in sync with video. Queued packets of the old stream are dropped.
+    pub fn select_audio_stream(&mut self, stream_index: usize) -> Result<()> {
+        if self.audio_stream_index == Some(stream_index) {
+            return Ok(());
+        }
+
+        let is_audio = self
+            .input
+            .stream(stream_index)
+            .map(|s| s.parameters().medium() == MediaType::Audio)
+            .unwrap_or(false);
+        if !is_audio {
+            return Err(Error::InvalidFormat(format!(
+                "Stream {} is not an audio stream",
+                stream_index
+            )));
+        }
+
+        log::info!(
+            "Switching audio stream: {:?} -> {}",
+            self.audio_stream_index,
+            stream_index
+        );
+
+        self.init_audio_decoder(stream_index)?;
+        self.audio_stream_index = Some(stream_index);
+        self.audio_packet_queue.clear();
+        self.audio_frame_number = 0;
+
+        Ok(())
+    }
+
     /// Initialize video decoder for a stream
     fn init_video_decoder(&mut self, stream_index: usize, config: &DecoderConfig) -> Result<()> {
         let stream = self.input.stream(stream_index).ok_or_else(|| {
@@ -258,7 +586,12 @@ impl FFmpegContext {
 
         // Get codec parameters
         let codec_params = stream.parameters();
+        self.video_hdr = Self::parameters_hdr(&codec_params);
+        self.video_rotation = Self::parameters_rotation(&codec_params);
+        self.video_sample_aspect_ratio = self.stream_sample_aspect_ratio(&stream);
         self.video_time_base = stream.time_base();
+        let container_frame_count = stream.frames();
+        self.video_frame_count = 0;
 
         // Calculate duration
         // For elementary streams like .m2v, neither stream nor container duration
@@ -403,6 +736,15 @@ impl FFmpegContext {
         log::info!("Frame rate from {}: {}/{} = {:.6} fps",
             rate_source, frame_rate.numerator(), frame_rate.denominator(), self.frame_rate);
 
+        // Timecodes count from the first frame at the stream's rational frame rate
+        self.video_frame_rate = frame_rate;
+        self.video_start_us = match stream.start_time() {
+            ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+            start_time => Self::pts_to_us(start_time, self.video_time_base),
+        };
+        self.start_timecode = self.find_start_timecode(stream_index);
+        self.timecode_anchor = self.start_timecode.map(|tc| (tc, self.video_start_us));
+
         // If duration_us is negative (but not the scan marker), it contains frame count
         if self.duration_us < 0 && self.duration_us != NEEDS_SCAN_MARKER {
             let nb_frames = -self.duration_us;
@@ -494,6 +836,10 @@ impl FFmpegContext {
             log::info!("Scanned {} frames in {:.2}s, max PTS: {}",
                 frame_count, scan_duration.as_secs_f64(), max_pts);
 
+            if frame_count > 0 {
+                self.video_frame_count = frame_count;
+            }
+
             if frame_count > 0 && self.frame_rate > 0.0 {
                 // Calculate duration from frame count and frame rate
                 self.duration_us = ((frame_count as f64 / self.frame_rate) * 1_000_000.0) as i64;
@@ -574,30 +920,26 @@ impl FFmpegContext {
             self.duration_us as f64 / 1_000_000.0
         );
 
-        // Initialize scaler for pixel format conversion
-        let target_ffmpeg_format = Self::pixel_format_to_ffmpeg(self.target_format);
-        let source_format = video_decoder.format();
-
-        if source_format != target_ffmpeg_format {
-            let scaler = ScalerContext::get(
-                source_format,
-                self.width,
-                self.height,
-                target_ffmpeg_format,
-                self.width,
-                self.height,
-                ScalerFlags::BILINEAR,
-            )
-            .map_err(|e| Error::DecodeFailed(format!("Failed to create scaler: {}", e)))?;
-
-            self.scaler = Some(scaler);
-            log::debug!(
-                "Scaler initialized: {:?} -> {:?}",
-                source_format,
-                target_ffmpeg_format
-            );
+        // Packet counts from a scan are exact; otherwise trust the container and
+        // fall back to duration (refined when the keyframe index is built)
+        if self.video_frame_count == 0 {
+            self.video_frame_count = if container_frame_count > 0 {
+                container_frame_count
+            } else {
+                self.frames_between(0, self.duration_us)
+            };
         }
 
+        self.target_format = match config.output_pixel_format {
+            PixelFormat::Native => Self::native_pixel_format(video_decoder.format()),
+            format => format,
+        };
+        log::debug!("Output pixel format: {:?}", self.target_format);
+
+        // The scaler is created on the first frame that needs conversion or resizing
+        self.scaler = None;
+        self.scaler_setup = None;
+
         self.video_decoder = Some(video_decoder);
         Ok(())
     }
@@ -610,7 +952,7 @@ impl FFmpegContext {
 
         // Get codec parameters
         let codec_params = stream.parameters();
-        self.audio_time_base = stream.time_base();
+        let time_base = stream.time_base();
 
         // Find decoder
         let decoder_codec = ffmpeg::decoder::find(codec_params.id()).ok_or_else(|| {
@@ -634,53 +976,58 @@ impl FFmpegContext {
             Error::DecodeFailed(format!("Failed to open audio decoder: {}", e))
         })?;
 
-        self.audio_sample_rate = audio_decoder.rate();
-        self.audio_channels = audio_decoder.channels() as u32;
-
         log::info!(
             "Audio: {} Hz, {} channels, format: {:?}",
-            self.audio_sample_rate,
-            self.audio_channels,
+            audio_decoder.rate(),
+            audio_decoder.channels(),
             audio_decoder.format()
         );
 
-        // Create resampler to convert to float32 stereo at target sample rate
+        // Create resampler to convert to the configured output format
         let source_format = audio_decoder.format();
         let source_rate = audio_decoder.rate();
         let source_channels = audio_decoder.channels() as u32;
 
-        // Get channel layout - if empty, create one from channel count
+        // Get channel layout - if empty, derive the default one for the channel count
         let source_layout = {
             let layout = audio_decoder.channel_layout();
             if layout.is_empty() {
-                // Create layout from channel count
-                match source_channels {
-                    1 => ffmpeg::channel_layout::ChannelLayout::MONO,
-                    2 => ffmpeg::channel_layout::ChannelLayout::STEREO,
-                    _ => {
-                        log::warn!("Unsupported channel count: {}, defaulting to stereo", source_channels);
-                        ffmpeg::channel_layout::ChannelLayout::STEREO
-                    }
-                }
+                log::debug!(
+                    "No channel layout, using default layout for {} channels",
+                    source_channels
+                );
+                ffmpeg::channel_layout::ChannelLayout::default(source_channels as i32)
             } else {
                 layout
             }
         };
 
-        // Target: stereo, float32, 48kHz
-        let target_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
-        let target_format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed);
+        // Resolve the output format (0 = follow the source)
+        let output = self.audio_output;
+        let target_rate = if output.sample_rate > 0 {
+            output.sample_rate
+        } else {
+            source_rate
+        };
+        let target_layout = if output.channel_layout != 0 {
+            Self::channel_layout_from_mask(output.channel_layout)?
+        } else if output.channels == 0 || output.channels == source_channels {
+            source_layout
+        } else {
+            ffmpeg::channel_layout::ChannelLayout::default(output.channels as i32)
+        };
+        let target_format = Self::sample_format_to_ffmpeg(output.sample_format, output.planar);
 
-        // Create resampler
-        let resampler = ResamplerContext::get(
+        // Create resampler (the downmix mode only matters when channels are reduced)
+        let resampler = Self::create_resampler(
             source_format,
             source_layout,
             source_rate,
             target_format,
             target_layout,
-            self.target_sample_rate,
-        )
-        .map_err(|e| Error::DecodeFailed(format!("Failed to create audio resampler: {}", e)))?;
+            target_rate,
+            output.downmix,
+        )?;
 
         log::info!(
             "Audio resampler: {:?} {:?} {}Hz -> {:?} {:?} {}Hz",
@@ -689,18 +1036,349 @@ impl FFmpegContext {
             source_rate,
             target_format,
             target_layout,
-            self.target_sample_rate
+            target_rate
         );
 
+        self.audio_time_base = time_base;
+        self.audio_sample_rate = source_rate;
+        self.audio_channels = source_channels;
+        self.target_sample_rate = target_rate;
+        self.target_channels = target_layout.channels() as u32;
+        self.target_channel_layout = target_layout;
         self.resampler = Some(resampler);
         self.audio_decoder = Some(audio_decoder);
         Ok(())
     }
 
+    /// Initialize subtitle decoder for a stream
+    fn init_subtitle_decoder(&mut self, stream_index: usize) -> Result<()> {
+        let stream = self.input.stream(stream_index).ok_or_else(|| {
+            Error::InvalidFormat(format!("Subtitle stream {} not found", stream_index))
+        })?;
+
+        let codec_params = stream.parameters();
+        let time_base = stream.time_base();
+        let codec_id = codec_params.id();
+
+        let decoder_codec = ffmpeg::decoder::find(codec_id).ok_or_else(|| {
+            Error::CodecNotSupported(format!("No decoder for subtitle codec: {:?}", codec_id))
+        })?;
+
+        log::info!(
+            "Using subtitle decoder: {} ({})",
+            decoder_codec.name(),
+            decoder_codec.description()
+        );
+
+        let mut decoder_ctx = CodecContext::new_with_codec(decoder_codec);
+        decoder_ctx.set_parameters(codec_params).map_err(|e| {
+            Error::DecodeFailed(format!("Failed to set subtitle codec parameters: {}", e))
+        })?;
+
+        // Text decoders rescale packet timestamps using pkt_timebase
+        unsafe {
+            (*decoder_ctx.as_mut_ptr()).pkt_timebase = time_base.into();
+        }
+
+        let subtitle_decoder = decoder_ctx.decoder().subtitle().map_err(|e| {
+            Error::DecodeFailed(format!("Failed to open subtitle decoder: {}", e))
+        })?;
+
+        self.subtitle_time_base = time_base;
+        self.subtitle_is_ass = matches!(codec_id, ffmpeg::codec::Id::ASS | ffmpeg::codec::Id::SSA);
+        self.subtitle_decoder = Some(subtitle_decoder);
+        self.subtitles.clear();
+        Ok(())
+    }
+
+    /// Switch the active subtitle stream.
+    ///
+    /// Previously decoded events are discarded; new events are collected as
+    /// packets are demuxed (see `scan_subtitles` to load the whole track).
+    pub fn select_subtitle_stream(&mut self, stream_index: usize) -> Result<()> {
+        if self.subtitle_stream_index == Some(stream_index) {
+            return Ok(());
+        }
+
+        let is_subtitle = self
+            .input
+            .stream(stream_index)
+            .map(|s| s.parameters().medium() == MediaType::Subtitle)
+            .unwrap_or(false);
+        if !is_subtitle {
+            return Err(Error::InvalidFormat(format!(
+                "Stream {} is not a subtitle stream",
+                stream_index
+            )));
+        }
+
+        log::info!(
+            "Switching subtitle stream: {:?} -> {}",
+            self.subtitle_stream_index,
+            stream_index
+        );
+
+        self.init_subtitle_decoder(stream_index)?;
+        self.subtitle_stream_index = Some(stream_index);
+        Ok(())
+    }
+
+    /// Decode a subtitle packet and store the resulting events
+    fn decode_subtitle_packet(&mut self, packet: &ffmpeg::Packet) {
+        let stream_index = match self.subtitle_stream_index {
+            Some(idx) => idx as i32,
+            None => return,
+        };
+        let decoder = match self.subtitle_decoder.as_mut() {
+            Some(d) => d,
+            None => return,
+        };
+
+        let mut subtitle = ffmpeg::Subtitle::new();
+        match decoder.decode(packet, &mut subtitle) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                log::warn!("decode_subtitle_packet - decode failed (skipping): {}", e);
+                return;
+            }
+        }
+
+        // AVSubtitle.pts is in AV_TIME_BASE (microseconds); fall back to the packet
+        let base_us = subtitle
+            .pts()
+            .or_else(|| packet.pts().map(|pts| Self::pts_to_us(pts, self.subtitle_time_base)))
+            .unwrap_or(0);
+        let start_us = base_us + subtitle.start() as i64 * 1000;
+        let end_us = if subtitle.end() > subtitle.start() && subtitle.end() != u32::MAX {
+            base_us + subtitle.end() as i64 * 1000
+        } else if packet.duration() > 0 {
+            base_us + Self::pts_to_us(packet.duration(), self.subtitle_time_base)
+        } else {
+            OPEN_END_US
+        };
+
+        let mut events = Vec::new();
+        for rect in subtitle.rects() {
+            let (kind, text, ass, bitmap) = match rect {
+                ffmpeg::subtitle::Rect::Text(text) => {
+                    (SubtitleKind::Text, text.get().to_string(), None, None)
+                }
+                ffmpeg::subtitle::Rect::Ass(ass) => {
+                    let raw = ass.get().to_string();
+                    let text = ass_dialogue_to_text(&raw);
+                    if self.subtitle_is_ass {
+                        (SubtitleKind::Ass, text, Some(raw), None)
+                    } else {
+                        (SubtitleKind::Text, text, None, None)
+                    }
+                }
+                ffmpeg::subtitle::Rect::Bitmap(bitmap) => {
+                    match Self::convert_subtitle_bitmap(&bitmap) {
+                        Some(b) => (SubtitleKind::Bitmap, String::new(), None, Some(b)),
+                        None => continue,
+                    }
+                }
+                ffmpeg::subtitle::Rect::None(_) => continue,
+            };
+
+            events.push(SubtitleEvent {
+                stream_index,
+                start_us,
+                end_us,
+                kind,
+                text,
+                ass,
+                bitmap,
+            });
+        }
+
+        unsafe {
+            ffmpeg::ffi::avsubtitle_free(subtitle.as_mut_ptr());
+        }
+
+        if events.is_empty() {
+            // An empty subtitle clears the screen (PGS/DVB "clear" segments)
+            self.subtitles.close_open_events(start_us);
+            return;
+        }
+
+        log::debug!(
+            "decode_subtitle_packet - {} event(s) at {} us (end: {} us)",
+            events.len(),
+            start_us,
+            end_us
+        );
+
+        for event in events {
+            self.subtitles.insert(event);
+        }
+    }
+
+    /// Convert a palettized subtitle rectangle to an RGBA bitmap
+    fn convert_subtitle_bitmap(bitmap: &ffmpeg::subtitle::Bitmap) -> Option<SubtitleBitmap> {
+        unsafe {
+            let rect = bitmap.as_ptr();
+            let width = (*rect).w;
+            let height = (*rect).h;
+            let indices_ptr = (*rect).data[0];
+            let palette_ptr = (*rect).data[1];
+
+            if width <= 0 || height <= 0 || indices_ptr.is_null() || palette_ptr.is_null() {
+                return None;
+            }
+
+            let linesize = (*rect).linesize[0] as usize;
+            let indices = std::slice::from_raw_parts(indices_ptr, linesize * height as usize);
+            let palette = std::slice::from_raw_parts(
+                palette_ptr as *const u32,
+                (*rect).nb_colors.max(0) as usize,
+            );
+
+            Some(SubtitleBitmap::from_palette(
+                (*rect).x,
+                (*rect).y,
+                width as u32,
+                height as u32,
+                indices,
+                linesize,
+                palette,
+            ))
+        }
+    }
+
+    /// Read the whole file and collect all events of the active subtitle stream.
+    ///
+    /// Leaves the demuxer at the end of the file; callers must seek afterwards.
+    pub fn scan_subtitles(&mut self) -> Result<usize> {
+        let subtitle_stream_idx = match self.subtitle_stream_index {
+            Some(idx) => idx,
+            None => return Ok(0),
+        };
+
+        log::info!("Scanning subtitle stream {}...", subtitle_stream_idx);
+        let start_time = std::time::Instant::now();
+
+        self.seek(0)?;
+
+        while let Some((stream, packet)) = self.input.packets().next() {
+            if stream.index() == subtitle_stream_idx {
+                self.decode_subtitle_packet(&packet);
+            }
+        }
+
+        log::info!(
+            "Subtitle scan complete: {} events in {:?}",
+            self.subtitles.len(),
+            start_time.elapsed()
+        );
+
+        Ok(self.subtitles.len())
+    }
+
+    /// Subtitle events visible at the given time
+    pub fn subtitles_at(&self, time_us: i64) -> Vec<SubtitleEvent> {
+        self.subtitles.active_at(time_us)
+    }
+
+    /// All subtitle events decoded so far
+    pub fn subtitle_events(&self) -> &[SubtitleEvent] {
+        self.subtitles.events()
+    }
+
+    /// Closed caption events of a channel visible at the given time
+    pub fn captions_at(&self, channel: CaptionChannel, time_us: i64) -> Vec<SubtitleEvent> {
+        self.captions.active_at(channel, time_us)
+    }
+
+    /// All closed caption events of a channel decoded so far
+    pub fn caption_events(&self, channel: CaptionChannel) -> &[SubtitleEvent] {
+        self.captions.events(channel)
+    }
+
+    /// Caption channels that produced events so far
+    pub fn caption_channels(&self) -> Vec<CaptionChannel> {
+        self.captions.channels()
+    }
+
+    /// Read the next compressed packet of any stream, without decoding.
+    ///
+    /// Packets already read ahead for decoding are returned first, in the
+    /// order the demuxer read them. Returns None at the end of the input.
+    pub fn read_packet(&mut self) -> Result<Option<Packet>> {
+        let packet = match self.pop_queued_packet() {
+            Some(packet) => packet,
+            None => match self.input.packets().next() {
+                Some((_, packet)) => packet,
+                None => return Ok(None),
+            },
+        };
+
+        let stream_index = packet.stream();
+        let time_base = self
+            .input
+            .stream(stream_index)
+            .map(|stream| stream.time_base())
+            .ok_or_else(|| Error::InvalidFormat(format!("Stream {} not found", stream_index)))?;
+        let position = packet.position() as i64;
+
+        Ok(Some(Packet {
+            stream_index: stream_index as i32,
+            pts: packet.pts(),
+            dts: packet.dts(),
+            duration: packet.duration(),
+            time_base: (time_base.numerator(), time_base.denominator()),
+            pts_us: packet.pts().map(|pts| Self::pts_to_us(pts, time_base)),
+            dts_us: packet.dts().map(|dts| Self::pts_to_us(dts, time_base)),
+            duration_us: Self::pts_to_us(packet.duration(), time_base),
+            is_keyframe: packet.is_key(),
+            is_corrupt: packet.is_corrupt(),
+            position: (position >= 0).then_some(position),
+            data: packet.data().map(<[u8]>::to_vec).unwrap_or_default(),
+        }))
+    }
+
+    /// Take the read-ahead packet (video or audio queue) that was demuxed
+    /// first
+    fn pop_queued_packet(&mut self) -> Option<ffmpeg::Packet> {
+        let order = |packet: &ffmpeg::Packet| {
+            let time_base = self.input.stream(packet.stream()).map(|s| s.time_base());
+            let dts_us = packet
+                .dts()
+                .zip(time_base)
+                .map(|(dts, time_base)| dts.rescale(time_base, Rational::new(1, 1_000_000)));
+            (packet.position() as i64, dts_us)
+        };
+        let video_first = match (
+            self.video_packet_queue.front(),
+            self.audio_packet_queue.front(),
+        ) {
+            (Some(video), Some(audio)) => Self::demuxed_before(order(video), order(audio)),
+            (video, _) => video.is_some(),
+        };
+
+        if video_first {
+            self.video_packet_queue.pop_front()
+        } else {
+            self.audio_packet_queue.pop_front()
+        }
+    }
+
+    /// Whether a packet with (byte position, DTS in us) was demuxed before
+    /// another: by position when both have one, else by DTS
+    fn demuxed_before(a: (i64, Option<i64>), b: (i64, Option<i64>)) -> bool {
+        match (a, b) {
+            ((a, _), (b, _)) if a >= 0 && b >= 0 => a <= b,
+            ((_, Some(a)), (_, Some(b))) => a <= b,
+            _ => true,
+        }
+    }
+
     /// Get media information
     pub fn get_media_info(&self) -> Result<MediaInfo> {
         let mut video_tracks = Vec::new();
         let mut audio_tracks = Vec::new();
+        let mut subtitle_tracks = Vec::new();
         let mut metadata = HashMap::new();
 
         // Extract metadata
@@ -713,7 +1391,7 @@ impl FFmpegContext {
             let params = stream.parameters();
             let medium = params.medium();
 
-            if medium == MediaType::Video {
+            if medium == MediaType::Video && !Self::is_attached_picture(&stream) {
                 let codec_id = params.id();
                 let codec = ffmpeg::decoder::find(codec_id);
 
@@ -732,19 +1410,67 @@ impl FFmpegContext {
                     0.0
                 };
 
+                // Color description (names are None when unspecified)
+                let color = Self::parameters_color(&params);
+                let hdr = Self::parameters_hdr(&params);
+                let (width, height) =
+                    unsafe { ((*params.as_ptr()).width, (*params.as_ptr()).height) };
+                let rotation = Self::parameters_rotation(&params) as i32;
+                let sar = self.stream_sample_aspect_ratio(&stream);
+                let sample_aspect_ratio = if sar.numerator() > 0 && sar.denominator() > 0 {
+                    (sar.numerator(), sar.denominator())
+                } else {
+                    (1, 1)
+                };
+                let (display_width, display_height) =
+                    VideoTrack::display_dimensions(width, height, sample_aspect_ratio, rotation);
+                let field_order =
+                    FieldOrder::from_raw(unsafe { (*params.as_ptr()).field_order } as u8);
+                let (color_space, color_primaries, color_transfer, color_range) = unsafe {
+                    let p = params.as_ptr();
+                    (
+                        Self::color_name(ffmpeg::ffi::av_color_space_name((*p).color_space)),
+                        Self::color_name(ffmpeg::ffi::av_color_primaries_name(
+                            (*p).color_primaries,
+                        )),
+                        Self::color_name(ffmpeg::ffi::av_color_transfer_name((*p).color_trc)),
+                        Self::color_name(ffmpeg::ffi::av_color_range_name((*p).color_range))
+                            .unwrap_or_else(|| "unknown".to_string()),
+                    )
+                };
+
+                let frame_count = if Some(stream.index()) == self.video_stream_index {
+                    self.video_frame_count
+                } else if stream.frames() > 0 {
+                    stream.frames()
+                } else {
+                    (self.duration_us as f64 / 1_000_000.0 * fps).round() as i64
+                };
+
+                let extradata = Self::parameters_extradata(&params);
+
                 let video_track = VideoTrack {
                     index: stream.index() as i32,
                     codec: codec_info,
-                    width: unsafe { (*params.as_ptr()).width },
-                    height: unsafe { (*params.as_ptr()).height },
+                    width,
+                    height,
                     frame_rate: fps,
                     bit_rate: unsafe { (*params.as_ptr()).bit_rate },
                     pixel_format: Self::get_pixel_format_name(params),
                     is_hardware_decodable: Self::is_hardware_decodable(codec_id),
-                    color_space: None,
-                    color_primaries: None,
-                    color_transfer: None,
-                    color_range: "unknown".to_string(),
+                    color_space,
+                    color_primaries,
+                    color_transfer,
+                    color_range,
+                    color,
+                    hdr,
+                    rotation,
+                    sample_aspect_ratio,
+                    display_width,
+                    display_height,
+                    field_order,
+                    frame_count,
+                    extradata,
                 };
 
                 video_tracks.push(video_track);
@@ -765,29 +1491,60 @@ impl FFmpegContext {
                     codec: codec_info,
                     sample_rate: unsafe { (*params.as_ptr()).sample_rate },
                     channels: unsafe { (*params.as_ptr()).ch_layout.nb_channels },
-                    channel_layout: None,
+                    channel_layout: Self::describe_channel_layout(unsafe {
+                        &(*params.as_ptr()).ch_layout
+                    }),
                     bit_rate: unsafe { (*params.as_ptr()).bit_rate },
                     language_code: stream
                         .metadata()
                         .get("language")
                         .map(|s| s.to_string()),
+                    extradata: Self::parameters_extradata(&params),
                 };
 
                 audio_tracks.push(audio_track);
-            }
-        }
-
-        // Get container format
-        let container_format = self
-            .input
-            .format()
-            .name()
-            .split(',')
-            .next()
-            .unwrap_or("unknown")
-            .to_string();
+            } else if medium == MediaType::Subtitle {
+                let codec_id = params.id();
+                let codec = ffmpeg::decoder::find(codec_id);
+                let disposition = stream.disposition();
 
-        // Calculate duration
+                let codec_info = CodecInfo {
+                    name: codec.map(|c| c.name().to_string()).unwrap_or_default(),
+                    long_name: codec
+                        .map(|c| c.description().to_string())
+                        .unwrap_or_default(),
+                    four_cc: None,
+                };
+
+                let subtitle_track = SubtitleTrack {
+                    index: stream.index() as i32,
+                    codec: codec_info,
+                    language_code: stream
+                        .metadata()
+                        .get("language")
+                        .map(|s| s.to_string()),
+                    title: stream.metadata().get("title").map(|s| s.to_string()),
+                    is_bitmap: Self::is_bitmap_subtitle(codec_id),
+                    is_default: disposition.contains(ffmpeg::format::stream::Disposition::DEFAULT),
+                    is_forced: disposition.contains(ffmpeg::format::stream::Disposition::FORCED),
+                    extradata: Self::parameters_extradata(&params),
+                };
+
+                subtitle_tracks.push(subtitle_track);
+            }
+        }
+
+        // Get container format
+        let container_format = self
+            .input
+            .format()
+            .name()
+            .split(',')
+            .next()
+            .unwrap_or("unknown")
+            .to_string();
+
+        // Calculate duration
         let duration = if self.duration_us > 0 {
             self.duration_us as f64 / 1_000_000.0
         } else {
@@ -800,10 +1557,208 @@ impl FFmpegContext {
             container_format,
             video_tracks,
             audio_tracks,
+            subtitle_tracks,
+            start_timecode: self.start_timecode,
+            chapters: self.read_chapters((duration * 1_000_000.0) as i64),
+            attachments: self.read_attachments(),
             metadata,
         })
     }
 
+    /// Read the container's chapters in start time order.
+    ///
+    /// Chapters without an end close at the next chapter, the last one at
+    /// the end of the media.
+    fn read_chapters(&self, duration_us: i64) -> Vec<Chapter> {
+        let mut chapters: Vec<Chapter> = self
+            .input
+            .chapters()
+            .map(|chapter| {
+                let time_base = chapter.time_base();
+                let start = chapter.start();
+                let end = chapter.end().max(start);
+                Chapter {
+                    id: chapter.id(),
+                    title: chapter.metadata().get("title").map(str::to_string),
+                    // Rescale: nanosecond time bases (Matroska) overflow pts_to_us
+                    start_us: start.rescale(time_base, Rational::new(1, 1_000_000)),
+                    end_us: end.rescale(time_base, Rational::new(1, 1_000_000)),
+                    start,
+                    end,
+                    time_base: (time_base.numerator(), time_base.denominator()),
+                }
+            })
+            .collect();
+        chapters.sort_by_key(|chapter| chapter.start_us);
+
+        for i in 0..chapters.len() {
+            if chapters[i].end_us > chapters[i].start_us {
+                continue;
+            }
+            let end_us = chapters
+                .get(i + 1)
+                .map_or(duration_us, |next| next.start_us)
+                .max(chapters[i].start_us);
+            let chapter = &mut chapters[i];
+            chapter.end_us = end_us;
+            chapter.end = end_us.rescale(Rational::new(1, 1_000_000), chapter.time_base);
+        }
+
+        chapters
+    }
+
+    /// List attached pictures and file attachments in stream order
+    fn read_attachments(&self) -> Vec<Attachment> {
+        self.input
+            .streams()
+            .filter_map(|stream| {
+                let params = stream.parameters();
+                let kind = if Self::is_attached_picture(&stream) {
+                    AttachmentKind::Picture
+                } else if params.medium() == MediaType::Attachment {
+                    AttachmentKind::File
+                } else {
+                    return None;
+                };
+
+                let metadata = stream.metadata();
+                let get = |key: &str| metadata.get(key).map(str::to_string);
+                let codec_id = params.id();
+                let (width, height) = match kind {
+                    AttachmentKind::Picture => unsafe {
+                        ((*params.as_ptr()).width, (*params.as_ptr()).height)
+                    },
+                    AttachmentKind::File => (0, 0),
+                };
+
+                Some(Attachment {
+                    index: stream.index() as i32,
+                    kind,
+                    codec_name: codec_id.name().to_string(),
+                    filename: get("filename"),
+                    mime_type: get("mimetype")
+                        .or_else(|| Self::picture_mime_type(codec_id).map(str::to_string)),
+                    title: get("title"),
+                    picture_type: match kind {
+                        AttachmentKind::Picture => get("comment"),
+                        AttachmentKind::File => None,
+                    },
+                    size: Self::attachment_bytes(&stream).len(),
+                    width,
+                    height,
+                })
+            })
+            .collect()
+    }
+
+    /// Raw contents of an attachment stream: the encoded image of an
+    /// attached picture, or the file data of an attachment
+    fn attachment_bytes<'a>(stream: &ffmpeg::format::stream::Stream<'a>) -> &'a [u8] {
+        unsafe {
+            let st = stream.as_ptr();
+            let (data, size) = if Self::is_attached_picture(stream) {
+                ((*st).attached_pic.data, (*st).attached_pic.size)
+            } else {
+                (
+                    (*(*st).codecpar).extradata,
+                    (*(*st).codecpar).extradata_size,
+                )
+            };
+            if data.is_null() || size <= 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(data, size as usize)
+            }
+        }
+    }
+
+    /// Get the contents of an attachment or attached picture stream
+    pub fn attachment_data(&self, stream_index: usize) -> Result<AttachmentData> {
+        let attachment = self
+            .read_attachments()
+            .into_iter()
+            .find(|attachment| attachment.index as usize == stream_index)
+            .ok_or_else(|| {
+                Error::InvalidFormat(format!("Stream {} is not an attachment", stream_index))
+            })?;
+        let stream = self
+            .input
+            .stream(stream_index)
+            .ok_or_else(|| Error::InvalidFormat(format!("Stream {} not found", stream_index)))?;
+
+        Ok(AttachmentData {
+            filename: attachment.filename,
+            mime_type: attachment.mime_type,
+            data: Self::attachment_bytes(&stream).to_vec(),
+        })
+    }
+
+    /// Decode an attached picture into a frame in the output pixel format.
+    ///
+    /// The output size applies; the main video's rotation does not. Decoding
+    /// uses its own codec context, so the playback position is unaffected.
+    pub fn decode_attached_picture(&mut self, stream_index: usize) -> Result<VideoFrame> {
+        let stream = self
+            .input
+            .stream(stream_index)
+            .filter(|stream| Self::is_attached_picture(stream))
+            .ok_or_else(|| {
+                Error::InvalidFormat(format!(
+                    "Stream {} is not an attached picture",
+                    stream_index
+                ))
+            })?;
+        let params = stream.parameters();
+        let codec = ffmpeg::decoder::find(params.id()).ok_or_else(|| {
+            Error::CodecNotSupported(format!("No decoder for codec: {:?}", params.id()))
+        })?;
+
+        let mut decoder_ctx = CodecContext::new_with_codec(codec);
+        decoder_ctx.set_parameters(params).map_err(|e| {
+            Error::DecodeFailed(format!("Failed to set picture codec parameters: {}", e))
+        })?;
+        let mut decoder = decoder_ctx
+            .decoder()
+            .video()
+            .map_err(|e| Error::DecodeFailed(format!("Failed to open picture decoder: {}", e)))?;
+
+        let sent = unsafe {
+            ffmpeg::ffi::avcodec_send_packet(decoder.as_mut_ptr(), &(*stream.as_ptr()).attached_pic)
+        };
+        if sent < 0 {
+            return Err(Error::DecodeFailed(format!(
+                "Failed to decode attached picture: {}",
+                ffmpeg::Error::from(sent)
+            )));
+        }
+        let _ = decoder.send_eof();
+        let mut decoded = VideoFrameFFmpeg::empty();
+        decoder.receive_frame(&mut decoded).map_err(|e| {
+            Error::DecodeFailed(format!("Failed to decode attached picture: {}", e))
+        })?;
+
+        // Convert without the main video's orientation, resolving Native for the
+        // picture itself (audio-only media never resolves it)
+        let saved = (self.target_format, self.apply_orientation);
+        if self.target_format == PixelFormat::Native {
+            self.target_format = Self::native_pixel_format(decoded.format());
+        }
+        self.apply_orientation = false;
+        let converted = self.scale_frame(decoded, &HdrMetadata::default());
+        let result = converted.and_then(|(output, color)| {
+            let mut frame = self.create_video_frame_with_pts(&output, 0, true)?;
+            frame.color = color;
+            Ok(frame)
+        });
+        (self.target_format, self.apply_orientation) = saved;
+
+        let mut frame = result?;
+        frame.pts_us = 0;
+        frame.duration_us = 0;
+        frame.frame_number = 0;
+        Ok(frame)
+    }
+
     /// Seek directly to a byte position in the file.
     ///
     /// This is used by seek_precise() when a keyframe index is available,
@@ -858,18 +1813,19 @@ impl FFmpegContext {
         if let Some(ref mut decoder) = self.audio_decoder {
             decoder.flush();
         }
+        if let Some(ref mut decoder) = self.subtitle_decoder {
+            decoder.flush();
+        }
 
         // Clear packet queues
         self.audio_packet_queue.clear();
         self.video_packet_queue.clear();
+        self.reset_video_pipeline();
+        self.reset_audio_pipeline();
+        self.captions.reset();
 
         // Flush resampler
-        if let Some(ref mut resampler) = self.resampler {
-            let target_format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed);
-            let target_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
-            let mut flush_output = ffmpeg::frame::Audio::new(target_format, 4096, target_layout);
-            let _ = resampler.flush(&mut flush_output);
-        }
+        self.flush_resampler();
 
         Ok(())
     }
@@ -977,23 +1933,24 @@ impl FFmpegContext {
             decoder.flush();
         }
 
+        if let Some(ref mut decoder) = self.subtitle_decoder {
+            decoder.flush();
+        }
+
         // Flush the resampler to clear any buffered samples from before the seek.
         // This is critical for MPEG audio (MP2/MP3) which uses overlapping synthesis windows.
-        if let Some(ref mut resampler) = self.resampler {
+        if self.resampler.is_some() {
             log::info!("FFmpegContext::seek - flushing audio resampler");
-            // Create a temporary output frame to receive any remaining samples (discard them)
-            let target_format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed);
-            let target_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
-            let mut flush_output = ffmpeg::frame::Audio::new(target_format, 4096, target_layout);
-            // Flush may fail if no samples buffered, ignore the error
-            let _ = resampler.flush(&mut flush_output);
+            self.flush_resampler();
         }
 
         self.audio_packet_queue.clear();
         self.video_packet_queue.clear();
+        self.reset_video_pipeline();
+        self.reset_audio_pipeline();
+        self.captions.reset();
 
-        // Reset frame counters for accurate tracking after seek
-        self.frame_number = 0;
+        // Reset the audio frame counter (video frame numbers derive from timestamps)
         self.audio_frame_number = 0;
 
         log::info!("FFmpegContext::seek - complete");
@@ -1044,7 +2001,8 @@ impl FFmpegContext {
         // Now decode frames until we reach the target time
         // We need to find the frame at or just before time_us
         let mut best_frame: Option<VideoFrame> = None;
-        let max_frames = 300; // Limit to prevent infinite loop (enough for ~10 seconds at 30fps)
+        // Limit to prevent infinite loop (enough for ~10 seconds at 30fps)
+        let max_frames = 300 * self.fields_per_frame();
         let mut frame_count = 0;
 
         log::info!("FFmpegContext::seek_precise - decoding frames to reach target");
@@ -1076,11 +2034,10 @@ impl FFmpegContext {
                         best_frame = Some(frame);
 
                         // Check if we're close enough (within one frame duration)
-                        let frame_duration_us = if self.frame_rate > 0.0 {
-                            (1_000_000.0 / self.frame_rate) as i64
-                        } else {
-                            33333 // ~30fps default
-                        };
+                        let frame_duration_us = Self::output_frame_duration_us(
+                            self.frame_rate,
+                            self.fields_per_frame(),
+                        );
 
                         // If the next frame would be past the target, we found our frame
                         if frame_pts + frame_duration_us > time_us {
@@ -1179,13 +2136,11 @@ impl FFmpegContext {
         }
 
         // Flush resampler to clear any buffered samples
-        if let Some(ref mut resampler) = self.resampler {
+        if self.resampler.is_some() {
             log::info!("prime_audio_after_seek - flushing audio resampler");
-            let target_format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed);
-            let target_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
-            let mut flush_output = ffmpeg::frame::Audio::new(target_format, 4096, target_layout);
-            let _ = resampler.flush(&mut flush_output);
+            self.flush_resampler();
         }
+        self.reset_audio_pipeline();
 
         log::info!("prime_audio_after_seek - starting, audio_queue={}, video_queue={}",
             self.audio_packet_queue.len(), self.video_packet_queue.len());
@@ -1211,8 +2166,10 @@ impl FFmpegContext {
                         log::trace!("prime_audio_after_seek - queueing video packet");
                         self.video_packet_queue.push_back(packet);
                         video_packets_queued += 1;
+                    } else if Some(stream.index()) == self.subtitle_stream_index {
+                        self.decode_subtitle_packet(&packet);
                     }
-                    // Skip other streams (subtitles, etc.)
+                    // Skip other streams
                 }
                 None => {
                     log::info!("prime_audio_after_seek - end of stream reached");
@@ -1232,6 +2189,11 @@ impl FFmpegContext {
     pub fn decode_next_frame(&mut self) -> Result<Option<VideoFrame>> {
         log::debug!("decode_next_frame - start");
 
+        // Deliver frames already produced (second field of bob deinterlacing)
+        if let Some(frame) = self.pending_frames.pop_front() {
+            return Ok(Some(frame));
+        }
+
         let video_stream_idx = match self.video_stream_index {
             Some(idx) => idx,
             None => {
@@ -1274,9 +2236,13 @@ impl FFmpegContext {
                             log::trace!("decode_next_frame - queueing audio packet (stream {})", stream.index());
                             self.audio_packet_queue.push_back(packet);
                             continue;
+                        } else if Some(stream.index()) == self.subtitle_stream_index {
+                            // Subtitles decode synchronously, no queueing needed
+                            self.decode_subtitle_packet(&packet);
+                            continue;
                         } else {
                             log::trace!("decode_next_frame - skipping other packet (stream {})", stream.index());
-                            continue; // Skip other streams (subtitles, etc.)
+                            continue; // Skip other streams
                         }
                     }
                     None => {
@@ -1337,648 +2303,2922 @@ impl FFmpegContext {
                     .unwrap_or(0);
 
                 let is_keyframe = decoded.is_key();
+                let is_interlaced = decoded.is_interlaced();
+                let top_field_first = decoded.is_top_first();
+                let hdr = Self::frame_hdr(&decoded).or(&self.video_hdr);
+
+                // Closed captions are decoded once per coded frame
+                let mut caption_data = Self::frame_caption_data(&decoded);
+                if !caption_data.is_empty() {
+                    let time_us = Self::pts_to_us(pts, self.video_time_base);
+                    self.captions.decode(&caption_data, time_us);
+                }
 
                 log::debug!("receive_frame - raw pts={}, is_keyframe={}", pts, is_keyframe);
 
-                // Convert frame to target format
-                let output_frame = if let Some(ref mut scaler) = self.scaler {
-                    let mut scaled = VideoFrameFFmpeg::empty();
-                    scaler.run(&decoded, &mut scaled).map_err(|e| {
-                        Error::DecodeFailed(format!("Failed to scale frame: {}", e))
-                    })?;
-                    scaled
-                } else {
-                    decoded
-                };
-
-                // Extract frame data using the pre-scaling timestamp
-                let frame = self.create_video_frame_with_pts(&output_frame, pts, is_keyframe)?;
-                self.frame_number += 1;
+                // One frame, or one per field when bob deinterlacing
+                let fields = self.deinterlace_frame(decoded);
+                let field_count = fields.len() as i64;
+
+                for (field, source) in fields.into_iter().enumerate() {
+                    for (filtered, pts) in self.filter_video(source, pts)? {
+                        let mut frame =
+                            self.finish_video_frame(filtered, pts, is_keyframe && field == 0, hdr)?;
+                        if field_count > 1 {
+                            // Fields split the frame's display interval
+                            frame.duration_us /= field_count;
+                            frame.pts_us += frame.duration_us * field as i64;
+                            frame.frame_number = self.frame_index(frame.pts_us);
+                        }
+                        frame.is_interlaced = is_interlaced;
+                        frame.top_field_first = top_field_first;
+                        frame.caption_data = std::mem::take(&mut caption_data);
+                        self.pending_frames.push_back(frame);
+                    }
+                }
 
-                Ok(Some(frame))
+                Ok(self.pending_frames.pop_front())
             }
             Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => {
                 // Need more data
                 Ok(None)
             }
             Err(ffmpeg::Error::Eof) => {
-                // End of stream
-                Ok(None)
+                // End of stream - release frames still held by the filter graph
+                self.flush_video_filter()?;
+                Ok(self.pending_frames.pop_front())
             }
             Err(e) => Err(Error::DecodeFailed(format!("Failed to receive frame: {}", e))),
         }
     }
 
-    /// Create a VideoFrame from FFmpeg frame (legacy, uses frame's PTS)
-    #[allow(dead_code)]
-    fn create_video_frame(&self, frame: &VideoFrameFFmpeg) -> Result<VideoFrame> {
-        let pts = frame.pts().unwrap_or(0);
-        let is_keyframe = frame.is_key();
-        self.create_video_frame_with_pts(frame, pts, is_keyframe)
-    }
-
-    /// Create a VideoFrame from FFmpeg frame with explicit PTS and keyframe info
-    /// This is needed because scaling may lose timestamp/keyframe information
-    fn create_video_frame_with_pts(&self, frame: &VideoFrameFFmpeg, pts: i64, is_keyframe: bool) -> Result<VideoFrame> {
-        let width = frame.width();
-        let height = frame.height();
-        let stride = frame.stride(0) as u32;
-
-        // Calculate PTS in microseconds
-        let pts_us = Self::pts_to_us(pts, self.video_time_base);
+    /// Deinterlace a decoded frame with the configured mode.
+    ///
+    /// Returns the frame unchanged when it is progressive or its pixel format
+    /// is not planar YUV, and two frames (one per field, in display order) in
+    /// bob mode. Yadif falls back to bob's first field without a previous frame.
+    fn deinterlace_frame(&mut self, decoded: VideoFrameFFmpeg) -> Vec<VideoFrameFFmpeg> {
+        if self.deinterlace == DeinterlaceMode::None || !decoded.is_interlaced() {
+            self.deinterlace_previous = None;
+            return vec![decoded];
+        }
 
-        // Calculate frame duration
-        let frame_duration_us = if self.frame_rate > 0.0 {
-            (1_000_000.0 / self.frame_rate) as i64
-        } else {
-            16666 // Default to ~60fps
+        let sample_size = match decoded.format().descriptor() {
+            Some(descriptor) => unsafe {
+                let desc = descriptor.as_ptr();
+                let unsupported = (ffmpeg::ffi::AV_PIX_FMT_FLAG_PAL
+                    | ffmpeg::ffi::AV_PIX_FMT_FLAG_BITSTREAM
+                    | ffmpeg::ffi::AV_PIX_FMT_FLAG_HWACCEL
+                    | ffmpeg::ffi::AV_PIX_FMT_FLAG_RGB) as u64;
+                if (*desc).flags & unsupported != 0 || (*desc).comp[0].depth > 16 {
+                    return vec![decoded];
+                }
+                if (*desc).comp[0].depth > 8 {
+                    2
+                } else {
+                    1
+                }
+            },
+            None => return vec![decoded],
         };
 
-        // Copy pixel data
-        let data = match self.target_format {
-            PixelFormat::Bgra => {
-                let plane = frame.data(0);
-                let size = (stride * height) as usize;
-                plane[..size].to_vec()
+        let top_first = decoded.is_top_first();
+        match self.deinterlace {
+            DeinterlaceMode::Bob => vec![
+                Self::map_planes(&decoded, |_, src, dst, row_bytes, rows| {
+                    bob_plane(src, dst, row_bytes, rows, sample_size, top_first)
+                }),
+                Self::map_planes(&decoded, |_, src, dst, row_bytes, rows| {
+                    bob_plane(src, dst, row_bytes, rows, sample_size, !top_first)
+                }),
+            ],
+            _ => {
+                let previous = self.deinterlace_previous.take().filter(|previous| {
+                    previous.format() == decoded.format()
+                        && previous.width() == decoded.width()
+                        && previous.height() == decoded.height()
+                        && (0..decoded.planes()).all(|p| previous.stride(p) == decoded.stride(p))
+                });
+                let output = match previous {
+                    Some(previous) => {
+                        Self::map_planes(&decoded, |plane, src, dst, row_bytes, rows| {
+                            let prev = Plane {
+                                data: previous.data(plane),
+                                stride: previous.stride(plane),
+                            };
+                            yadif_plane(src, prev, dst, row_bytes, rows, sample_size, top_first)
+                        })
+                    }
+                    None => Self::map_planes(&decoded, |_, src, dst, row_bytes, rows| {
+                        bob_plane(src, dst, row_bytes, rows, sample_size, top_first)
+                    }),
+                };
+                self.deinterlace_previous = Some(decoded);
+                vec![output]
             }
-            PixelFormat::Nv12 => {
-                // NV12: Y plane + interleaved UV plane
-                let y_plane = frame.data(0);
-                let uv_plane = frame.data(1);
-                let y_size = (stride * height) as usize;
-                let uv_size = (stride * height / 2) as usize;
+        }
+    }
 
-                let mut data = Vec::with_capacity(y_size + uv_size);
-                data.extend_from_slice(&y_plane[..y_size]);
-                data.extend_from_slice(&uv_plane[..uv_size]);
-                data
-            }
-            PixelFormat::Yuv420p => {
-                // YUV420P: Y plane + U plane + V plane
-                let y_plane = frame.data(0);
-                let u_plane = frame.data(1);
-                let v_plane = frame.data(2);
-                let y_size = (stride * height) as usize;
-                let uv_size = (stride * height / 4) as usize;
+    /// Build a frame of the same format and properties by processing each plane
+    ///
+    /// The callback gets the plane index, source and destination planes, the
+    /// bytes per row both can hold and the row count.
+    fn map_planes(
+        source: &VideoFrameFFmpeg,
+        mut process: impl FnMut(usize, Plane, PlaneMut, usize, usize),
+    ) -> VideoFrameFFmpeg {
+        let mut output = VideoFrameFFmpeg::new(source.format(), source.width(), source.height());
+        unsafe {
+            ffmpeg::ffi::av_frame_copy_props(output.as_mut_ptr(), source.as_ptr());
+        }
 
-                let mut data = Vec::with_capacity(y_size + uv_size * 2);
-                data.extend_from_slice(&y_plane[..y_size]);
-                data.extend_from_slice(&u_plane[..uv_size]);
-                data.extend_from_slice(&v_plane[..uv_size]);
-                data
-            }
-        };
+        for plane in 0..source.planes() {
+            let rows = source.plane_height(plane) as usize;
+            let row_bytes = source.stride(plane).min(output.stride(plane));
+            let stride = output.stride(plane);
+            let src = Plane {
+                data: source.data(plane),
+                stride: source.stride(plane),
+            };
+            let dst = PlaneMut {
+                data: output.data_mut(plane),
+                stride,
+            };
+            process(plane, src, dst, row_bytes, rows);
+        }
+        output
+    }
 
-        Ok(VideoFrame::new(
-            data,
-            width,
-            height,
-            stride,
-            pts_us,
-            frame_duration_us,
-            is_keyframe,
-            self.frame_number,
-            self.target_format,
-        ))
+    /// Drop deinterlacer and filter graph state and undelivered frames (after a seek)
+    fn reset_video_pipeline(&mut self) {
+        self.deinterlace_previous = None;
+        self.video_filter_graph = None;
+        self.pending_frames.clear();
     }
 
-    /// Convert PTS to microseconds
-    fn pts_to_us(pts: i64, time_base: Rational) -> i64 {
-        if time_base.denominator() == 0 {
-            return pts;
-        }
-        (pts * 1_000_000 * time_base.numerator() as i64) / time_base.denominator() as i64
+    /// Drop the audio filter graph and undelivered audio frames (after a seek)
+    fn reset_audio_pipeline(&mut self) {
+        self.audio_filter_graph = None;
+        self.pending_audio_frames.clear();
     }
 
-    /// Convert microseconds to PTS
-    fn us_to_pts(us: i64, time_base: Rational) -> i64 {
-        if time_base.numerator() == 0 {
-            return us;
+    /// Set the filter graph applied to decoded video frames (None = no filtering).
+    ///
+    /// `spec` is a libavfilter description such as `"crop=iw/2:ih/2,hflip"`.
+    /// It is validated against the current stream parameters.
+    pub fn set_video_filter(&mut self, spec: Option<&str>) -> Result<()> {
+        self.reset_video_pipeline();
+        if let (Some(spec), Some(decoder)) = (spec, self.video_decoder.as_ref()) {
+            // Some decoders only know their pixel format after the first frame
+            if decoder.format() != ffmpeg::format::Pixel::None {
+                let input = FilterInput::Video {
+                    format: decoder.format(),
+                    width: decoder.width(),
+                    height: decoder.height(),
+                    time_base: self.video_time_base,
+                    sample_aspect_ratio: self.video_sample_aspect_ratio,
+                };
+                self.video_filter_graph = Some(FilterGraph::new(spec, input, false)?);
+            }
         }
-        (us * time_base.denominator() as i64) / (1_000_000 * time_base.numerator() as i64)
+        self.video_filter = spec.map(str::to_string);
+        Ok(())
     }
 
-    /// Convert our PixelFormat to FFmpeg format
-    fn pixel_format_to_ffmpeg(format: PixelFormat) -> ffmpeg::format::Pixel {
-        match format {
-            PixelFormat::Bgra => ffmpeg::format::Pixel::BGRA,
-            PixelFormat::Nv12 => ffmpeg::format::Pixel::NV12,
-            PixelFormat::Yuv420p => ffmpeg::format::Pixel::YUV420P,
-        }
+    /// Get the filter description applied to decoded video frames
+    pub fn video_filter(&self) -> Option<&str> {
+        self.video_filter.as_deref()
     }
 
-    /// Get FourCC for a codec
-    fn get_fourcc(codec_id: ffmpeg::codec::Id) -> Option<String> {
-        match codec_id {
-            ffmpeg::codec::Id::H264 => Some("avc1".to_string()),
-            ffmpeg::codec::Id::HEVC => Some("hvc1".to_string()),
-            ffmpeg::codec::Id::VP9 => Some("vp09".to_string()),
-            ffmpeg::codec::Id::AV1 => Some("av01".to_string()),
-            ffmpeg::codec::Id::PRORES => Some("apch".to_string()),
-            ffmpeg::codec::Id::MPEG2VIDEO => Some("m2v1".to_string()),
-            ffmpeg::codec::Id::MPEG4 => Some("mp4v".to_string()),
-            ffmpeg::codec::Id::DNXHD => Some("AVdn".to_string()),
-            _ => None,
+    /// Set the filter graph applied to decoded audio frames (None = no filtering).
+    ///
+    /// `spec` is a libavfilter description such as `"volume=0.5,atempo=1.25"`.
+    /// The graph output is converted back to the decoder's sample format, rate
+    /// and layout, so the configured audio output format still applies.
+    pub fn set_audio_filter(&mut self, spec: Option<&str>) -> Result<()> {
+        self.reset_audio_pipeline();
+        if let (Some(spec), Some(resampler)) = (spec, self.resampler.as_ref()) {
+            let source = resampler.input();
+            let input = FilterInput::Audio {
+                format: source.format,
+                sample_rate: source.rate,
+                channel_layout: Self::describe_channel_layout(&source.channel_layout.0)
+                    .unwrap_or_else(|| "stereo".to_string()),
+                time_base: self.audio_time_base,
+            };
+            self.audio_filter_graph = Some(FilterGraph::new(spec, input, true)?);
         }
+        self.audio_filter = spec.map(str::to_string);
+        Ok(())
     }
 
-    /// Check if codec is VideoToolbox decodable
-    fn is_hardware_decodable(codec_id: ffmpeg::codec::Id) -> bool {
-        matches!(
-            codec_id,
-            ffmpeg::codec::Id::H264
-                | ffmpeg::codec::Id::HEVC
-                | ffmpeg::codec::Id::VP9
-                | ffmpeg::codec::Id::PRORES
-        )
+    /// Get the filter description applied to decoded audio frames
+    pub fn audio_filter(&self) -> Option<&str> {
+        self.audio_filter.as_deref()
     }
 
-    /// Get pixel format name from codec parameters
-    fn get_pixel_format_name(params: ffmpeg::codec::Parameters) -> String {
-        let format = unsafe { (*params.as_ptr()).format };
+    /// Run a frame through the video filter graph, if one is configured.
+    ///
+    /// Returns the filtered frames with pts in the video stream time base
+    /// (none while a filter buffers). The graph is rebuilt when the frame
+    /// format or size changes.
+    fn filter_video(
+        &mut self,
+        mut frame: VideoFrameFFmpeg,
+        pts: i64,
+    ) -> Result<Vec<(VideoFrameFFmpeg, i64)>> {
+        let spec = match self.video_filter.as_deref() {
+            Some(spec) => spec,
+            None => return Ok(vec![(frame, pts)]),
+        };
 
-        // Map common pixel format codes to names
-        match format {
-            0 => "yuv420p".to_string(),    // AV_PIX_FMT_YUV420P
-            3 => "rgb24".to_string(),      // AV_PIX_FMT_RGB24
-            23 => "nv12".to_string(),      // AV_PIX_FMT_NV12
-            28 => "bgra".to_string(),      // AV_PIX_FMT_BGRA
-            4 => "yuv422p".to_string(),    // AV_PIX_FMT_YUV422P
-            5 => "yuv444p".to_string(),    // AV_PIX_FMT_YUV444P
-            _ => format!("pix_fmt_{}", format),
+        let frame_sar = unsafe { Rational::from((*frame.as_ptr()).sample_aspect_ratio) };
+        let input = FilterInput::Video {
+            format: frame.format(),
+            width: frame.width(),
+            height: frame.height(),
+            time_base: self.video_time_base,
+            sample_aspect_ratio: if frame_sar.numerator() > 0 {
+                frame_sar
+            } else {
+                self.video_sample_aspect_ratio
+            },
+        };
+        let graph = match self.video_filter_graph.as_mut() {
+            Some(graph) if *graph.input() == input => graph,
+            _ => self
+                .video_filter_graph
+                .insert(FilterGraph::new(spec, input, false)?),
+        };
+
+        frame.set_pts(Some(pts));
+        graph.push(&frame)?;
+        Self::drain_video_filter(graph, self.video_time_base)
+    }
+
+    /// Collect all frames the video filter graph has ready
+    fn drain_video_filter(
+        graph: &mut FilterGraph,
+        time_base: Rational,
+    ) -> Result<Vec<(VideoFrameFFmpeg, i64)>> {
+        let mut frames = Vec::new();
+        loop {
+            let mut filtered = VideoFrameFFmpeg::empty();
+            if !graph.pull(&mut filtered)? {
+                return Ok(frames);
+            }
+            let pts = filtered
+                .pts()
+                .unwrap_or(0)
+                .rescale(graph.time_base(), time_base);
+            frames.push((filtered, pts));
         }
     }
 
-    /// Get video stream index
-    pub fn video_stream_index(&self) -> Option<usize> {
-        self.video_stream_index
+    /// Flush the video filter graph at end of stream and queue what it released
+    fn flush_video_filter(&mut self) -> Result<()> {
+        let mut graph = match self.video_filter_graph.take() {
+            Some(graph) => graph,
+            None => return Ok(()),
+        };
+        graph.flush()?;
+        let hdr = self.video_hdr;
+        for (filtered, pts) in Self::drain_video_filter(&mut graph, self.video_time_base)? {
+            let frame = self.finish_video_frame(filtered, pts, false, hdr)?;
+            self.pending_frames.push_back(frame);
+        }
+        Ok(())
     }
 
-    /// Get audio stream index
-    pub fn audio_stream_index(&self) -> Option<usize> {
-        self.audio_stream_index
+    /// Convert a decoded (and filtered) frame into a `VideoFrame`
+    fn finish_video_frame(
+        &mut self,
+        source: VideoFrameFFmpeg,
+        pts: i64,
+        is_keyframe: bool,
+        hdr: HdrMetadata,
+    ) -> Result<VideoFrame> {
+        let timecode = self.frame_timecode(&source, Self::pts_to_us(pts, self.video_time_base));
+
+        // Convert frame to target format, size and color description
+        let (output_frame, color) = self.scale_frame(source, &hdr)?;
+
+        // Extract frame data using the pre-scaling timestamp
+        let mut frame = self.create_video_frame_with_pts(&output_frame, pts, is_keyframe)?;
+        frame.color = color;
+        frame.hdr = hdr;
+        frame.timecode = timecode;
+        if self.apply_orientation && self.video_rotation != 0 {
+            frame = frame.rotated(self.video_rotation);
+        } else {
+            frame.rotation = self.video_rotation;
+        }
+        Ok(frame)
     }
 
-    /// Get frame rate
-    pub fn frame_rate(&self) -> f64 {
-        self.frame_rate
+    /// Frame rate timecodes count at, as (numerator, denominator)
+    fn timecode_rate(&self) -> (i32, i32) {
+        (
+            self.video_frame_rate.numerator(),
+            self.video_frame_rate.denominator(),
+        )
     }
 
-    /// Get video dimensions
-    pub fn dimensions(&self) -> (u32, u32) {
-        (self.width, self.height)
+    /// Whole frames between two timestamps at the video frame rate
+    fn frames_between(&self, from_us: i64, to_us: i64) -> i64 {
+        let rate = self.video_frame_rate;
+        if rate.numerator() <= 0 || rate.denominator() <= 0 {
+            return 0;
+        }
+        ((to_us - from_us) as f64 * rate.numerator() as f64
+            / (rate.denominator() as f64 * 1_000_000.0))
+            .round() as i64
     }
 
-    /// Get duration in microseconds
-    pub fn duration_us(&self) -> i64 {
-        self.duration_us
+    /// Start timecode from stream metadata (video stream first, then tmcd
+    /// tracks) or container metadata
+    fn find_start_timecode(&self, video_stream_index: usize) -> Option<Timecode> {
+        let from_streams = self
+            .input
+            .stream(video_stream_index)
+            .into_iter()
+            .chain(self.input.streams())
+            .find_map(|stream| stream.metadata().get("timecode").map(str::to_string));
+        let text =
+            from_streams.or_else(|| self.input.metadata().get("timecode").map(str::to_string))?;
+
+        match Timecode::parse(&text, self.timecode_rate()) {
+            Ok(timecode) => {
+                log::info!("Start timecode: {}", timecode);
+                Some(timecode)
+            }
+            Err(e) => {
+                log::warn!("Ignoring start timecode: {}", e);
+                None
+            }
+        }
     }
 
-    /// Get audio sample rate (returns target/output sample rate after resampling)
-    pub fn audio_sample_rate(&self) -> u32 {
-        // Return target sample rate since we resample to this rate
-        if self.resampler.is_some() {
-            self.target_sample_rate
-        } else {
-            self.audio_sample_rate
+    /// A/53 closed caption data (cc_data triplets) of a decoded frame
+    fn frame_caption_data(frame: &VideoFrameFFmpeg) -> Vec<u8> {
+        frame
+            .side_data(ffmpeg::util::frame::side_data::Type::A53CC)
+            .map(|sd| sd.data().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Timecode of a decoded frame.
+    ///
+    /// Timecodes carried by the frame (SMPTE 12M in H.264/HEVC SEI, or an
+    /// MPEG-2 GOP header) re-anchor the count; other frames count on from the
+    /// last anchor, which starts at the start timecode.
+    fn frame_timecode(&mut self, frame: &VideoFrameFFmpeg, pts_us: i64) -> Option<Timecode> {
+        use ffmpeg::util::frame::side_data::Type;
+
+        let rate = self.timecode_rate();
+        let read_u32 = |data: &[u8], at: usize| {
+            data.get(at..at + 4)
+                .map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let carried = frame
+            .side_data(Type::S12M_TIMECODE)
+            .and_then(|sd| {
+                // uint32 count followed by up to three timecodes
+                match read_u32(sd.data(), 0) {
+                    Some(count) if count > 0 => read_u32(sd.data(), 4),
+                    _ => None,
+                }
+            })
+            .map(|value| Timecode::from_smpte_12m(value, rate))
+            .or_else(|| {
+                frame
+                    .side_data(Type::GOPTimecode)
+                    .and_then(|sd| sd.data().get(..8).map(|b| b.try_into().unwrap()))
+                    .map(i64::from_ne_bytes)
+                    .filter(|&value| value >= 0)
+                    .map(|value| Timecode::from_gop_header(value as u32, rate))
+            })
+            .filter(Timecode::is_valid);
+
+        if let Some(timecode) = carried {
+            if self.start_timecode.is_none() {
+                let offset = self.frames_between(self.video_start_us, pts_us);
+                self.start_timecode = Some(timecode.offset(-offset));
+            }
+            self.timecode_anchor = Some((timecode, pts_us));
         }
+
+        let (anchor, anchor_us) = self.timecode_anchor?;
+        Some(anchor.offset(self.frames_between(anchor_us, pts_us)))
     }
 
-    /// Get audio channels (returns target/output channel count after resampling)
-    pub fn audio_channels(&self) -> u32 {
-        // Return target channels since we resample to stereo
-        if self.resampler.is_some() {
-            self.target_channels
+    /// Get the timecode of the first video frame
+    pub fn start_timecode(&self) -> Option<Timecode> {
+        self.start_timecode
+    }
+
+    /// Convert a timecode string (e.g. "01:00:10;12") to the presentation time
+    /// of that frame.
+    ///
+    /// Frames count from the start timecode (00:00:00:00 if the media has
+    /// none) at the stream's rational frame rate, using the start timecode's
+    /// drop-frame mode. The returned time lies mid-frame so that rounding
+    /// cannot select the neighbouring frame.
+    pub fn timecode_to_us(&self, text: &str) -> Result<i64> {
+        let rate = self.timecode_rate();
+        let mut timecode = Timecode::parse(text, rate)?;
+        let start = match self.start_timecode {
+            Some(start) => start,
+            None => Timecode::from_frame_number(0, rate, timecode.drop_frame),
+        };
+        timecode.drop_frame = start.drop_frame;
+        if !timecode.is_valid() {
+            return Err(Error::InvalidTimecode(text.to_string()));
+        }
+
+        let frames = timecode.frames_since(&start);
+        Ok(Self::frame_time(self.video_start_us, frames, rate, 1))
+    }
+
+    /// Frames delivered per coded frame (2 when bob deinterlacing outputs
+    /// each field as a frame)
+    fn fields_per_frame(&self) -> i64 {
+        if self.deinterlace == DeinterlaceMode::Bob {
+            2
         } else {
-            self.audio_channels
+            1
         }
     }
 
-    /// Check if audio is available
-    pub fn has_audio(&self) -> bool {
-        self.audio_decoder.is_some()
+    /// Absolute index of the frame presented at `pts_us`, counted from the
+    /// first frame at the stream's rational frame rate (at the field rate
+    /// with bob deinterlacing)
+    pub fn frame_index(&self, pts_us: i64) -> i64 {
+        let (start_us, rate) = (self.video_start_us, self.timecode_rate());
+        Self::frame_number_at(start_us, pts_us, rate, self.fields_per_frame())
     }
 
-    /// Decode the next audio frame
-    pub fn decode_next_audio_frame(&mut self) -> Result<Option<AudioFrame>> {
-        log::debug!("decode_next_audio_frame - start, queue_size={}", self.audio_packet_queue.len());
+    /// Time in the middle of frame `frame_index`'s display interval (counted
+    /// like `frame_index`).
+    ///
+    /// Aiming mid-frame keeps timestamp rounding from selecting a neighbour.
+    pub fn frame_to_us(&self, frame_index: i64) -> i64 {
+        let (start_us, rate) = (self.video_start_us, self.timecode_rate());
+        Self::frame_time(start_us, frame_index, rate, self.fields_per_frame())
+    }
 
-        let audio_stream_idx = match self.audio_stream_index {
-            Some(idx) => {
-                log::debug!("decode_next_audio_frame - audio stream index={}", idx);
-                idx
-            }
-            None => {
-                log::warn!("decode_next_audio_frame - no audio stream index");
-                return Ok(None);
-            }
-        };
+    /// Display duration of one output frame (one field with bob
+    /// deinterlacing)
+    fn output_frame_duration_us(frame_rate: f64, fields: i64) -> i64 {
+        if frame_rate > 0.0 {
+            (1_000_000.0 / (frame_rate * fields as f64)) as i64
+        } else {
+            33333 / fields // ~30fps default
+        }
+    }
 
-        if self.audio_decoder.is_none() {
-            log::warn!("decode_next_audio_frame - no audio decoder");
-            return Ok(None);
+    /// Index of the frame presented at `pts_us` at `fields` frames per
+    /// period of the rate `(num, den)`
+    fn frame_number_at(start_us: i64, pts_us: i64, (num, den): (i32, i32), fields: i64) -> i64 {
+        if num <= 0 || den <= 0 {
+            return 0;
         }
+        ((pts_us - start_us) as f64 * (num as i64 * fields) as f64 / (den as f64 * 1_000_000.0))
+            .round() as i64
+    }
 
-        let max_packets = 500;
-        let mut packet_count = 0;
+    /// Middle of the display interval of frame `frame_index`, counted like
+    /// `frame_number_at`
+    fn frame_time(start_us: i64, frame_index: i64, (num, den): (i32, i32), fields: i64) -> i64 {
+        if num <= 0 || den <= 0 {
+            return start_us;
+        }
+        let offset_us = (2 * frame_index as i128 + 1) * den as i128 * 1_000_000
+            / (2 * num as i128 * fields as i128);
+        start_us + offset_us as i64
+    }
 
-        loop {
-            if packet_count >= max_packets {
-                log::warn!("decode_next_audio_frame - exceeded max packet count");
-                return Ok(None);
-            }
+    /// Get the total number of video frames (0 = unknown)
+    pub fn frame_count(&self) -> i64 {
+        self.video_frame_count
+    }
 
-            // First, try to get audio packets from the queue (collected during video decoding)
-            let packet = if let Some(queued_packet) = self.audio_packet_queue.pop_front() {
-                log::trace!("decode_next_audio_frame - using queued packet, remaining={}", self.audio_packet_queue.len());
-                packet_count += 1;
-                Some(queued_packet)
-            } else {
-                // Queue is empty, read from stream
-                match self.input.packets().next() {
-                    Some((stream, packet)) => {
-                        packet_count += 1;
-                        if stream.index() == audio_stream_idx {
-                            Some(packet)
-                        } else if Some(stream.index()) == self.video_stream_index {
-                            // Queue video packets for later decoding
-                            log::trace!("decode_next_audio_frame - queueing video packet (stream {})", stream.index());
-                            self.video_packet_queue.push_back(packet);
-                            continue;
-                        } else {
-                            // Skip other streams (subtitles, etc.)
-                            continue;
-                        }
-                    }
-                    None => {
-                        // End of stream - flush decoder
-                        if let Some(ref mut decoder) = self.audio_decoder {
-                            decoder.send_eof().ok();
-                        }
-                        return self.receive_audio_frame();
-                    }
-                }
-            };
+    /// Run a frame through the audio filter graph, if one is configured.
+    ///
+    /// Returns the filtered frames with pts in the audio stream time base.
+    fn filter_audio(
+        &mut self,
+        mut frame: AudioFrameFFmpeg,
+        pts: i64,
+    ) -> Result<Vec<(AudioFrameFFmpeg, i64)>> {
+        let spec = match self.audio_filter.as_deref() {
+            Some(spec) => spec,
+            None => return Ok(vec![(frame, pts)]),
+        };
 
-            if let Some(packet) = packet {
-                log::debug!("decode_next_audio_frame - sending audio packet {} to decoder", packet_count);
-                // Send packet to decoder
-                if let Some(ref mut decoder) = self.audio_decoder {
-                    match decoder.send_packet(&packet) {
-                        Ok(()) => {
-                            // Packet sent successfully
-                        }
-                        Err(e) => {
-                            // After seek, MP2/MP3 decoder may fail on first few packets
-                            // because they don't start at a valid frame boundary (sync header).
-                            // Skip invalid packets and continue to the next one.
-                            log::warn!(
-                                "decode_next_audio_frame - send_packet failed (skipping): {}",
-                                e
-                            );
-                            continue;
-                        }
-                    }
-                }
+        let input = FilterInput::Audio {
+            format: frame.format(),
+            sample_rate: frame.rate(),
+            channel_layout: Self::describe_channel_layout(unsafe { &(*frame.as_ptr()).ch_layout })
+                .unwrap_or_else(|| "stereo".to_string()),
+            time_base: self.audio_time_base,
+        };
+        let graph = match self.audio_filter_graph.as_mut() {
+            Some(graph) if *graph.input() == input => graph,
+            _ => self
+                .audio_filter_graph
+                .insert(FilterGraph::new(spec, input, true)?),
+        };
 
-                // Try to receive a frame
-                if let Some(frame) = self.receive_audio_frame()? {
-                    log::debug!("decode_next_audio_frame - returning frame with {} samples", frame.sample_count);
-                    return Ok(Some(frame));
-                }
+        frame.set_pts(Some(pts));
+        graph.push(&frame)?;
+        Self::drain_audio_filter(graph, self.audio_time_base)
+    }
+
+    /// Collect all frames the audio filter graph has ready
+    fn drain_audio_filter(
+        graph: &mut FilterGraph,
+        time_base: Rational,
+    ) -> Result<Vec<(AudioFrameFFmpeg, i64)>> {
+        let mut frames = Vec::new();
+        loop {
+            let mut filtered = AudioFrameFFmpeg::empty();
+            if !graph.pull(&mut filtered)? {
+                return Ok(frames);
             }
+            let pts = filtered
+                .pts()
+                .unwrap_or(0)
+                .rescale(graph.time_base(), time_base);
+            frames.push((filtered, pts));
         }
     }
 
-    /// Receive a decoded audio frame from the decoder
-    fn receive_audio_frame(&mut self) -> Result<Option<AudioFrame>> {
-        let decoder = match self.audio_decoder.as_mut() {
-            Some(d) => d,
-            None => return Ok(None),
+    /// Flush the audio filter graph at end of stream and queue what it released
+    fn flush_audio_filter(&mut self) -> Result<()> {
+        let mut graph = match self.audio_filter_graph.take() {
+            Some(graph) => graph,
+            None => return Ok(()),
         };
+        graph.flush()?;
+        for (filtered, pts) in Self::drain_audio_filter(&mut graph, self.audio_time_base)? {
+            let pts_us = Self::pts_to_us(pts, self.audio_time_base);
+            let frame = self.convert_audio_frame(&filtered, pts_us)?;
+            self.audio_frame_number += 1;
+            self.pending_audio_frames.push_back(frame);
+        }
+        Ok(())
+    }
 
-        let mut decoded = AudioFrameFFmpeg::empty();
+    /// Convert a decoded frame to the target pixel format and output size.
+    ///
+    /// HDR frames are tone mapped when enabled. Returns the converted frame and
+    /// the color description of its data.
+    fn scale_frame(
+        &mut self,
+        decoded: VideoFrameFFmpeg,
+        hdr: &HdrMetadata,
+    ) -> Result<(VideoFrameFFmpeg, ColorInfo)> {
+        let target_format = Self::pixel_format_to_ffmpeg(self.target_format);
+        let (width, height) = (decoded.width(), decoded.height());
+        let geometry = self.frame_geometry(&decoded);
+        let cropped = geometry.crop_width != width || geometry.crop_height != height;
+
+        // Color conversion parameters, filling in what the source leaves unsignalled
+        let source_color = Self::frame_color(&decoded);
+        let (src_matrix, src_full_range) =
+            Self::effective_source_color(&source_color, decoded.format(), height);
+
+        // Tone mapping works on a full-range RGBA64 intermediate
+        let tone_mapping = self.prepare_tone_mapper(&source_color, hdr);
+        let (stage_format, dst_matrix, dst_full_range) = if tone_mapping {
+            (ffmpeg::format::Pixel::RGBA64LE, src_matrix, true)
+        } else {
+            let dst_full_range = match self.output_color_range {
+                _ if self.target_format.is_rgb() => true,
+                Some(ColorRange::Full) => true,
+                Some(ColorRange::Limited) => false,
+                _ => src_full_range,
+            };
+            (
+                target_format,
+                self.output_color_matrix.unwrap_or(src_matrix),
+                dst_full_range,
+            )
+        };
 
-        match decoder.receive_frame(&mut decoded) {
-            Ok(()) => {
-                // Get timestamp
-                let pts = decoded.pts().unwrap_or(0);
-                let pts_us = Self::pts_to_us(pts, self.audio_time_base);
+        let output_color = Self::output_color(
+            &source_color,
+            self.target_format.is_rgb(),
+            dst_matrix,
+            dst_full_range,
+        );
 
-                log::debug!(
-                    "receive_audio_frame - decoded: pts={}, samples={}, rate={}, channels={}, format={:?}",
-                    pts,
-                    decoded.samples(),
-                    decoded.rate(),
-                    decoded.channels(),
-                    decoded.format()
-                );
+        if !tone_mapping
+            && decoded.format() == target_format
+            && !cropped
+            && geometry.width == width
+            && geometry.height == height
+            && src_matrix == dst_matrix
+            && src_full_range == dst_full_range
+        {
+            return Ok((decoded, output_color));
+        }
 
-                // Convert to float32 stereo using resampler
-                let frame = self.convert_audio_frame(&decoded, pts_us)?;
-                self.audio_frame_number += 1;
+        let setup = ScalerSetup {
+            format: decoded.format(),
+            width: geometry.crop_width,
+            height: geometry.crop_height,
+            out_format: stage_format,
+            out_width: geometry.width,
+            out_height: geometry.height,
+            src_matrix,
+            src_full_range,
+            dst_matrix,
+            dst_full_range,
+        };
 
-                if frame.sample_count > 0 {
-                    log::debug!(
-                        "receive_audio_frame - output: samples={}, channels={}, data_len={}",
-                        frame.sample_count,
-                        frame.channels,
-                        frame.data.len()
-                    );
-                }
+        let input = if cropped {
+            Self::crop_frame(&decoded, &geometry)?
+        } else {
+            decoded
+        };
 
-                Ok(Some(frame))
-            }
-            Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => {
-                // Need more data
-                log::trace!("receive_audio_frame - EAGAIN, need more data");
-                Ok(None)
-            }
-            Err(ffmpeg::Error::Eof) => {
-                // End of stream
-                log::debug!("receive_audio_frame - EOF");
-                Ok(None)
-            }
-            Err(e) => Err(Error::DecodeFailed(format!("Failed to receive audio frame: {}", e))),
+        let scaler = Self::prepare_scaler(
+            &mut self.scaler,
+            &mut self.scaler_setup,
+            setup,
+            self.scaling_algorithm,
+        )?;
+        let mut scaled = VideoFrameFFmpeg::empty();
+        scaler
+            .run(&input, &mut scaled)
+            .map_err(|e| Error::DecodeFailed(format!("Failed to scale frame: {}", e)))?;
+
+        if tone_mapping {
+            return self.tone_map_frame(scaled, &source_color);
         }
+        Ok((scaled, output_color))
     }
 
-    /// Convert FFmpeg audio frame to our AudioFrame format
-    fn convert_audio_frame(&mut self, frame: &AudioFrameFFmpeg, pts_us: i64) -> Result<AudioFrame> {
-        let resampler = match self.resampler.as_mut() {
-            Some(r) => r,
-            None => {
-                return Err(Error::DecodeFailed("No audio resampler available".to_string()));
-            }
-        };
+    /// Resolve crop and output size of a decoded frame.
+    ///
+    /// With orientation applied, the requested output size refers to the upright,
+    /// square-pixel picture: the geometry is resolved on the display size and
+    /// mapped back to the stored frame, which is rotated after conversion.
+    fn frame_geometry(&self, frame: &VideoFrameFFmpeg) -> ScaleGeometry {
+        let (width, height) = (frame.width(), frame.height());
+        let size = self.output_size.unwrap_or_default();
+        if !self.apply_orientation {
+            return size.resolve(width, height);
+        }
 
-        // Pre-allocate output frame with target format
-        // Calculate expected output samples based on sample rate conversion
-        let input_samples = frame.samples();
-        let input_rate = frame.rate() as u64;
-        let output_rate = self.target_sample_rate as u64;
+        // Prefer the frame's SAR, which can change mid-stream
+        let frame_sar = unsafe { Rational::from((*frame.as_ptr()).sample_aspect_ratio) };
+        let sar = if frame_sar.numerator() > 0 && frame_sar.denominator() > 0 {
+            frame_sar
+        } else {
+            self.video_sample_aspect_ratio
+        };
+        let (display_width, _) = VideoTrack::display_dimensions(
+            width as i32,
+            height as i32,
+            (sar.numerator(), sar.denominator()),
+            0,
+        );
+        let display_width = display_width as u32;
 
-        // Calculate output samples (with some extra buffer for rounding)
-        let expected_output_samples = if input_rate > 0 {
-            ((input_samples as u64 * output_rate + input_rate - 1) / input_rate) as usize + 32
+        let mut geometry = if self.video_rotation % 180 != 0 {
+            size.resolve(height, display_width).transposed()
         } else {
-            input_samples + 32
+            size.resolve(display_width, height)
         };
 
-        let target_format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed);
-        let target_layout = ffmpeg::channel_layout::ChannelLayout::STEREO;
+        // Map the horizontal crop back to stored (non-square) pixels
+        if display_width != width {
+            let to_stored = |v: u32| (v as u64 * width as u64 / display_width as u64) as u32 & !1;
+            geometry.crop_x = to_stored(geometry.crop_x);
+            geometry.crop_width = to_stored(geometry.crop_width)
+                .max(2)
+                .min(width - geometry.crop_x);
+        }
+        geometry
+    }
 
-        // Create and allocate output frame
-        let mut resampled = ffmpeg::frame::Audio::new(target_format, expected_output_samples, target_layout);
+    /// Get the scaler in `slot`, (re)creating it when the setup changed
+    pub(crate) fn prepare_scaler<'a>(
+        slot: &'a mut Option<ScalerContext>,
+        current: &mut Option<ScalerSetup>,
+        setup: ScalerSetup,
+        algorithm: ScalingAlgorithm,
+    ) -> Result<&'a mut ScalerContext> {
+        if slot.is_none() || *current != Some(setup) {
+            let mut scaler = ScalerContext::get(
+                setup.format,
+                setup.width,
+                setup.height,
+                setup.out_format,
+                setup.out_width,
+                setup.out_height,
+                Self::scaler_flags(algorithm),
+            )
+            .map_err(|e| Error::DecodeFailed(format!("Failed to create scaler: {}", e)))?;
 
-        // Set the sample rate on output frame
-        unsafe {
-            (*resampled.as_mut_ptr()).sample_rate = self.target_sample_rate as i32;
+            // swscale defaults to BT.601 limited range; use the actual coefficients
+            unsafe {
+                ffmpeg::ffi::sws_setColorspaceDetails(
+                    scaler.as_mut_ptr(),
+                    ffmpeg::ffi::sws_getCoefficients(setup.src_matrix as i32),
+                    setup.src_full_range as i32,
+                    ffmpeg::ffi::sws_getCoefficients(setup.dst_matrix as i32),
+                    setup.dst_full_range as i32,
+                    0,
+                    1 << 16,
+                    1 << 16,
+                );
+            }
+
+            log::debug!(
+                "Scaler initialized: {:?} {}x{} -> {:?} {}x{} ({:?}, matrix {} -> {}, full range {} -> {})",
+                setup.format,
+                setup.width,
+                setup.height,
+                setup.out_format,
+                setup.out_width,
+                setup.out_height,
+                algorithm,
+                setup.src_matrix,
+                setup.dst_matrix,
+                setup.src_full_range,
+                setup.dst_full_range
+            );
+            *slot = Some(scaler);
+            *current = Some(setup);
         }
 
-        // Fix input frame channel layout if empty (some decoders don't set it)
-        // The resampler needs matching channel layout to what it was initialized with
-        let frame_layout = frame.channel_layout();
-        let input_frame = if frame_layout.is_empty() {
-            // Clone the frame and set the channel layout
-            let mut fixed_frame = unsafe {
-                let mut new_frame = ffmpeg::frame::Audio::empty();
-                // Copy frame data via FFI
-                ffmpeg::ffi::av_frame_ref(new_frame.as_mut_ptr(), frame.as_ptr());
-                new_frame
-            };
+        slot.as_mut()
+            .ok_or_else(|| Error::DecodeFailed("No scaler available".to_string()))
+    }
 
-            // Set channel layout based on channel count
-            let layout = match frame.channels() {
-                1 => ffmpeg::channel_layout::ChannelLayout::MONO,
-                2 => ffmpeg::channel_layout::ChannelLayout::STEREO,
-                _ => ffmpeg::channel_layout::ChannelLayout::STEREO,
-            };
-            fixed_frame.set_channel_layout(layout);
+    /// Create or update the tone mapper for an HDR source.
+    ///
+    /// Returns whether frames with this color description are tone mapped.
+    fn prepare_tone_mapper(&mut self, color: &ColorInfo, hdr: &HdrMetadata) -> bool {
+        if !self.tone_mapping.is_enabled() || !color.is_hdr() {
+            return false;
+        }
 
+        let key = (
+            color.transfer,
+            color.primaries,
+            hdr.peak_luminance().map(|peak| peak as u32),
+        );
+        if self.tone_mapper_key != Some(key) {
+            self.tone_mapper = ToneMapper::new(&self.tone_mapping, color, hdr);
+            self.tone_mapper_key = Some(key);
             log::debug!(
-                "convert_audio_frame - fixed empty channel layout to {:?}",
-                layout
+                "Tone mapper initialized: {:?}, transfer {}, primaries {}, peak {:?}",
+                self.tone_mapping.operator,
+                color.transfer,
+                color.primaries,
+                key.2
             );
+        }
 
-            Some(fixed_frame)
-        } else {
-            None
+        self.tone_mapper.is_some()
+    }
+
+    /// Tone map an RGBA64 frame in place and convert it to the target format
+    fn tone_map_frame(
+        &mut self,
+        mut frame: VideoFrameFFmpeg,
+        source_color: &ColorInfo,
+    ) -> Result<(VideoFrameFFmpeg, ColorInfo)> {
+        let mapper = self
+            .tone_mapper
+            .as_ref()
+            .ok_or_else(|| Error::DecodeFailed("No tone mapper available".to_string()))?;
+
+        let (width, height) = (frame.width(), frame.height());
+        let stride = frame.stride(0);
+        mapper.process_rgba64(frame.data_mut(0), width as usize, height as usize, stride);
+
+        let sdr_color = ColorInfo {
+            primaries: if mapper.converts_gamut() {
+                primaries::BT709
+            } else {
+                source_color.primaries
+            },
+            transfer: transfer::BT709,
+            ..*source_color
         };
 
-        // Use the fixed frame if we created one, otherwise use original
-        let input_ref = input_frame.as_ref().unwrap_or(frame);
+        let target_format = Self::pixel_format_to_ffmpeg(self.target_format);
+        if target_format == ffmpeg::format::Pixel::RGBA64LE {
+            return Ok((frame, sdr_color.as_rgb()));
+        }
 
-        log::trace!(
-            "convert_audio_frame - input: samples={}, rate={}, channels={}, format={:?}, layout={:?}",
-            input_ref.samples(),
-            input_ref.rate(),
-            input_ref.channels(),
-            input_ref.format(),
-            input_ref.channel_layout()
+        let dst_matrix = self.output_color_matrix.unwrap_or(matrix::BT709);
+        let dst_full_range =
+            self.target_format.is_rgb() || self.output_color_range == Some(ColorRange::Full);
+        let setup = ScalerSetup {
+            format: ffmpeg::format::Pixel::RGBA64LE,
+            width,
+            height,
+            out_format: target_format,
+            out_width: width,
+            out_height: height,
+            src_matrix: matrix::BT709,
+            src_full_range: true,
+            dst_matrix,
+            dst_full_range,
+        };
+        let scaler = Self::prepare_scaler(
+            &mut self.tone_map_scaler,
+            &mut self.tone_map_scaler_setup,
+            setup,
+            self.scaling_algorithm,
+        )?;
+        let mut output = VideoFrameFFmpeg::empty();
+        scaler
+            .run(&frame, &mut output)
+            .map_err(|e| Error::DecodeFailed(format!("Failed to scale frame: {}", e)))?;
+
+        let color = Self::output_color(
+            &sdr_color,
+            self.target_format.is_rgb(),
+            dst_matrix,
+            dst_full_range,
         );
+        Ok((output, color))
+    }
+
+    /// Color description of converted frame data: the matrix and range the
+    /// scaler converted to, so values it had to guess for the source (see
+    /// `effective_source_color`) are reported as applied
+    fn output_color(source: &ColorInfo, rgb: bool, matrix: u8, full_range: bool) -> ColorInfo {
+        if rgb {
+            return source.as_rgb();
+        }
+        ColorInfo {
+            matrix,
+            range: if full_range {
+                ColorRange::Full
+            } else {
+                ColorRange::Limited
+            },
+            ..*source
+        }
+    }
+
+    /// Matrix and range of a source, guessing unsignalled values like most players:
+    /// BT.709 above SD resolution, full range for JPEG-style and RGB formats.
+    pub(crate) fn effective_source_color(
+        color: &ColorInfo,
+        format: ffmpeg::format::Pixel,
+        height: u32,
+    ) -> (u8, bool) {
+        use ffmpeg::format::Pixel;
+
+        let source_matrix = match color.matrix {
+            // Unspecified and reserved
+            2 | 3 if height > 576 => matrix::BT709,
+            2 | 3 => matrix::SMPTE170M,
+            m => m,
+        };
+
+        let full_range = match color.range {
+            ColorRange::Full => true,
+            ColorRange::Limited => false,
+            ColorRange::Unspecified => {
+                matches!(
+                    format,
+                    Pixel::YUVJ420P | Pixel::YUVJ422P | Pixel::YUVJ444P | Pixel::YUVJ440P
+                ) || format.descriptor().is_some_and(|d| unsafe {
+                    (*d.as_ptr()).flags & ffmpeg::ffi::AV_PIX_FMT_FLAG_RGB as u64 != 0
+                })
+            }
+        };
+
+        (source_matrix, full_range)
+    }
+
+    /// Reference a frame with its visible area reduced to the crop region
+    fn crop_frame(frame: &VideoFrameFFmpeg, geometry: &ScaleGeometry) -> Result<VideoFrameFFmpeg> {
+        let mut cropped = VideoFrameFFmpeg::empty();
+        unsafe {
+            if ffmpeg::ffi::av_frame_ref(cropped.as_mut_ptr(), frame.as_ptr()) < 0 {
+                return Err(Error::Memory);
+            }
+
+            let ptr = cropped.as_mut_ptr();
+            (*ptr).crop_left = geometry.crop_x as usize;
+            (*ptr).crop_top = geometry.crop_y as usize;
+            (*ptr).crop_right = (frame.width() - geometry.crop_x - geometry.crop_width) as usize;
+            (*ptr).crop_bottom = (frame.height() - geometry.crop_y - geometry.crop_height) as usize;
+
+            // Flags = AV_FRAME_CROP_UNALIGNED (offsets are already even)
+            if ffmpeg::ffi::av_frame_apply_cropping(ptr, 1) < 0 {
+                return Err(Error::DecodeFailed("Failed to crop frame".to_string()));
+            }
+        }
+        Ok(cropped)
+    }
+
+    /// Map a scaling algorithm to swscale flags
+    fn scaler_flags(algorithm: ScalingAlgorithm) -> ScalerFlags {
+        match algorithm {
+            ScalingAlgorithm::FastBilinear => ScalerFlags::FAST_BILINEAR,
+            ScalingAlgorithm::Bilinear => ScalerFlags::BILINEAR,
+            ScalingAlgorithm::Bicubic => ScalerFlags::BICUBIC,
+            ScalingAlgorithm::Point => ScalerFlags::POINT,
+            ScalingAlgorithm::Area => ScalerFlags::AREA,
+            ScalingAlgorithm::Lanczos => ScalerFlags::LANCZOS,
+            ScalingAlgorithm::Spline => ScalerFlags::SPLINE,
+        }
+    }
+
+    /// Set the output size for subsequently decoded frames (None = source size)
+    pub fn set_output_size(&mut self, size: Option<OutputSize>) {
+        self.output_size = size;
+    }
+
+    /// Get the output size applied to decoded frames
+    pub fn output_size(&self) -> Option<OutputSize> {
+        self.output_size
+    }
+
+    /// Create a VideoFrame from FFmpeg frame (legacy, uses frame's PTS)
+    #[allow(dead_code)]
+    fn create_video_frame(&self, frame: &VideoFrameFFmpeg) -> Result<VideoFrame> {
+        let pts = frame.pts().unwrap_or(0);
+        let is_keyframe = frame.is_key();
+        self.create_video_frame_with_pts(frame, pts, is_keyframe)
+    }
+
+    /// Create a VideoFrame from FFmpeg frame with explicit PTS and keyframe info
+    /// This is needed because scaling may lose timestamp/keyframe information
+    fn create_video_frame_with_pts(&self, frame: &VideoFrameFFmpeg, pts: i64, is_keyframe: bool) -> Result<VideoFrame> {
+        let width = frame.width();
+        let height = frame.height();
+        let stride = frame.stride(0) as u32;
+
+        // Calculate PTS in microseconds
+        let pts_us = Self::pts_to_us(pts, self.video_time_base);
+
+        // Calculate frame duration
+        let frame_duration_us = if self.frame_rate > 0.0 {
+            (1_000_000.0 / self.frame_rate) as i64
+        } else {
+            16666 // Default to ~60fps
+        };
+
+        // Copy pixel data, planes back to back (chroma strides derive from the luma stride)
+        let format = self.target_format;
+        let mut data = Vec::with_capacity(VideoFrame::planes_size(stride, height, format));
+        for plane in 0..format.plane_count() {
+            let (row_size, rows) = match format.plane_layout(plane, stride, height) {
+                Some(layout) => layout,
+                None => break,
+            };
+            let source = frame.data(plane);
+            let source_stride = frame.stride(plane);
+            let row_size = row_size as usize;
+
+            if source_stride == row_size {
+                data.extend_from_slice(&source[..row_size * rows as usize]);
+            } else {
+                let copy = row_size.min(source_stride);
+                for row in 0..rows as usize {
+                    let start = row * source_stride;
+                    data.extend_from_slice(&source[start..start + copy]);
+                    data.resize(data.len() + row_size - copy, 0);
+                }
+            }
+        }
+
+        Ok(VideoFrame::new(
+            data,
+            width,
+            height,
+            stride,
+            pts_us,
+            frame_duration_us,
+            is_keyframe,
+            self.frame_index(pts_us),
+            self.target_format,
+        ))
+    }
+
+    /// Convert PTS to microseconds
+    fn pts_to_us(pts: i64, time_base: Rational) -> i64 {
+        if time_base.denominator() == 0 {
+            return pts;
+        }
+        (pts * 1_000_000 * time_base.numerator() as i64) / time_base.denominator() as i64
+    }
+
+    /// Convert microseconds to PTS
+    fn us_to_pts(us: i64, time_base: Rational) -> i64 {
+        if time_base.numerator() == 0 {
+            return us;
+        }
+        (us * time_base.denominator() as i64) / (1_000_000 * time_base.numerator() as i64)
+    }
+
+    /// Read the color description of a stream's codec parameters
+    fn parameters_color(params: &ffmpeg::codec::Parameters) -> ColorInfo {
+        unsafe {
+            let p = params.as_ptr();
+            ColorInfo {
+                matrix: (*p).color_space as u8,
+                primaries: (*p).color_primaries as u8,
+                transfer: (*p).color_trc as u8,
+                range: ColorRange::from_raw((*p).color_range as u8),
+                chroma_location: ChromaLocation::from_raw((*p).chroma_location as u8),
+            }
+        }
+    }
+
+    /// Read the color description of a decoded frame
+    fn frame_color(frame: &VideoFrameFFmpeg) -> ColorInfo {
+        unsafe {
+            let f = frame.as_ptr();
+            ColorInfo {
+                matrix: (*f).colorspace as u8,
+                primaries: (*f).color_primaries as u8,
+                transfer: (*f).color_trc as u8,
+                range: ColorRange::from_raw((*f).color_range as u8),
+                chroma_location: ChromaLocation::from_raw((*f).chroma_location as u8),
+            }
+        }
+    }
+
+    /// Read HDR side data attached to a stream's codec parameters
+    fn parameters_hdr(params: &ffmpeg::codec::Parameters) -> HdrMetadata {
+        use ffmpeg::ffi::AVPacketSideDataType::*;
+
+        let side_data = |kind| Self::coded_side_data(params, kind);
+
+        HdrMetadata {
+            mastering_display: side_data(AV_PKT_DATA_MASTERING_DISPLAY_METADATA)
+                .and_then(Self::parse_mastering_display),
+            content_light_level: side_data(AV_PKT_DATA_CONTENT_LIGHT_LEVEL)
+                .and_then(Self::parse_content_light_level),
+            has_hdr10_plus: side_data(AV_PKT_DATA_DYNAMIC_HDR10_PLUS).is_some(),
+            has_dolby_vision: side_data(AV_PKT_DATA_DOVI_CONF).is_some(),
+        }
+    }
+
+    /// Get side data of the given type attached to a stream's codec parameters
+    fn coded_side_data(
+        params: &ffmpeg::codec::Parameters,
+        kind: ffmpeg::ffi::AVPacketSideDataType,
+    ) -> Option<&[u8]> {
+        unsafe {
+            let p = params.as_ptr();
+            let entry = ffmpeg::ffi::av_packet_side_data_get(
+                (*p).coded_side_data,
+                (*p).nb_coded_side_data,
+                kind,
+            );
+            if entry.is_null() || (*entry).data.is_null() {
+                None
+            } else {
+                Some(std::slice::from_raw_parts((*entry).data, (*entry).size))
+            }
+        }
+    }
+
+    /// Codec extradata of a stream
+    fn parameters_extradata(params: &ffmpeg::codec::Parameters) -> Vec<u8> {
+        unsafe {
+            let p = params.as_ptr();
+            if (*p).extradata.is_null() || (*p).extradata_size <= 0 {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts((*p).extradata, (*p).extradata_size as usize).to_vec()
+            }
+        }
+    }
+
+    /// Clockwise rotation (0, 90, 180 or 270) from a stream's display matrix
+    fn parameters_rotation(params: &ffmpeg::codec::Parameters) -> u32 {
+        let matrix = Self::coded_side_data(
+            params,
+            ffmpeg::ffi::AVPacketSideDataType::AV_PKT_DATA_DISPLAYMATRIX,
+        );
+        match matrix {
+            // 3x3 matrix of 32-bit fixed point values
+            Some(data) if data.len() >= 9 * 4 => {
+                let angle =
+                    unsafe { ffmpeg::ffi::av_display_rotation_get(data.as_ptr() as *const i32) };
+                // av_display_rotation_get reports counterclockwise degrees
+                Self::normalize_rotation(-angle)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Snap an angle in degrees to the nearest clockwise quarter turn in [0, 360)
+    fn normalize_rotation(degrees: f64) -> u32 {
+        if !degrees.is_finite() {
+            return 0;
+        }
+        ((degrees / 90.0).round() as i64).rem_euclid(4) as u32 * 90
+    }
+
+    /// Sample aspect ratio of a stream, from the container or the codec
+    fn stream_sample_aspect_ratio(&self, stream: &ffmpeg::Stream) -> Rational {
+        unsafe {
+            Rational::from(ffmpeg::ffi::av_guess_sample_aspect_ratio(
+                self.input.as_ptr() as *mut _,
+                stream.as_ptr() as *mut _,
+                std::ptr::null_mut(),
+            ))
+        }
+    }
+
+    /// Read HDR side data attached to a decoded frame
+    fn frame_hdr(frame: &VideoFrameFFmpeg) -> HdrMetadata {
+        use ffmpeg::util::frame::side_data::Type;
+
+        HdrMetadata {
+            mastering_display: frame
+                .side_data(Type::MasteringDisplayMetadata)
+                .and_then(|sd| Self::parse_mastering_display(sd.data())),
+            content_light_level: frame
+                .side_data(Type::ContentLightLevel)
+                .and_then(|sd| Self::parse_content_light_level(sd.data())),
+            has_hdr10_plus: frame.side_data(Type::DYNAMIC_HDR_PLUS).is_some(),
+            has_dolby_vision: frame.side_data(Type::DOVI_RPU_BUFFER).is_some()
+                || frame.side_data(Type::DOVI_METADATA).is_some(),
+        }
+    }
+
+    /// Parse an AVMasteringDisplayMetadata payload
+    fn parse_mastering_display(data: &[u8]) -> Option<MasteringDisplay> {
+        if data.len() < std::mem::size_of::<RawMasteringDisplay>() {
+            return None;
+        }
+        let raw = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const RawMasteringDisplay) };
+        if raw.has_primaries == 0 && raw.has_luminance == 0 {
+            return None;
+        }
+
+        let q = |r: ffmpeg::ffi::AVRational| {
+            if r.den != 0 {
+                r.num as f64 / r.den as f64
+            } else {
+                0.0
+            }
+        };
+        Some(MasteringDisplay {
+            primaries: raw.display_primaries.map(|xy| [q(xy[0]), q(xy[1])]),
+            white_point: [q(raw.white_point[0]), q(raw.white_point[1])],
+            min_luminance: q(raw.min_luminance),
+            max_luminance: q(raw.max_luminance),
+        })
+    }
+
+    /// Parse an AVContentLightMetadata payload
+    fn parse_content_light_level(data: &[u8]) -> Option<ContentLightLevel> {
+        if data.len() < std::mem::size_of::<RawContentLightLevel>() {
+            return None;
+        }
+        let raw = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const RawContentLightLevel) };
+        Some(ContentLightLevel {
+            max_cll: raw.max_cll,
+            max_fall: raw.max_fall,
+        })
+    }
+
+    /// Name of a color property (None when unspecified)
+    fn color_name(name: *const std::os::raw::c_char) -> Option<String> {
+        if name.is_null() {
+            return None;
+        }
+        let name = unsafe { std::ffi::CStr::from_ptr(name) }.to_string_lossy();
+        match name.as_ref() {
+            "unknown" | "unspecified" | "reserved" => None,
+            _ => Some(name.into_owned()),
+        }
+    }
+
+    /// Convert our PixelFormat to FFmpeg format
+    pub(crate) fn pixel_format_to_ffmpeg(format: PixelFormat) -> ffmpeg::format::Pixel {
+        match format {
+            PixelFormat::Bgra => ffmpeg::format::Pixel::BGRA,
+            PixelFormat::Nv12 => ffmpeg::format::Pixel::NV12,
+            PixelFormat::Yuv420p => ffmpeg::format::Pixel::YUV420P,
+            PixelFormat::Rgba => ffmpeg::format::Pixel::RGBA,
+            PixelFormat::P010 => ffmpeg::format::Pixel::P010LE,
+            PixelFormat::Yuv422p10 => ffmpeg::format::Pixel::YUV422P10LE,
+            PixelFormat::Yuv444p16 => ffmpeg::format::Pixel::YUV444P16LE,
+            PixelFormat::Rgba64 => ffmpeg::format::Pixel::RGBA64LE,
+            PixelFormat::Rgb48 => ffmpeg::format::Pixel::RGB48LE,
+            // Resolved in init_video_decoder; BGRA only as a safe fallback
+            PixelFormat::Native => ffmpeg::format::Pixel::BGRA,
+        }
+    }
+
+    /// Pick the output format closest to the decoder's own format, keeping
+    /// its bit depth and chroma resolution (only float formats are reduced,
+    /// to 16 bits)
+    fn native_pixel_format(format: ffmpeg::format::Pixel) -> PixelFormat {
+        use ffmpeg::format::Pixel;
+
+        match format {
+            Pixel::BGRA => return PixelFormat::Bgra,
+            Pixel::RGBA => return PixelFormat::Rgba,
+            Pixel::NV12 => return PixelFormat::Nv12,
+            Pixel::YUV420P | Pixel::YUVJ420P => return PixelFormat::Yuv420p,
+            Pixel::P010LE => return PixelFormat::P010,
+            Pixel::YUV422P10LE => return PixelFormat::Yuv422p10,
+            Pixel::YUV444P16LE => return PixelFormat::Yuv444p16,
+            Pixel::RGBA64LE => return PixelFormat::Rgba64,
+            Pixel::RGB48LE => return PixelFormat::Rgb48,
+            _ => {}
+        }
+
+        let descriptor = match format.descriptor() {
+            Some(descriptor) => descriptor,
+            None => return PixelFormat::Yuv420p,
+        };
+        let (depth, is_rgb) = unsafe {
+            let desc = descriptor.as_ptr();
+            (
+                (*desc).comp[0].depth,
+                (*desc).flags & ffmpeg::ffi::AV_PIX_FMT_FLAG_RGB as u64 != 0,
+            )
+        };
+
+        if is_rgb {
+            return match (depth, descriptor.nb_components()) {
+                (0..=8, _) => PixelFormat::Rgba,
+                (_, 4) => PixelFormat::Rgba64,
+                _ => PixelFormat::Rgb48,
+            };
+        }
+
+        let grayscale = descriptor.nb_components() <= 2;
+        match (
+            grayscale,
+            descriptor.log2_chroma_w(),
+            descriptor.log2_chroma_h(),
+            depth,
+        ) {
+            // Grayscale and 4:2:0
+            (true, _, _, 0..=8) | (false, 1, 1, 0..=8) => PixelFormat::Yuv420p,
+            (true, _, _, 9..=10) | (false, 1, 1, 9..=10) => PixelFormat::P010,
+            // No 8-bit 4:2:2 format; widened to 10 bits
+            (false, 1, 0, 0..=10) => PixelFormat::Yuv422p10,
+            // 4:4:4 and anything deeper than 10 bits
+            _ => PixelFormat::Yuv444p16,
+        }
+    }
+
+    /// Get FourCC for a codec
+    fn get_fourcc(codec_id: ffmpeg::codec::Id) -> Option<String> {
+        match codec_id {
+            ffmpeg::codec::Id::H264 => Some("avc1".to_string()),
+            ffmpeg::codec::Id::HEVC => Some("hvc1".to_string()),
+            ffmpeg::codec::Id::VP9 => Some("vp09".to_string()),
+            ffmpeg::codec::Id::AV1 => Some("av01".to_string()),
+            ffmpeg::codec::Id::PRORES => Some("apch".to_string()),
+            ffmpeg::codec::Id::MPEG2VIDEO => Some("m2v1".to_string()),
+            ffmpeg::codec::Id::MPEG4 => Some("mp4v".to_string()),
+            ffmpeg::codec::Id::DNXHD => Some("AVdn".to_string()),
+            _ => None,
+        }
+    }
+
+    /// Check if codec is VideoToolbox decodable
+    fn is_hardware_decodable(codec_id: ffmpeg::codec::Id) -> bool {
+        matches!(
+            codec_id,
+            ffmpeg::codec::Id::H264
+                | ffmpeg::codec::Id::HEVC
+                | ffmpeg::codec::Id::VP9
+                | ffmpeg::codec::Id::PRORES
+        )
+    }
+
+    /// Check if a stream carries an attached picture (cover art)
+    fn is_attached_picture(stream: &ffmpeg::format::stream::Stream) -> bool {
+        stream
+            .disposition()
+            .contains(ffmpeg::format::stream::Disposition::ATTACHED_PIC)
+    }
+
+    /// MIME type of an image codec, for pictures stored without one
+    fn picture_mime_type(codec_id: ffmpeg::codec::Id) -> Option<&'static str> {
+        match codec_id {
+            ffmpeg::codec::Id::MJPEG => Some("image/jpeg"),
+            ffmpeg::codec::Id::PNG => Some("image/png"),
+            ffmpeg::codec::Id::BMP => Some("image/bmp"),
+            ffmpeg::codec::Id::GIF => Some("image/gif"),
+            ffmpeg::codec::Id::TIFF => Some("image/tiff"),
+            ffmpeg::codec::Id::WEBP => Some("image/webp"),
+            _ => None,
+        }
+    }
+
+    /// Check if a subtitle codec produces bitmap images
+    fn is_bitmap_subtitle(codec_id: ffmpeg::codec::Id) -> bool {
+        unsafe {
+            let descriptor = ffmpeg::ffi::avcodec_descriptor_get(codec_id.into());
+            !descriptor.is_null()
+                && ((*descriptor).props & ffmpeg::ffi::AV_CODEC_PROP_BITMAP_SUB as i32) != 0
+        }
+    }
+
+    /// Get pixel format name from codec parameters
+    fn get_pixel_format_name(params: ffmpeg::codec::Parameters) -> String {
+        let format = unsafe { (*params.as_ptr()).format };
+
+        // Map common pixel format codes to names
+        match format {
+            0 => "yuv420p".to_string(),    // AV_PIX_FMT_YUV420P
+            3 => "rgb24".to_string(),      // AV_PIX_FMT_RGB24
+            23 => "nv12".to_string(),      // AV_PIX_FMT_NV12
+            28 => "bgra".to_string(),      // AV_PIX_FMT_BGRA
+            4 => "yuv422p".to_string(),    // AV_PIX_FMT_YUV422P
+            5 => "yuv444p".to_string(),    // AV_PIX_FMT_YUV444P
+            _ => format!("pix_fmt_{}", format),
+        }
+    }
+
+    /// Get video stream index
+    pub fn video_stream_index(&self) -> Option<usize> {
+        self.video_stream_index
+    }
+
+    /// Get audio stream index
+    pub fn audio_stream_index(&self) -> Option<usize> {
+        self.audio_stream_index
+    }
+
+    /// Get subtitle stream index
+    pub fn subtitle_stream_index(&self) -> Option<usize> {
+        self.subtitle_stream_index
+    }
+
+    /// Get frame rate
+    pub fn frame_rate(&self) -> f64 {
+        self.frame_rate
+    }
+
+    /// Get video dimensions
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Get duration in microseconds
+    pub fn duration_us(&self) -> i64 {
+        self.duration_us
+    }
+
+    /// Get audio sample rate (returns target/output sample rate after resampling)
+    pub fn audio_sample_rate(&self) -> u32 {
+        // Return target sample rate since we resample to this rate
+        if self.resampler.is_some() {
+            self.target_sample_rate
+        } else {
+            self.audio_sample_rate
+        }
+    }
+
+    /// Get audio channels (returns target/output channel count after resampling)
+    pub fn audio_channels(&self) -> u32 {
+        // Return target channels since we resample to the output layout
+        if self.resampler.is_some() {
+            self.target_channels
+        } else {
+            self.audio_channels
+        }
+    }
+
+    /// FFmpeg channel mask of the output audio layout (0 = no audio, or a
+    /// layout with a custom channel order that a mask cannot describe)
+    pub fn audio_channel_mask(&self) -> u64 {
+        match self.resampler {
+            Some(_) => Self::native_channel_mask(&self.target_channel_layout).unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    /// Check if audio is available
+    pub fn has_audio(&self) -> bool {
+        self.audio_decoder.is_some()
+    }
+
+    /// Decode the next audio frame
+    pub fn decode_next_audio_frame(&mut self) -> Result<Option<AudioFrame>> {
+        log::debug!("decode_next_audio_frame - start, queue_size={}", self.audio_packet_queue.len());
+
+        // Deliver frames already produced by the filter graph
+        if let Some(frame) = self.pending_audio_frames.pop_front() {
+            return Ok(Some(frame));
+        }
+
+        let audio_stream_idx = match self.audio_stream_index {
+            Some(idx) => {
+                log::debug!("decode_next_audio_frame - audio stream index={}", idx);
+                idx
+            }
+            None => {
+                log::warn!("decode_next_audio_frame - no audio stream index");
+                return Ok(None);
+            }
+        };
+
+        if self.audio_decoder.is_none() {
+            log::warn!("decode_next_audio_frame - no audio decoder");
+            return Ok(None);
+        }
+
+        let max_packets = 500;
+        let mut packet_count = 0;
+
+        loop {
+            if packet_count >= max_packets {
+                log::warn!("decode_next_audio_frame - exceeded max packet count");
+                return Ok(None);
+            }
+
+            // First, try to get audio packets from the queue (collected during video decoding)
+            let packet = if let Some(queued_packet) = self.audio_packet_queue.pop_front() {
+                log::trace!("decode_next_audio_frame - using queued packet, remaining={}", self.audio_packet_queue.len());
+                packet_count += 1;
+                Some(queued_packet)
+            } else {
+                // Queue is empty, read from stream
+                match self.input.packets().next() {
+                    Some((stream, packet)) => {
+                        packet_count += 1;
+                        if stream.index() == audio_stream_idx {
+                            Some(packet)
+                        } else if Some(stream.index()) == self.video_stream_index {
+                            // Queue video packets for later decoding
+                            log::trace!("decode_next_audio_frame - queueing video packet (stream {})", stream.index());
+                            self.video_packet_queue.push_back(packet);
+                            continue;
+                        } else if Some(stream.index()) == self.subtitle_stream_index {
+                            self.decode_subtitle_packet(&packet);
+                            continue;
+                        } else {
+                            // Skip other streams
+                            continue;
+                        }
+                    }
+                    None => {
+                        // End of stream - flush decoder
+                        if let Some(ref mut decoder) = self.audio_decoder {
+                            decoder.send_eof().ok();
+                        }
+                        return self.receive_audio_frame();
+                    }
+                }
+            };
+
+            if let Some(packet) = packet {
+                log::debug!("decode_next_audio_frame - sending audio packet {} to decoder", packet_count);
+                // Send packet to decoder
+                if let Some(ref mut decoder) = self.audio_decoder {
+                    match decoder.send_packet(&packet) {
+                        Ok(()) => {
+                            // Packet sent successfully
+                        }
+                        Err(e) => {
+                            // After seek, MP2/MP3 decoder may fail on first few packets
+                            // because they don't start at a valid frame boundary (sync header).
+                            // Skip invalid packets and continue to the next one.
+                            log::warn!(
+                                "decode_next_audio_frame - send_packet failed (skipping): {}",
+                                e
+                            );
+                            continue;
+                        }
+                    }
+                }
+
+                // Try to receive a frame
+                if let Some(frame) = self.receive_audio_frame()? {
+                    log::debug!("decode_next_audio_frame - returning frame with {} samples", frame.sample_count);
+                    return Ok(Some(frame));
+                }
+            }
+        }
+    }
+
+    /// Receive a decoded audio frame from the decoder
+    fn receive_audio_frame(&mut self) -> Result<Option<AudioFrame>> {
+        let decoder = match self.audio_decoder.as_mut() {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+
+        let mut decoded = AudioFrameFFmpeg::empty();
+
+        match decoder.receive_frame(&mut decoded) {
+            Ok(()) => {
+                // Get timestamp
+                let pts = decoded.pts().unwrap_or(0);
+
+                log::debug!(
+                    "receive_audio_frame - decoded: pts={}, samples={}, rate={}, channels={}, format={:?}",
+                    pts,
+                    decoded.samples(),
+                    decoded.rate(),
+                    decoded.channels(),
+                    decoded.format()
+                );
+
+                for (filtered, pts) in self.filter_audio(decoded, pts)? {
+                    // Convert to float32 stereo using resampler
+                    let pts_us = Self::pts_to_us(pts, self.audio_time_base);
+                    let frame = self.convert_audio_frame(&filtered, pts_us)?;
+                    self.audio_frame_number += 1;
+
+                    if frame.sample_count > 0 {
+                        log::debug!(
+                            "receive_audio_frame - output: samples={}, channels={}, data_len={}",
+                            frame.sample_count,
+                            frame.channels,
+                            frame.data.len()
+                        );
+                    }
+                    self.pending_audio_frames.push_back(frame);
+                }
+
+                Ok(self.pending_audio_frames.pop_front())
+            }
+            Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => {
+                // Need more data
+                log::trace!("receive_audio_frame - EAGAIN, need more data");
+                Ok(None)
+            }
+            Err(ffmpeg::Error::Eof) => {
+                // End of stream - release frames still held by the filter graph
+                log::debug!("receive_audio_frame - EOF");
+                self.flush_audio_filter()?;
+                Ok(self.pending_audio_frames.pop_front())
+            }
+            Err(e) => Err(Error::DecodeFailed(format!("Failed to receive audio frame: {}", e))),
+        }
+    }
+
+    /// Convert FFmpeg audio frame to our AudioFrame format
+    fn convert_audio_frame(&mut self, frame: &AudioFrameFFmpeg, pts_us: i64) -> Result<AudioFrame> {
+        let resampler = match self.resampler.as_mut() {
+            Some(r) => r,
+            None => {
+                return Err(Error::DecodeFailed("No audio resampler available".to_string()));
+            }
+        };
+
+        // Pre-allocate output frame with target format
+        // Calculate expected output samples based on sample rate conversion
+        let input_samples = frame.samples();
+        let input_rate = frame.rate() as u64;
+        let output_rate = self.target_sample_rate as u64;
+
+        // Calculate output samples (with some extra buffer for rounding)
+        let expected_output_samples = if input_rate > 0 {
+            ((input_samples as u64 * output_rate + input_rate - 1) / input_rate) as usize + 32
+        } else {
+            input_samples + 32
+        };
+
+        let output = self.audio_output;
+        let target_format = Self::sample_format_to_ffmpeg(output.sample_format, output.planar);
+        let target_layout = self.target_channel_layout;
+
+        // Create and allocate output frame
+        let mut resampled = ffmpeg::frame::Audio::new(target_format, expected_output_samples, target_layout);
+
+        // Set the sample rate on output frame
+        unsafe {
+            (*resampled.as_mut_ptr()).sample_rate = self.target_sample_rate as i32;
+        }
+
+        // Fix input frame channel layout if empty (some decoders don't set it)
+        // The resampler needs matching channel layout to what it was initialized with
+        let frame_layout = frame.channel_layout();
+        let input_frame = if frame_layout.is_empty() {
+            // Clone the frame and set the channel layout
+            let mut fixed_frame = unsafe {
+                let mut new_frame = ffmpeg::frame::Audio::empty();
+                // Copy frame data via FFI
+                ffmpeg::ffi::av_frame_ref(new_frame.as_mut_ptr(), frame.as_ptr());
+                new_frame
+            };
+
+            // Set the default channel layout for the channel count
+            let layout = ffmpeg::channel_layout::ChannelLayout::default(frame.channels() as i32);
+            fixed_frame.set_channel_layout(layout);
+
+            log::debug!(
+                "convert_audio_frame - fixed empty channel layout to {:?}",
+                layout
+            );
+
+            Some(fixed_frame)
+        } else {
+            None
+        };
+
+        // Use the fixed frame if we created one, otherwise use original
+        let input_ref = input_frame.as_ref().unwrap_or(frame);
+
+        log::trace!(
+            "convert_audio_frame - input: samples={}, rate={}, channels={}, format={:?}, layout={:?}",
+            input_ref.samples(),
+            input_ref.rate(),
+            input_ref.channels(),
+            input_ref.format(),
+            input_ref.channel_layout()
+        );
+
+        // Run resampler - use run() which handles the conversion
+        let delay = resampler.run(input_ref, &mut resampled).map_err(|e| {
+            log::error!("Audio resample failed: {}", e);
+            Error::DecodeFailed(format!("Audio resample failed: {}", e))
+        })?;
 
-        // Run resampler - use run() which handles the conversion
-        let delay = resampler.run(input_ref, &mut resampled).map_err(|e| {
-            log::error!("Audio resample failed: {}", e);
-            Error::DecodeFailed(format!("Audio resample failed: {}", e))
-        })?;
-
         // Get actual output sample count
         let actual_samples = resampled.samples();
 
-        log::trace!(
-            "convert_audio_frame - output: samples={}, delay={:?}",
-            actual_samples,
-            delay
-        );
+        log::trace!(
+            "convert_audio_frame - output: samples={}, delay={:?}",
+            actual_samples,
+            delay
+        );
+
+        if actual_samples == 0 {
+            // Resampler may need more input data before producing output
+            log::trace!("convert_audio_frame - no output samples yet (buffering)");
+
+            // Return empty frame - caller should continue feeding input
+            return Ok(AudioFrame::new(
+                AudioSamples::empty(output.sample_format),
+                output.planar,
+                0,
+                self.target_channels,
+                self.target_sample_rate,
+                pts_us,
+                0,
+                self.audio_frame_number,
+            ));
+        }
+
+        // Extract samples in the configured format from resampled frame
+        let output_samples = Self::extract_samples(&resampled, output.sample_format, output.planar);
+
+        let sample_count = if self.target_channels > 0 {
+            output_samples.len() / self.target_channels as usize
+        } else {
+            0
+        };
+
+        log::trace!(
+            "convert_audio_frame - extracted {} samples ({} frames)",
+            output_samples.len(),
+            sample_count
+        );
+
+        let duration_us = AudioFrame::calculate_duration_us(
+            sample_count as u32,
+            self.target_sample_rate,
+        );
+
+        Ok(AudioFrame::new(
+            output_samples,
+            output.planar,
+            sample_count as u32,
+            self.target_channels,
+            self.target_sample_rate,
+            pts_us,
+            duration_us,
+            self.audio_frame_number,
+        ))
+    }
+
+    /// Extract samples from a resampled FFmpeg audio frame.
+    ///
+    /// Packed frames are copied as-is; planar frames are concatenated channel
+    /// after channel.
+    fn extract_samples(
+        frame: &ffmpeg::frame::Audio,
+        format: SampleFormat,
+        planar: bool,
+    ) -> AudioSamples {
+        let samples = frame.samples();
+        let channels = frame.channels() as usize;
+
+        if samples == 0 || channels == 0 {
+            return AudioSamples::empty(format);
+        }
+
+        // Audio frames only set linesize[0], so planes are read via extended_data
+        let (planes, per_plane) = if planar {
+            (channels, samples)
+        } else {
+            (1, samples * channels)
+        };
+
+        match format {
+            SampleFormat::Float32 => {
+                AudioSamples::Float32(Self::copy_audio_planes(frame, planes, per_plane))
+            }
+            SampleFormat::Int16 => {
+                AudioSamples::Int16(Self::copy_audio_planes(frame, planes, per_plane))
+            }
+            SampleFormat::Int32 => {
+                AudioSamples::Int32(Self::copy_audio_planes(frame, planes, per_plane))
+            }
+        }
+    }
+
+    /// Copy `per_plane` samples of type `T` from each of the first `planes` planes
+    fn copy_audio_planes<T: Copy>(
+        frame: &ffmpeg::frame::Audio,
+        planes: usize,
+        per_plane: usize,
+    ) -> Vec<T> {
+        let mut output = Vec::with_capacity(planes * per_plane);
+        unsafe {
+            let extended_data = (*frame.as_ptr()).extended_data;
+            for plane in 0..planes {
+                let data = *extended_data.add(plane) as *const T;
+                output.extend_from_slice(std::slice::from_raw_parts(data, per_plane));
+            }
+        }
+        output
+    }
+
+    /// Map an output sample format to FFmpeg
+    pub(crate) fn sample_format_to_ffmpeg(
+        format: SampleFormat,
+        planar: bool,
+    ) -> ffmpeg::format::Sample {
+        use ffmpeg::format::sample::Type;
+
+        let kind = if planar { Type::Planar } else { Type::Packed };
+        match format {
+            SampleFormat::Float32 => ffmpeg::format::Sample::F32(kind),
+            SampleFormat::Int16 => ffmpeg::format::Sample::I16(kind),
+            SampleFormat::Int32 => ffmpeg::format::Sample::I32(kind),
+        }
+    }
+
+    /// Build a native channel layout from an FFmpeg channel mask
+    fn channel_layout_from_mask(mask: u64) -> Result<ffmpeg::channel_layout::ChannelLayout> {
+        unsafe {
+            let mut layout = std::mem::zeroed();
+            if ffmpeg::ffi::av_channel_layout_from_mask(&mut layout, mask) < 0 {
+                return Err(Error::InvalidFormat(format!(
+                    "Invalid channel layout mask: {:#x}",
+                    mask
+                )));
+            }
+            Ok(ffmpeg::channel_layout::ChannelLayout::from(layout))
+        }
+    }
+
+    /// Channel mask of a layout in FFmpeg's native order (None for custom or
+    /// unspecified orders, which a mask cannot describe)
+    fn native_channel_mask(layout: &ffmpeg::channel_layout::ChannelLayout) -> Option<u64> {
+        (layout.0.order == ffmpeg::ffi::AVChannelOrder::AV_CHANNEL_ORDER_NATIVE)
+            .then(|| layout.bits())
+    }
+
+    /// Create an audio resampler.
+    ///
+    /// In the ITU modes, downmixes to mono or stereo use the BS.775 matrix
+    /// as is, for integer output too (swresample would otherwise scale it
+    /// down so no sum can clip).
+    fn create_resampler(
+        source_format: ffmpeg::format::Sample,
+        source_layout: ffmpeg::channel_layout::ChannelLayout,
+        source_rate: u32,
+        target_format: ffmpeg::format::Sample,
+        target_layout: ffmpeg::channel_layout::ChannelLayout,
+        target_rate: u32,
+        downmix: DownmixMode,
+    ) -> Result<ResamplerContext> {
+        let mut resampler = ResamplerContext::get_with(
+            source_format,
+            source_layout,
+            source_rate,
+            target_format,
+            target_layout,
+            target_rate,
+            Self::downmix_options(downmix),
+        )
+        .map_err(|e| Error::DecodeFailed(format!("Failed to create audio resampler: {}", e)))?;
+
+        let matrix = Self::native_channel_mask(&source_layout)
+            .zip(Self::native_channel_mask(&target_layout))
+            .and_then(|(source, target)| Self::itu_downmix_matrix(source, target, downmix));
+        if let Some(matrix) = matrix {
+            // A custom matrix can only be set while the context is closed
+            let result = unsafe {
+                let swr = resampler.as_mut_ptr();
+                ffmpeg::ffi::swr_close(swr);
+                match ffmpeg::ffi::swr_set_matrix(swr, matrix.as_ptr(), source_layout.channels()) {
+                    0 => ffmpeg::ffi::swr_init(swr),
+                    error => error,
+                }
+            };
+            if result < 0 {
+                return Err(Error::DecodeFailed(format!(
+                    "Failed to set the downmix matrix: {}",
+                    ffmpeg::Error::from(result)
+                )));
+            }
+        }
+        Ok(resampler)
+    }
 
-        if actual_samples == 0 {
-            // Resampler may need more input data before producing output
-            log::trace!("convert_audio_frame - no output samples yet (buffering)");
+    /// ITU-R BS.775 downmix matrix from `source` to a mono or stereo `target`
+    /// (FFmpeg channel masks), one row of source channel gains per output
+    /// channel.
+    ///
+    /// None in the default mode, for other targets, when channels are not
+    /// reduced, and for sources with channels BS.775 does not place (e.g.
+    /// top or wide channels), which keep swresample's matrix.
+    fn itu_downmix_matrix(source: u64, target: u64, mode: DownmixMode) -> Option<Vec<f64>> {
+        const MINUS_3DB: f64 = std::f64::consts::FRAC_1_SQRT_2;
+
+        let lfe = match mode {
+            DownmixMode::Default => return None,
+            DownmixMode::Itu => 0.0,
+            DownmixMode::ItuWithLfe => MINUS_3DB,
+        };
+        if source.count_ones() <= target.count_ones() {
+            return None;
+        }
 
-            // Return empty frame - caller should continue feeding input
-            return Ok(AudioFrame::new(
-                Vec::new(),
-                0,
-                self.target_channels,
-                self.target_sample_rate,
-                pts_us,
-                0,
-                self.audio_frame_number,
-            ));
+        // Left and right gains of each source channel, in mask order
+        let gains = (0..64)
+            .map(|bit| 1u64 << bit)
+            .filter(|channel| source & channel != 0)
+            .map(|channel| match channel {
+                // Front left, front left of center
+                0x1 | 0x40 => Some((1.0, 0.0)),
+                0x2 | 0x80 => Some((0.0, 1.0)),
+                // Front center
+                0x4 => Some((MINUS_3DB, MINUS_3DB)),
+                // LFE
+                0x8 => Some((lfe, lfe)),
+                // Back left, side left
+                0x10 | 0x200 => Some((MINUS_3DB, 0.0)),
+                0x20 | 0x400 => Some((0.0, MINUS_3DB)),
+                // Back center
+                0x100 => Some((0.5, 0.5)),
+                _ => None,
+            })
+            .collect::<Option<Vec<(f64, f64)>>>()?;
+
+        match target {
+            // Stereo
+            0x3 => Some(
+                gains
+                    .iter()
+                    .map(|&(left, _)| left)
+                    .chain(gains.iter().map(|&(_, right)| right))
+                    .collect(),
+            ),
+            // Mono: both sides at -3 dB (center 0 dB, surrounds -6 dB), LFE
+            // as in stereo
+            0x4 => Some(
+                (0..64)
+                    .map(|bit| 1u64 << bit)
+                    .filter(|channel| source & channel != 0)
+                    .zip(&gains)
+                    .map(|(channel, &(left, right))| match channel {
+                        0x8 => lfe,
+                        _ => (left + right) * MINUS_3DB,
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Resampler options for a downmix mode
+    fn downmix_options(mode: DownmixMode) -> ffmpeg::Dictionary<'static> {
+        // -3 dB as used by ITU-R BS.775
+        const MINUS_3DB: &str = "0.7071067811865476";
+
+        let mut options = ffmpeg::Dictionary::new();
+        match mode {
+            DownmixMode::Default => {}
+            DownmixMode::Itu => {
+                options.set("center_mix_level", MINUS_3DB);
+                options.set("surround_mix_level", MINUS_3DB);
+                options.set("lfe_mix_level", "0");
+            }
+            DownmixMode::ItuWithLfe => {
+                options.set("center_mix_level", MINUS_3DB);
+                options.set("surround_mix_level", MINUS_3DB);
+                options.set("lfe_mix_level", MINUS_3DB);
+            }
+        }
+        options
+    }
+
+    /// Describe a channel layout (e.g. "5.1(side)").
+    ///
+    /// Layouts without channel order are described as the default layout for
+    /// their channel count, which is how they are decoded.
+    fn describe_channel_layout(layout: &ffmpeg::ffi::AVChannelLayout) -> Option<String> {
+        if layout.nb_channels <= 0 {
+            return None;
         }
 
-        // Extract float32 samples from resampled frame
-        let output_samples = Self::extract_float_samples_static(&resampled);
+        let layout = if layout.order == ffmpeg::ffi::AVChannelOrder::AV_CHANNEL_ORDER_UNSPEC {
+            ffmpeg::channel_layout::ChannelLayout::default(layout.nb_channels).0
+        } else {
+            *layout
+        };
 
-        let sample_count = if self.target_channels > 0 {
-            output_samples.len() / self.target_channels as usize
+        let mut buf = [0 as std::ffi::c_char; 128];
+        unsafe {
+            if ffmpeg::ffi::av_channel_layout_describe(&layout, buf.as_mut_ptr(), buf.len()) < 0 {
+                return None;
+            }
+            Some(
+                std::ffi::CStr::from_ptr(buf.as_ptr())
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    /// Drop any samples buffered in the resampler
+    fn flush_resampler(&mut self) {
+        let output = self.audio_output;
+        let target_layout = self.target_channel_layout;
+
+        if let Some(ref mut resampler) = self.resampler {
+            // Create a temporary output frame to receive any remaining samples (discard them)
+            let target_format = Self::sample_format_to_ffmpeg(output.sample_format, output.planar);
+            let mut flush_output = ffmpeg::frame::Audio::new(target_format, 4096, target_layout);
+            // Flush may fail if no samples buffered, ignore the error
+            let _ = resampler.flush(&mut flush_output);
+        }
+    }
+
+    /// Seek audio stream
+    pub fn seek_audio(&mut self, time_us: i64) -> Result<()> {
+        log::debug!("seek_audio - seeking to {} us", time_us);
+
+        // Seek using container-level seek (affects all streams)
+        self.input
+            .seek(time_us, ..time_us)
+            .map_err(|e| Error::SeekFailed(time_us))?;
+
+        // Flush audio decoder
+        if let Some(ref mut decoder) = self.audio_decoder {
+            decoder.flush();
+        }
+        self.reset_audio_pipeline();
+
+        self.audio_frame_number = 0;
+        Ok(())
+    }
+
+    /// Flush audio decoder buffers
+    pub fn flush_audio(&mut self) {
+        if let Some(ref mut decoder) = self.audio_decoder {
+            decoder.flush();
+        }
+        self.reset_audio_pipeline();
+    }
+
+    /// Build a keyframe index by scanning all packets in the file.
+    ///
+    /// This is called during prepare() to enable fast seeking.
+    /// The index contains (pts_us, byte_position) pairs for all keyframes.
+    ///
+    /// # Arguments
+    /// * `max_keyframes` - Maximum number of keyframes to index (to limit memory usage)
+    ///
+    /// # Returns
+    /// The number of keyframes indexed
+    pub fn build_keyframe_index(&mut self, max_keyframes: usize) -> Result<usize> {
+        let video_stream_index = match self.video_stream_index {
+            Some(idx) => idx,
+            None => {
+                log::debug!("No video stream, skipping keyframe index");
+                return Ok(0);
+            }
+        };
+
+        log::info!("Building keyframe index (max: {} entries)...", max_keyframes);
+        let start_time = std::time::Instant::now();
+
+        let mut index = KeyframeIndex::new();
+        let time_base = self.video_time_base;
+        let mut packet_count: i64 = 0;
+        let mut complete = false;
+
+        // Read all packets and collect keyframe positions
+        loop {
+            match self.input.packets().next() {
+                Some((stream, packet)) => {
+                    // Collect subtitles in the same pass
+                    if Some(stream.index()) == self.subtitle_stream_index {
+                        self.decode_subtitle_packet(&packet);
+                        continue;
+                    }
+
+                    // Only process video stream packets
+                    if stream.index() != video_stream_index {
+                        continue;
+                    }
+                    packet_count += 1;
+
+                    // Check if this is a keyframe
+                    if packet.is_key() {
+                        if let Some(pts) = packet.pts() {
+                            let pts_us = Self::pts_to_us(pts, time_base);
+                            let position = packet.position();
+
+                            // Only add if position is valid (>= 0)
+                            if position >= 0 {
+                                index.add(pts_us, position as i64);
+
+                                if index.len() >= max_keyframes {
+                                    log::warn!(
+                                        "Keyframe index limit reached: {} entries",
+                                        max_keyframes
+                                    );
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+                None => {
+                    complete = true;
+                    break;
+                }
+            }
+        }
+
+        // A full pass counts every frame (one packet per frame)
+        if complete && packet_count > 0 {
+            log::info!("Counted {} video frames", packet_count);
+            self.video_frame_count = packet_count;
+        }
+
+        // Sort the index by PTS (should already be sorted, but ensure it)
+        index.sort();
+
+        let count = index.len();
+        let elapsed = start_time.elapsed();
+
+        log::info!(
+            "Keyframe index built: {} entries in {:?} ({:.1} entries/sec)",
+            count,
+            elapsed,
+            count as f64 / elapsed.as_secs_f64().max(0.001)
+        );
+
+        // Store the index
+        self.keyframe_index = Some(index);
+
+        // Seek back to the beginning of the file
+        self.seek(0)?;
+
+        Ok(count)
+    }
+
+    /// Get a reference to the keyframe index
+    pub fn keyframe_index(&self) -> Option<&KeyframeIndex> {
+        self.keyframe_index.as_ref()
+    }
+
+    /// Check if keyframe index is available
+    pub fn has_keyframe_index(&self) -> bool {
+        self.keyframe_index.is_some()
+    }
+
+    /// Decode thumbnails evenly spaced over a time range, calling
+    /// `on_thumbnail` as each one completes.
+    ///
+    /// Without `options.exact`, each thumbnail is the keyframe nearest to its
+    /// time: looked up in `keyframes` when it covers the time (neighbouring
+    /// thumbnails sharing a keyframe reuse it), or the keyframe a seek lands
+    /// on. Exact thumbnails are the first frame at or after each time;
+    /// targets in the GOP being decoded continue decoding instead of seeking
+    /// again, and a time more than 1000 frames past its keyframe fails.
+    ///
+    /// Returns the number of thumbnails delivered, which is smaller than
+    /// requested when the stream ends early. `on_thumbnail` returns false to
+    /// cancel, failing with `Error::Cancelled`.
+    pub fn thumbnails(
+        &mut self,
+        options: &ThumbnailOptions,
+        keyframes: Option<&KeyframeIndex>,
+        on_thumbnail: &mut dyn FnMut(Thumbnail) -> bool,
+    ) -> Result<usize> {
+        if self.video_stream_index.is_none() {
+            return Err(Error::InvalidFormat("No video stream".to_string()));
+        }
+
+        let end_us = match options.end_us {
+            Some(end_us) => end_us,
+            None => self.duration_us,
+        };
+        let times = thumbnail_times(options.start_us, end_us, options.count, options.interval_us);
+        let keyframes = keyframes.filter(|index| !index.is_empty());
+        let half_frame_us = if self.frame_rate > 0.0 {
+            (500_000.0 / self.frame_rate) as i64
         } else {
             0
         };
+        log::info!(
+            "Generating {} thumbnails from {} to {} us ({}, keyframe index: {})",
+            times.len(),
+            options.start_us,
+            end_us,
+            if options.exact { "exact" } else { "keyframes" },
+            keyframes.is_some()
+        );
+
+        self.set_output_size(Some(options.size));
+        let mut previous: Option<VideoFrame> = None;
+        for (index, &time_us) in times.iter().enumerate() {
+            let frame = if options.exact {
+                self.exact_thumbnail(time_us, half_frame_us, keyframes, previous.as_ref())?
+            } else {
+                self.keyframe_thumbnail(time_us, half_frame_us, keyframes, previous.as_ref())?
+            };
+            // Audio is not needed; don't let its packets pile up between seeks
+            self.audio_packet_queue.clear();
+
+            let frame = match frame {
+                Some(frame) => frame,
+                None => {
+                    log::info!(
+                        "Thumbnails: stream ended after {} of {}",
+                        index,
+                        times.len()
+                    );
+                    return Ok(index);
+                }
+            };
+            let thumbnail = Thumbnail {
+                index,
+                time_us,
+                frame: frame.clone(),
+            };
+            if !on_thumbnail(thumbnail) {
+                return Err(Error::Cancelled);
+            }
+            previous = Some(frame);
+        }
+        Ok(times.len())
+    }
+
+    /// The keyframe nearest to `time_us`
+    fn keyframe_thumbnail(
+        &mut self,
+        time_us: i64,
+        half_frame_us: i64,
+        keyframes: Option<&KeyframeIndex>,
+        previous: Option<&VideoFrame>,
+    ) -> Result<Option<VideoFrame>> {
+        // The index has a size limit, so the keyframe nearest to times past
+        // its last entry may be missing from it
+        let after = keyframes.and_then(|index| index.find_keyframe_after(time_us));
+        let (index, after) = match keyframes.zip(after) {
+            Some(found) => found,
+            None => {
+                self.seek(time_us)?;
+                return self.decode_next_frame();
+            }
+        };
+
+        let (pts_us, position) = match index.find_keyframe_before(time_us) {
+            Some(before) if time_us - before.0 <= after.0 - time_us => before,
+            _ => after,
+        };
+        if let Some(previous) = previous.filter(|frame| frame.pts_us == pts_us) {
+            return Ok(Some(previous.clone()));
+        }
+
+        if self.seek_to_byte_position(position).is_err() {
+            self.seek(pts_us)?;
+        }
+        // Skip leading pictures that reference the previous GOP
+        for _ in 0..100 {
+            match self.decode_next_frame()? {
+                Some(frame) if frame.pts_us >= pts_us - half_frame_us => return Ok(Some(frame)),
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+        // The indexed keyframe was not reached; use the one a seek lands on
+        self.seek(time_us)?;
+        self.decode_next_frame()
+    }
+
+    /// The first frame at or after `time_us`
+    fn exact_thumbnail(
+        &mut self,
+        time_us: i64,
+        half_frame_us: i64,
+        keyframes: Option<&KeyframeIndex>,
+        previous: Option<&VideoFrame>,
+    ) -> Result<Option<VideoFrame>> {
+        // Thumbnails denser than the frame rate repeat the previous frame
+        if let Some(previous) = previous.filter(|frame| frame.pts_us >= time_us - half_frame_us) {
+            return Ok(Some(previous.clone()));
+        }
+
+        // Past its last entry the index misses keyframes (it has a size limit)
+        let keyframes = keyframes.filter(|index| index.find_keyframe_after(time_us).is_some());
+        let keyframe_before = |time_us: i64| {
+            keyframes
+                .and_then(|index| index.find_keyframe_before(time_us))
+                .map(|(pts_us, _)| pts_us)
+        };
+        let same_gop = previous.is_some_and(|frame| {
+            keyframes.is_some() && keyframe_before(frame.pts_us) == keyframe_before(time_us)
+        });
+        if !same_gop {
+            match keyframes.and_then(|index| index.find_keyframe_before(time_us)) {
+                Some((_, position)) if self.seek_to_byte_position(position).is_ok() => {}
+                _ => self.seek(time_us)?,
+            }
+        }
+
+        // Limit to prevent infinite loop (a long GOP at a high frame rate)
+        for _ in 0..1000 {
+            match self.decode_next_frame()? {
+                Some(frame) if frame.pts_us >= time_us - half_frame_us => return Ok(Some(frame)),
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+        Err(Error::DecodeFailed(format!(
+            "No frame at {} us within 1000 frames of its keyframe",
+            time_us
+        )))
+    }
+
+    /// Decode the frames of one stream over a time range: the pass shared
+    /// by waveform, loudness and scene analysis.
+    ///
+    /// `analyze` receives each frame and returns false once the range is
+    /// complete. `report` is called with the state and the completed fraction
+    /// (0.0-1.0) each time another percent is done, and returns false to
+    /// cancel (failing with `Error::Cancelled`).
+    fn analyze<F: AnalysisFrame, S>(
+        &mut self,
+        start_us: i64,
+        end_us: Option<i64>,
+        state: &mut S,
+        mut analyze: impl FnMut(&mut S, &F) -> Result<bool>,
+        mut report: impl FnMut(&S, f64) -> bool,
+    ) -> Result<()> {
+        if !F::has_stream(self) {
+            return Err(Error::InvalidFormat(format!("No {} stream", F::KIND)));
+        }
+        if start_us > 0 {
+            self.seek(start_us)?;
+        }
+        let end_us = end_us.unwrap_or(self.duration_us);
+        log::info!("Analyzing {} from {} to {} us", F::KIND, start_us, end_us);
+
+        let mut reported = 0.0;
+        while let Some(frame) = F::decode_next(self)? {
+            if !analyze(state, &frame)? {
+                break;
+            }
+
+            let fraction = export_progress(frame.end_us(), start_us, end_us);
+            if fraction - reported >= 0.01 {
+                reported = fraction;
+                if !report(state, fraction) {
+                    return Err(Error::Cancelled);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reduce the selected audio track to waveform buckets.
+    ///
+    /// `progress` is called with the buckets completed so far and the
+    /// completed fraction (0.0-1.0) each time another percent is done, and
+    /// returns false to cancel (failing with `Error::Cancelled`).
+    pub fn waveform(
+        &mut self,
+        options: &WaveformOptions,
+        progress: &mut dyn FnMut(&Waveform, f64) -> bool,
+    ) -> Result<Waveform> {
+        log::info!(
+            "Generating waveform ({} samples per bucket)",
+            options.samples_per_bucket
+        );
+        let mut builder: Option<WaveformBuilder> = None;
+        self.analyze(
+            options.start_us,
+            options.end_us,
+            &mut builder,
+            |builder, frame: &AudioFrame| {
+                builder
+                    .get_or_insert_with(|| {
+                        WaveformBuilder::new(
+                            frame.sample_rate,
+                            frame.channels,
+                            options.samples_per_bucket,
+                            options.start_us,
+                            options.end_us,
+                        )
+                    })
+                    .push(frame)
+            },
+            |builder, fraction| {
+                builder
+                    .as_ref()
+                    .map_or(true, |builder| progress(builder.waveform(), fraction))
+            },
+        )?;
+
+        let waveform = builder
+            .ok_or_else(|| Error::DecodeFailed("No audio in the requested range".to_string()))?
+            .finish();
+        progress(&waveform, 1.0);
+        log::info!(
+            "Waveform complete: {} buckets x {} channels",
+            waveform.bucket_count(),
+            waveform.channel_count()
+        );
+        Ok(waveform)
+    }
+
+    /// Measure the loudness of the audio stream over a time range.
+    ///
+    /// Channels are weighted by the stream's channel layout. `progress`
+    /// receives the points measured so far and the completed fraction
+    /// (0.0-1.0) and returns false to cancel.
+    pub fn loudness(
+        &mut self,
+        options: &LoudnessOptions,
+        progress: &mut dyn FnMut(&[LoudnessPoint], f64) -> bool,
+    ) -> Result<LoudnessReport> {
+        let channel_mask = self.audio_channel_mask();
+        log::info!("Measuring loudness (channel mask {:#x})", channel_mask);
+        let mut meter: Option<LoudnessMeter> = None;
+        self.analyze(
+            options.start_us,
+            options.end_us,
+            &mut meter,
+            |meter, frame: &AudioFrame| {
+                meter
+                    .get_or_insert_with(|| {
+                        LoudnessMeter::new(
+                            frame.sample_rate,
+                            frame.channels,
+                            channel_mask,
+                            options.start_us,
+                            options.end_us,
+                        )
+                    })
+                    .push(frame)
+            },
+            |meter, fraction| {
+                meter
+                    .as_ref()
+                    .map_or(true, |meter| progress(meter.series(), fraction))
+            },
+        )?;
+
+        let report = meter
+            .ok_or_else(|| Error::DecodeFailed("No audio in the requested range".to_string()))?
+            .finish();
+        progress(&report.series, 1.0);
+        log::info!(
+            "Loudness: {:.1} LUFS integrated, {:.1} LU range, {:.1} dBTP true peak",
+            report.summary.integrated_lufs,
+            report.summary.loudness_range_lu,
+            report.summary.true_peak_dbtp
+        );
+        Ok(report)
+    }
 
-        log::trace!(
-            "convert_audio_frame - extracted {} float samples ({} frames)",
-            output_samples.len(),
-            sample_count
+    /// Find the cuts between shots of the video stream over a time range.
+    ///
+    /// Frames are decoded at `options.analysis_size`, so the output size is
+    /// changed for this context. `progress` receives the cuts found so far
+    /// and the completed fraction (0.0-1.0) and returns false to cancel.
+    pub fn detect_scenes(
+        &mut self,
+        options: &SceneDetectionOptions,
+        progress: &mut dyn FnMut(&[SceneCut], f64) -> bool,
+    ) -> Result<Vec<SceneCut>> {
+        log::info!(
+            "Detecting scenes ({:?}, threshold {})",
+            options.method,
+            options.threshold
         );
+        self.set_output_size(options.analysis_size);
+        let mut detector = SceneDetector::new(options);
+        self.analyze(
+            options.start_us,
+            options.end_us,
+            &mut detector,
+            |detector, frame: &VideoFrame| {
+                if options.end_us.is_some_and(|end_us| frame.pts_us >= end_us) {
+                    return Ok(false);
+                }
+                detector.push(frame)?;
+                Ok(true)
+            },
+            |detector, fraction| progress(detector.cuts(), fraction),
+        )?;
+
+        let cuts = detector.finish();
+        progress(&cuts, 1.0);
+        log::info!("Scene detection complete: {} cuts", cuts.len());
+        Ok(cuts)
+    }
 
-        let duration_us = AudioFrame::calculate_duration_us(
-            sample_count as u32,
-            self.target_sample_rate,
+    /// Stream-copy a time range of the selected tracks into a new file.
+    ///
+    /// The in point snaps back to the video keyframe that starts its GOP,
+    /// looked up in `keyframes` when the index covers it and found by seeking
+    /// otherwise. The video track ends before the first keyframe at or after
+    /// the out point and the other tracks end with it. Timestamps are
+    /// rewritten to start at zero.
+    ///
+    /// `progress` receives the completed fraction (0.0-1.0) and returns false
+    /// to cancel. A cancelled or failed export removes the partial file.
+    pub fn export(
+        &mut self,
+        path: &Path,
+        options: &ExportOptions,
+        keyframes: Option<&KeyframeIndex>,
+        progress: &mut dyn FnMut(f64) -> bool,
+    ) -> Result<ExportSummary> {
+        let format = options
+            .format
+            .or_else(|| ExportFormat::from_path(path))
+            .ok_or_else(|| {
+                Error::InvalidFormat(format!("Unknown export container: {}", path.display()))
+            })?;
+        if options
+            .end_us
+            .is_some_and(|end_us| end_us <= options.start_us)
+        {
+            return Err(Error::InvalidFormat(format!(
+                "Empty export range: {}..{:?} us",
+                options.start_us, options.end_us
+            )));
+        }
+
+        log::info!(
+            "Exporting {}..{:?} us to {} ({})",
+            options.start_us,
+            options.end_us,
+            path.display(),
+            format.muxer_name()
         );
+        let mut output = ffmpeg::format::output_as(path, format.muxer_name()).map_err(|e| {
+            Error::InvalidFormat(format!("Failed to create {}: {}", path.display(), e))
+        })?;
 
-        Ok(AudioFrame::new(
-            output_samples,
-            sample_count as u32,
-            self.target_channels,
-            self.target_sample_rate,
-            pts_us,
-            duration_us,
-            self.audio_frame_number,
-        ))
+        let result = self.copy_to_output(&mut output, format, options, keyframes, progress);
+        drop(output);
+        match result {
+            Ok(summary) => {
+                log::info!(
+                    "Exported {} packets ({} bytes), {}..{} us",
+                    summary.packet_count,
+                    summary.byte_count,
+                    summary.start_us,
+                    summary.end_us
+                );
+                Ok(summary)
+            }
+            Err(e) => {
+                log::warn!("Export failed: {}", e);
+                let _ = std::fs::remove_file(path);
+                Err(e)
+            }
+        }
     }
 
-    /// Extract float32 samples from an FFmpeg audio frame (static version)
-    fn extract_float_samples_static(frame: &ffmpeg::frame::Audio) -> Vec<f32> {
-        let samples = frame.samples();
-        let channels = frame.channels() as usize;
-
-        if samples == 0 || channels == 0 {
-            return Vec::new();
+    /// Copy the export range into an opened output context
+    fn copy_to_output(
+        &mut self,
+        output: &mut ffmpeg::format::context::Output,
+        format: ExportFormat,
+        options: &ExportOptions,
+        keyframes: Option<&KeyframeIndex>,
+        progress: &mut dyn FnMut(f64) -> bool,
+    ) -> Result<ExportSummary> {
+        let tracks = self.export_tracks(output, format, &options.tracks)?;
+        if tracks.is_empty() {
+            return Err(Error::InvalidFormat("No track to export".to_string()));
         }
 
-        let total_samples = samples * channels;
-        let mut output = Vec::with_capacity(total_samples);
+        // One output stream per copied track, with the source parameters
+        let oformat = unsafe { (*output.as_ptr()).oformat };
+        let time_bases: Vec<Rational> = self.input.streams().map(|s| s.time_base()).collect();
+        let mut stream_map = vec![None; time_bases.len()];
+        let mut video_index = None;
+        let mut waits_for_end = Vec::with_capacity(tracks.len());
+        for (output_index, &index) in tracks.iter().enumerate() {
+            let stream = self
+                .input
+                .stream(index)
+                .ok_or_else(|| Error::InvalidFormat(format!("Stream {} not found", index)))?;
+            let parameters = stream.parameters();
+            let medium = parameters.medium();
+            let codec_id = parameters.id();
+            let source_tag = unsafe { (*parameters.as_ptr()).codec_tag };
+
+            let mut output_stream = output
+                .add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))
+                .map_err(|e| Error::InvalidFormat(format!("Failed to add stream: {}", e)))?;
+            output_stream.set_parameters(parameters);
+            output_stream.set_time_base(stream.time_base());
+            output_stream.set_avg_frame_rate(stream.avg_frame_rate());
+            if options.copy_metadata {
+                output_stream.set_metadata(stream.metadata().to_owned());
+            }
+            unsafe {
+                let raw = output_stream.as_mut_ptr();
+                (*raw).disposition = (*stream.as_ptr()).disposition;
+                (*(*raw).codecpar).codec_tag =
+                    Self::export_codec_tag(oformat, format, codec_id, source_tag);
+            }
+
+            if medium == MediaType::Video && video_index.is_none() {
+                video_index = Some(index);
+            }
+            // Sparse subtitle tracks do not hold up the end of the copy
+            waits_for_end.push(medium != MediaType::Subtitle);
+            stream_map[index] = Some(output_index);
+        }
+        if !waits_for_end.contains(&true) {
+            waits_for_end.fill(true);
+        }
 
-        // Get data from plane 0 (packed format)
-        let data = frame.data(0);
-        let float_slice = unsafe {
-            std::slice::from_raw_parts(
-                data.as_ptr() as *const f32,
-                total_samples.min(data.len() / 4),
-            )
+        // The playback index only covers the decoder's video stream, and is
+        // only exact for points before its last entry (it has a size limit)
+        let keyframes = keyframes.filter(|index| {
+            video_index.is_some()
+                && video_index == self.video_stream_index
+                && index.find_keyframe_after(options.start_us).is_some()
+        });
+        let keyframe_start_us = keyframes
+            .and_then(|index| index.find_keyframe_before(options.start_us))
+            .map(|(pts_us, _)| pts_us);
+        let keyframe_end_us = keyframes
+            .zip(options.end_us)
+            .and_then(|(index, end_us)| index.find_keyframe_after(end_us))
+            .map(|(pts_us, _)| pts_us);
+
+        // Find the in point: the demuxer lands on a keyframe at or before the
+        // seek target. Packets of the other tracks are kept until it is known.
+        self.seek(keyframe_start_us.unwrap_or(options.start_us).max(0))?;
+        let snap_floor_us = keyframe_start_us.unwrap_or(i64::MIN);
+        let mut pending = Vec::new();
+        let start_us = match video_index {
+            Some(video_index) => loop {
+                let (index, packet) = match self.input.packets().next() {
+                    Some((stream, packet)) => (stream.index(), packet),
+                    None => {
+                        return Err(Error::InvalidFormat(
+                            "No video keyframe in export range".to_string(),
+                        ))
+                    }
+                };
+                if stream_map[index].is_none() {
+                    continue;
+                }
+                if index != video_index {
+                    pending.push(packet);
+                    continue;
+                }
+                // Video before the first keyframe cannot be decoded without its GOP
+                let keyframe_us = packet
+                    .pts()
+                    .filter(|_| packet.is_key())
+                    .map(|pts| Self::pts_to_us(pts, time_bases[index]))
+                    .filter(|&pts_us| pts_us >= snap_floor_us);
+                if let Some(pts_us) = keyframe_us {
+                    pending.push(packet);
+                    break pts_us;
+                }
+            },
+            None => options.start_us,
         };
+        // Without index coverage the video cut is only known once reached
+        let mut cut = ExportCut::new(
+            start_us,
+            options.end_us,
+            keyframe_end_us,
+            video_index.is_some(),
+        );
+        let end_us = keyframe_end_us.or(options.end_us).unwrap_or(i64::MAX);
+        let progress_end_us = end_us.min(self.duration_us.max(start_us));
 
-        output.extend_from_slice(float_slice);
-        output
-    }
+        if options.copy_metadata {
+            output.set_metadata(self.input.metadata().to_owned());
+        }
+        if options.copy_chapters {
+            for chapter in self.read_chapters(self.duration_us) {
+                let Some((chapter_start, chapter_end)) =
+                    clip_chapter(chapter.start_us, chapter.end_us, start_us, end_us)
+                else {
+                    continue;
+                };
+                let time_base = Rational::new(chapter.time_base.0, chapter.time_base.1);
+                let microseconds = Rational::new(1, 1_000_000);
+                output
+                    .add_chapter(
+                        chapter.id,
+                        time_base,
+                        chapter_start.rescale(microseconds, time_base),
+                        chapter_end.rescale(microseconds, time_base),
+                        chapter.title.unwrap_or_default(),
+                    )
+                    .map_err(|e| Error::InvalidFormat(format!("Failed to add chapter: {}", e)))?;
+            }
+        }
 
-    /// Seek audio stream
-    pub fn seek_audio(&mut self, time_us: i64) -> Result<()> {
-        log::debug!("seek_audio - seeking to {} us", time_us);
+        output.write_header().map_err(|e| Error::FFmpeg {
+            code: e.into(),
+            message: format!("Failed to write header: {}", e),
+        })?;
+        let output_time_bases: Vec<Rational> = output.streams().map(|s| s.time_base()).collect();
+
+        let mut summary = ExportSummary {
+            start_us,
+            end_us: start_us,
+            tracks: tracks.clone(),
+            packet_count: 0,
+            byte_count: 0,
+        };
+        let mut finished = vec![false; tracks.len()];
+        let mut reported = 0.0;
+        let mut pending = VecDeque::from(pending);
+        let mut held = Vec::new();
+        loop {
+            let mut packet = match pending.pop_front() {
+                Some(packet) => packet,
+                None => match self.input.packets().next() {
+                    Some((_, packet)) => packet,
+                    None if held.is_empty() => break,
+                    None => {
+                        // Video ended before a keyframe past the out point
+                        cut.finish();
+                        pending.extend(held.drain(..));
+                        continue;
+                    }
+                },
+            };
+            let index = packet.stream();
+            let Some(output_index) = stream_map.get(index).copied().flatten() else {
+                continue;
+            };
+            let Some(timestamp) = packet.pts().or(packet.dts()) else {
+                continue;
+            };
+            if finished[output_index] {
+                continue;
+            }
+            let time_base = time_bases[index];
+            let timestamp_us = Self::pts_to_us(timestamp, time_base);
 
-        // Seek using container-level seek (affects all streams)
-        self.input
-            .seek(time_us, ..time_us)
-            .map_err(|e| Error::SeekFailed(time_us))?;
+            // Video ends at a keyframe so no kept frame references a dropped
+            // one, and the other tracks end at the same point
+            let action = if Some(index) == video_index {
+                cut.video(packet.is_key(), timestamp_us)
+            } else {
+                cut.other(timestamp_us)
+            };
+            if action == CutAction::Hold {
+                held.push(packet);
+                continue;
+            }
+            if cut.is_known() && !held.is_empty() {
+                // Decide the held packets before anything that follows them
+                pending.push_front(packet);
+                for packet in held.drain(..).rev() {
+                    pending.push_front(packet);
+                }
+                continue;
+            }
+            if action == CutAction::End {
+                finished[output_index] = true;
+                if finished
+                    .iter()
+                    .zip(&waits_for_end)
+                    .all(|(&done, &wait)| done || !wait)
+                {
+                    break;
+                }
+                continue;
+            }
+            if timestamp_us < start_us {
+                continue;
+            }
 
-        // Flush audio decoder
-        if let Some(ref mut decoder) = self.audio_decoder {
-            decoder.flush();
+            let offset = Self::us_to_pts(start_us, time_base);
+            let duration_us = Self::pts_to_us(packet.duration(), time_base);
+            let size = packet.size();
+            packet.set_pts(packet.pts().map(|pts| pts - offset));
+            packet.set_dts(packet.dts().map(|dts| dts - offset));
+            packet.rescale_ts(time_base, output_time_bases[output_index]);
+            packet.set_position(-1);
+            packet.set_stream(output_index);
+            packet
+                .write_interleaved(output)
+                .map_err(|e| Error::FFmpeg {
+                    code: e.into(),
+                    message: format!("Failed to write packet: {}", e),
+                })?;
+
+            summary.packet_count += 1;
+            summary.byte_count += size as u64;
+            summary.end_us = summary.end_us.max(timestamp_us + duration_us);
+
+            let fraction = export_progress(timestamp_us, start_us, progress_end_us);
+            if fraction - reported >= 0.001 {
+                reported = fraction;
+                if !progress(fraction) {
+                    return Err(Error::Cancelled);
+                }
+            }
         }
 
-        self.audio_frame_number = 0;
-        Ok(())
+        output.write_trailer().map_err(|e| Error::FFmpeg {
+            code: e.into(),
+            message: format!("Failed to write trailer: {}", e),
+        })?;
+        progress(1.0);
+        Ok(summary)
     }
 
-    /// Flush audio decoder buffers
-    pub fn flush_audio(&mut self) {
-        if let Some(ref mut decoder) = self.audio_decoder {
-            decoder.flush();
+    /// Source streams to copy: the requested ones, which must fit the
+    /// container, or every video, audio and subtitle track it can hold
+    fn export_tracks(
+        &self,
+        output: &ffmpeg::format::context::Output,
+        format: ExportFormat,
+        requested: &[usize],
+    ) -> Result<Vec<usize>> {
+        let oformat = unsafe { (*output.as_ptr()).oformat };
+        // 0 = FF_COMPLIANCE_NORMAL; a negative answer means the muxer decides
+        let supports = |id: ffmpeg::codec::Id| unsafe {
+            ffmpeg::ffi::avformat_query_codec(oformat, id.into(), 0) != 0
+        };
+        let copyable = |stream: &ffmpeg::format::stream::Stream| {
+            matches!(
+                stream.parameters().medium(),
+                MediaType::Video | MediaType::Audio | MediaType::Subtitle
+            ) && !Self::is_attached_picture(stream)
+        };
+
+        if requested.is_empty() {
+            return Ok(self
+                .input
+                .streams()
+                .filter(|stream| copyable(stream))
+                .filter(|stream| {
+                    let codec_id = stream.parameters().id();
+                    let supported = supports(codec_id);
+                    if !supported {
+                        log::info!(
+                            "Export: skipping stream {} ({} cannot be stored in {})",
+                            stream.index(),
+                            codec_id.name(),
+                            format.muxer_name()
+                        );
+                    }
+                    supported
+                })
+                .map(|stream| stream.index())
+                .collect());
         }
-    }
 
-    /// Build a keyframe index by scanning all packets in the file.
-    ///
-    /// This is called during prepare() to enable fast seeking.
-    /// The index contains (pts_us, byte_position) pairs for all keyframes.
-    ///
-    /// # Arguments
-    /// * `max_keyframes` - Maximum number of keyframes to index (to limit memory usage)
-    ///
-    /// # Returns
-    /// The number of keyframes indexed
-    pub fn build_keyframe_index(&mut self, max_keyframes: usize) -> Result<usize> {
-        let video_stream_index = match self.video_stream_index {
-            Some(idx) => idx,
-            None => {
-                log::debug!("No video stream, skipping keyframe index");
-                return Ok(0);
+        let mut tracks = Vec::with_capacity(requested.len());
+        for &index in requested {
+            let stream = self
+                .input
+                .stream(index)
+                .ok_or_else(|| Error::InvalidFormat(format!("Stream {} not found", index)))?;
+            if !copyable(&stream) {
+                return Err(Error::InvalidFormat(format!(
+                    "Stream {} is not a video, audio or subtitle track",
+                    index
+                )));
             }
-        };
+            let codec_id = stream.parameters().id();
+            if !supports(codec_id) {
+                return Err(Error::CodecNotSupported(format!(
+                    "{} cannot be stored in {}",
+                    codec_id.name(),
+                    format.muxer_name()
+                )));
+            }
+            if !tracks.contains(&index) {
+                tracks.push(index);
+            }
+        }
+        Ok(tracks)
+    }
 
-        log::info!("Building keyframe index (max: {} entries)...", max_keyframes);
-        let start_time = std::time::Instant::now();
+    /// Codec tag of a copied stream: the source tag when the muxer maps it to
+    /// the same codec, `hvc1` for HEVC in MP4/MOV (Apple players require it),
+    /// else 0 to let the muxer choose
+    fn export_codec_tag(
+        oformat: *const ffmpeg::ffi::AVOutputFormat,
+        format: ExportFormat,
+        codec_id: ffmpeg::codec::Id,
+        source_tag: u32,
+    ) -> u32 {
+        if format.is_iso_bmff()
+            && codec_id == ffmpeg::codec::Id::HEVC
+            && source_tag != u32::from_le_bytes(*b"hev1")
+        {
+            return u32::from_le_bytes(*b"hvc1");
+        }
 
-        let mut index = KeyframeIndex::new();
-        let time_base = self.video_time_base;
+        let tags = unsafe { (*oformat).codec_tag };
+        let maps_to_codec = !tags.is_null()
+            && unsafe { ffmpeg::ffi::av_codec_get_id(tags, source_tag) }
+                == ffmpeg::ffi::AVCodecID::from(codec_id);
+        if maps_to_codec {
+            source_tag
+        } else {
+            0
+        }
+    }
+}
 
-        // Read all packets and collect keyframe positions
-        loop {
-            match self.input.packets().next() {
-                Some((stream, packet)) => {
-                    // Only process video stream packets
-                    if stream.index() != video_stream_index {
-                        continue;
-                    }
+/// Frames of the stream an analysis pass decodes
+trait AnalysisFrame: Sized {
+    /// Stream type, for logs and errors
+    const KIND: &'static str;
 
-                    // Check if this is a keyframe
-                    if packet.is_key() {
-                        if let Some(pts) = packet.pts() {
-                            let pts_us = Self::pts_to_us(pts, time_base);
-                            let position = packet.position();
+    /// Check if the context has a stream of this type
+    fn has_stream(ctx: &FFmpegContext) -> bool;
 
-                            // Only add if position is valid (>= 0)
-                            if position >= 0 {
-                                index.add(pts_us, position as i64);
+    /// Decode the next frame, dropping the packets of the other stream
+    fn decode_next(ctx: &mut FFmpegContext) -> Result<Option<Self>>;
 
-                                if index.len() >= max_keyframes {
-                                    log::warn!(
-                                        "Keyframe index limit reached: {} entries",
-                                        max_keyframes
-                                    );
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-                None => break,
-            }
-        }
+    /// End time of the frame in microseconds
+    fn end_us(&self) -> i64;
+}
 
-        // Sort the index by PTS (should already be sorted, but ensure it)
-        index.sort();
+impl AnalysisFrame for AudioFrame {
+    const KIND: &'static str = "audio";
 
-        let count = index.len();
-        let elapsed = start_time.elapsed();
+    fn has_stream(ctx: &FFmpegContext) -> bool {
+        ctx.audio_stream_index.is_some()
+    }
 
-        log::info!(
-            "Keyframe index built: {} entries in {:?} ({:.1} entries/sec)",
-            count,
-            elapsed,
-            count as f64 / elapsed.as_secs_f64().max(0.001)
-        );
+    fn decode_next(ctx: &mut FFmpegContext) -> Result<Option<Self>> {
+        let frame = ctx.decode_next_audio_frame()?;
+        // Video is not needed; don't let its packets pile up
+        ctx.video_packet_queue.clear();
+        Ok(frame)
+    }
 
-        // Store the index
-        self.keyframe_index = Some(index);
+    fn end_us(&self) -> i64 {
+        self.pts_us + self.duration_us
+    }
+}
 
-        // Seek back to the beginning of the file
-        self.seek(0)?;
+impl AnalysisFrame for VideoFrame {
+    const KIND: &'static str = "video";
 
-        Ok(count)
+    fn has_stream(ctx: &FFmpegContext) -> bool {
+        ctx.video_stream_index.is_some()
     }
 
-    /// Get a reference to the keyframe index
-    pub fn keyframe_index(&self) -> Option<&KeyframeIndex> {
-        self.keyframe_index.as_ref()
+    fn decode_next(ctx: &mut FFmpegContext) -> Result<Option<Self>> {
+        let frame = ctx.decode_next_frame()?;
+        // Audio is not needed; don't let its packets pile up
+        ctx.audio_packet_queue.clear();
+        Ok(frame)
     }
 
-    /// Check if keyframe index is available
-    pub fn has_keyframe_index(&self) -> bool {
-        self.keyframe_index.is_some()
+    fn end_us(&self) -> i64 {
+        self.pts_us + self.duration_us
     }
 }
 
@@ -1999,6 +5239,138 @@ mod tests {
         assert_eq!(pts, 90000);
     }
 
+    #[test]
+    fn test_bob_field_numbers() {
+        // 29.97 fps: fields of frame 30 are 1/59.94 s apart
+        let rate = (30000, 1001);
+        let frame_us = 1_001_000;
+        let field_us = 1_001_000 / 60;
+        assert_eq!(FFmpegContext::frame_number_at(0, frame_us, rate, 1), 30);
+        assert_eq!(FFmpegContext::frame_number_at(0, frame_us, rate, 2), 60);
+        assert_eq!(
+            FFmpegContext::frame_number_at(0, frame_us + field_us, rate, 2),
+            61
+        );
+
+        // Seeks aim at the middle of field 61
+        assert_eq!(FFmpegContext::frame_time(0, 61, rate, 2), 1_026_025);
+    }
+
+    #[test]
+    fn test_bob_seek_frame_numbers() {
+        // Replay seek_precise's stop test over the fields of a 29.97 fps
+        // bob stream and check the numbers of the frames it would return
+        let rate = (30000, 1001);
+        let duration_us = FFmpegContext::output_frame_duration_us(30000.0 / 1001.0, 2);
+        let fields: Vec<i64> = (0..200).map(|n| n * 1_001_000 / 60).collect();
+        for target in 50..70 {
+            let time_us = FFmpegContext::frame_time(0, target, rate, 2);
+            let mut best = None;
+            for &pts in &fields {
+                if pts > time_us {
+                    break;
+                }
+                best = Some(pts);
+                if pts + duration_us > time_us {
+                    break;
+                }
+            }
+            let pts = best.unwrap();
+            assert_eq!(FFmpegContext::frame_number_at(0, pts, rate, 2), target);
+        }
+    }
+
+    #[test]
+    fn test_queued_packet_order() {
+        let before = FFmpegContext::demuxed_before;
+        // Byte positions decide when both packets have one
+        assert!(before((100, Some(50)), (200, Some(0))));
+        assert!(!before((300, None), (200, None)));
+        // Otherwise decode times
+        assert!(!before((-1, Some(40_000)), (200, Some(20_000))));
+        assert!(before((-1, None), (200, Some(20_000))));
+    }
+
+    #[test]
+    fn test_downmix_options() {
+        let options = FFmpegContext::downmix_options(DownmixMode::Itu);
+        assert_eq!(options.get("lfe_mix_level"), Some("0"));
+        assert!(FFmpegContext::downmix_options(DownmixMode::Default)
+            .get("center_mix_level")
+            .is_none());
+    }
+
+    #[test]
+    fn test_itu_downmix_matrix() {
+        const A: f64 = std::f64::consts::FRAC_1_SQRT_2;
+        let matrix = FFmpegContext::itu_downmix_matrix;
+
+        // 5.1(side): FL FR FC LFE SL SR
+        assert_eq!(
+            matrix(0x60F, 0x3, DownmixMode::Itu).unwrap(),
+            vec![1.0, 0.0, A, 0.0, A, 0.0, 0.0, 1.0, A, 0.0, 0.0, A]
+        );
+        let with_lfe = matrix(0x60F, 0x3, DownmixMode::ItuWithLfe).unwrap();
+        assert_eq!((with_lfe[3], with_lfe[9]), (A, A));
+        let mono = matrix(0x60F, 0x4, DownmixMode::Itu).unwrap();
+        let expected = [A, A, 1.0, 0.0, 0.5, 0.5];
+        for (gain, expected) in mono.into_iter().zip(expected) {
+            assert!((gain - expected).abs() < 1e-12);
+        }
+
+        assert!(matrix(0x60F, 0x3, DownmixMode::Default).is_none());
+        assert!(matrix(0x3, 0x3, DownmixMode::Itu).is_none());
+        // Top front left is not placed by BS.775
+        assert!(matrix(0x1007, 0x3, DownmixMode::Itu).is_none());
+    }
+
+    #[test]
+    fn test_itu_downmix_resampler() {
+        use ffmpeg::channel_layout::ChannelLayout;
+        use ffmpeg::format::sample::{Sample, Type};
+
+        // Center at 0.5 and left surround at 0.25
+        const A: f64 = std::f64::consts::FRAC_1_SQRT_2;
+        let levels = [0.0, 0.0, 0.5, 0.0, 0.25, 0.0];
+        let expected = [0.75 * A, 0.5 * A];
+        for format in [Sample::F32(Type::Packed), Sample::I16(Type::Packed)] {
+            let mut input = AudioFrameFFmpeg::new(format, 4, ChannelLayout::_5POINT1);
+            input.set_rate(48000);
+            let size = format.bytes();
+            let samples = input.data_mut(0).chunks_exact_mut(size);
+            for (sample, level) in samples.zip(levels.iter().cycle()) {
+                match format {
+                    Sample::F32(_) => sample.copy_from_slice(&(*level as f32).to_ne_bytes()),
+                    _ => sample.copy_from_slice(&((level * 32768.0) as i16).to_ne_bytes()),
+                }
+            }
+
+            let mut resampler = FFmpegContext::create_resampler(
+                format,
+                ChannelLayout::_5POINT1,
+                48000,
+                format,
+                ChannelLayout::STEREO,
+                48000,
+                DownmixMode::Itu,
+            )
+            .unwrap();
+            let mut output = AudioFrameFFmpeg::empty();
+            resampler.run(&input, &mut output).unwrap();
+            assert_eq!(output.samples(), 4);
+
+            let data = output.data(0);
+            for (channel, expected) in expected.iter().enumerate() {
+                let bytes = &data[channel * size..][..size];
+                let value = match format {
+                    Sample::F32(_) => f32::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+                    _ => i16::from_ne_bytes(bytes.try_into().unwrap()) as f64 / 32768.0,
+                };
+                assert!((value - expected).abs() < 1e-3, "{:?}: {}", format, value);
+            }
+        }
+    }
+
     #[test]
     fn test_pixel_format_conversion() {
         assert_eq!(
@@ -2009,6 +5381,95 @@ mod tests {
             FFmpegContext::pixel_format_to_ffmpeg(PixelFormat::Nv12),
             ffmpeg::format::Pixel::NV12
         );
+        assert_eq!(
+            FFmpegContext::pixel_format_to_ffmpeg(PixelFormat::P010),
+            ffmpeg::format::Pixel::P010LE
+        );
+    }
+
+    #[test]
+    fn test_parse_hdr_side_data() {
+        let mut cll = Vec::new();
+        cll.extend_from_slice(&1000u32.to_ne_bytes());
+        cll.extend_from_slice(&400u32.to_ne_bytes());
+
+        let parsed = FFmpegContext::parse_content_light_level(&cll).unwrap();
+        assert_eq!((parsed.max_cll, parsed.max_fall), (1000, 400));
+        assert!(FFmpegContext::parse_content_light_level(&cll[..4]).is_none());
+        assert!(FFmpegContext::parse_mastering_display(&[0u8; 8]).is_none());
+    }
+
+    #[test]
+    fn test_effective_source_color() {
+        use ffmpeg::format::Pixel;
+
+        let unspecified = ColorInfo::default();
+        assert_eq!(
+            FFmpegContext::effective_source_color(&unspecified, Pixel::YUV420P, 1080),
+            (matrix::BT709, false)
+        );
+        assert_eq!(
+            FFmpegContext::effective_source_color(&unspecified, Pixel::YUVJ420P, 480),
+            (matrix::SMPTE170M, true)
+        );
+
+        let uhd = ColorInfo {
+            matrix: matrix::BT2020_NCL,
+            range: ColorRange::Limited,
+            ..ColorInfo::default()
+        };
+        assert_eq!(
+            FFmpegContext::effective_source_color(&uhd, Pixel::YUV420P10LE, 2160),
+            (matrix::BT2020_NCL, false)
+        );
+
+        // Frames report the guessed matrix and range they were converted with
+        let (guessed_matrix, guessed_full_range) =
+            FFmpegContext::effective_source_color(&unspecified, Pixel::YUV420P, 1080);
+        let output =
+            FFmpegContext::output_color(&unspecified, false, guessed_matrix, guessed_full_range);
+        assert_eq!(
+            (output.matrix, output.range),
+            (matrix::BT709, ColorRange::Limited)
+        );
+        let rgb = FFmpegContext::output_color(&unspecified, true, matrix::BT709, true);
+        assert_eq!(rgb.matrix, matrix::RGB);
+    }
+
+    #[test]
+    fn test_normalize_rotation() {
+        assert_eq!(FFmpegContext::normalize_rotation(0.0), 0);
+        assert_eq!(FFmpegContext::normalize_rotation(90.0), 90);
+        assert_eq!(FFmpegContext::normalize_rotation(-90.0), 270);
+        assert_eq!(FFmpegContext::normalize_rotation(179.6), 180);
+        assert_eq!(FFmpegContext::normalize_rotation(450.0), 90);
+        assert_eq!(FFmpegContext::normalize_rotation(f64::NAN), 0);
+    }
+
+    #[test]
+    fn test_native_pixel_format() {
+        use ffmpeg::format::Pixel;
+
+        for (decoded, expected) in [
+            (Pixel::NV12, PixelFormat::Nv12),
+            (Pixel::GRAY8, PixelFormat::Yuv420p),
+            (Pixel::YUV420P10LE, PixelFormat::P010),
+            // Widened rather than reduced: no 8-bit 4:2:2 or 4:4:4 formats
+            (Pixel::YUV422P, PixelFormat::Yuv422p10),
+            (Pixel::YUV444P, PixelFormat::Yuv444p16),
+            // Deeper than 10 bits
+            (Pixel::YUV420P12LE, PixelFormat::Yuv444p16),
+            (Pixel::GRAY12LE, PixelFormat::Yuv444p16),
+            (Pixel::YUV422P12LE, PixelFormat::Yuv444p16),
+            (Pixel::GBRP12LE, PixelFormat::Rgb48),
+        ] {
+            assert_eq!(
+                FFmpegContext::native_pixel_format(decoded),
+                expected,
+                "{:?}",
+                decoded
+            );
+        }
     }
 
     /// Test audio decoding with sample WMV file
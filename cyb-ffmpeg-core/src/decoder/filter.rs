@@ -0,0 +1,239 @@
+//! libavfilter graphs applied to decoded frames
+//!
+//! A graph is built from a filter description such as `"crop=640:360,hflip"`
+//! or `"volume=0.5,atempo=1.25"`, fed by a `buffer`/`abuffer` source and
+//! drained through a `buffersink`/`abuffersink`.
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::Rational;
+
+use crate::error::{Error, Result};
+
+/// Stream parameters a filter graph is configured for.
+///
+/// A graph has to be rebuilt when incoming frames stop matching them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FilterInput {
+    /// Video frames
+    Video {
+        format: ffmpeg::format::Pixel,
+        width: u32,
+        height: u32,
+        time_base: Rational,
+        sample_aspect_ratio: Rational,
+    },
+    /// Audio frames
+    Audio {
+        format: ffmpeg::format::Sample,
+        sample_rate: u32,
+        /// Channel layout description (e.g. "stereo", "5.1(side)")
+        channel_layout: String,
+        time_base: Rational,
+    },
+}
+
+impl FilterInput {
+    /// Source filter name and its arguments
+    fn source(&self) -> (&'static str, String) {
+        match self {
+            FilterInput::Video {
+                format,
+                width,
+                height,
+                time_base,
+                sample_aspect_ratio,
+            } => {
+                let sar = if sample_aspect_ratio.numerator() > 0 {
+                    *sample_aspect_ratio
+                } else {
+                    Rational::new(1, 1)
+                };
+                (
+                    "buffer",
+                    format!(
+                        "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+                        width,
+                        height,
+                        ffmpeg::ffi::AVPixelFormat::from(*format) as i32,
+                        time_base.numerator(),
+                        time_base.denominator(),
+                        sar.numerator(),
+                        sar.denominator()
+                    ),
+                )
+            }
+            FilterInput::Audio {
+                format,
+                sample_rate,
+                channel_layout,
+                time_base,
+            } => (
+                "abuffer",
+                format!(
+                    "time_base={}/{}:sample_rate={}:sample_fmt={}:channel_layout={}",
+                    time_base.numerator(),
+                    time_base.denominator(),
+                    sample_rate,
+                    format.name(),
+                    channel_layout
+                ),
+            ),
+        }
+    }
+
+    /// Filter that converts the graph output back to these parameters
+    fn format_filter(&self) -> String {
+        match self {
+            FilterInput::Video { format, .. } => format!("format=pix_fmts={}", format.name()),
+            FilterInput::Audio {
+                format,
+                sample_rate,
+                channel_layout,
+                ..
+            } => format!(
+                "aformat=sample_fmts={}:sample_rates={}:channel_layouts={}",
+                format.name(),
+                sample_rate,
+                channel_layout
+            ),
+        }
+    }
+}
+
+/// Configured filter graph with one input and one output
+pub(crate) struct FilterGraph {
+    graph: ffmpeg::filter::Graph,
+    input: FilterInput,
+    time_base: Rational,
+}
+
+impl FilterGraph {
+    /// Build a graph running `spec` on frames described by `input`.
+    ///
+    /// With `keep_format`, the output is converted back to the input's pixel
+    /// or sample format (and audio rate and layout) so downstream converters
+    /// configured for the decoder stay valid.
+    pub fn new(spec: &str, input: FilterInput, keep_format: bool) -> Result<Self> {
+        let invalid = |e: ffmpeg::Error| Error::InvalidFilter(format!("{}: {}", spec, e));
+        let find = |name: &str| {
+            ffmpeg::filter::find(name)
+                .ok_or_else(|| Error::InvalidFilter(format!("filter '{}' not available", name)))
+        };
+
+        let (source_name, source_args) = input.source();
+        let sink_name = match input {
+            FilterInput::Video { .. } => "buffersink",
+            FilterInput::Audio { .. } => "abuffersink",
+        };
+
+        let mut graph = ffmpeg::filter::Graph::new();
+        graph
+            .add(&find(source_name)?, "in", &source_args)
+            .map_err(invalid)?;
+        graph.add(&find(sink_name)?, "out", "").map_err(invalid)?;
+
+        let spec_with_format = if keep_format {
+            format!("{},{}", spec, input.format_filter())
+        } else {
+            spec.to_string()
+        };
+        graph
+            .output("in", 0)
+            .and_then(|parser| parser.input("out", 0))
+            .and_then(|parser| parser.parse(&spec_with_format))
+            .map_err(invalid)?;
+        graph.validate().map_err(invalid)?;
+
+        let time_base = graph
+            .get("out")
+            .map(|mut sink| sink.sink().time_base())
+            .unwrap_or(match &input {
+                FilterInput::Video { time_base, .. } | FilterInput::Audio { time_base, .. } => {
+                    *time_base
+                }
+            });
+
+        log::debug!("Filter graph '{}' configured for {:?}", spec, input);
+
+        Ok(Self {
+            graph,
+            input,
+            time_base,
+        })
+    }
+
+    /// Parameters the graph was built for
+    pub fn input(&self) -> &FilterInput {
+        &self.input
+    }
+
+    /// Time base of frames leaving the graph
+    pub fn time_base(&self) -> Rational {
+        self.time_base
+    }
+
+    /// Feed a frame into the graph
+    pub fn push(&mut self, frame: &ffmpeg::Frame) -> Result<()> {
+        let mut source = self.graph.get("in").ok_or(Error::Memory)?;
+        source
+            .source()
+            .add(frame)
+            .map_err(|e| Error::DecodeFailed(format!("Failed to feed filter graph: {}", e)))
+    }
+
+    /// Signal end of stream so buffering filters release their frames
+    pub fn flush(&mut self) -> Result<()> {
+        let mut source = self.graph.get("in").ok_or(Error::Memory)?;
+        source
+            .source()
+            .flush()
+            .map_err(|e| Error::DecodeFailed(format!("Failed to flush filter graph: {}", e)))
+    }
+
+    /// Take the next filtered frame, if one is ready
+    pub fn pull(&mut self, frame: &mut ffmpeg::Frame) -> Result<bool> {
+        let mut sink = self.graph.get("out").ok_or(Error::Memory)?;
+        match sink.sink().frame(frame) {
+            Ok(()) => Ok(true),
+            Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => Ok(false),
+            Err(ffmpeg::Error::Eof) => Ok(false),
+            Err(e) => Err(Error::DecodeFailed(format!(
+                "Failed to read from filter graph: {}",
+                e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_arguments() {
+        let video = FilterInput::Video {
+            format: ffmpeg::format::Pixel::YUV420P,
+            width: 1920,
+            height: 1080,
+            time_base: Rational::new(1, 90000),
+            sample_aspect_ratio: Rational::new(0, 1),
+        };
+        let (name, args) = video.source();
+        assert_eq!(name, "buffer");
+        assert_eq!(
+            args,
+            "video_size=1920x1080:pix_fmt=0:time_base=1/90000:pixel_aspect=1/1"
+        );
+
+        let audio = FilterInput::Audio {
+            format: ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Planar),
+            sample_rate: 48000,
+            channel_layout: "stereo".to_string(),
+            time_base: Rational::new(1, 48000),
+        };
+        assert_eq!(
+            audio.format_filter(),
+            "aformat=sample_fmts=fltp:sample_rates=48000:channel_layouts=stereo"
+        );
+    }
+}
@@ -1,6 +1,9 @@
 //! Video frame types
 
+use super::color::ColorInfo;
 use super::config::PixelFormat;
+use super::hdr::HdrMetadata;
+use super::timecode::Timecode;
 
 /// Decoded video frame
 #[derive(Clone)]
@@ -31,6 +34,28 @@ pub struct VideoFrame {
 
     /// Pixel format
     pub pixel_format: PixelFormat,
+
+    /// Color description of the pixel data (matrix and range as converted,
+    /// including values guessed for sources that leave them unspecified)
+    pub color: ColorInfo,
+
+    /// HDR metadata of the source frame
+    pub hdr: HdrMetadata,
+
+    /// Clockwise rotation still needed for upright display (0 once applied)
+    pub rotation: u32,
+
+    /// Whether the decoded frame was interlaced (still set after deinterlacing)
+    pub is_interlaced: bool,
+
+    /// Whether the top field of the decoded frame is displayed first
+    pub top_field_first: bool,
+
+    /// SMPTE timecode of the frame (None if the source carries none)
+    pub timecode: Option<Timecode>,
+
+    /// A/53 closed caption data carried by the frame (3-byte cc_data triplets)
+    pub caption_data: Vec<u8>,
 }
 
 impl VideoFrame {
@@ -56,6 +81,13 @@ impl VideoFrame {
             is_keyframe,
             frame_number,
             pixel_format,
+            color: ColorInfo::default(),
+            hdr: HdrMetadata::default(),
+            rotation: 0,
+            is_interlaced: false,
+            top_field_first: false,
+            timecode: None,
+            caption_data: Vec::new(),
         }
     }
 
@@ -84,12 +116,100 @@ impl VideoFrame {
         self.duration_us as f64 / 1_000_000.0
     }
 
-    /// Calculate expected data size for format
+    /// Calculate expected data size for format (rows without padding)
     pub fn expected_size(width: u32, height: u32, format: PixelFormat) -> usize {
-        match format {
-            PixelFormat::Bgra => (width * height * 4) as usize,
-            PixelFormat::Nv12 => (width * height * 3 / 2) as usize,
-            PixelFormat::Yuv420p => (width * height * 3 / 2) as usize,
+        Self::planes_size(width * format.bytes_per_pixel(), height, format)
+    }
+
+    /// Total size of all planes for a given first-plane stride
+    pub fn planes_size(stride: u32, height: u32, format: PixelFormat) -> usize {
+        (0..format.plane_count())
+            .filter_map(|plane| format.plane_layout(plane, stride, height))
+            .map(|(row_size, rows)| row_size as usize * rows as usize)
+            .sum()
+    }
+
+    /// Bytes per row of a plane
+    pub fn plane_stride(&self, plane: usize) -> u32 {
+        self.pixel_format
+            .plane_layout(plane, self.stride, self.height)
+            .map_or(0, |(row_size, _)| row_size)
+    }
+
+    /// Byte offset of a plane within `data`
+    pub fn plane_offset(&self, plane: usize) -> usize {
+        (0..plane)
+            .filter_map(|p| self.pixel_format.plane_layout(p, self.stride, self.height))
+            .map(|(row_size, rows)| row_size as usize * rows as usize)
+            .sum()
+    }
+
+    /// Copy of the frame rotated clockwise by 90, 180 or 270 degrees.
+    ///
+    /// Planes are rebuilt without row padding. Chroma planes are resampled
+    /// with nearest neighbor when the subsampling is not symmetric (4:2:2
+    /// rotated by 90 or 270 degrees). Other angles return an unrotated copy.
+    pub fn rotated(&self, degrees: u32) -> VideoFrame {
+        let format = self.pixel_format;
+        let (width, height) = (self.width, self.height);
+        let (out_width, out_height) = if degrees == 90 || degrees == 270 {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let out_stride = out_width * format.bytes_per_pixel();
+        let mut data = vec![0u8; Self::planes_size(out_stride, out_height, format)];
+
+        let mut out_offset = 0;
+        for plane in 0..format.plane_count() {
+            let (row_size, rows) = match format.plane_layout(plane, out_stride, out_height) {
+                Some(layout) => layout,
+                None => break,
+            };
+            let (shift_x, shift_y) = format.plane_subsampling(plane);
+            let unit = format.plane_unit_size(plane) as usize;
+            let src_offset = self.plane_offset(plane);
+            let src_stride = self.plane_stride(plane) as usize;
+            let columns = out_width.div_ceil(1 << shift_x);
+
+            for y in 0..rows {
+                let dst_row = out_offset + y as usize * row_size as usize;
+                for x in 0..columns {
+                    // Map through luma coordinates so differing subsampling works
+                    let (lx, ly) = (x << shift_x, y << shift_y);
+                    let (sx, sy) = match degrees {
+                        90 => (ly, height - 1 - lx),
+                        180 => (width - 1 - lx, height - 1 - ly),
+                        270 => (width - 1 - ly, lx),
+                        _ => (lx, ly),
+                    };
+                    let src = src_offset
+                        + (sy >> shift_y) as usize * src_stride
+                        + (sx >> shift_x) as usize * unit;
+                    let dst = dst_row + x as usize * unit;
+                    data[dst..dst + unit].copy_from_slice(&self.data[src..src + unit]);
+                }
+            }
+            out_offset += row_size as usize * rows as usize;
+        }
+
+        VideoFrame {
+            data,
+            width: out_width,
+            height: out_height,
+            stride: out_stride,
+            pts_us: self.pts_us,
+            duration_us: self.duration_us,
+            is_keyframe: self.is_keyframe,
+            frame_number: self.frame_number,
+            pixel_format: format,
+            color: self.color,
+            hdr: self.hdr,
+            rotation: 0,
+            is_interlaced: self.is_interlaced,
+            top_field_first: self.top_field_first,
+            timecode: self.timecode,
+            caption_data: self.caption_data.clone(),
         }
     }
 
@@ -107,6 +227,13 @@ impl VideoFrame {
             is_keyframe: pts_us == 0,
             frame_number: pts_us / 16666,
             pixel_format: PixelFormat::Bgra,
+            color: ColorInfo::default(),
+            hdr: HdrMetadata::default(),
+            rotation: 0,
+            is_interlaced: false,
+            top_field_first: false,
+            timecode: None,
+            caption_data: Vec::new(),
         }
     }
 }
@@ -119,6 +246,7 @@ impl std::fmt::Debug for VideoFrame {
             .field("pts_us", &self.pts_us)
             .field("is_keyframe", &self.is_keyframe)
             .field("frame_number", &self.frame_number)
+            .field("color", &self.color)
             .field("data_size", &self.data.len())
             .finish()
     }
@@ -146,4 +274,72 @@ mod tests {
         let size = VideoFrame::expected_size(1920, 1080, PixelFormat::Nv12);
         assert_eq!(size, 3_110_400);
     }
+
+    #[test]
+    fn test_high_bit_depth_layout() {
+        // P010: 16-bit luma + interleaved 16-bit chroma at half height
+        assert_eq!(
+            VideoFrame::expected_size(1920, 1080, PixelFormat::P010),
+            6_220_800
+        );
+        // YUV422P10: full-height chroma planes at half width
+        assert_eq!(
+            VideoFrame::expected_size(1920, 1080, PixelFormat::Yuv422p10),
+            8_294_400
+        );
+        assert_eq!(VideoFrame::expected_size(16, 16, PixelFormat::Rgba64), 2048);
+
+        let frame = VideoFrame::new(
+            vec![0u8; VideoFrame::planes_size(64, 4, PixelFormat::Yuv420p)],
+            60,
+            4,
+            64,
+            0,
+            0,
+            true,
+            0,
+            PixelFormat::Yuv420p,
+        );
+        assert_eq!(frame.plane_stride(1), 32);
+        assert_eq!(frame.plane_offset(2), 64 * 4 + 32 * 2);
+        assert_eq!(frame.plane_stride(3), 0);
+    }
+
+    #[test]
+    fn test_rotated() {
+        // 3x2 BGRA with padded rows, pixel value = index
+        let mut frame = VideoFrame::test_frame(0, 3, 2);
+        frame.stride = 16;
+        frame.data = vec![0u8; 32];
+        for i in 0..6u8 {
+            let offset = (i as usize / 3) * 16 + (i as usize % 3) * 4;
+            frame.data[offset] = i;
+        }
+        let pixels = |f: &VideoFrame| -> Vec<u8> { f.data.chunks(4).map(|p| p[0]).collect() };
+
+        let cw = frame.rotated(90);
+        assert_eq!((cw.width, cw.height, cw.stride), (2, 3, 8));
+        assert_eq!(pixels(&cw), vec![3, 0, 4, 1, 5, 2]);
+        assert_eq!(pixels(&frame.rotated(180)), vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(pixels(&frame.rotated(270)), vec![2, 5, 1, 4, 0, 3]);
+
+        // NV12 keeps its plane layout when transposed
+        let nv12 = VideoFrame::new(
+            vec![0u8; VideoFrame::expected_size(4, 2, PixelFormat::Nv12)],
+            4,
+            2,
+            4,
+            0,
+            0,
+            true,
+            0,
+            PixelFormat::Nv12,
+        );
+        let rotated = nv12.rotated(90);
+        assert_eq!((rotated.width, rotated.height), (2, 4));
+        assert_eq!(
+            rotated.data.len(),
+            VideoFrame::expected_size(2, 4, PixelFormat::Nv12)
+        );
+    }
 }
@@ -0,0 +1,430 @@
+//! HDR metadata and software tone mapping
+//!
+//! PQ (SMPTE ST 2084) and HLG (ARIB STD-B67) content is mapped to SDR by
+//! converting the decoded frame to 16-bit nonlinear RGB, linearizing it,
+//! compressing luminance with the selected operator, converting BT.2020
+//! primaries to BT.709 and re-encoding for a BT.1886 display.
+
+use super::color::{primaries, transfer, ColorInfo};
+
+/// Mastering display color volume (SMPTE ST 2086)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MasteringDisplay {
+    /// Red, green and blue primaries as CIE 1931 xy chromaticity
+    pub primaries: [[f64; 2]; 3],
+
+    /// White point as CIE 1931 xy chromaticity
+    pub white_point: [f64; 2],
+
+    /// Minimum luminance in cd/m²
+    pub min_luminance: f64,
+
+    /// Maximum luminance in cd/m²
+    pub max_luminance: f64,
+}
+
+/// Content light level (CTA-861.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContentLightLevel {
+    /// Maximum content light level in cd/m²
+    pub max_cll: u32,
+
+    /// Maximum frame-average light level in cd/m²
+    pub max_fall: u32,
+}
+
+/// HDR metadata of a stream or frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HdrMetadata {
+    /// Static mastering display metadata
+    pub mastering_display: Option<MasteringDisplay>,
+
+    /// Static content light level
+    pub content_light_level: Option<ContentLightLevel>,
+
+    /// HDR10+ (SMPTE ST 2094-40) dynamic metadata present
+    pub has_hdr10_plus: bool,
+
+    /// Dolby Vision configuration or RPU present
+    pub has_dolby_vision: bool,
+}
+
+impl HdrMetadata {
+    /// Check if no HDR metadata is present
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Check if dynamic (per-scene or per-frame) metadata is present
+    pub fn has_dynamic_metadata(&self) -> bool {
+        self.has_hdr10_plus || self.has_dolby_vision
+    }
+
+    /// Peak luminance of the content in cd/m² (MaxCLL, else mastering display maximum)
+    pub fn peak_luminance(&self) -> Option<f64> {
+        self.content_light_level
+            .filter(|cll| cll.max_cll > 0)
+            .map(|cll| cll.max_cll as f64)
+            .or_else(|| {
+                self.mastering_display
+                    .map(|md| md.max_luminance)
+                    .filter(|&max| max > 0.0)
+            })
+    }
+
+    /// Fill in static metadata missing here from `fallback` (e.g. stream-level metadata)
+    pub fn or(self, fallback: &HdrMetadata) -> HdrMetadata {
+        HdrMetadata {
+            mastering_display: self.mastering_display.or(fallback.mastering_display),
+            content_light_level: self.content_light_level.or(fallback.content_light_level),
+            ..self
+        }
+    }
+}
+
+/// Tone-mapping operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ToneMapOperator {
+    /// No tone mapping (HDR frames are converted as-is)
+    None = 0,
+    /// Hable / Uncharted 2 filmic curve
+    Hable = 1,
+    /// ITU-R BT.2390 EETF (hermite spline in the PQ domain)
+    Bt2390 = 2,
+}
+
+impl Default for ToneMapOperator {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// HDR→SDR tone-mapping configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapConfig {
+    /// Operator (None disables tone mapping)
+    pub operator: ToneMapOperator,
+
+    /// Peak luminance of the SDR target display in cd/m²
+    pub target_peak: f64,
+}
+
+impl Default for ToneMapConfig {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::None,
+            target_peak: 100.0,
+        }
+    }
+}
+
+impl ToneMapConfig {
+    /// Check if tone mapping is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.operator != ToneMapOperator::None
+    }
+}
+
+// SMPTE ST 2084 constants
+const PQ_M1: f64 = 2610.0 / 16384.0;
+const PQ_M2: f64 = 2523.0 / 4096.0 * 128.0;
+const PQ_C1: f64 = 3424.0 / 4096.0;
+const PQ_C2: f64 = 2413.0 / 4096.0 * 32.0;
+const PQ_C3: f64 = 2392.0 / 4096.0 * 32.0;
+
+/// Nominal peak of HLG displays in cd/m²
+const HLG_PEAK: f64 = 1000.0;
+
+/// Peak assumed for PQ content without metadata
+const DEFAULT_PQ_PEAK: f64 = 1000.0;
+
+/// Display gamma of the SDR target (ITU-R BT.1886)
+const SDR_GAMMA: f64 = 2.4;
+
+/// BT.2020 luma coefficients
+const BT2020_LUMA: [f64; 3] = [0.2627, 0.6780, 0.0593];
+
+/// Linear BT.2020 → BT.709 RGB
+const BT2020_TO_BT709: [[f64; 3]; 3] = [
+    [1.6605, -0.5876, -0.0728],
+    [-0.1246, 1.1329, -0.0083],
+    [-0.0182, -0.1006, 1.1187],
+];
+
+/// PQ EOTF: nonlinear signal [0, 1] → cd/m²
+fn pq_eotf(signal: f64) -> f64 {
+    let p = signal.max(0.0).powf(1.0 / PQ_M2);
+    10000.0 * ((p - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * p)).powf(1.0 / PQ_M1)
+}
+
+/// PQ inverse EOTF: cd/m² → nonlinear signal [0, 1]
+fn pq_inverse_eotf(nits: f64) -> f64 {
+    let y = (nits / 10000.0).max(0.0).powf(PQ_M1);
+    ((PQ_C1 + PQ_C2 * y) / (1.0 + PQ_C3 * y)).powf(PQ_M2)
+}
+
+/// HLG inverse OETF: nonlinear signal [0, 1] → scene linear [0, 1]
+fn hlg_inverse_oetf(signal: f64) -> f64 {
+    const A: f64 = 0.178_832_77;
+    const B: f64 = 0.284_668_92;
+    const C: f64 = 0.559_910_73;
+
+    let signal = signal.max(0.0);
+    if signal <= 0.5 {
+        signal * signal / 3.0
+    } else {
+        (((signal - C) / A).exp() + B) / 12.0
+    }
+}
+
+/// Hable filmic curve
+fn hable(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+
+    (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+}
+
+/// Tone mapper for 16-bit nonlinear RGB of one HDR transfer
+pub(crate) struct ToneMapper {
+    operator: ToneMapOperator,
+    is_hlg: bool,
+    convert_gamut: bool,
+    source_peak: f64,
+    target_peak: f64,
+
+    /// 16-bit code value → linear light (cd/m² for PQ, scene linear for HLG)
+    linear_lut: Vec<f32>,
+
+    /// Linear light relative to the target peak, in 65536 steps → encoded 16-bit value
+    encode_lut: Vec<u16>,
+}
+
+impl ToneMapper {
+    /// Create a tone mapper for a source, or None if it is not HDR or tone mapping is disabled
+    pub(crate) fn new(
+        config: &ToneMapConfig,
+        color: &ColorInfo,
+        metadata: &HdrMetadata,
+    ) -> Option<Self> {
+        if !config.is_enabled() || !color.is_hdr() {
+            return None;
+        }
+
+        let is_hlg = color.transfer == transfer::ARIB_STD_B67;
+        let target_peak = config.target_peak.max(1.0);
+        let source_peak = if is_hlg {
+            HLG_PEAK
+        } else {
+            metadata
+                .peak_luminance()
+                .unwrap_or(DEFAULT_PQ_PEAK)
+                .clamp(target_peak.min(10000.0), 10000.0)
+        };
+
+        let linear_lut = (0..=u16::MAX)
+            .map(|code| {
+                let signal = code as f64 / u16::MAX as f64;
+                if is_hlg {
+                    hlg_inverse_oetf(signal) as f32
+                } else {
+                    pq_eotf(signal) as f32
+                }
+            })
+            .collect();
+
+        let encode_lut = (0..=u16::MAX)
+            .map(|step| {
+                let linear = step as f64 / u16::MAX as f64;
+                (linear.powf(1.0 / SDR_GAMMA) * u16::MAX as f64).round() as u16
+            })
+            .collect();
+
+        Some(Self {
+            operator: config.operator,
+            is_hlg,
+            convert_gamut: color.primaries == primaries::BT2020,
+            source_peak,
+            target_peak,
+            linear_lut,
+            encode_lut,
+        })
+    }
+
+    /// Whether BT.2020 primaries are converted to BT.709
+    pub(crate) fn converts_gamut(&self) -> bool {
+        self.convert_gamut
+    }
+
+    /// Map luminance in cd/m² to the target display
+    fn curve(&self, nits: f64) -> f64 {
+        if nits <= 0.0 {
+            return 0.0;
+        }
+        if self.source_peak <= self.target_peak {
+            return nits.min(self.target_peak);
+        }
+
+        match self.operator {
+            ToneMapOperator::None => nits.min(self.target_peak),
+            ToneMapOperator::Hable => {
+                let peak = self.source_peak / self.target_peak;
+                let signal = (nits / self.target_peak).min(peak);
+                hable(signal) / hable(peak) * self.target_peak
+            }
+            ToneMapOperator::Bt2390 => {
+                // EETF with source black and target black at 0 cd/m²
+                let source_max = pq_inverse_eotf(self.source_peak);
+                let max_lum = pq_inverse_eotf(self.target_peak) / source_max;
+                let knee = 1.5 * max_lum - 0.5;
+                let e1 = (pq_inverse_eotf(nits) / source_max).min(1.0);
+
+                let e2 = if e1 < knee {
+                    e1
+                } else {
+                    let t = (e1 - knee) / (1.0 - knee);
+                    let (t2, t3) = (t * t, t * t * t);
+                    (2.0 * t3 - 3.0 * t2 + 1.0) * knee
+                        + (t3 - 2.0 * t2 + t) * (1.0 - knee)
+                        + (-2.0 * t3 + 3.0 * t2) * max_lum
+                };
+
+                pq_eotf(e2 * source_max).min(self.target_peak)
+            }
+        }
+    }
+
+    /// Tone map one nonlinear 16-bit RGB pixel
+    pub(crate) fn map_pixel(&self, rgb: [u16; 3]) -> [u16; 3] {
+        let mut linear = rgb.map(|code| self.linear_lut[code as usize] as f64);
+
+        // HLG: apply the OOTF for the nominal display (system gamma 1.2)
+        if self.is_hlg {
+            let scene_luma = dot(&BT2020_LUMA, &linear);
+            let scale = HLG_PEAK * scene_luma.max(1e-6).powf(0.2);
+            linear = linear.map(|c| c * scale);
+        }
+
+        // Compress luminance, preserving the color ratios
+        let luma = dot(&BT2020_LUMA, &linear);
+        if luma > 0.0 {
+            let ratio = self.curve(luma) / luma;
+            linear = linear.map(|c| c * ratio);
+        }
+
+        if self.convert_gamut {
+            linear = BT2020_TO_BT709.map(|row| dot(&row, &linear));
+        }
+
+        linear.map(|c| {
+            let relative = (c / self.target_peak).clamp(0.0, 1.0);
+            self.encode_lut[(relative * u16::MAX as f64).round() as usize]
+        })
+    }
+
+    /// Tone map little-endian RGBA64 rows in place (alpha is left untouched)
+    pub(crate) fn process_rgba64(
+        &self,
+        data: &mut [u8],
+        width: usize,
+        height: usize,
+        stride: usize,
+    ) {
+        for row in data.chunks_mut(stride).take(height) {
+            for pixel in row[..width * 8].chunks_exact_mut(8) {
+                let rgb = [
+                    u16::from_le_bytes([pixel[0], pixel[1]]),
+                    u16::from_le_bytes([pixel[2], pixel[3]]),
+                    u16::from_le_bytes([pixel[4], pixel[5]]),
+                ];
+                for (i, value) in self.map_pixel(rgb).iter().enumerate() {
+                    pixel[i * 2..i * 2 + 2].copy_from_slice(&value.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::color::{matrix, ColorRange};
+
+    fn hdr10() -> ColorInfo {
+        ColorInfo {
+            matrix: matrix::BT2020_NCL,
+            primaries: primaries::BT2020,
+            transfer: transfer::SMPTE2084,
+            range: ColorRange::Limited,
+            ..ColorInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_pq_round_trip() {
+        for nits in [0.1, 100.0, 1000.0, 4000.0] {
+            let signal = pq_inverse_eotf(nits);
+            assert!((pq_eotf(signal) - nits).abs() / nits < 1e-6);
+        }
+        assert!((pq_inverse_eotf(10000.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_metadata_peak() {
+        let mut metadata = HdrMetadata::default();
+        assert!(metadata.is_empty());
+        assert_eq!(metadata.peak_luminance(), None);
+
+        metadata.mastering_display = Some(MasteringDisplay {
+            max_luminance: 4000.0,
+            ..MasteringDisplay::default()
+        });
+        assert_eq!(metadata.peak_luminance(), Some(4000.0));
+
+        metadata.content_light_level = Some(ContentLightLevel {
+            max_cll: 1200,
+            max_fall: 400,
+        });
+        assert_eq!(metadata.peak_luminance(), Some(1200.0));
+    }
+
+    #[test]
+    fn test_curves_map_peak_to_target() {
+        let metadata = HdrMetadata::default();
+        for operator in [ToneMapOperator::Hable, ToneMapOperator::Bt2390] {
+            let config = ToneMapConfig {
+                operator,
+                target_peak: 100.0,
+            };
+            let mapper = ToneMapper::new(&config, &hdr10(), &metadata).unwrap();
+
+            assert!((mapper.curve(1000.0) - 100.0).abs() < 0.5);
+            let mut last = 0.0;
+            for nits in [1.0, 10.0, 100.0, 500.0, 1000.0] {
+                let mapped = mapper.curve(nits);
+                assert!(mapped > last && mapped <= 100.0);
+                last = mapped;
+            }
+        }
+
+        assert!(ToneMapper::new(&ToneMapConfig::default(), &hdr10(), &metadata).is_none());
+        assert!(ToneMapper::new(
+            &ToneMapConfig {
+                operator: ToneMapOperator::Hable,
+                target_peak: 100.0,
+            },
+            &ColorInfo::default(),
+            &metadata
+        )
+        .is_none());
+    }
+}
@@ -1,5 +1,10 @@
 //! Media information types
 
+use super::color::ColorInfo;
+use super::deinterlace::FieldOrder;
+use super::hdr::HdrMetadata;
+use super::timecode::Timecode;
+
 /// Codec information
 #[derive(Debug, Clone)]
 pub struct CodecInfo {
@@ -62,6 +67,34 @@ pub struct VideoTrack {
 
     /// Color range
     pub color_range: String,
+
+    /// Color description as code points
+    pub color: ColorInfo,
+
+    /// Static HDR metadata and dynamic metadata presence
+    pub hdr: HdrMetadata,
+
+    /// Clockwise rotation needed for upright display (0, 90, 180 or 270)
+    pub rotation: i32,
+
+    /// Sample aspect ratio as (numerator, denominator), 1:1 when unknown
+    pub sample_aspect_ratio: (i32, i32),
+
+    /// Display width after SAR correction and rotation
+    pub display_width: i32,
+
+    /// Display height after SAR correction and rotation
+    pub display_height: i32,
+
+    /// Field order (interlacing) of the stream
+    pub field_order: FieldOrder,
+
+    /// Total number of frames (counted for the active track, else reported
+    /// by the container or estimated from duration and frame rate)
+    pub frame_count: i64,
+
+    /// Decoder configuration (avcC/hvcC record, parameter sets; empty if in-band)
+    pub extradata: Vec<u8>,
 }
 
 impl VideoTrack {
@@ -80,6 +113,40 @@ impl VideoTrack {
             color_primaries: None,
             color_transfer: None,
             color_range: "unknown".to_string(),
+            color: ColorInfo::default(),
+            hdr: HdrMetadata::default(),
+            rotation: 0,
+            sample_aspect_ratio: (1, 1),
+            display_width: 1920,
+            display_height: 1080,
+            field_order: FieldOrder::Unknown,
+            frame_count: 0,
+            extradata: Vec::new(),
+        }
+    }
+
+    /// Compute display dimensions from coded size, sample aspect ratio and rotation
+    ///
+    /// The width is stretched by the SAR (rounded to even) and the axes are
+    /// swapped for 90 and 270 degree rotations.
+    pub fn display_dimensions(
+        width: i32,
+        height: i32,
+        sample_aspect_ratio: (i32, i32),
+        rotation: i32,
+    ) -> (i32, i32) {
+        let (num, den) = sample_aspect_ratio;
+        let display_width = if num > 0 && den > 0 && num != den {
+            let scaled = (width as i64 * num as i64 + den as i64 / 2) / den as i64;
+            ((scaled as i32 + 1) & !1).max(2)
+        } else {
+            width
+        };
+
+        if rotation % 180 != 0 {
+            (height, display_width)
+        } else {
+            (display_width, height)
         }
     }
 }
@@ -107,6 +174,9 @@ pub struct AudioTrack {
 
     /// Language code
     pub language_code: Option<String>,
+
+    /// Decoder configuration (e.g. AudioSpecificConfig for AAC, OpusHead for Opus)
+    pub extradata: Vec<u8>,
 }
 
 impl AudioTrack {
@@ -120,10 +190,171 @@ impl AudioTrack {
             channel_layout: Some("stereo".to_string()),
             bit_rate: 0,
             language_code: None,
+            extradata: Vec::new(),
         }
     }
 }
 
+/// Subtitle track information
+#[derive(Debug, Clone)]
+pub struct SubtitleTrack {
+    /// Track index
+    pub index: i32,
+
+    /// Codec info
+    pub codec: CodecInfo,
+
+    /// Language code
+    pub language_code: Option<String>,
+
+    /// Track title
+    pub title: Option<String>,
+
+    /// Whether the codec produces bitmap images (PGS, DVB, DVD)
+    pub is_bitmap: bool,
+
+    /// Whether the track is flagged as default
+    pub is_default: bool,
+
+    /// Whether the track is flagged as forced
+    pub is_forced: bool,
+
+    /// Codec private data (e.g. the ASS/SSA header or the VobSub palette)
+    pub extradata: Vec<u8>,
+}
+
+/// Chapter marker (of the default edition for Matroska files with several)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    /// Chapter id from the container
+    pub id: i64,
+
+    /// Chapter title
+    pub title: Option<String>,
+
+    /// Start time in microseconds
+    pub start_us: i64,
+
+    /// End time in microseconds
+    pub end_us: i64,
+
+    /// Start time in `time_base` units
+    pub start: i64,
+
+    /// End time in `time_base` units
+    pub end: i64,
+
+    /// Time base of `start` and `end` as (numerator, denominator)
+    pub time_base: (i32, i32),
+}
+
+impl Chapter {
+    /// Duration in microseconds
+    pub fn duration_us(&self) -> i64 {
+        self.end_us - self.start_us
+    }
+
+    /// Check if a time falls within the chapter
+    pub fn contains(&self, time_us: i64) -> bool {
+        (self.start_us..self.end_us).contains(&time_us)
+    }
+}
+
+/// Kind of attachment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AttachmentKind {
+    /// Attached picture (cover art) carried as a single-image video stream
+    Picture = 0,
+    /// File attachment (fonts, for instance)
+    File = 1,
+}
+
+/// Attached picture or file attachment
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    /// Stream index
+    pub index: i32,
+
+    /// Picture or file
+    pub kind: AttachmentKind,
+
+    /// Codec of the stream (e.g. "mjpeg", "png", "ttf")
+    pub codec_name: String,
+
+    /// File name
+    pub filename: Option<String>,
+
+    /// MIME type (e.g. "image/jpeg", "font/ttf")
+    pub mime_type: Option<String>,
+
+    /// Description
+    pub title: Option<String>,
+
+    /// Picture type for tagged audio (e.g. "Cover (front)")
+    pub picture_type: Option<String>,
+
+    /// Data size in bytes
+    pub size: usize,
+
+    /// Picture width (0 for files)
+    pub width: i32,
+
+    /// Picture height (0 for files)
+    pub height: i32,
+}
+
+impl Attachment {
+    /// Check if the attachment is a picture
+    pub fn is_picture(&self) -> bool {
+        self.kind == AttachmentKind::Picture
+    }
+
+    /// Check if the attachment is a font (as used by ASS subtitles)
+    pub fn is_font(&self) -> bool {
+        let by_mime = self.mime_type.as_deref().is_some_and(|mime| {
+            let mime = mime.to_ascii_lowercase();
+            mime.starts_with("font/")
+                || mime.starts_with("application/font-")
+                || mime.starts_with("application/x-font")
+                || mime.contains("truetype")
+                || mime.contains("opentype")
+        });
+        let by_extension = self.filename.as_deref().is_some_and(|name| {
+            let name = name.to_ascii_lowercase();
+            [".ttf", ".otf", ".ttc", ".woff", ".woff2"]
+                .iter()
+                .any(|extension| name.ends_with(extension))
+        });
+        self.kind == AttachmentKind::File && (by_mime || by_extension)
+    }
+
+    /// Check if the picture is tagged as the front cover
+    pub fn is_front_cover(&self) -> bool {
+        self.is_picture()
+            && [&self.picture_type, &self.title, &self.filename]
+                .iter()
+                .filter_map(|value| value.as_deref())
+                .any(|value| {
+                    let value = value.to_ascii_lowercase();
+                    value.contains("cover (front)") || value.starts_with("cover.")
+                })
+    }
+}
+
+/// Raw attachment contents
+#[derive(Debug, Clone)]
+pub struct AttachmentData {
+    /// File name
+    pub filename: Option<String>,
+
+    /// MIME type
+    pub mime_type: Option<String>,
+
+    /// Attachment bytes (the encoded image for pictures)
+    pub data: Vec<u8>,
+}
+
 /// Complete media information
 #[derive(Debug, Clone)]
 pub struct MediaInfo {
@@ -139,6 +370,18 @@ pub struct MediaInfo {
     /// Audio tracks
     pub audio_tracks: Vec<AudioTrack>,
 
+    /// Subtitle tracks
+    pub subtitle_tracks: Vec<SubtitleTrack>,
+
+    /// Start timecode (from a tmcd track, stream or container metadata, or GOP header)
+    pub start_timecode: Option<Timecode>,
+
+    /// Chapters in start time order
+    pub chapters: Vec<Chapter>,
+
+    /// Attached pictures and file attachments
+    pub attachments: Vec<Attachment>,
+
     /// Metadata
     pub metadata: std::collections::HashMap<String, String>,
 }
@@ -153,10 +396,28 @@ impl MediaInfo {
             container_format: "unknown".to_string(),
             video_tracks: vec![VideoTrack::placeholder()],
             audio_tracks: vec![AudioTrack::placeholder()],
+            subtitle_tracks: Vec::new(),
+            start_timecode: None,
+            chapters: Vec::new(),
+            attachments: Vec::new(),
             metadata: std::collections::HashMap::new(),
         }
     }
 
+    /// Check if the primary video track uses an HDR transfer (PQ or HLG)
+    pub fn is_hdr(&self) -> bool {
+        self.video_tracks
+            .first()
+            .is_some_and(|track| track.color.is_hdr())
+    }
+
+    /// Total number of frames of the primary video track (0 without video)
+    pub fn frame_count(&self) -> i64 {
+        self.video_tracks
+            .first()
+            .map_or(0, |track| track.frame_count)
+    }
+
     /// Check if media has video
     pub fn has_video(&self) -> bool {
         !self.video_tracks.is_empty()
@@ -167,6 +428,11 @@ impl MediaInfo {
         !self.audio_tracks.is_empty()
     }
 
+    /// Check if media has subtitles
+    pub fn has_subtitles(&self) -> bool {
+        !self.subtitle_tracks.is_empty()
+    }
+
     /// Get primary video track
     pub fn primary_video(&self) -> Option<&VideoTrack> {
         self.video_tracks.first()
@@ -176,6 +442,21 @@ impl MediaInfo {
     pub fn primary_audio(&self) -> Option<&AudioTrack> {
         self.audio_tracks.first()
     }
+
+    /// Index of the chapter containing a time
+    pub fn chapter_at(&self, time_us: i64) -> Option<usize> {
+        self.chapters
+            .iter()
+            .position(|chapter| chapter.contains(time_us))
+    }
+
+    /// Cover art: the picture tagged as front cover, else the first picture
+    pub fn cover_art(&self) -> Option<&Attachment> {
+        self.attachments
+            .iter()
+            .find(|attachment| attachment.is_front_cover())
+            .or_else(|| self.attachments.iter().find(|a| a.is_picture()))
+    }
 }
 
 #[cfg(test)]
@@ -187,6 +468,7 @@ mod tests {
         let info = MediaInfo::placeholder("/test/video.mp4");
         assert!(info.has_video());
         assert!(info.has_audio());
+        assert!(!info.has_subtitles());
     }
 
     #[test]
@@ -195,4 +477,88 @@ mod tests {
         assert!(info.primary_video().is_some());
         assert!(info.primary_audio().is_some());
     }
+
+    #[test]
+    fn test_display_dimensions() {
+        // Square pixels, no rotation
+        assert_eq!(
+            VideoTrack::display_dimensions(1920, 1080, (1, 1), 0),
+            (1920, 1080)
+        );
+        // Anamorphic DV NTSC widescreen
+        assert_eq!(
+            VideoTrack::display_dimensions(720, 480, (40, 33), 0),
+            (874, 480)
+        );
+        // Portrait phone recording
+        assert_eq!(
+            VideoTrack::display_dimensions(1920, 1080, (1, 1), 90),
+            (1080, 1920)
+        );
+        // Unknown SAR is treated as square
+        assert_eq!(
+            VideoTrack::display_dimensions(640, 480, (0, 1), 180),
+            (640, 480)
+        );
+    }
+
+    #[test]
+    fn test_chapter_at() {
+        let chapter = |id: i64, start_us: i64, end_us: i64| Chapter {
+            id,
+            title: None,
+            start_us,
+            end_us,
+            start: start_us / 1000,
+            end: end_us / 1000,
+            time_base: (1, 1000),
+        };
+        let mut info = MediaInfo::placeholder("/test/book.m4b");
+        info.chapters = vec![
+            chapter(1, 0, 60_000_000),
+            chapter(2, 60_000_000, 95_500_000),
+        ];
+
+        assert_eq!(info.chapter_at(0), Some(0));
+        assert_eq!(info.chapter_at(60_000_000), Some(1));
+        assert_eq!(info.chapter_at(95_500_000), None);
+        assert_eq!(info.chapters[1].duration_us(), 35_500_000);
+    }
+
+    #[test]
+    fn test_attachments() {
+        let attachment = |index: i32, kind, filename: &str, mime_type: &str| Attachment {
+            index,
+            kind,
+            codec_name: String::new(),
+            filename: Some(filename.to_string()),
+            mime_type: Some(mime_type.to_string()),
+            title: None,
+            picture_type: None,
+            size: 0,
+            width: 0,
+            height: 0,
+        };
+        let mut info = MediaInfo::placeholder("/test/album.mkv");
+        assert!(info.cover_art().is_none());
+
+        info.attachments = vec![
+            attachment(
+                2,
+                AttachmentKind::File,
+                "Font.TTF",
+                "application/octet-stream",
+            ),
+            attachment(3, AttachmentKind::File, "notes.txt", "text/plain"),
+            attachment(4, AttachmentKind::Picture, "back.jpg", "image/jpeg"),
+            attachment(5, AttachmentKind::Picture, "cover.png", "image/png"),
+        ];
+        assert!(info.attachments[0].is_font());
+        assert!(!info.attachments[1].is_font());
+        assert_eq!(info.cover_art().map(|a| a.index), Some(5));
+
+        info.attachments.pop();
+        info.attachments[2].picture_type = Some("Cover (back)".to_string());
+        assert_eq!(info.cover_art().map(|a| a.index), Some(4));
+    }
 }
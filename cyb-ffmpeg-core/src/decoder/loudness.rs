@@ -0,0 +1,560 @@
+//! Loudness measurement (ITU-R BS.1770-4 / EBU R128)
+//!
+//! Samples are K-weighted per channel and summed with the BS.1770 channel
+//! weights into 100 ms steps. Momentary (400 ms) and short-term (3 s)
+//! loudness slide over those steps; integrated loudness gates the momentary
+//! blocks (EBU Tech 3341) and the loudness range gates the short-term values
+//! (EBU Tech 3342). True peak is measured on a 4x oversampled signal using
+//! the interpolation filter of BS.1770-4 Annex 2.
+
+use std::collections::VecDeque;
+
+use super::audio_frame::AudioFrame;
+use crate::error::{Error, Result};
+
+/// Gating blocks below this loudness are ignored (LUFS)
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+
+/// Relative gate of the integrated loudness (LU below the ungated level)
+const INTEGRATED_RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Relative gate of the loudness range (LU below the ungated level)
+const RANGE_RELATIVE_GATE_LU: f64 = -20.0;
+
+/// Steps (of 100 ms) in a momentary window
+const MOMENTARY_STEPS: usize = 4;
+
+/// Steps (of 100 ms) in a short-term window
+const SHORT_TERM_STEPS: usize = 30;
+
+/// BS.1770-4 Annex 2 interpolation filter: 4 phases of 12 taps
+const TRUE_PEAK_PHASES: [[f64; 12]; 4] = [
+    [
+        0.0017089843750,
+        0.0109863281250,
+        -0.0196533203125,
+        0.0332031250000,
+        -0.0594482421875,
+        0.1373291015625,
+        0.9721679687500,
+        -0.1022949218750,
+        0.0476074218750,
+        -0.0266113281250,
+        0.0148925781250,
+        -0.0083007812500,
+    ],
+    [
+        -0.0291748046875,
+        0.0292968750000,
+        -0.0517578125000,
+        0.0891113281250,
+        -0.1665039062500,
+        0.4650878906250,
+        0.7797851562500,
+        -0.2003173828125,
+        0.1015625000000,
+        -0.0582275390625,
+        0.0330810546875,
+        -0.0189208984375,
+    ],
+    [
+        -0.0189208984375,
+        0.0330810546875,
+        -0.0582275390625,
+        0.1015625000000,
+        -0.2003173828125,
+        0.7797851562500,
+        0.4650878906250,
+        -0.1665039062500,
+        0.0891113281250,
+        -0.0517578125000,
+        0.0292968750000,
+        -0.0291748046875,
+    ],
+    [
+        -0.0083007812500,
+        0.0148925781250,
+        -0.0266113281250,
+        0.0476074218750,
+        -0.1022949218750,
+        0.9721679687500,
+        0.1373291015625,
+        -0.0594482421875,
+        0.0332031250000,
+        -0.0196533203125,
+        0.0109863281250,
+        0.0017089843750,
+    ],
+];
+
+/// What to measure
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoudnessOptions {
+    /// Start of the range in microseconds
+    pub start_us: i64,
+
+    /// End of the range in microseconds (None = end of media)
+    pub end_us: Option<i64>,
+
+    /// Audio stream index (None = active audio track)
+    pub track: Option<usize>,
+}
+
+/// Momentary and short-term loudness at one point in time
+///
+/// Levels are `f64::NEG_INFINITY` for silence and until enough audio has
+/// been measured to fill the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoudnessPoint {
+    /// End of the measured windows in microseconds
+    pub time_us: i64,
+
+    /// Loudness of the last 400 ms (LUFS)
+    pub momentary_lufs: f64,
+
+    /// Loudness of the last 3 s (LUFS)
+    pub short_term_lufs: f64,
+}
+
+/// Loudness of a whole range
+///
+/// Levels are `f64::NEG_INFINITY` when the range is silent (or too short).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoudnessSummary {
+    /// Gated integrated loudness (LUFS)
+    pub integrated_lufs: f64,
+
+    /// Loudness range (LU)
+    pub loudness_range_lu: f64,
+
+    /// Highest true peak of all channels (dBTP)
+    pub true_peak_dbtp: f64,
+
+    /// Highest sample peak of all channels (dBFS)
+    pub sample_peak_dbfs: f64,
+
+    /// Highest momentary loudness (LUFS)
+    pub max_momentary_lufs: f64,
+
+    /// Highest short-term loudness (LUFS)
+    pub max_short_term_lufs: f64,
+}
+
+/// Result of a loudness measurement
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessReport {
+    /// Values of the whole range
+    pub summary: LoudnessSummary,
+
+    /// Momentary and short-term loudness every 100 ms
+    pub series: Vec<LoudnessPoint>,
+}
+
+/// Biquad section (transposed direct form II)
+#[derive(Clone)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// K-weighting filter (high shelf followed by high pass) for a sample rate.
+///
+/// The analog prototypes are matched to the 48 kHz coefficients of
+/// BS.1770-4 so that other sample rates get the same response.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f64;
+
+    let k = (std::f64::consts::PI * 1681.974450955533 / rate).tan();
+    let q = 0.7071752369554196;
+    let vh = 10f64.powf(3.999843853973347 / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    let k = (std::f64::consts::PI * 38.13547087602444 / rate).tan();
+    let q = 0.5003270373238773;
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Low frequency effects channels of an FFmpeg channel mask (LFE, LFE2)
+const LFE_CHANNELS: u64 = 0x8 | 0x8_0000_0000;
+
+/// Surround channels of an FFmpeg channel mask, weighted +1.5 dB as by
+/// FFmpeg's ebur128 filter: back left, center and right and side left and
+/// right (0x730), top back (0x3_8000) and surround direct (0x6_0000_0000)
+const SURROUND_CHANNELS: u64 = 0x730 | 0x3_8000 | 0x6_0000_0000;
+
+/// BS.1770 weights of the channels of a layout given as an FFmpeg channel
+/// mask, with the channels in mask bit order (LFE excluded, surround
+/// channels +1.5 dB).
+///
+/// Layouts the mask does not describe (0 for unknown or custom channel
+/// orders, or a channel count that differs) weigh all channels equally.
+fn channel_weights(channels: usize, channel_mask: u64) -> Vec<f64> {
+    if channel_mask.count_ones() as usize != channels {
+        return vec![1.0; channels];
+    }
+    (0..64)
+        .map(|bit| 1u64 << bit)
+        .filter(|channel| channel_mask & channel != 0)
+        .map(|channel| {
+            if channel & LFE_CHANNELS != 0 {
+                0.0
+            } else if channel & SURROUND_CHANNELS != 0 {
+                1.41
+            } else {
+                1.0
+            }
+        })
+        .collect()
+}
+
+/// Loudness of a mean square power
+fn lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Mean of the powers whose loudness is above `gate_lufs`, if any
+fn gated_mean(powers: &[f64], gate_lufs: f64) -> Option<f64> {
+    let (sum, count) = powers
+        .iter()
+        .filter(|&&power| lufs(power) > gate_lufs)
+        .fold((0.0, 0), |(sum, count), power| (sum + power, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// Per-channel state of the meter
+#[derive(Clone)]
+struct ChannelState {
+    filter: [Biquad; 2],
+    weight: f64,
+    /// Last 12 input samples, newest first
+    history: [f64; 12],
+    sample_peak: f64,
+    true_peak: f64,
+}
+
+impl ChannelState {
+    /// Track peaks and return the weighted power of one sample
+    fn add(&mut self, sample: f64) -> f64 {
+        self.history.copy_within(0..11, 1);
+        self.history[0] = sample;
+        self.sample_peak = self.sample_peak.max(sample.abs());
+        self.true_peak = self.true_peak.max(sample.abs());
+        for phase in &TRUE_PEAK_PHASES {
+            let value: f64 = phase.iter().zip(&self.history).map(|(c, x)| c * x).sum();
+            self.true_peak = self.true_peak.max(value.abs());
+        }
+
+        let weighted = self.filter.iter_mut().fold(sample, |x, f| f.process(x));
+        self.weight * weighted * weighted
+    }
+}
+
+/// Measures the loudness of decoded audio frames
+pub(crate) struct LoudnessMeter {
+    sample_rate: u32,
+    start_us: i64,
+    end_us: Option<i64>,
+    channels: Vec<ChannelState>,
+    /// Time of the first measured sample
+    origin_us: Option<i64>,
+    samples_per_step: u32,
+    step_samples: u32,
+    step_power: f64,
+    /// Summed power of the last steps (up to a short-term window)
+    steps: VecDeque<f64>,
+    step_count: i64,
+    momentary_blocks: Vec<f64>,
+    short_term_blocks: Vec<f64>,
+    series: Vec<LoudnessPoint>,
+}
+
+impl LoudnessMeter {
+    /// Start measuring the range `start_us..end_us` of audio whose layout is
+    /// `channel_mask` (an FFmpeg channel mask, 0 = unknown)
+    pub fn new(
+        sample_rate: u32,
+        channels: u32,
+        channel_mask: u64,
+        start_us: i64,
+        end_us: Option<i64>,
+    ) -> Self {
+        let state = channel_weights(channels as usize, channel_mask)
+            .into_iter()
+            .map(|weight| ChannelState {
+                filter: k_weighting(sample_rate),
+                weight,
+                history: [0.0; 12],
+                sample_peak: 0.0,
+                true_peak: 0.0,
+            })
+            .collect();
+        Self {
+            sample_rate,
+            start_us,
+            end_us,
+            channels: state,
+            origin_us: None,
+            samples_per_step: (sample_rate / 10).max(1),
+            step_samples: 0,
+            step_power: 0.0,
+            steps: VecDeque::with_capacity(SHORT_TERM_STEPS),
+            step_count: 0,
+            momentary_blocks: Vec::new(),
+            short_term_blocks: Vec::new(),
+            series: Vec::new(),
+        }
+    }
+
+    /// Add the samples of a frame that fall into the range.
+    ///
+    /// Returns false once the frame starts past the end of the range.
+    pub fn push(&mut self, frame: &AudioFrame) -> Result<bool> {
+        let channels = self.channels.len();
+        if frame.channels as usize != channels || frame.sample_rate != self.sample_rate {
+            return Err(Error::InvalidFormat(format!(
+                "Audio format changed to {} Hz, {} channels",
+                frame.sample_rate, frame.channels
+            )));
+        }
+        if self.end_us.is_some_and(|end_us| frame.pts_us >= end_us) {
+            return Ok(false);
+        }
+
+        let count = frame.sample_count as usize;
+        if frame.data.len() < count * channels {
+            return Err(Error::InvalidFormat(format!(
+                "Audio frame holds {} samples, {} expected",
+                frame.data.len(),
+                count * channels
+            )));
+        }
+        let to_samples =
+            |us: i64| (us as i128 * frame.sample_rate as i128 / 1_000_000).clamp(0, count as i128);
+        let first = to_samples(self.start_us - frame.pts_us) as usize;
+        let last = match self.end_us {
+            Some(end_us) => to_samples(end_us - frame.pts_us) as usize,
+            None => count,
+        };
+        if first < last && self.origin_us.is_none() {
+            self.origin_us =
+                Some(frame.pts_us + (first as i64 * 1_000_000) / frame.sample_rate as i64);
+        }
+
+        for n in first..last {
+            for (channel, state) in self.channels.iter_mut().enumerate() {
+                let index = if frame.planar {
+                    channel * count + n
+                } else {
+                    n * channels + channel
+                };
+                self.step_power += state.add(frame.data.sample_f64(index));
+            }
+            self.step_samples += 1;
+            if self.step_samples == self.samples_per_step {
+                self.finish_step();
+            }
+        }
+        Ok(true)
+    }
+
+    /// Points measured so far
+    pub fn series(&self) -> &[LoudnessPoint] {
+        &self.series
+    }
+
+    /// Summarize the measured range (an incomplete last step is dropped)
+    pub fn finish(self) -> LoudnessReport {
+        let integrated_lufs = gated_mean(&self.momentary_blocks, ABSOLUTE_GATE_LUFS)
+            .and_then(|mean| {
+                gated_mean(
+                    &self.momentary_blocks,
+                    (lufs(mean) + INTEGRATED_RELATIVE_GATE_LU).max(ABSOLUTE_GATE_LUFS),
+                )
+            })
+            .map_or(f64::NEG_INFINITY, lufs);
+
+        let loudness_range_lu =
+            gated_mean(&self.short_term_blocks, ABSOLUTE_GATE_LUFS).map_or(0.0, |mean| {
+                let gate = (lufs(mean) + RANGE_RELATIVE_GATE_LU).max(ABSOLUTE_GATE_LUFS);
+                let mut levels: Vec<f64> = self
+                    .short_term_blocks
+                    .iter()
+                    .map(|&power| lufs(power))
+                    .filter(|&level| level > gate)
+                    .collect();
+                levels.sort_by(f64::total_cmp);
+                let percentile = |p: f64| levels[((levels.len() - 1) as f64 * p).round() as usize];
+                percentile(0.95) - percentile(0.10)
+            });
+
+        let peak = |value: fn(&ChannelState) -> f64| {
+            let peak = self.channels.iter().map(value).fold(0.0, f64::max);
+            20.0 * peak.log10()
+        };
+        let max_level = |blocks: &[f64]| lufs(blocks.iter().copied().fold(0.0, f64::max));
+
+        LoudnessReport {
+            summary: LoudnessSummary {
+                integrated_lufs,
+                loudness_range_lu,
+                true_peak_dbtp: peak(|state| state.true_peak),
+                sample_peak_dbfs: peak(|state| state.sample_peak),
+                max_momentary_lufs: max_level(&self.momentary_blocks),
+                max_short_term_lufs: max_level(&self.short_term_blocks),
+            },
+            series: self.series,
+        }
+    }
+
+    /// Close a 100 ms step and record the windows ending with it
+    fn finish_step(&mut self) {
+        if self.steps.len() == SHORT_TERM_STEPS {
+            self.steps.pop_front();
+        }
+        self.steps.push_back(self.step_power);
+        self.step_power = 0.0;
+        self.step_samples = 0;
+        self.step_count += 1;
+
+        let window_power = |steps: usize| {
+            let sum: f64 = self.steps.iter().rev().take(steps).sum();
+            sum / (steps as u32 * self.samples_per_step) as f64
+        };
+        let mut momentary_lufs = f64::NEG_INFINITY;
+        if self.steps.len() >= MOMENTARY_STEPS {
+            let power = window_power(MOMENTARY_STEPS);
+            self.momentary_blocks.push(power);
+            momentary_lufs = lufs(power);
+        }
+        let mut short_term_lufs = f64::NEG_INFINITY;
+        if self.steps.len() >= SHORT_TERM_STEPS {
+            let power = window_power(SHORT_TERM_STEPS);
+            self.short_term_blocks.push(power);
+            short_term_lufs = lufs(power);
+        }
+
+        let elapsed = self.step_count * self.samples_per_step as i64;
+        self.series.push(LoudnessPoint {
+            time_us: self.origin_us.unwrap_or(self.start_us)
+                + elapsed * 1_000_000 / self.sample_rate as i64,
+            momentary_lufs,
+            short_term_lufs,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::AudioSamples;
+
+    /// Measure stereo sine segments of (seconds, frequency, dBFS, phase)
+    fn measure(segments: &[(u32, f64, f64, f64)]) -> LoudnessReport {
+        let rate = 48000;
+        let mut meter = LoudnessMeter::new(rate, 2, 0x3, 0, None);
+        let mut pts_us = 0;
+        for &(seconds, frequency, dbfs, phase) in segments {
+            let amplitude = 10f64.powf(dbfs / 20.0);
+            for _ in 0..seconds {
+                let mut data = Vec::with_capacity(rate as usize * 2);
+                for n in 0..rate {
+                    let t = n as f64 / rate as f64;
+                    let value =
+                        amplitude * (2.0 * std::f64::consts::PI * frequency * t + phase).sin();
+                    data.extend([value as f32; 2]);
+                }
+                let frame = AudioFrame::new(
+                    AudioSamples::Float32(data),
+                    false,
+                    rate,
+                    2,
+                    rate,
+                    pts_us,
+                    1_000_000,
+                    0,
+                );
+                assert!(meter.push(&frame).unwrap());
+                pts_us += 1_000_000;
+            }
+        }
+        meter.finish()
+    }
+
+    #[test]
+    fn test_ebu_tech_3341() {
+        // Cases 1 and 2 (shortened): stereo 1 kHz sine at -23 and -33 dBFS
+        for dbfs in [-23.0, -33.0] {
+            let report = measure(&[(10, 1000.0, dbfs, 0.0)]);
+            let summary = report.summary;
+            assert!((summary.integrated_lufs - dbfs).abs() < 0.1);
+            assert!((summary.max_momentary_lufs - dbfs).abs() < 0.1);
+            assert!((summary.max_short_term_lufs - dbfs).abs() < 0.1);
+
+            assert_eq!(report.series.len(), 100);
+            assert_eq!(report.series[2].momentary_lufs, f64::NEG_INFINITY);
+            assert!((report.series[3].momentary_lufs - dbfs).abs() < 0.1);
+            assert_eq!(report.series[28].short_term_lufs, f64::NEG_INFINITY);
+            assert_eq!(report.series[99].time_us, 10_000_000);
+        }
+
+        // Case 3 (shortened): -36 dBFS is gated out relative to -23 dBFS
+        let report = measure(&[(10, 1000.0, -36.0, 0.0), (20, 1000.0, -23.0, 0.0)]);
+        assert!((report.summary.integrated_lufs + 23.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_ebu_tech_3342_and_true_peak() {
+        // Case 1: 20 s at -20 dBFS followed by 20 s at -30 dBFS
+        let report = measure(&[(20, 1000.0, -20.0, 0.0), (20, 1000.0, -30.0, 0.0)]);
+        assert!((report.summary.loudness_range_lu - 10.0).abs() < 1.0);
+
+        // A quarter-rate sine at 45 degrees peaks between its samples
+        let report = measure(&[(1, 12000.0, -6.0, std::f64::consts::FRAC_PI_4)]);
+        let summary = report.summary;
+        assert!((summary.sample_peak_dbfs + 9.0).abs() < 0.1);
+        assert!((summary.true_peak_dbtp + 6.0).abs() < 0.5);
+        assert_eq!(summary.loudness_range_lu, 0.0);
+    }
+
+    #[test]
+    fn test_channel_weights() {
+        // Quad: FL FR BL BR
+        assert_eq!(channel_weights(4, 0x33), vec![1.0, 1.0, 1.41, 1.41]);
+        // 5.1(side): FL FR FC LFE SL SR
+        assert_eq!(
+            channel_weights(6, 0x60F),
+            vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41]
+        );
+        // 4.0: FL FR FC BC
+        assert_eq!(channel_weights(4, 0x107), vec![1.0, 1.0, 1.0, 1.41]);
+        assert_eq!(channel_weights(4, 0), vec![1.0; 4]);
+        assert_eq!(channel_weights(3, 0x33), vec![1.0; 3]);
+    }
+}
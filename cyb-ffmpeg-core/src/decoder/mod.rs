@@ -2,6 +2,7 @@
 //!
 //! This module provides the core decoding functionality using FFmpeg.
 
+use std::io::{Read, Seek};
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
 use std::sync::Arc;
@@ -13,22 +14,55 @@ use crate::error::{Error, Result};
 use crate::threading::{PrefetchContext, PrefetchManager};
 
 mod audio_frame;
+mod caption;
+pub mod color;
 pub(crate) mod config;
+mod deinterlace;
+mod export;
 pub(crate) mod ffmpeg_decoder;
+mod filter;
 mod frame;
+mod hdr;
 mod info;
-
-pub use audio_frame::{AudioFrame, SampleFormat};
-pub use config::{DecoderConfig, PixelFormat};
+mod loudness;
+mod packet;
+mod scene;
+mod source;
+mod subtitle;
+mod thumbnail;
+mod timecode;
+mod waveform;
+
+pub use audio_frame::{AudioFrame, AudioSamples, SampleFormat};
+pub use caption::CaptionChannel;
+pub use color::{ChromaLocation, ColorInfo, ColorRange};
+pub use config::{
+    AudioOutputConfig, DecoderConfig, DownmixMode, OutputSize, PixelFormat, ScaleGeometry,
+    ScaleMode, ScalingAlgorithm, StreamDisposition, StreamSelector,
+};
+pub use deinterlace::{DeinterlaceMode, FieldOrder};
+pub use export::{ExportFormat, ExportOptions, ExportSummary};
 pub use frame::VideoFrame;
-pub use info::{AudioTrack, CodecInfo, MediaInfo, VideoTrack};
+pub use hdr::{ContentLightLevel, HdrMetadata, MasteringDisplay, ToneMapConfig, ToneMapOperator};
+pub use info::{
+    Attachment, AttachmentData, AttachmentKind, AudioTrack, Chapter, CodecInfo, MediaInfo,
+    SubtitleTrack, VideoTrack,
+};
+pub use loudness::{LoudnessOptions, LoudnessPoint, LoudnessReport, LoudnessSummary};
+pub use packet::{Packet, Packets};
+pub use scene::{SceneCut, SceneDetectionMethod, SceneDetectionOptions};
+pub use source::{MediaSource, ReadSeek};
+pub use subtitle::{SubtitleBitmap, SubtitleEvent, SubtitleKind, OPEN_END_US};
+pub use thumbnail::{compose_contact_sheet, ContactSheetLayout, Thumbnail, ThumbnailOptions};
+pub use timecode::Timecode;
+pub use waveform::{Waveform, WaveformBucket, WaveformOptions};
 
 use ffmpeg_decoder::FFmpegContext;
 
 /// Main decoder struct
 pub struct Decoder {
-    /// Path to the media file
-    path: String,
+    /// Media source (file, memory buffer or stream)
+    source: MediaSource,
 
     /// Decoder configuration
     config: DecoderConfig,
@@ -64,13 +98,29 @@ pub struct Decoder {
 impl Decoder {
     /// Create a new decoder
     pub fn new<P: AsRef<Path>>(path: P, config: DecoderConfig) -> Result<Self> {
-        let path_str = path.as_ref().to_string_lossy().to_string();
-
         // Verify file exists
         if !path.as_ref().exists() {
             return Err(Error::FileNotFound(path.as_ref().to_path_buf()));
         }
 
+        Self::from_source(MediaSource::from_path(path), config)
+    }
+
+    /// Create a decoder for media held in memory
+    pub fn from_bytes<B: Into<Arc<[u8]>>>(bytes: B, config: DecoderConfig) -> Result<Self> {
+        Self::from_source(MediaSource::from_bytes(bytes), config)
+    }
+
+    /// Create a decoder reading from a `Read + Seek` implementation
+    pub fn from_reader<R: Read + Seek + Send + 'static>(
+        reader: R,
+        config: DecoderConfig,
+    ) -> Result<Self> {
+        Self::from_source(MediaSource::from_reader(reader), config)
+    }
+
+    /// Create a decoder for any media source
+    pub fn from_source(source: MediaSource, config: DecoderConfig) -> Result<Self> {
         let cache_config = CacheConfig {
             l1_capacity: config.l1_cache_capacity as usize,
             l2_capacity: config.l2_cache_capacity as usize,
@@ -79,7 +129,7 @@ impl Decoder {
         };
 
         Ok(Self {
-            path: path_str,
+            source,
             config,
             media_info: RwLock::new(None),
             ffmpeg_ctx: Mutex::new(None),
@@ -99,13 +149,10 @@ impl Decoder {
             return Ok(());
         }
 
-        log::info!("Preparing decoder for: {}", self.path);
+        log::info!("Preparing decoder for: {}", self.source);
 
         // Initialize FFmpeg context
-        let mut ctx = FFmpegContext::new(&self.path, &self.config)?;
-
-        // Extract media info
-        let media_info = ctx.get_media_info()?;
+        let mut ctx = FFmpegContext::from_source(&self.source, &self.config)?;
 
         // Build keyframe index for fast seeking (synchronous during prepare)
         // Limit to 2000 entries to prevent excessive memory usage on very long videos
@@ -117,6 +164,9 @@ impl Decoder {
             log::info!("Built keyframe index with {} entries", keyframe_count);
         }
 
+        // Extract media info (after indexing, which counts the video frames)
+        let media_info = ctx.get_media_info()?;
+
         // Store context and info
         {
             let mut ctx_lock = self.ffmpeg_ctx.lock();
@@ -241,6 +291,103 @@ impl Decoder {
         Ok(None)
     }
 
+    /// Seek precisely to the frame labelled with a SMPTE timecode.
+    ///
+    /// Accepts "HH:MM:SS:FF" or drop-frame "HH:MM:SS;FF". Frames count from
+    /// the start timecode at the stream's rational frame rate.
+    pub fn seek_to_timecode(&self, timecode: &str) -> Result<Option<VideoFrame>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let time_us = match self.ffmpeg_ctx.lock().as_ref() {
+            Some(ctx) => ctx.timecode_to_us(timecode)?,
+            None => return Ok(None),
+        };
+        log::info!("Decoder::seek_to_timecode - {} -> {} us", timecode, time_us);
+
+        self.seek_precise(time_us)
+    }
+
+    /// Seek precisely to the start of chapter `index` (in `MediaInfo::chapters` order)
+    pub fn seek_to_chapter(&self, index: usize) -> Result<Option<VideoFrame>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let start_us = self
+            .media_info
+            .read()
+            .as_ref()
+            .and_then(|info| info.chapters.get(index))
+            .map(|chapter| chapter.start_us)
+            .ok_or_else(|| Error::InvalidFormat(format!("Chapter {} does not exist", index)))?;
+        log::info!(
+            "Decoder::seek_to_chapter - chapter {} starts at {} us",
+            index,
+            start_us
+        );
+
+        self.seek_precise(start_us)
+    }
+
+    /// Seek precisely to frame `frame_number` (0-based, counted at the
+    /// stream's rational frame rate, or at the field rate with bob
+    /// deinterlacing).
+    ///
+    /// Returns None if the stream has no frame with that number.
+    pub fn seek_to_frame(&self, frame_number: i64) -> Result<Option<VideoFrame>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+        if frame_number < 0 {
+            return Ok(None);
+        }
+
+        let time_us = match self.ffmpeg_ctx.lock().as_ref() {
+            Some(ctx) => ctx.frame_to_us(frame_number),
+            None => return Ok(None),
+        };
+        log::info!(
+            "Decoder::seek_to_frame - frame {} -> {} us",
+            frame_number,
+            time_us
+        );
+
+        let frame = self.seek_precise(time_us)?;
+        Ok(frame.filter(|f| f.frame_number == frame_number))
+    }
+
+    /// Get frame `frame_number` (0-based), from the cache or by seeking.
+    ///
+    /// Returns None if the stream has no frame with that number.
+    pub fn get_frame_by_index(&self, frame_number: i64) -> Result<Option<VideoFrame>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+        if frame_number < 0 {
+            return Ok(None);
+        }
+
+        let (time_us, half_frame_us) = match self.ffmpeg_ctx.lock().as_ref() {
+            Some(ctx) => {
+                let time_us = ctx.frame_to_us(frame_number);
+                (time_us, (ctx.frame_to_us(frame_number + 1) - time_us) / 2)
+            }
+            None => return Ok(None),
+        };
+
+        if let Some(frame) = self
+            .cache
+            .get(time_us, half_frame_us)
+            .filter(|f| f.frame_number == frame_number)
+        {
+            return Ok(Some(frame));
+        }
+
+        self.seek_to_frame(frame_number)
+    }
+
     /// Prime the audio decoder after seek.
     /// Call this after seek() and before get_next_audio_frame() to ensure
     /// audio packets are pre-loaded into the queue for immediate decoding.
@@ -363,6 +510,96 @@ impl Decoder {
         Ok(None)
     }
 
+    /// Get frame at specific time, scaled to the given size.
+    ///
+    /// Useful for thumbnails and previews. Frames decoded this way bypass the
+    /// cache, which only holds frames at the configured output size.
+    pub fn get_frame_at_size(
+        &self,
+        time_us: i64,
+        tolerance_us: i64,
+        size: OutputSize,
+    ) -> Result<Option<VideoFrame>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        let ctx = match ctx_lock.as_mut() {
+            Some(ctx) => ctx,
+            None => return Ok(None),
+        };
+
+        let current = self.current_time_us.load(Ordering::Acquire);
+        if (time_us - current).abs() > tolerance_us * 10 {
+            ctx.seek(time_us)?;
+        }
+
+        ctx.set_output_size(Some(size));
+        let result = Self::decode_until(ctx, time_us, tolerance_us);
+        ctx.set_output_size(self.config.output_size);
+
+        if let Ok(Some(ref frame)) = result {
+            self.current_time_us.store(frame.pts_us, Ordering::Release);
+            self.current_frame
+                .store(frame.frame_number, Ordering::Release);
+        }
+        result
+    }
+
+    /// Get frame at specific time, run through a libavfilter graph.
+    ///
+    /// `filter` replaces the configured video filter for this request only
+    /// (e.g. `"crop=iw/2:ih/2"` or `"drawtext=text='%{pts}'"`). Frames decoded
+    /// this way bypass the cache.
+    pub fn get_frame_with_filter(
+        &self,
+        time_us: i64,
+        tolerance_us: i64,
+        filter: &str,
+    ) -> Result<Option<VideoFrame>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        let ctx = match ctx_lock.as_mut() {
+            Some(ctx) => ctx,
+            None => return Ok(None),
+        };
+
+        // Switching graphs drops buffered frames, so always start from a seek
+        ctx.set_video_filter(Some(filter))?;
+        let result = ctx
+            .seek(time_us)
+            .and_then(|()| Self::decode_until(ctx, time_us, tolerance_us));
+        ctx.set_video_filter(self.config.video_filter.as_deref())?;
+
+        if let Ok(Some(ref frame)) = result {
+            self.current_time_us.store(frame.pts_us, Ordering::Release);
+            self.current_frame
+                .store(frame.frame_number, Ordering::Release);
+        }
+        result
+    }
+
+    /// Decode until a frame within tolerance of (or past) the target time
+    fn decode_until(
+        ctx: &mut FFmpegContext,
+        time_us: i64,
+        tolerance_us: i64,
+    ) -> Result<Option<VideoFrame>> {
+        // Limit to prevent infinite loop
+        for _ in 0..100 {
+            match ctx.decode_next_frame()? {
+                Some(frame) if frame.pts_us >= time_us - tolerance_us => return Ok(Some(frame)),
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
     /// Get next frame in sequence
     pub fn get_next_frame(&self) -> Result<Option<VideoFrame>> {
         if !self.is_prepared() {
@@ -412,6 +649,402 @@ impl Decoder {
         Ok(None)
     }
 
+    /// Replace the audio filter graph (None = no filtering).
+    ///
+    /// Takes effect with the next audio frame, e.g. to change `volume` or
+    /// `atempo` during playback. Audio frames already filtered are dropped.
+    pub fn set_audio_filter(&self, filter: Option<&str>) -> Result<()> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        if let Some(ref mut ctx) = *ctx_lock {
+            ctx.set_audio_filter(filter)?;
+        }
+
+        Ok(())
+    }
+
+    /// Switch the active audio track without reopening the file.
+    ///
+    /// `stream_index` is the container stream index as reported by
+    /// `AudioTrack::index`. Decoding continues from the current position.
+    pub fn select_audio_track(&self, stream_index: usize) -> Result<()> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        if let Some(ref mut ctx) = *ctx_lock {
+            ctx.select_audio_stream(stream_index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the stream index of the active video track
+    pub fn active_video_track(&self) -> Option<usize> {
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        ctx_lock.as_ref().and_then(|ctx| ctx.video_stream_index())
+    }
+
+    /// Get the stream index of the active audio track
+    pub fn active_audio_track(&self) -> Option<usize> {
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        ctx_lock.as_ref().and_then(|ctx| ctx.audio_stream_index())
+    }
+
+    /// Get the stream index of the active subtitle track
+    pub fn active_subtitle_track(&self) -> Option<usize> {
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        ctx_lock.as_ref().and_then(|ctx| ctx.subtitle_stream_index())
+    }
+
+    /// Switch the active subtitle track.
+    ///
+    /// Events from the previous track are discarded. Call `load_subtitles`
+    /// to decode the whole new track up front.
+    pub fn select_subtitle_track(&self, stream_index: usize) -> Result<()> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        if let Some(ref mut ctx) = *ctx_lock {
+            ctx.select_subtitle_stream(stream_index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Decode every event of the active subtitle track.
+    ///
+    /// This reads the whole file, then seeks back to the current position.
+    /// Returns the number of events available.
+    pub fn load_subtitles(&self) -> Result<usize> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        if let Some(ref mut ctx) = *ctx_lock {
+            let count = ctx.scan_subtitles()?;
+            ctx.seek(self.current_time_us.load(Ordering::Acquire))?;
+            return Ok(count);
+        }
+
+        Ok(0)
+    }
+
+    /// Get subtitle events active at the given time
+    ///
+    /// Events are collected as packets are demuxed (during prepare, playback
+    /// and seeking), or all at once with `load_subtitles`.
+    pub fn subtitles_at(&self, time_us: i64) -> Result<Vec<SubtitleEvent>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        Ok(ctx_lock
+            .as_ref()
+            .map(|ctx| ctx.subtitles_at(time_us))
+            .unwrap_or_default())
+    }
+
+    /// Get all subtitle events decoded so far
+    pub fn subtitle_events(&self) -> Vec<SubtitleEvent> {
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        ctx_lock
+            .as_ref()
+            .map(|ctx| ctx.subtitle_events().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Decode the cover art: the attached picture tagged as front cover, else
+    /// the first one.
+    ///
+    /// The picture is converted to the configured pixel format and output
+    /// size. Returns None if the media has no attached picture.
+    pub fn get_cover_art(&self) -> Result<Option<VideoFrame>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let index = self
+            .media_info
+            .read()
+            .as_ref()
+            .and_then(|info| info.cover_art())
+            .map(|attachment| attachment.index as usize);
+        match index {
+            Some(index) => self.get_attached_picture(index).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Decode the attached picture in stream `stream_index`
+    pub fn get_attached_picture(&self, stream_index: usize) -> Result<VideoFrame> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        let ctx = ctx_lock.as_mut().ok_or(Error::NotPrepared)?;
+        ctx.decode_attached_picture(stream_index)
+    }
+
+    /// Get the raw bytes of an attachment (a font, or an attached picture's
+    /// encoded image) with its file name and MIME type
+    pub fn get_attachment(&self, stream_index: usize) -> Result<AttachmentData> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        let ctx = ctx_lock.as_ref().ok_or(Error::NotPrepared)?;
+        ctx.attachment_data(stream_index)
+    }
+
+    /// Get closed caption events of a channel active at the given time
+    ///
+    /// Captions (CEA-608/708 carried in the video stream) are decoded along
+    /// with video frames, so only times around decoded frames are covered.
+    pub fn captions_at(&self, channel: CaptionChannel, time_us: i64) -> Result<Vec<SubtitleEvent>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        Ok(ctx_lock
+            .as_ref()
+            .map(|ctx| ctx.captions_at(channel, time_us))
+            .unwrap_or_default())
+    }
+
+    /// Get all closed caption events of a channel decoded so far
+    pub fn caption_events(&self, channel: CaptionChannel) -> Vec<SubtitleEvent> {
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        ctx_lock
+            .as_ref()
+            .map(|ctx| ctx.caption_events(channel).to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Get the caption channels that produced events so far
+    pub fn caption_channels(&self) -> Vec<CaptionChannel> {
+        let ctx_lock = self.ffmpeg_ctx.lock();
+        ctx_lock
+            .as_ref()
+            .map(|ctx| ctx.caption_channels())
+            .unwrap_or_default()
+    }
+
+    /// Read the next compressed packet of any stream, without decoding.
+    ///
+    /// Packets come from the demuxer that also feeds decoding, so packets
+    /// read here are not decoded: stop prefetching first, and seek before
+    /// resuming playback. Returns None at the end of the input.
+    pub fn read_packet(&self) -> Result<Option<Packet>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let mut ctx_lock = self.ffmpeg_ctx.lock();
+        match ctx_lock.as_mut() {
+            Some(ctx) => ctx.read_packet(),
+            None => Ok(None),
+        }
+    }
+
+    /// Iterate over compressed packets from the current position
+    /// (see `read_packet`)
+    pub fn packets(&self) -> Packets<'_> {
+        Packets::new(self)
+    }
+
+    /// Export a time range without re-encoding (stream copy) to MP4, MOV or
+    /// MKV. See `ExportOptions` for track selection and keyframe snapping.
+    ///
+    /// The export reads through its own demuxer, so playback is not
+    /// disturbed. `progress` receives the completed fraction (0.0-1.0) and
+    /// returns false to cancel, which removes the partial file and fails
+    /// with `Error::Cancelled`.
+    pub fn export<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &ExportOptions,
+        mut progress: impl FnMut(f64) -> bool,
+    ) -> Result<ExportSummary> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let keyframes = self
+            .ffmpeg_ctx
+            .lock()
+            .as_ref()
+            .and_then(|ctx| ctx.keyframe_index().cloned());
+        let mut ctx = FFmpegContext::from_source(&self.source, &self.config)?;
+        ctx.export(path.as_ref(), options, keyframes.as_ref(), &mut progress)
+    }
+
+    /// Generate thumbnails evenly spaced over a time range (e.g. a timeline
+    /// filmstrip).
+    ///
+    /// Thumbnails are decoded through their own demuxer at `options.size`, so
+    /// the playhead and the frame cache are not affected. `on_thumbnail` is
+    /// called as each thumbnail completes and returns false to cancel, which
+    /// fails with `Error::Cancelled`. Returns the number of thumbnails
+    /// delivered.
+    pub fn thumbnails(
+        &self,
+        options: &ThumbnailOptions,
+        mut on_thumbnail: impl FnMut(Thumbnail) -> bool,
+    ) -> Result<usize> {
+        self.generate_thumbnails(options, self.config.output_pixel_format, &mut on_thumbnail)
+    }
+
+    /// Generate thumbnails and composite them into a single image.
+    ///
+    /// The sheet uses the configured output format if it is packed RGB, and
+    /// BGRA otherwise.
+    pub fn contact_sheet(
+        &self,
+        options: &ThumbnailOptions,
+        layout: &ContactSheetLayout,
+    ) -> Result<VideoFrame> {
+        let format = match self.config.output_pixel_format {
+            format if format.plane_count() == 1 => format,
+            _ => PixelFormat::Bgra,
+        };
+
+        let mut frames = Vec::new();
+        self.generate_thumbnails(options, format, &mut |thumbnail| {
+            frames.push(thumbnail.frame);
+            true
+        })?;
+        compose_contact_sheet(&frames, layout)
+    }
+
+    /// Decode thumbnails in `pixel_format` on a separate FFmpeg context
+    fn generate_thumbnails(
+        &self,
+        options: &ThumbnailOptions,
+        pixel_format: PixelFormat,
+        on_thumbnail: &mut dyn FnMut(Thumbnail) -> bool,
+    ) -> Result<usize> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let (keyframes, video_track) = {
+            let ctx_lock = self.ffmpeg_ctx.lock();
+            match ctx_lock.as_ref() {
+                Some(ctx) => (ctx.keyframe_index().cloned(), ctx.video_stream_index()),
+                None => (None, None),
+            }
+        };
+
+        let mut config = self.config.clone();
+        config.output_pixel_format = pixel_format;
+        if let Some(index) = video_track {
+            config.video_stream = StreamSelector::Index(index);
+        }
+        let mut ctx = FFmpegContext::from_source(&self.source, &config)?;
+        ctx.thumbnails(options, keyframes.as_ref(), on_thumbnail)
+    }
+
+    /// Compute per-channel min/max/RMS waveform buckets of an audio track.
+    ///
+    /// The audio is decoded through its own demuxer at the source sample rate
+    /// and channel count, so playback is not disturbed. `progress` receives
+    /// the buckets completed so far and the completed fraction (0.0-1.0) and
+    /// returns false to cancel, which fails with `Error::Cancelled`.
+    pub fn waveform(
+        &self,
+        options: &WaveformOptions,
+        mut progress: impl FnMut(&Waveform, f64) -> bool,
+    ) -> Result<Waveform> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let config = self.analysis_audio_config(options.track);
+        let mut ctx = FFmpegContext::from_source(&self.source, &config)?;
+        ctx.waveform(options, &mut progress)
+    }
+
+    /// Config decoding `track` (None = the active audio track) as planar
+    /// float samples at the source rate and layout, without the audio filter
+    fn analysis_audio_config(&self, track: Option<usize>) -> DecoderConfig {
+        let mut config = self.config.clone();
+        config.audio_output = AudioOutputConfig {
+            sample_format: SampleFormat::Float32,
+            planar: true,
+            ..AudioOutputConfig::passthrough()
+        };
+        config.audio_filter = None;
+        if let Some(index) = track.or_else(|| self.active_audio_track()) {
+            config.audio_stream = StreamSelector::Index(index);
+        }
+        config
+    }
+
+    /// Measure the loudness (EBU R128) of an audio track.
+    ///
+    /// Returns integrated loudness, loudness range and true peak along with
+    /// the momentary and short-term loudness every 100 ms. The audio is
+    /// decoded through its own demuxer, so playback is not disturbed.
+    /// `progress` receives the points measured so far and the completed
+    /// fraction (0.0-1.0) and returns false to cancel, which fails with
+    /// `Error::Cancelled`.
+    pub fn loudness(
+        &self,
+        options: &LoudnessOptions,
+        mut progress: impl FnMut(&[LoudnessPoint], f64) -> bool,
+    ) -> Result<LoudnessReport> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let config = self.analysis_audio_config(options.track);
+        let mut ctx = FFmpegContext::from_source(&self.source, &config)?;
+        ctx.loudness(options, &mut progress)
+    }
+
+    /// Find the cuts between shots of the video track.
+    ///
+    /// Each decoded frame is compared with the previous one; cut times match
+    /// the `pts_us` of the first frame of each new shot. The video is decoded
+    /// through its own demuxer, so playback is not disturbed. `progress`
+    /// receives the cuts found so far and the completed fraction (0.0-1.0)
+    /// and returns false to cancel, which fails with `Error::Cancelled`.
+    pub fn detect_scenes(
+        &self,
+        options: &SceneDetectionOptions,
+        mut progress: impl FnMut(&[SceneCut], f64) -> bool,
+    ) -> Result<Vec<SceneCut>> {
+        if !self.is_prepared() {
+            return Err(Error::NotPrepared);
+        }
+
+        let video_track = self
+            .ffmpeg_ctx
+            .lock()
+            .as_ref()
+            .and_then(|ctx| ctx.video_stream_index());
+
+        let mut config = self.config.clone();
+        config.output_pixel_format = PixelFormat::Yuv420p;
+        if let Some(index) = video_track {
+            config.video_stream = StreamSelector::Index(index);
+        }
+        let mut ctx = FFmpegContext::from_source(&self.source, &config)?;
+        ctx.detect_scenes(options, &mut progress)
+    }
+
     /// Check if media has audio
     pub fn has_audio(&self) -> bool {
         if let Some(ref info) = *self.media_info.read() {
@@ -482,7 +1115,7 @@ impl Decoder {
         let manager = if pm_lock.is_none() {
             // Create new prefetch context
             let context = PrefetchContext::new(
-                self.path.clone(),
+                self.source.clone(),
                 self.config.clone(),
                 self.cache.clone(),
                 self.current_time_us.clone(),
@@ -531,9 +1164,14 @@ impl Decoder {
         self.cache.clear();
     }
 
-    /// Get path
-    pub fn path(&self) -> &str {
-        &self.path
+    /// Get path (None for memory and stream sources)
+    pub fn path(&self) -> Option<&Path> {
+        self.source.path()
+    }
+
+    /// Get media source
+    pub fn source(&self) -> &MediaSource {
+        &self.source
     }
 
     /// Get configuration
@@ -546,7 +1184,7 @@ impl Drop for Decoder {
     fn drop(&mut self) {
         self.stop_decoding();
         self.stop_prefetch();
-        log::debug!("Decoder dropped for: {}", self.path);
+        log::debug!("Decoder dropped for: {}", self.source);
     }
 }
 
@@ -563,4 +1201,11 @@ mod tests {
         let result = Decoder::new("/nonexistent/file.mp4", DecoderConfig::default());
         assert!(matches!(result, Err(Error::FileNotFound(_))));
     }
+
+    #[test]
+    fn test_decoder_from_bytes_invalid_data() {
+        let decoder = Decoder::from_bytes(vec![0u8; 1024], DecoderConfig::default()).unwrap();
+        assert!(decoder.path().is_none());
+        assert!(decoder.prepare().is_err());
+    }
 }
@@ -0,0 +1,135 @@
+//! Compressed packets read from the demuxer
+
+use super::Decoder;
+use crate::error::Result;
+
+/// Compressed packet of one stream, as stored in the container
+#[derive(Clone, PartialEq, Eq)]
+pub struct Packet {
+    /// Stream index
+    pub stream_index: i32,
+
+    /// Presentation timestamp in `time_base` units
+    pub pts: Option<i64>,
+
+    /// Decoding timestamp in `time_base` units
+    pub dts: Option<i64>,
+
+    /// Duration in `time_base` units (0 if unknown)
+    pub duration: i64,
+
+    /// Time base of the stream as (numerator, denominator)
+    pub time_base: (i32, i32),
+
+    /// Presentation timestamp in microseconds
+    pub pts_us: Option<i64>,
+
+    /// Decoding timestamp in microseconds
+    pub dts_us: Option<i64>,
+
+    /// Duration in microseconds (0 if unknown)
+    pub duration_us: i64,
+
+    /// Whether the packet starts with a keyframe
+    pub is_keyframe: bool,
+
+    /// Whether the demuxer flagged the packet as corrupt
+    pub is_corrupt: bool,
+
+    /// Byte position in the file
+    pub position: Option<i64>,
+
+    /// Packet payload
+    pub data: Vec<u8>,
+}
+
+impl Packet {
+    /// Payload size in bytes
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Time to order packets by when interleaving (dts, else pts)
+    pub fn timestamp_us(&self) -> Option<i64> {
+        self.dts_us.or(self.pts_us)
+    }
+
+    /// Presentation end time in microseconds
+    pub fn end_us(&self) -> Option<i64> {
+        self.pts_us.map(|pts_us| pts_us + self.duration_us)
+    }
+}
+
+impl std::fmt::Debug for Packet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Packet")
+            .field("stream_index", &self.stream_index)
+            .field("pts", &self.pts)
+            .field("dts", &self.dts)
+            .field("duration", &self.duration)
+            .field("time_base", &self.time_base)
+            .field("is_keyframe", &self.is_keyframe)
+            .field("position", &self.position)
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+/// Iterator over the compressed packets of a `Decoder`
+///
+/// Ends after the last packet or the first error.
+pub struct Packets<'a> {
+    decoder: &'a Decoder,
+    done: bool,
+}
+
+impl<'a> Packets<'a> {
+    pub(crate) fn new(decoder: &'a Decoder) -> Self {
+        Self {
+            decoder,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Packets<'_> {
+    type Item = Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.decoder.read_packet().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packet_times() {
+        let mut packet = Packet {
+            stream_index: 0,
+            pts: Some(3003),
+            dts: None,
+            duration: 1501,
+            time_base: (1, 90000),
+            pts_us: Some(33_366),
+            dts_us: None,
+            duration_us: 16_677,
+            is_keyframe: true,
+            is_corrupt: false,
+            position: Some(48),
+            data: vec![0, 0, 0, 1, 0x65],
+        };
+        assert_eq!(packet.size(), 5);
+        assert_eq!(packet.timestamp_us(), Some(33_366));
+        assert_eq!(packet.end_us(), Some(50_043));
+
+        packet.dts_us = Some(0);
+        assert_eq!(packet.timestamp_us(), Some(0));
+    }
+}
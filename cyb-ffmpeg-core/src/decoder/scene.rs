@@ -0,0 +1,395 @@
+//! Scene change (shot boundary) detection
+//!
+//! Each decoded frame is compared with the one before it, either by color
+//! histograms, which tolerate motion within a shot, or by the mean absolute
+//! difference of the luma, which also separates shots of similar colors. A
+//! cut is reported where the difference reaches the threshold. Frames are
+//! usually compared at a small size, which is much faster and hardly changes
+//! the scores.
+
+use std::collections::VecDeque;
+
+use super::config::{OutputSize, PixelFormat, ScaleMode};
+use super::frame::VideoFrame;
+use crate::error::{Error, Result};
+
+/// Histogram bins per component
+const HISTOGRAM_BINS: usize = 32;
+
+/// Scores of the frames before a cut that its confidence is measured against
+const RECENT_SCORES: usize = 12;
+
+/// How consecutive frames are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SceneDetectionMethod {
+    /// Difference of the Y, U and V histograms (typical thresholds 0.3-0.5)
+    #[default]
+    Histogram,
+    /// Mean absolute luma difference (typical thresholds 0.1-0.2)
+    Difference,
+}
+
+/// What to analyze and how
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneDetectionOptions {
+    /// Start of the range in microseconds
+    pub start_us: i64,
+
+    /// End of the range in microseconds (None = end of media)
+    pub end_us: Option<i64>,
+
+    /// Frame comparison
+    pub method: SceneDetectionMethod,
+
+    /// Difference (0.0-1.0) from which two frames are a cut
+    pub threshold: f64,
+
+    /// Shortest shot in microseconds; closer cuts (e.g. flashes) are ignored
+    pub min_shot_us: i64,
+
+    /// Size frames are scaled to before comparing (None = decoded size)
+    pub analysis_size: Option<OutputSize>,
+}
+
+impl Default for SceneDetectionOptions {
+    fn default() -> Self {
+        Self {
+            start_us: 0,
+            end_us: None,
+            method: SceneDetectionMethod::Histogram,
+            threshold: 0.35,
+            min_shot_us: 500_000,
+            analysis_size: Some(OutputSize::new(160, 0, ScaleMode::Fit)),
+        }
+    }
+}
+
+/// A detected cut
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SceneCut {
+    /// Time of the first frame of the new shot (its `VideoFrame::pts_us`)
+    pub time_us: i64,
+
+    /// Frame number of the first frame of the new shot
+    pub frame_number: i64,
+
+    /// Difference between the frames on either side of the cut (0.0-1.0)
+    pub score: f64,
+
+    /// How much the score stands out from the frames before it (0.0-1.0);
+    /// low for cuts within fast motion or flicker
+    pub confidence: f64,
+}
+
+/// What a frame is compared by
+enum Signature {
+    /// Normalized Y, U and V histograms
+    Histogram([[f64; HISTOGRAM_BINS]; 3]),
+    /// Unpadded luma plane
+    Luma {
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    },
+}
+
+impl Signature {
+    fn new(frame: &VideoFrame, method: SceneDetectionMethod) -> Result<Self> {
+        let [luma, u, v] = yuv_planes(frame)?;
+        Ok(match method {
+            SceneDetectionMethod::Histogram => {
+                let histogram = |plane: &[u8]| {
+                    let mut bins = [0.0; HISTOGRAM_BINS];
+                    for &value in plane {
+                        bins[value as usize * HISTOGRAM_BINS / 256] += 1.0;
+                    }
+                    let total = plane.len().max(1) as f64;
+                    bins.map(|count| count / total)
+                };
+                Signature::Histogram([histogram(&luma), histogram(&u), histogram(&v)])
+            }
+            SceneDetectionMethod::Difference => Signature::Luma {
+                width: frame.width,
+                height: frame.height,
+                data: luma,
+            },
+        })
+    }
+
+    /// Difference to another signature (0.0 = identical, 1.0 = opposite)
+    fn difference(&self, other: &Signature) -> f64 {
+        match (self, other) {
+            (Signature::Histogram(a), Signature::Histogram(b)) => {
+                let distance: f64 = a
+                    .iter()
+                    .flatten()
+                    .zip(b.iter().flatten())
+                    .map(|(a, b)| (a - b).abs())
+                    .sum();
+                // Each histogram contributes at most 2.0
+                distance / 6.0
+            }
+            (
+                Signature::Luma {
+                    width,
+                    height,
+                    data: a,
+                },
+                Signature::Luma {
+                    width: other_width,
+                    height: other_height,
+                    data: b,
+                },
+            ) if (width, height) == (other_width, other_height) && !a.is_empty() => {
+                let sum: u64 = a.iter().zip(b).map(|(&a, &b)| a.abs_diff(b) as u64).sum();
+                sum as f64 / (a.len() as f64 * 255.0)
+            }
+            // A change of size is a cut
+            _ => 1.0,
+        }
+    }
+}
+
+/// Luma (width x height) and chroma planes of a frame, without row padding.
+///
+/// Packed RGB is converted with integer BT.601 coefficients.
+fn yuv_planes(frame: &VideoFrame) -> Result<[Vec<u8>; 3]> {
+    let format = frame.pixel_format;
+    if frame.data.len() < VideoFrame::planes_size(frame.stride, frame.height, format) {
+        return Err(Error::InvalidFormat(
+            "Frame data is smaller than its size".to_string(),
+        ));
+    }
+    let (width, height) = (frame.width as usize, frame.height as usize);
+
+    match format {
+        PixelFormat::Yuv420p => {
+            let plane = |index: usize, width: usize, height: usize| -> Vec<u8> {
+                let offset = frame.plane_offset(index);
+                let stride = frame.plane_stride(index) as usize;
+                (0..height)
+                    .flat_map(|row| &frame.data[offset + row * stride..][..width])
+                    .copied()
+                    .collect()
+            };
+            let (chroma_width, chroma_height) = (width.div_ceil(2), height.div_ceil(2));
+            Ok([
+                plane(0, width, height),
+                plane(1, chroma_width, chroma_height),
+                plane(2, chroma_width, chroma_height),
+            ])
+        }
+        PixelFormat::Bgra | PixelFormat::Rgba => {
+            let (red, blue) = if format == PixelFormat::Bgra {
+                (2, 0)
+            } else {
+                (0, 2)
+            };
+            let mut planes: [Vec<u8>; 3] =
+                std::array::from_fn(|_| Vec::with_capacity(width * height));
+            for row in 0..height {
+                let line = &frame.data[row * frame.stride as usize..][..width * 4];
+                for pixel in line.chunks_exact(4) {
+                    let (r, g, b) = (pixel[red] as i32, pixel[1] as i32, pixel[blue] as i32);
+                    planes[0].push(((77 * r + 150 * g + 29 * b) >> 8) as u8);
+                    planes[1].push((((-43 * r - 85 * g + 128 * b) >> 8) + 128) as u8);
+                    planes[2].push((((128 * r - 107 * g - 21 * b) >> 8) + 128) as u8);
+                }
+            }
+            Ok(planes)
+        }
+        other => Err(Error::InvalidFormat(format!(
+            "Scene detection needs YUV420P, BGRA or RGBA frames, not {:?}",
+            other
+        ))),
+    }
+}
+
+/// Finds cuts between consecutive decoded frames
+pub(crate) struct SceneDetector {
+    method: SceneDetectionMethod,
+    threshold: f64,
+    min_shot_us: i64,
+    start_us: i64,
+    previous: Option<Signature>,
+    last_cut_us: Option<i64>,
+    /// Scores of the latest frames that were not cuts
+    recent: VecDeque<f64>,
+    cuts: Vec<SceneCut>,
+}
+
+impl SceneDetector {
+    /// Start detecting with the method, threshold and range of `options`
+    pub fn new(options: &SceneDetectionOptions) -> Self {
+        Self {
+            method: options.method,
+            threshold: options.threshold,
+            min_shot_us: options.min_shot_us,
+            start_us: options.start_us,
+            previous: None,
+            last_cut_us: None,
+            recent: VecDeque::with_capacity(RECENT_SCORES),
+            cuts: Vec::new(),
+        }
+    }
+
+    /// Compare a frame with the previous one.
+    ///
+    /// Frames before the start of the range only serve as reference.
+    /// Returns the cut starting with this frame, if any.
+    pub fn push(&mut self, frame: &VideoFrame) -> Result<Option<SceneCut>> {
+        let signature = Signature::new(frame, self.method)?;
+        let score = match self.previous.replace(signature) {
+            Some(previous) => previous.difference(self.previous.as_ref().unwrap()),
+            None => return Ok(None),
+        };
+        if frame.pts_us < self.start_us {
+            return Ok(None);
+        }
+
+        let too_close = self
+            .last_cut_us
+            .is_some_and(|last_cut_us| frame.pts_us - last_cut_us < self.min_shot_us);
+        if score < self.threshold || too_close {
+            if self.recent.len() == RECENT_SCORES {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(score);
+            return Ok(None);
+        }
+
+        let typical = self.recent.iter().sum::<f64>() / self.recent.len().max(1) as f64;
+        let cut = SceneCut {
+            time_us: frame.pts_us,
+            frame_number: frame.frame_number,
+            score,
+            confidence: ((score - typical) / score).clamp(0.0, 1.0),
+        };
+        self.last_cut_us = Some(frame.pts_us);
+        self.recent.clear();
+        self.cuts.push(cut);
+        Ok(Some(cut))
+    }
+
+    /// Cuts found so far
+    pub fn cuts(&self) -> &[SceneCut] {
+        &self.cuts
+    }
+
+    /// Return all cuts found
+    pub fn finish(self) -> Vec<SceneCut> {
+        self.cuts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 8x8 BGRA frame: left half `left`, right half `right`
+    fn frame(index: i64, left: [u8; 3], right: [u8; 3]) -> VideoFrame {
+        let mut frame = VideoFrame::test_frame(index * 40_000, 8, 8);
+        frame.frame_number = index;
+        for (i, pixel) in frame.data.chunks_exact_mut(4).enumerate() {
+            let [b, g, r] = if i % 8 < 4 { left } else { right };
+            pixel.copy_from_slice(&[b, g, r, 0xFF]);
+        }
+        frame
+    }
+
+    /// 7x5 YUV420P frame with a luma stride of 8 (padding set to 0xFF)
+    fn yuv_frame(index: i64, [y, u, v]: [u8; 3]) -> VideoFrame {
+        let format = PixelFormat::Yuv420p;
+        let mut data = vec![0xFF; VideoFrame::planes_size(8, 5, format)];
+        for row in 0..5 {
+            data[row * 8..][..7].fill(y);
+        }
+        data[40..52].fill(u);
+        data[52..].fill(v);
+        VideoFrame::new(data, 7, 5, 8, index * 40_000, 40_000, true, index, format)
+    }
+
+    #[test]
+    fn test_detect_cuts() {
+        let gray = [128, 128, 128];
+        let red = [0, 0, 255];
+        let blue = [255, 0, 0];
+        for method in [
+            SceneDetectionMethod::Histogram,
+            SceneDetectionMethod::Difference,
+        ] {
+            let options = SceneDetectionOptions {
+                method,
+                threshold: 0.1,
+                min_shot_us: 0,
+                ..SceneDetectionOptions::default()
+            };
+            let mut detector = SceneDetector::new(&options);
+            for index in 0..3 {
+                assert!(detector.push(&frame(index, gray, gray)).unwrap().is_none());
+            }
+            let cut = detector.push(&frame(3, red, blue)).unwrap().unwrap();
+            assert_eq!((cut.time_us, cut.frame_number), (120_000, 3));
+            assert!(cut.score > 0.1);
+            assert_eq!(cut.confidence, 1.0);
+
+            // Swapping the halves moves luma but keeps the histograms
+            let swapped = detector.push(&frame(4, blue, red)).unwrap();
+            assert_eq!(
+                swapped.is_some(),
+                method == SceneDetectionMethod::Difference
+            );
+        }
+    }
+
+    #[test]
+    fn test_range_and_min_shot() {
+        let black = [0, 0, 0];
+        let white = [255, 255, 255];
+        let options = SceneDetectionOptions {
+            start_us: 80_000,
+            threshold: 0.3,
+            min_shot_us: 100_000,
+            ..SceneDetectionOptions::default()
+        };
+        let mut detector = SceneDetector::new(&options);
+        // The cut at frame 1 is before the range, the one at frame 4 too close
+        // to the cut at frame 3
+        let colors = [black, white, white, black, white, white, white, black];
+        for (index, &color) in colors.iter().enumerate() {
+            detector.push(&frame(index as i64, color, color)).unwrap();
+        }
+        let times: Vec<i64> = detector.finish().iter().map(|cut| cut.time_us).collect();
+        assert_eq!(times, vec![120_000, 280_000]);
+
+        let mut nv12 = VideoFrame::test_frame(0, 8, 8);
+        nv12.pixel_format = PixelFormat::Nv12;
+        nv12.stride = 8;
+        assert!(SceneDetector::new(&options).push(&nv12).is_err());
+    }
+
+    #[test]
+    fn test_yuv420p_odd_size() {
+        let [luma, u, v] = yuv_planes(&yuv_frame(0, [100, 90, 80])).unwrap();
+        assert_eq!(luma, vec![100; 35]);
+        assert_eq!((u, v), (vec![90; 12], vec![80; 12]));
+
+        for method in [
+            SceneDetectionMethod::Histogram,
+            SceneDetectionMethod::Difference,
+        ] {
+            let options = SceneDetectionOptions {
+                method,
+                threshold: 0.1,
+                min_shot_us: 0,
+                ..SceneDetectionOptions::default()
+            };
+            let mut detector = SceneDetector::new(&options);
+            let (dark, light) = ([100, 128, 128], [200, 128, 128]);
+            assert!(detector.push(&yuv_frame(0, dark)).unwrap().is_none());
+            assert!(detector.push(&yuv_frame(1, dark)).unwrap().is_none());
+            let cut = detector.push(&yuv_frame(2, light)).unwrap().unwrap();
+            assert_eq!((cut.time_us, cut.frame_number), (80_000, 2));
+        }
+    }
+}
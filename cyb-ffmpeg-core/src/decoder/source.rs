@@ -0,0 +1,339 @@
+//! Media input sources
+//!
+//! Media can be opened from a filesystem path, an in-memory buffer or any
+//! `Read + Seek` implementation. Non-path sources are fed to FFmpeg through a
+//! custom AVIOContext. Every FFmpegContext opened from the same source (main
+//! decoder and prefetch workers) gets its own read position, so the source can
+//! be shared freely.
+
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::raw::{c_int, c_void};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::Arc;
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::format::context::Input as FormatContext;
+use parking_lot::Mutex;
+
+use crate::error::{Error, Result};
+
+/// Size of the AVIOContext buffer
+const IO_BUFFER_SIZE: usize = 64 * 1024;
+
+/// `Read + Seek` sources that can be moved to other threads
+pub trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+/// Where the decoder reads media data from
+#[derive(Clone)]
+pub enum MediaSource {
+    /// File on disk
+    Path(PathBuf),
+
+    /// Complete media file held in memory
+    Memory(Arc<[u8]>),
+
+    /// Seekable stream shared between all contexts opened from it
+    Stream(Arc<Mutex<Box<dyn ReadSeek>>>),
+}
+
+impl MediaSource {
+    /// Create a source from a filesystem path
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        MediaSource::Path(path.as_ref().to_path_buf())
+    }
+
+    /// Create a source from an in-memory buffer
+    pub fn from_bytes<B: Into<Arc<[u8]>>>(bytes: B) -> Self {
+        MediaSource::Memory(bytes.into())
+    }
+
+    /// Create a source from a `Read + Seek` implementation
+    pub fn from_reader<R: ReadSeek + 'static>(reader: R) -> Self {
+        MediaSource::Stream(Arc::new(Mutex::new(Box::new(reader))))
+    }
+
+    /// Get the path (None for memory and stream sources)
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            MediaSource::Path(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MediaSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaSource::Path(path) => write!(f, "{}", path.display()),
+            MediaSource::Memory(data) => write!(f, "<memory: {} bytes>", data.len()),
+            MediaSource::Stream(_) => write!(f, "<stream>"),
+        }
+    }
+}
+
+impl fmt::Debug for MediaSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MediaSource({})", self)
+    }
+}
+
+/// Reader with its own position over a memory or stream source
+pub(crate) struct SourceReader {
+    source: MediaSource,
+    position: u64,
+}
+
+impl SourceReader {
+    /// Create a reader positioned at the start of the source
+    pub(crate) fn new(source: MediaSource) -> Self {
+        Self {
+            source,
+            position: 0,
+        }
+    }
+
+    /// Total size of the source in bytes
+    pub(crate) fn size(&self) -> io::Result<u64> {
+        match &self.source {
+            MediaSource::Path(path) => Ok(std::fs::metadata(path)?.len()),
+            MediaSource::Memory(data) => Ok(data.len() as u64),
+            MediaSource::Stream(stream) => stream.lock().seek(SeekFrom::End(0)),
+        }
+    }
+}
+
+impl Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = match &self.source {
+            MediaSource::Path(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "path sources are opened by FFmpeg directly",
+                ))
+            }
+            MediaSource::Memory(data) => {
+                let start = (self.position as usize).min(data.len());
+                let count = buf.len().min(data.len() - start);
+                buf[..count].copy_from_slice(&data[start..start + count]);
+                count
+            }
+            MediaSource::Stream(stream) => {
+                let mut stream = stream.lock();
+                stream.seek(SeekFrom::Start(self.position))?;
+                stream.read(buf)?
+            }
+        };
+
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SourceReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.size()?.checked_add_signed(offset),
+        };
+
+        match target {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "seek to a negative position",
+            )),
+        }
+    }
+}
+
+/// Custom AVIOContext reading from a `SourceReader`.
+///
+/// Must outlive the format context using it, so owners declare it after the
+/// input it backs.
+pub(crate) struct CustomIo {
+    ctx: *mut ffmpeg::ffi::AVIOContext,
+    reader: *mut SourceReader,
+}
+
+impl CustomIo {
+    fn new(source: &MediaSource) -> Result<Self> {
+        let reader = Box::into_raw(Box::new(SourceReader::new(source.clone())));
+
+        unsafe {
+            let buffer = ffmpeg::ffi::av_malloc(IO_BUFFER_SIZE) as *mut u8;
+            if buffer.is_null() {
+                drop(Box::from_raw(reader));
+                return Err(Error::Memory);
+            }
+
+            let ctx = ffmpeg::ffi::avio_alloc_context(
+                buffer,
+                IO_BUFFER_SIZE as c_int,
+                0,
+                reader as *mut c_void,
+                Some(read_packet),
+                None,
+                Some(seek_packet),
+            );
+            if ctx.is_null() {
+                ffmpeg::ffi::av_free(buffer as *mut c_void);
+                drop(Box::from_raw(reader));
+                return Err(Error::Memory);
+            }
+
+            Ok(Self { ctx, reader })
+        }
+    }
+}
+
+impl Drop for CustomIo {
+    fn drop(&mut self) {
+        unsafe {
+            // FFmpeg may have replaced the buffer, so free the current one
+            ffmpeg::ffi::av_freep(&mut (*self.ctx).buffer as *mut *mut u8 as *mut c_void);
+            ffmpeg::ffi::avio_context_free(&mut self.ctx);
+            drop(Box::from_raw(self.reader));
+        }
+    }
+}
+
+// The reader only holds Send data and is used by one context at a time
+unsafe impl Send for CustomIo {}
+
+unsafe extern "C" fn read_packet(opaque: *mut c_void, buf: *mut u8, buf_size: c_int) -> c_int {
+    let reader = &mut *(opaque as *mut SourceReader);
+    let buf = std::slice::from_raw_parts_mut(buf, buf_size.max(0) as usize);
+
+    match reader.read(buf) {
+        Ok(0) => ffmpeg::ffi::AVERROR_EOF,
+        Ok(n) => n as c_int,
+        Err(e) => {
+            log::warn!("Custom I/O read failed: {}", e);
+            ffmpeg::ffi::AVERROR_EXTERNAL
+        }
+    }
+}
+
+unsafe extern "C" fn seek_packet(opaque: *mut c_void, offset: i64, whence: c_int) -> i64 {
+    let reader = &mut *(opaque as *mut SourceReader);
+    let whence = whence & !(ffmpeg::ffi::AVSEEK_FORCE as c_int);
+
+    let result = if whence == ffmpeg::ffi::AVSEEK_SIZE as c_int {
+        reader.size()
+    } else {
+        let pos = match whence {
+            0 => SeekFrom::Start(offset.max(0) as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => return ffmpeg::ffi::AVERROR_EXTERNAL as i64,
+        };
+        reader.seek(pos)
+    };
+
+    match result {
+        Ok(position) => position as i64,
+        Err(e) => {
+            log::warn!("Custom I/O seek failed: {}", e);
+            ffmpeg::ffi::AVERROR_EXTERNAL as i64
+        }
+    }
+}
+
+/// Open a format context for a media source.
+///
+/// Returns the custom I/O backing the input for memory and stream sources;
+/// it must be kept alive (and dropped after) the returned input.
+pub(crate) fn open_input(source: &MediaSource) -> Result<(FormatContext, Option<CustomIo>)> {
+    if let MediaSource::Path(path) = source {
+        let input = ffmpeg::format::input(path).map_err(|e| {
+            if e.to_string().contains("No such file") {
+                Error::FileNotFound(path.clone())
+            } else {
+                Error::InvalidFormat(e.to_string())
+            }
+        })?;
+        return Ok((input, None));
+    }
+
+    let io = CustomIo::new(source)?;
+
+    unsafe {
+        let mut ps = ffmpeg::ffi::avformat_alloc_context();
+        if ps.is_null() {
+            return Err(Error::Memory);
+        }
+        (*ps).pb = io.ctx;
+        (*ps).flags |= ffmpeg::ffi::AVFMT_FLAG_CUSTOM_IO as c_int;
+
+        // On failure avformat_open_input frees the context (but not our pb)
+        match ffmpeg::ffi::avformat_open_input(
+            &mut ps,
+            ptr::null(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        ) {
+            0 => match ffmpeg::ffi::avformat_find_stream_info(ps, ptr::null_mut()) {
+                r if r >= 0 => Ok((FormatContext::wrap(ps), Some(io))),
+                e => {
+                    ffmpeg::ffi::avformat_close_input(&mut ps);
+                    Err(Error::InvalidFormat(ffmpeg::Error::from(e).to_string()))
+                }
+            },
+            e => Err(Error::InvalidFormat(ffmpeg::Error::from(e).to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_reader_positions_are_independent() {
+        let source = MediaSource::from_bytes(vec![1u8, 2, 3, 4, 5]);
+        let mut a = SourceReader::new(source.clone());
+        let mut b = SourceReader::new(source);
+
+        let mut buf = [0u8; 3];
+        assert_eq!(a.read(&mut buf).unwrap(), 3);
+        assert_eq!(buf, [1, 2, 3]);
+        assert_eq!(b.read(&mut buf).unwrap(), 3);
+        assert_eq!(buf, [1, 2, 3]);
+
+        assert_eq!(a.read(&mut buf).unwrap(), 2);
+        assert_eq!(a.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_stream_reader_seek() {
+        let source = MediaSource::from_reader(io::Cursor::new(vec![10u8, 11, 12, 13]));
+        let mut reader = SourceReader::new(source);
+
+        assert_eq!(reader.size().unwrap(), 4);
+        assert_eq!(reader.seek(SeekFrom::End(-1)).unwrap(), 3);
+
+        let mut buf = [0u8; 2];
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], 13);
+
+        assert_eq!(reader.seek(SeekFrom::Current(-3)).unwrap(), 1);
+        assert!(reader.seek(SeekFrom::Current(-5)).is_err());
+    }
+
+    #[test]
+    fn test_source_display() {
+        assert_eq!(
+            MediaSource::from_bytes(vec![0u8; 16]).to_string(),
+            "<memory: 16 bytes>"
+        );
+        assert!(MediaSource::from_path("/tmp/a.mp4").path().is_some());
+    }
+}
@@ -0,0 +1,293 @@
+//! Subtitle event types
+
+/// End time used for events whose duration is not known yet
+pub const OPEN_END_US: i64 = i64::MAX;
+
+/// Kind of subtitle payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SubtitleKind {
+    /// Plain text (SRT, WebVTT, mov_text, ...)
+    Text = 0,
+    /// ASS/SSA dialogue line (raw text kept in `ass`)
+    Ass = 1,
+    /// Bitmap image (PGS, DVB, DVD subtitles)
+    Bitmap = 2,
+}
+
+/// Bitmap subtitle image converted to RGBA
+#[derive(Clone, PartialEq, Eq)]
+pub struct SubtitleBitmap {
+    /// Left position in video pixels
+    pub x: i32,
+
+    /// Top position in video pixels
+    pub y: i32,
+
+    /// Image width
+    pub width: u32,
+
+    /// Image height
+    pub height: u32,
+
+    /// RGBA pixel data (stride = width * 4)
+    pub data: Vec<u8>,
+}
+
+impl SubtitleBitmap {
+    /// Bytes per row
+    pub fn stride(&self) -> u32 {
+        self.width * 4
+    }
+
+    /// Convert palettized subtitle pixels to RGBA.
+    ///
+    /// `palette` entries are 0xAARRGGBB as produced by FFmpeg's bitmap
+    /// subtitle decoders. Out-of-range indices become transparent.
+    pub fn from_palette(
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        indices: &[u8],
+        linesize: usize,
+        palette: &[u32],
+    ) -> Self {
+        let mut data = Vec::with_capacity((width * height * 4) as usize);
+
+        for row in 0..height as usize {
+            let start = row * linesize;
+            let line = &indices[start..start + width as usize];
+            for &index in line {
+                let color = palette.get(index as usize).copied().unwrap_or(0);
+                data.push(((color >> 16) & 0xff) as u8);
+                data.push(((color >> 8) & 0xff) as u8);
+                data.push((color & 0xff) as u8);
+                data.push((color >> 24) as u8);
+            }
+        }
+
+        Self {
+            x,
+            y,
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+impl std::fmt::Debug for SubtitleBitmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubtitleBitmap")
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("data_size", &self.data.len())
+            .finish()
+    }
+}
+
+/// Decoded subtitle event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleEvent {
+    /// Container stream index
+    pub stream_index: i32,
+
+    /// Start time in microseconds
+    pub start_us: i64,
+
+    /// End time in microseconds (`OPEN_END_US` until known)
+    pub end_us: i64,
+
+    /// Payload kind
+    pub kind: SubtitleKind,
+
+    /// Plain text (empty for bitmaps)
+    pub text: String,
+
+    /// Raw ASS dialogue line (ASS/SSA tracks only)
+    pub ass: Option<String>,
+
+    /// RGBA image (bitmap tracks only)
+    pub bitmap: Option<SubtitleBitmap>,
+}
+
+impl SubtitleEvent {
+    /// Check if the event is visible at the given time
+    pub fn is_active_at(&self, time_us: i64) -> bool {
+        self.start_us <= time_us && time_us < self.end_us
+    }
+
+    /// Check if two events carry the same payload at the same start time
+    fn is_duplicate_of(&self, other: &SubtitleEvent) -> bool {
+        self.start_us == other.start_us
+            && self.kind == other.kind
+            && self.text == other.text
+            && self.ass == other.ass
+            && self.bitmap == other.bitmap
+    }
+}
+
+/// Time-ordered store of decoded subtitle events for one stream.
+///
+/// Packets can be demuxed more than once (after seeking back), so duplicate
+/// events are ignored. Events without a known end are closed by the next
+/// event or by an explicit clear.
+#[derive(Debug, Clone, Default)]
+pub struct SubtitleBuffer {
+    /// Events sorted by start time
+    events: Vec<SubtitleEvent>,
+}
+
+impl SubtitleBuffer {
+    /// Create an empty buffer
+    pub fn new() -> Self {
+        Self { events: Vec::new() }
+    }
+
+    /// Number of stored events
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Remove all events
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// All events in start time order
+    pub fn events(&self) -> &[SubtitleEvent] {
+        &self.events
+    }
+
+    /// Insert an event, keeping the buffer sorted by start time
+    pub fn insert(&mut self, event: SubtitleEvent) {
+        if self.events.iter().any(|e| e.is_duplicate_of(&event)) {
+            return;
+        }
+
+        self.close_open_events(event.start_us);
+
+        let pos = self
+            .events
+            .partition_point(|e| e.start_us <= event.start_us);
+        self.events.insert(pos, event);
+    }
+
+    /// Give open-ended events that started before `time_us` an end time
+    pub fn close_open_events(&mut self, time_us: i64) {
+        for event in self.events.iter_mut() {
+            if event.end_us == OPEN_END_US && event.start_us < time_us {
+                event.end_us = time_us;
+            }
+        }
+    }
+
+    /// Events visible at the given time
+    pub fn active_at(&self, time_us: i64) -> Vec<SubtitleEvent> {
+        let end = self.events.partition_point(|e| e.start_us <= time_us);
+        self.events[..end]
+            .iter()
+            .filter(|e| e.is_active_at(time_us))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Extract display text from an ASS dialogue line.
+///
+/// FFmpeg emits `ReadOrder,Layer,Style,Name,MarginL,MarginR,MarginV,Effect,Text`
+/// for text subtitles; this drops the leading fields and override tags.
+pub fn ass_dialogue_to_text(dialogue: &str) -> String {
+    let text = dialogue.splitn(9, ',').nth(8).unwrap_or(dialogue);
+
+    let mut output = String::with_capacity(text.len());
+    let mut in_override = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => in_override = true,
+            '}' if in_override => in_override = false,
+            _ if in_override => {}
+            '\\' => match chars.peek() {
+                Some('N') | Some('n') => {
+                    chars.next();
+                    output.push('\n');
+                }
+                Some('h') => {
+                    chars.next();
+                    output.push(' ');
+                }
+                _ => output.push(c),
+            },
+            _ => output.push(c),
+        }
+    }
+
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_event(start_us: i64, end_us: i64, text: &str) -> SubtitleEvent {
+        SubtitleEvent {
+            stream_index: 2,
+            start_us,
+            end_us,
+            kind: SubtitleKind::Text,
+            text: text.to_string(),
+            ass: None,
+            bitmap: None,
+        }
+    }
+
+    #[test]
+    fn test_active_at() {
+        let mut buffer = SubtitleBuffer::new();
+        buffer.insert(text_event(1_000_000, 2_000_000, "first"));
+        buffer.insert(text_event(1_500_000, 3_000_000, "second"));
+
+        assert!(buffer.active_at(500_000).is_empty());
+        assert_eq!(buffer.active_at(1_600_000).len(), 2);
+        assert_eq!(buffer.active_at(2_500_000)[0].text, "second");
+        assert!(buffer.active_at(3_000_000).is_empty());
+    }
+
+    #[test]
+    fn test_duplicates_and_open_events() {
+        let mut buffer = SubtitleBuffer::new();
+        buffer.insert(text_event(0, OPEN_END_US, "open"));
+        buffer.insert(text_event(0, OPEN_END_US, "open"));
+        assert_eq!(buffer.len(), 1);
+
+        buffer.insert(text_event(2_000_000, 4_000_000, "next"));
+        assert_eq!(buffer.events()[0].end_us, 2_000_000);
+    }
+
+    #[test]
+    fn test_ass_dialogue_to_text() {
+        let line = "0,0,Default,,0,0,0,,{\\i1}Hello,{\\i0} world\\Nsecond line";
+        assert_eq!(ass_dialogue_to_text(line), "Hello, world\nsecond line");
+    }
+
+    #[test]
+    fn test_bitmap_from_palette() {
+        let palette = [0x00000000, 0xff102030];
+        let indices = [0u8, 1, 9, 1, 0, 0];
+        let bitmap = SubtitleBitmap::from_palette(0, 0, 2, 2, &indices, 3, &palette);
+
+        assert_eq!(bitmap.data.len(), 16);
+        assert_eq!(&bitmap.data[4..8], &[0x10, 0x20, 0x30, 0xff]);
+        assert_eq!(&bitmap.data[8..12], &[0x10, 0x20, 0x30, 0xff]);
+    }
+}
@@ -0,0 +1,264 @@
+//! Thumbnail strips and contact sheets
+//!
+//! Thumbnails are decoded through their own demuxer at the requested size,
+//! so building a filmstrip neither moves the playhead nor evicts frames from
+//! the cache. Unless exact frames are requested, each thumbnail is the
+//! keyframe nearest to its time, which needs no decoding past the keyframe.
+
+use super::config::{OutputSize, PixelFormat, ScaleMode};
+use super::frame::VideoFrame;
+use crate::error::{Error, Result};
+
+/// What thumbnails to generate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailOptions {
+    /// Start of the range in microseconds
+    pub start_us: i64,
+
+    /// End of the range in microseconds (None = end of media)
+    pub end_us: Option<i64>,
+
+    /// Number of evenly spaced thumbnails (ignored when `interval_us` is set)
+    pub count: u32,
+
+    /// Distance between thumbnails in microseconds (None = derived from `count`)
+    pub interval_us: Option<i64>,
+
+    /// Thumbnail size
+    pub size: OutputSize,
+
+    /// Decode the frame shown at each time instead of the nearest keyframe
+    /// (slower: decodes from the preceding keyframe)
+    pub exact: bool,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            start_us: 0,
+            end_us: None,
+            count: 10,
+            interval_us: None,
+            size: OutputSize::new(160, 0, ScaleMode::Fit),
+            exact: false,
+        }
+    }
+}
+
+impl ThumbnailOptions {
+    /// `count` thumbnails of the given width spread over the whole media
+    pub fn strip(count: u32, width: u32) -> Self {
+        Self {
+            count,
+            size: OutputSize::new(width, 0, ScaleMode::Fit),
+            ..Self::default()
+        }
+    }
+}
+
+/// A generated thumbnail
+#[derive(Clone)]
+pub struct Thumbnail {
+    /// Position in the strip (0-based)
+    pub index: usize,
+
+    /// Requested time in microseconds (the frame's own time is `frame.pts_us`)
+    pub time_us: i64,
+
+    /// Thumbnail image
+    pub frame: VideoFrame,
+}
+
+/// Grid layout of a contact sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ContactSheetLayout {
+    /// Thumbnails per row (0 = as close to a square grid as possible)
+    pub columns: u32,
+
+    /// Gap between and around thumbnails in pixels
+    pub spacing: u32,
+}
+
+impl ContactSheetLayout {
+    /// Columns and rows of the grid for `count` thumbnails
+    pub fn grid(&self, count: usize) -> (u32, u32) {
+        if count == 0 {
+            return (0, 0);
+        }
+        let columns = match self.columns {
+            0 => (count as f64).sqrt().ceil() as u32,
+            columns => columns.min(count as u32),
+        };
+        (columns, (count as u32).div_ceil(columns))
+    }
+}
+
+/// Times of the thumbnails in `start_us..end_us`.
+///
+/// With an interval, thumbnails start at `start_us` and repeat until the end;
+/// otherwise `count` thumbnails split the range into equal parts. An empty
+/// range (e.g. a still image) yields a single thumbnail at `start_us`.
+pub(crate) fn thumbnail_times(
+    start_us: i64,
+    end_us: i64,
+    count: u32,
+    interval_us: Option<i64>,
+) -> Vec<i64> {
+    let interval_us = interval_us.filter(|&interval| interval > 0);
+    if interval_us.is_none() && count == 0 {
+        return Vec::new();
+    }
+    if end_us <= start_us {
+        return vec![start_us];
+    }
+
+    match interval_us {
+        Some(interval) => (0..)
+            .map(|i| start_us + i * interval)
+            .take_while(|&time| time < end_us)
+            .collect(),
+        None => {
+            let span = (end_us - start_us) as i128;
+            (0..count as i128)
+                .map(|i| start_us + (span * i / count as i128) as i64)
+                .collect()
+        }
+    }
+}
+
+/// Composite thumbnails into a single image, left to right and top to bottom.
+///
+/// Thumbnails must share one packed RGB pixel format. Each is centered in a
+/// cell the size of the largest thumbnail; gaps are opaque black.
+pub fn compose_contact_sheet(
+    thumbnails: &[VideoFrame],
+    layout: &ContactSheetLayout,
+) -> Result<VideoFrame> {
+    let first = thumbnails
+        .first()
+        .ok_or_else(|| Error::InvalidFormat("No thumbnails to compose".to_string()))?;
+    let format = first.pixel_format;
+    if format.plane_count() != 1 {
+        return Err(Error::InvalidFormat(format!(
+            "Contact sheets need a packed RGB pixel format, not {:?}",
+            format
+        )));
+    }
+    if let Some(other) = thumbnails.iter().find(|t| t.pixel_format != format) {
+        return Err(Error::InvalidFormat(format!(
+            "Thumbnails mix {:?} and {:?}",
+            format, other.pixel_format
+        )));
+    }
+
+    let bpp = format.bytes_per_pixel() as usize;
+    let cell_width = thumbnails.iter().map(|t| t.width).max().unwrap_or(0);
+    let cell_height = thumbnails.iter().map(|t| t.height).max().unwrap_or(0);
+    let (columns, rows) = layout.grid(thumbnails.len());
+    let width = columns * cell_width + (columns + 1) * layout.spacing;
+    let height = rows * cell_height + (rows + 1) * layout.spacing;
+    let stride = width as usize * bpp;
+
+    let background = opaque_black(format);
+    let mut data = background.repeat(width as usize * height as usize);
+
+    for (i, thumbnail) in thumbnails.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let x = layout.spacing
+            + column * (cell_width + layout.spacing)
+            + (cell_width - thumbnail.width) / 2;
+        let y = layout.spacing
+            + row * (cell_height + layout.spacing)
+            + (cell_height - thumbnail.height) / 2;
+
+        let row_bytes = thumbnail.width as usize * bpp;
+        for line in 0..thumbnail.height as usize {
+            let source = line * thumbnail.stride as usize;
+            let target = (y as usize + line) * stride + x as usize * bpp;
+            let pixels = thumbnail
+                .data
+                .get(source..source + row_bytes)
+                .ok_or_else(|| {
+                    Error::InvalidFormat("Thumbnail data is smaller than its size".to_string())
+                })?;
+            data[target..target + row_bytes].copy_from_slice(pixels);
+        }
+    }
+
+    let mut sheet = VideoFrame::new(
+        data,
+        width,
+        height,
+        stride as u32,
+        first.pts_us,
+        0,
+        true,
+        0,
+        format,
+    );
+    sheet.color = first.color;
+    Ok(sheet)
+}
+
+/// One opaque black pixel of a packed RGB format
+fn opaque_black(format: PixelFormat) -> Vec<u8> {
+    match format {
+        PixelFormat::Bgra | PixelFormat::Rgba => vec![0, 0, 0, 0xFF],
+        PixelFormat::Rgba64 => vec![0, 0, 0, 0, 0, 0, 0xFF, 0xFF],
+        _ => vec![0; format.bytes_per_pixel() as usize],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbnail_times() {
+        assert_eq!(
+            thumbnail_times(0, 10_000_000, 5, None),
+            vec![0, 2_000_000, 4_000_000, 6_000_000, 8_000_000]
+        );
+        assert_eq!(
+            thumbnail_times(1_000_000, 4_000_000, 0, Some(1_000_000)),
+            vec![1_000_000, 2_000_000, 3_000_000]
+        );
+        assert_eq!(thumbnail_times(0, 0, 5, None), vec![0]);
+        assert!(thumbnail_times(0, 10_000_000, 0, None).is_empty());
+    }
+
+    #[test]
+    fn test_compose_contact_sheet() {
+        let thumbnail = |value: u8| {
+            VideoFrame::new(
+                vec![value; 2 * 2 * 4],
+                2,
+                2,
+                8,
+                0,
+                0,
+                true,
+                0,
+                PixelFormat::Bgra,
+            )
+        };
+        let thumbnails = vec![thumbnail(10), thumbnail(20), thumbnail(30)];
+        let layout = ContactSheetLayout {
+            columns: 0,
+            spacing: 1,
+        };
+        assert_eq!(layout.grid(3), (2, 2));
+
+        let sheet = compose_contact_sheet(&thumbnails, &layout).unwrap();
+        assert_eq!((sheet.width, sheet.height), (7, 7));
+        let pixel = |x: usize, y: usize| &sheet.data[(y * 7 + x) * 4..(y * 7 + x) * 4 + 4];
+        assert_eq!(pixel(0, 0), &[0, 0, 0, 0xFF]);
+        assert_eq!(pixel(1, 1), &[10; 4]);
+        assert_eq!(pixel(5, 2), &[20; 4]);
+        assert_eq!(pixel(2, 5), &[30; 4]);
+        assert_eq!(pixel(5, 5), &[0, 0, 0, 0xFF]);
+
+        let nv12 = VideoFrame::new(vec![0; 6], 2, 2, 2, 0, 0, true, 0, PixelFormat::Nv12);
+        assert!(compose_contact_sheet(&[nv12], &layout).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! SMPTE timecode
+//!
+//! Timecodes count frames at the nominal integer rate of the stream (30 for
+//! 30000/1001). Drop-frame timecode skips the frame labels 0 and 1 (0 to 3 at
+//! 59.94) at the start of every minute except each tenth, which keeps it in
+//! step with the wall clock at NTSC rates.
+
+use std::fmt;
+
+use crate::error::{Error, Result};
+
+/// SMPTE timecode (HH:MM:SS:FF, or HH:MM:SS;FF for drop-frame)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    /// Hours (0-23)
+    pub hours: u32,
+
+    /// Minutes (0-59)
+    pub minutes: u32,
+
+    /// Seconds (0-59)
+    pub seconds: u32,
+
+    /// Frame within the second
+    pub frames: u32,
+
+    /// Drop-frame counting
+    pub drop_frame: bool,
+
+    /// Frame rate the timecode counts at (numerator, denominator)
+    pub rate: (i32, i32),
+}
+
+impl Timecode {
+    /// Parse a timecode string.
+    ///
+    /// Fields may be separated by `:` (non-drop) or by `;`, `.` or `,`
+    /// (drop-frame), e.g. "01:00:10:12" or "01:00:10;12".
+    pub fn parse(text: &str, rate: (i32, i32)) -> Result<Self> {
+        let invalid = || Error::InvalidTimecode(text.to_string());
+        let fields = text
+            .trim()
+            .split([':', ';', '.', ','])
+            .map(|field| field.parse::<u32>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let timecode = match fields.as_slice() {
+            &[hours, minutes, seconds, frames] => Self {
+                hours,
+                minutes,
+                seconds,
+                frames,
+                drop_frame: text.contains([';', '.', ',']),
+                rate,
+            },
+            _ => return Err(invalid()),
+        };
+        if !timecode.is_valid() {
+            return Err(invalid());
+        }
+        Ok(timecode)
+    }
+
+    /// Timecode of the frame `frame_number` frames after 00:00:00:00
+    /// (wraps at 24 hours)
+    pub fn from_frame_number(frame_number: i64, rate: (i32, i32), drop_frame: bool) -> Self {
+        let fps = nominal_rate(rate) as i64;
+        let dropped = dropped_per_minute(fps, drop_frame);
+        let mut n = frame_number.rem_euclid(frames_per_day(fps, dropped));
+        if dropped > 0 {
+            // Add back the skipped labels of every minute not divisible by ten
+            let frames_per_ten_minutes = 600 * fps - 9 * dropped;
+            let frames_per_minute = 60 * fps - dropped;
+            let tens = n / frames_per_ten_minutes;
+            let rest = n % frames_per_ten_minutes;
+            n += 9 * dropped * tens;
+            if rest > dropped {
+                n += dropped * ((rest - dropped) / frames_per_minute);
+            }
+        }
+
+        Self {
+            hours: (n / (3600 * fps)) as u32,
+            minutes: (n / (60 * fps) % 60) as u32,
+            seconds: (n / fps % 60) as u32,
+            frames: (n % fps) as u32,
+            drop_frame: dropped > 0,
+            rate,
+        }
+    }
+
+    /// Decode a SMPTE 12M binary timecode (as carried in frame side data)
+    pub fn from_smpte_12m(value: u32, rate: (i32, i32)) -> Self {
+        let bcd = |v: u32| (v >> 4) * 10 + (v & 0xf);
+        let mut frames = bcd(value >> 24 & 0x3f);
+        if nominal_rate(rate) > 30 {
+            // High frame rates count frame pairs; the field bit selects the frame
+            let field_bit = if rate.0 == 50 * rate.1 { 7 } else { 23 };
+            frames = frames * 2 + (value >> field_bit & 1);
+        }
+
+        Self {
+            hours: bcd(value & 0x3f),
+            minutes: bcd(value >> 8 & 0x7f),
+            seconds: bcd(value >> 16 & 0x7f),
+            frames,
+            drop_frame: value & 1 << 30 != 0,
+            rate,
+        }
+    }
+
+    /// Decode the 25-bit timecode of an MPEG-2 GOP header
+    pub fn from_gop_header(value: u32, rate: (i32, i32)) -> Self {
+        Self {
+            hours: value >> 19 & 0x1f,
+            minutes: value >> 13 & 0x3f,
+            seconds: value >> 6 & 0x3f,
+            frames: value & 0x3f,
+            drop_frame: value & 1 << 24 != 0,
+            rate,
+        }
+    }
+
+    /// Nominal frames per second (30 for 29.97)
+    pub fn frames_per_second(&self) -> u32 {
+        nominal_rate(self.rate)
+    }
+
+    /// Number of frames since 00:00:00:00
+    pub fn to_frame_number(&self) -> i64 {
+        let fps = self.frames_per_second() as i64;
+        let dropped = dropped_per_minute(fps, self.drop_frame);
+        let total_minutes = 60 * self.hours as i64 + self.minutes as i64;
+
+        (3600 * self.hours as i64 + 60 * self.minutes as i64 + self.seconds as i64) * fps
+            + self.frames as i64
+            - dropped * (total_minutes - total_minutes / 10)
+    }
+
+    /// Timecode `frames` frames later (or earlier, if negative)
+    pub fn offset(&self, frames: i64) -> Self {
+        Self::from_frame_number(self.to_frame_number() + frames, self.rate, self.drop_frame)
+    }
+
+    /// Number of frames from `start` to this timecode, crossing midnight if needed
+    pub fn frames_since(&self, start: &Timecode) -> i64 {
+        let fps = self.frames_per_second() as i64;
+        let dropped = dropped_per_minute(fps, self.drop_frame);
+        (self.to_frame_number() - start.to_frame_number()).rem_euclid(frames_per_day(fps, dropped))
+    }
+
+    /// Check that all fields are in range and the label is not a dropped one
+    pub fn is_valid(&self) -> bool {
+        let fps = self.frames_per_second();
+        let dropped = dropped_per_minute(fps as i64, self.drop_frame) as u32;
+        self.rate.0 > 0
+            && self.rate.1 > 0
+            && self.hours < 24
+            && self.minutes < 60
+            && self.seconds < 60
+            && self.frames < fps
+            && (!self.drop_frame || dropped > 0)
+            && !(self.seconds == 0 && self.minutes % 10 != 0 && self.frames < dropped)
+    }
+}
+
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{}{:02}",
+            self.hours,
+            self.minutes,
+            self.seconds,
+            if self.drop_frame { ';' } else { ':' },
+            self.frames
+        )
+    }
+}
+
+/// Integer frame rate timecodes count at (at least 1)
+fn nominal_rate(rate: (i32, i32)) -> u32 {
+    if rate.0 <= 0 || rate.1 <= 0 {
+        return 1;
+    }
+    ((rate.0 as f64 / rate.1 as f64).round() as u32).max(1)
+}
+
+/// Frame labels skipped per minute (drop-frame is defined for multiples of 30 fps)
+fn dropped_per_minute(fps: i64, drop_frame: bool) -> i64 {
+    if drop_frame && fps % 30 == 0 {
+        fps / 15
+    } else {
+        0
+    }
+}
+
+/// Frame labels in 24 hours (six minutes per hour keep all their labels)
+fn frames_per_day(fps: i64, dropped: i64) -> i64 {
+    24 * (3600 * fps - 54 * dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NTSC: (i32, i32) = (30000, 1001);
+
+    #[test]
+    fn test_parse_and_display() {
+        let tc = Timecode::parse("01:00:10;12", NTSC).unwrap();
+        assert_eq!(
+            (tc.hours, tc.minutes, tc.seconds, tc.frames),
+            (1, 0, 10, 12)
+        );
+        assert!(tc.drop_frame);
+        assert_eq!(tc.to_string(), "01:00:10;12");
+
+        let tc = Timecode::parse("10:00:00:00", (25, 1)).unwrap();
+        assert!(!tc.drop_frame);
+        assert_eq!(tc.to_frame_number(), 900_000);
+
+        assert!(Timecode::parse("00:00:00:25", (25, 1)).is_err());
+        assert!(Timecode::parse("00:01:00;01", NTSC).is_err());
+        assert!(Timecode::parse("00:00:00;00", (25, 1)).is_err());
+        assert!(Timecode::parse("01:00:10", NTSC).is_err());
+    }
+
+    #[test]
+    fn test_drop_frame_numbering() {
+        let tc = Timecode::parse("00:01:00;02", NTSC).unwrap();
+        assert_eq!(tc.to_frame_number(), 1800);
+        assert_eq!(tc.offset(-1).to_string(), "00:00:59;29");
+        assert_eq!(
+            Timecode::parse("00:10:00;00", NTSC)
+                .unwrap()
+                .to_frame_number(),
+            17982
+        );
+
+        for n in [0, 1799, 1800, 17981, 17982, 107_891, 2_589_407] {
+            assert_eq!(
+                Timecode::from_frame_number(n, NTSC, true).to_frame_number(),
+                n
+            );
+        }
+
+        // Wraps at midnight
+        let end = Timecode::parse("23:59:59;29", NTSC).unwrap();
+        assert_eq!(end.offset(1).to_string(), "00:00:00;00");
+        let start = Timecode::parse("23:59:00;02", NTSC).unwrap();
+        let after = Timecode::parse("00:00:00;10", NTSC).unwrap();
+        assert_eq!(after.frames_since(&start), 1798 + 10);
+    }
+
+    #[test]
+    fn test_binary_timecodes() {
+        // 01:23:45:12 drop-frame, BCD digits
+        let value = 0x01 | 0x23 << 8 | 0x45 << 16 | 0x12 << 24 | 1 << 30;
+        let tc = Timecode::from_smpte_12m(value, NTSC);
+        assert_eq!(tc.to_string(), "01:23:45;12");
+
+        // 59.94 counts frame pairs
+        let tc = Timecode::from_smpte_12m(0x12 << 24 | 1 << 23, (60000, 1001));
+        assert_eq!(tc.frames, 25);
+
+        let gop = 1 << 24 | 10 << 19 | 20 << 13 | 1 << 12 | 30 << 6 | 15;
+        assert_eq!(
+            Timecode::from_gop_header(gop, NTSC).to_string(),
+            "10:20:30;15"
+        );
+    }
+}
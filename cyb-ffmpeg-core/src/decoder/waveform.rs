@@ -0,0 +1,473 @@
+//! Audio waveform (peak) data
+//!
+//! The audio track is reduced to buckets of a fixed number of samples holding
+//! the minimum, maximum and RMS level of each channel. Waveforms can be saved
+//! next to the media in a compact binary format:
+//!
+//! ```text
+//! magic "CYBW" | version u8 | reserved u8 | channels u16 | sample_rate u32 |
+//! samples_per_bucket u32 | start_us i64 | bucket_count u32 |
+//! bucket_count x channels x (min i16, max i16, rms i16)
+//! ```
+//!
+//! All values are little-endian; levels are stored as 16-bit fractions of
+//! full scale, bucket after bucket with the channels interleaved.
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use super::audio_frame::AudioFrame;
+use crate::error::{Error, Result};
+
+/// Magic bytes of the waveform file format
+const MAGIC: &[u8; 4] = b"CYBW";
+
+/// Current waveform file format version
+const VERSION: u8 = 1;
+
+/// Size of the file header in bytes
+const HEADER_SIZE: usize = 28;
+
+/// What to analyze and at which resolution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaveformOptions {
+    /// Start of the range in microseconds
+    pub start_us: i64,
+
+    /// End of the range in microseconds (None = end of media)
+    pub end_us: Option<i64>,
+
+    /// Samples per channel summarized by one bucket
+    pub samples_per_bucket: u32,
+
+    /// Audio stream index (None = active audio track)
+    pub track: Option<usize>,
+}
+
+impl Default for WaveformOptions {
+    fn default() -> Self {
+        Self {
+            start_us: 0,
+            end_us: None,
+            samples_per_bucket: 256,
+            track: None,
+        }
+    }
+}
+
+/// Level summary of one bucket of samples (-1.0 to 1.0)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct WaveformBucket {
+    /// Lowest sample
+    pub min: f32,
+
+    /// Highest sample
+    pub max: f32,
+
+    /// Root mean square level
+    pub rms: f32,
+}
+
+/// Per-channel waveform of an audio track
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    /// Sample rate of the analyzed audio in Hz
+    pub sample_rate: u32,
+
+    /// Samples per channel summarized by one bucket (the last bucket may
+    /// hold fewer)
+    pub samples_per_bucket: u32,
+
+    /// Time of the first bucket in microseconds
+    pub start_us: i64,
+
+    /// Buckets of each channel (all channels hold the same number)
+    pub channels: Vec<Vec<WaveformBucket>>,
+}
+
+impl Waveform {
+    /// Create an empty waveform
+    pub fn new(sample_rate: u32, channels: u32, samples_per_bucket: u32, start_us: i64) -> Self {
+        Self {
+            sample_rate,
+            samples_per_bucket,
+            start_us,
+            channels: vec![Vec::new(); channels as usize],
+        }
+    }
+
+    /// Number of channels
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Number of buckets per channel
+    pub fn bucket_count(&self) -> usize {
+        self.channels.first().map_or(0, Vec::len)
+    }
+
+    /// Duration of one bucket in microseconds
+    pub fn bucket_duration_us(&self) -> f64 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.samples_per_bucket as f64 * 1_000_000.0 / self.sample_rate as f64
+    }
+
+    /// Index of the bucket covering a time
+    pub fn bucket_at(&self, time_us: i64) -> Option<usize> {
+        let duration = self.bucket_duration_us();
+        if time_us < self.start_us || duration <= 0.0 {
+            return None;
+        }
+        let index = ((time_us - self.start_us) as f64 / duration) as usize;
+        (index < self.bucket_count()).then_some(index)
+    }
+
+    /// Coarser waveform with `factor` buckets combined into one (for zoomed
+    /// out views)
+    pub fn merged(&self, factor: u32) -> Waveform {
+        let factor = factor.max(1);
+        let channels = self
+            .channels
+            .iter()
+            .map(|buckets| {
+                buckets
+                    .chunks(factor as usize)
+                    .map(|group| WaveformBucket {
+                        min: group.iter().map(|b| b.min).fold(f32::INFINITY, f32::min),
+                        max: group
+                            .iter()
+                            .map(|b| b.max)
+                            .fold(f32::NEG_INFINITY, f32::max),
+                        rms: (group.iter().map(|b| b.rms * b.rms).sum::<f32>()
+                            / group.len() as f32)
+                            .sqrt(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Waveform {
+            sample_rate: self.sample_rate,
+            samples_per_bucket: self.samples_per_bucket.saturating_mul(factor),
+            start_us: self.start_us,
+            channels,
+        }
+    }
+
+    /// Write the waveform in the binary format
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        header.push(0);
+        header.extend_from_slice(&(self.channel_count() as u16).to_le_bytes());
+        header.extend_from_slice(&self.sample_rate.to_le_bytes());
+        header.extend_from_slice(&self.samples_per_bucket.to_le_bytes());
+        header.extend_from_slice(&self.start_us.to_le_bytes());
+        header.extend_from_slice(&(self.bucket_count() as u32).to_le_bytes());
+        writer.write_all(&header)?;
+
+        let mut data = Vec::with_capacity(self.bucket_count() * self.channel_count() * 6);
+        for bucket in 0..self.bucket_count() {
+            for channel in &self.channels {
+                let level = channel[bucket];
+                for value in [level.min, level.max, level.rms] {
+                    data.extend_from_slice(&quantize(value).to_le_bytes());
+                }
+            }
+        }
+        writer.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Read a waveform written by `write_to`
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != MAGIC {
+            return Err(Error::InvalidFormat("Not a waveform file".to_string()));
+        }
+        if header[4] != VERSION {
+            return Err(Error::InvalidFormat(format!(
+                "Unsupported waveform version {}",
+                header[4]
+            )));
+        }
+
+        let channels = u16::from_le_bytes([header[6], header[7]]) as usize;
+        let sample_rate = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let samples_per_bucket = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        let start_us = i64::from_le_bytes(header[16..24].try_into().unwrap());
+        let bucket_count = u32::from_le_bytes(header[24..28].try_into().unwrap()) as usize;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        if data.len() < bucket_count * channels * 6 {
+            return Err(Error::InvalidFormat(format!(
+                "Waveform data is truncated ({} of {} bytes)",
+                data.len(),
+                bucket_count * channels * 6
+            )));
+        }
+
+        let mut waveform =
+            Waveform::new(sample_rate, channels as u32, samples_per_bucket, start_us);
+        let mut values = data
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32767.0);
+        for _ in 0..bucket_count {
+            for channel in waveform.channels.iter_mut() {
+                let (min, max, rms) = (values.next(), values.next(), values.next());
+                channel.push(WaveformBucket {
+                    min: min.unwrap_or_default(),
+                    max: max.unwrap_or_default(),
+                    rms: rms.unwrap_or_default(),
+                });
+            }
+        }
+        Ok(waveform)
+    }
+
+    /// Encode the waveform in the binary format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // Writing to a Vec cannot fail
+        let _ = self.write_to(&mut bytes);
+        bytes
+    }
+
+    /// Decode a waveform from the binary format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Self::read_from(bytes)
+    }
+
+    /// Save the waveform to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_to(std::io::BufWriter::new(file))
+    }
+
+    /// Load a waveform saved with `save`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Err(Error::FileNotFound(path.to_path_buf()));
+        }
+        Self::read_from(std::io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+/// Level as a 16-bit fraction of full scale
+fn quantize(value: f32) -> i16 {
+    (value.clamp(-1.0, 1.0) * 32767.0).round() as i16
+}
+
+/// Running level of the bucket being filled
+#[derive(Clone, Copy)]
+struct Accumulator {
+    min: f32,
+    max: f32,
+    sum_squares: f64,
+    count: u32,
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self {
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            sum_squares: 0.0,
+            count: 0,
+        }
+    }
+}
+
+impl Accumulator {
+    fn add(&mut self, sample: f32) {
+        self.min = self.min.min(sample);
+        self.max = self.max.max(sample);
+        self.sum_squares += sample as f64 * sample as f64;
+        self.count += 1;
+    }
+
+    fn bucket(&self) -> WaveformBucket {
+        WaveformBucket {
+            min: self.min,
+            max: self.max,
+            rms: (self.sum_squares / self.count.max(1) as f64).sqrt() as f32,
+        }
+    }
+}
+
+/// Reduces decoded audio frames to waveform buckets
+pub(crate) struct WaveformBuilder {
+    waveform: Waveform,
+    accumulators: Vec<Accumulator>,
+    end_us: Option<i64>,
+}
+
+impl WaveformBuilder {
+    /// Start a waveform of the range `start_us..end_us`
+    pub fn new(
+        sample_rate: u32,
+        channels: u32,
+        samples_per_bucket: u32,
+        start_us: i64,
+        end_us: Option<i64>,
+    ) -> Self {
+        Self {
+            waveform: Waveform::new(sample_rate, channels, samples_per_bucket.max(1), start_us),
+            accumulators: vec![Accumulator::default(); channels as usize],
+            end_us,
+        }
+    }
+
+    /// Add the samples of a frame that fall into the range.
+    ///
+    /// Returns false once the frame starts past the end of the range.
+    pub fn push(&mut self, frame: &AudioFrame) -> Result<bool> {
+        let channels = self.accumulators.len();
+        if frame.channels as usize != channels || frame.sample_rate != self.waveform.sample_rate {
+            return Err(Error::InvalidFormat(format!(
+                "Audio format changed to {} Hz, {} channels",
+                frame.sample_rate, frame.channels
+            )));
+        }
+        if self.end_us.is_some_and(|end_us| frame.pts_us >= end_us) {
+            return Ok(false);
+        }
+
+        let count = frame.sample_count as usize;
+        if frame.data.len() < count * channels {
+            return Err(Error::InvalidFormat(format!(
+                "Audio frame holds {} samples, {} expected",
+                frame.data.len(),
+                count * channels
+            )));
+        }
+        let to_samples =
+            |us: i64| (us as i128 * frame.sample_rate as i128 / 1_000_000).clamp(0, count as i128);
+        let first = to_samples(self.waveform.start_us - frame.pts_us) as usize;
+        let last = match self.end_us {
+            Some(end_us) => to_samples(end_us - frame.pts_us) as usize,
+            None => count,
+        };
+
+        for n in first..last {
+            for channel in 0..channels {
+                let index = if frame.planar {
+                    channel * count + n
+                } else {
+                    n * channels + channel
+                };
+                self.accumulators[channel].add(frame.data.sample_f32(index));
+            }
+            if self.accumulators[0].count == self.waveform.samples_per_bucket {
+                self.flush_bucket();
+            }
+        }
+        Ok(true)
+    }
+
+    /// Buckets completed so far
+    pub fn waveform(&self) -> &Waveform {
+        &self.waveform
+    }
+
+    /// Complete the last, partial bucket and return the waveform
+    pub fn finish(mut self) -> Waveform {
+        if self.accumulators.first().is_some_and(|a| a.count > 0) {
+            self.flush_bucket();
+        }
+        self.waveform
+    }
+
+    fn flush_bucket(&mut self) {
+        for (accumulator, buckets) in self
+            .accumulators
+            .iter_mut()
+            .zip(self.waveform.channels.iter_mut())
+        {
+            buckets.push(accumulator.bucket());
+            *accumulator = Accumulator::default();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::AudioSamples;
+
+    #[test]
+    fn test_builder() {
+        // Stereo at 1000 Hz, 4 samples per bucket, starting at 2 ms
+        let mut builder = WaveformBuilder::new(1000, 2, 4, 2_000, Some(9_000));
+        let left = [0.5, -0.5, 0.5, -0.5, 1.0, 0.0, 0.0, 0.0, 0.25, 0.25];
+        let mut data = left.to_vec();
+        data.extend([0.0; 10]);
+        let frame = AudioFrame::new(AudioSamples::Float32(data), true, 10, 2, 1000, 0, 0, 0);
+
+        assert!(builder.push(&frame).unwrap());
+        // Samples 2..9 fill one bucket and start another
+        assert_eq!(builder.waveform().bucket_count(), 1);
+        let late = AudioFrame::new(
+            AudioSamples::Float32(vec![0.0; 2]),
+            true,
+            1,
+            2,
+            1000,
+            9_000,
+            0,
+            0,
+        );
+        assert!(!builder.push(&late).unwrap());
+
+        let waveform = builder.finish();
+        assert_eq!(waveform.bucket_count(), 2);
+        let first = waveform.channels[0][0];
+        assert_eq!((first.min, first.max), (-0.5, 1.0));
+        assert!((first.rms - (1.5f32 / 4.0).sqrt()).abs() < 1e-6);
+        assert_eq!(waveform.channels[0][1].max, 0.25);
+        assert_eq!(waveform.channels[1][0].rms, 0.0);
+        assert_eq!(waveform.bucket_at(6_500), Some(1));
+
+        let mono = AudioFrame::new(AudioSamples::Float32(vec![0.0]), false, 1, 1, 1000, 0, 0, 0);
+        assert!(WaveformBuilder::new(1000, 2, 4, 0, None)
+            .push(&mono)
+            .is_err());
+    }
+
+    #[test]
+    fn test_binary_format() {
+        let mut waveform = Waveform::new(48000, 2, 512, 1_000_000);
+        for i in 0..3 {
+            let level = i as f32 / 4.0;
+            waveform.channels[0].push(WaveformBucket {
+                min: -level,
+                max: level,
+                rms: level / 2.0,
+            });
+            waveform.channels[1].push(WaveformBucket::default());
+        }
+
+        let bytes = waveform.to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 3 * 2 * 6);
+        let loaded = Waveform::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.sample_rate, 48000);
+        assert_eq!(loaded.start_us, 1_000_000);
+        assert_eq!(loaded.bucket_count(), 3);
+        assert!((loaded.channels[0][2].max - 0.5).abs() < 1e-4);
+        assert!((loaded.channels[0][2].min + 0.5).abs() < 1e-4);
+
+        assert!(Waveform::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Waveform::from_bytes(b"RIFF0000").is_err());
+
+        let merged = waveform.merged(2);
+        assert_eq!(merged.bucket_count(), 2);
+        assert_eq!(merged.samples_per_bucket, 1024);
+        assert_eq!(merged.channels[0][0].max, 0.25);
+    }
+}
@@ -0,0 +1,314 @@
+//! Encoder configuration
+
+use crate::decoder::{ExportFormat, PixelFormat, SampleFormat, ScalingAlgorithm};
+
+/// Video codecs available in LGPL builds of FFmpeg
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum VideoCodec {
+    /// MPEG-4 Part 2 (review copies and proxies)
+    Mpeg4 = 0,
+    /// FFV1 (lossless)
+    Ffv1 = 1,
+    /// Apple ProRes (prores_ks)
+    ProRes = 2,
+    /// PNG (lossless, for image sequences)
+    Png = 3,
+    /// Motion JPEG
+    Mjpeg = 4,
+}
+
+impl VideoCodec {
+    /// FFmpeg encoder name
+    pub fn encoder_name(&self) -> &'static str {
+        match self {
+            VideoCodec::Mpeg4 => "mpeg4",
+            VideoCodec::Ffv1 => "ffv1",
+            VideoCodec::ProRes => "prores_ks",
+            VideoCodec::Png => "png",
+            VideoCodec::Mjpeg => "mjpeg",
+        }
+    }
+
+    /// Whether the codec reproduces its input exactly
+    pub fn is_lossless(&self) -> bool {
+        matches!(self, VideoCodec::Ffv1 | VideoCodec::Png)
+    }
+
+    /// Whether the codec takes a fixed quantizer (`VideoEncoderConfig::quality`)
+    pub fn uses_quantizer(&self) -> bool {
+        matches!(self, VideoCodec::Mpeg4 | VideoCodec::Mjpeg)
+    }
+}
+
+/// ProRes profile (prores_ks profile numbers)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ProResProfile {
+    /// ProRes 422 Proxy
+    Proxy = 0,
+    /// ProRes 422 LT
+    Lt = 1,
+    /// ProRes 422
+    Standard = 2,
+    /// ProRes 422 HQ
+    Hq = 3,
+    /// ProRes 4444 (with alpha)
+    P4444 = 4,
+    /// ProRes 4444 XQ (with alpha)
+    P4444Xq = 5,
+}
+
+impl Default for ProResProfile {
+    fn default() -> Self {
+        Self::Hq
+    }
+}
+
+impl ProResProfile {
+    /// Whether the profile stores 4:4:4 with an alpha channel
+    pub fn is_4444(&self) -> bool {
+        matches!(self, ProResProfile::P4444 | ProResProfile::P4444Xq)
+    }
+}
+
+/// Audio codecs available in LGPL builds of FFmpeg
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AudioCodec {
+    /// 16-bit PCM
+    Pcm16 = 0,
+    /// 24-bit PCM
+    Pcm24 = 1,
+    /// 32-bit float PCM
+    PcmFloat = 2,
+    /// AAC (FFmpeg's native encoder)
+    Aac = 3,
+    /// FLAC (lossless)
+    Flac = 4,
+}
+
+impl AudioCodec {
+    /// FFmpeg encoder name
+    pub fn encoder_name(&self) -> &'static str {
+        match self {
+            AudioCodec::Pcm16 => "pcm_s16le",
+            AudioCodec::Pcm24 => "pcm_s24le",
+            AudioCodec::PcmFloat => "pcm_f32le",
+            AudioCodec::Aac => "aac",
+            AudioCodec::Flac => "flac",
+        }
+    }
+
+    /// Sample format and planarity the encoder takes (24-bit PCM is
+    /// carried in 32-bit samples)
+    pub fn sample_layout(&self) -> (SampleFormat, bool) {
+        match self {
+            AudioCodec::Pcm16 | AudioCodec::Flac => (SampleFormat::Int16, false),
+            AudioCodec::Pcm24 => (SampleFormat::Int32, false),
+            AudioCodec::PcmFloat => (SampleFormat::Float32, false),
+            AudioCodec::Aac => (SampleFormat::Float32, true),
+        }
+    }
+
+    /// Bit rate used when none is configured (0 = not applicable)
+    pub fn default_bit_rate(&self, channels: u32) -> u64 {
+        match self {
+            AudioCodec::Aac => 96_000 * channels.max(1) as u64,
+            _ => 0,
+        }
+    }
+}
+
+/// Video stream settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoEncoderConfig {
+    /// Codec
+    pub codec: VideoCodec,
+
+    /// Output width (frames of other sizes are scaled to it)
+    pub width: u32,
+
+    /// Output height
+    pub height: u32,
+
+    /// Pixel format of the frames that will be written. It selects the
+    /// encoded format of FFV1 and PNG, so high bit depth and YUV input stay
+    /// lossless; frames of other formats are converted.
+    pub input_format: PixelFormat,
+
+    /// Frame rate as (numerator, denominator); frames are written at this
+    /// constant rate in the order they arrive
+    pub frame_rate: (i32, i32),
+
+    /// Target bit rate in bits per second (0 = codec default / quantizer)
+    pub bit_rate: u64,
+
+    /// Fixed quantizer for MPEG-4 and MJPEG (1-31, lower is better;
+    /// 0 = use `bit_rate`)
+    pub quality: u32,
+
+    /// Keyframe interval in frames (0 = codec default)
+    pub gop_size: u32,
+
+    /// Profile used with `VideoCodec::ProRes`
+    pub prores_profile: ProResProfile,
+
+    /// Scaling algorithm for size and pixel format conversion
+    pub scaling_algorithm: ScalingAlgorithm,
+}
+
+impl VideoEncoderConfig {
+    /// Settings for `codec` at the given size and frame rate
+    pub fn new(codec: VideoCodec, width: u32, height: u32, frame_rate: (i32, i32)) -> Self {
+        Self {
+            codec,
+            width,
+            height,
+            input_format: PixelFormat::Bgra,
+            frame_rate,
+            bit_rate: 0,
+            quality: if codec.uses_quantizer() { 3 } else { 0 },
+            gop_size: 0,
+            prores_profile: ProResProfile::default(),
+            scaling_algorithm: ScalingAlgorithm::default(),
+        }
+    }
+
+    /// Encoder time base (one tick per frame)
+    pub fn time_base(&self) -> (i32, i32) {
+        (self.frame_rate.1, self.frame_rate.0)
+    }
+}
+
+/// Audio stream settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioEncoderConfig {
+    /// Codec
+    pub codec: AudioCodec,
+
+    /// Sample rate in Hz (frames must match it)
+    pub sample_rate: u32,
+
+    /// Channel count (frames must match it)
+    pub channels: u32,
+
+    /// Target bit rate in bits per second (0 = codec default)
+    pub bit_rate: u64,
+}
+
+impl AudioEncoderConfig {
+    /// Settings for `codec` at the given format
+    pub fn new(codec: AudioCodec, sample_rate: u32, channels: u32) -> Self {
+        Self {
+            codec,
+            sample_rate,
+            channels,
+            bit_rate: 0,
+        }
+    }
+}
+
+/// Encoder configuration
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EncoderConfig {
+    /// Video stream (None = no video)
+    pub video: Option<VideoEncoderConfig>,
+
+    /// Audio stream (None = no audio)
+    pub audio: Option<AudioEncoderConfig>,
+
+    /// Container (None = guessed by FFmpeg from the path, e.g. an image
+    /// sequence for "frame_%05d.png")
+    pub format: Option<ExportFormat>,
+}
+
+/// Encoder-side pixel format for frames of `input` format
+pub(crate) fn encoder_pixel_format(
+    config: &VideoEncoderConfig,
+    input: PixelFormat,
+) -> ffmpeg_next::format::Pixel {
+    use ffmpeg_next::format::Pixel;
+
+    let has_alpha = matches!(
+        input,
+        PixelFormat::Bgra | PixelFormat::Rgba | PixelFormat::Rgba64
+    );
+    match config.codec {
+        VideoCodec::Mpeg4 => Pixel::YUV420P,
+        VideoCodec::Mjpeg => Pixel::YUVJ420P,
+        VideoCodec::ProRes if config.prores_profile.is_4444() && has_alpha => Pixel::YUVA444P10LE,
+        VideoCodec::ProRes if config.prores_profile.is_4444() => Pixel::YUV444P10LE,
+        VideoCodec::ProRes => Pixel::YUV422P10LE,
+        VideoCodec::Ffv1 => match input {
+            PixelFormat::Bgra | PixelFormat::Rgba => Pixel::BGRA,
+            PixelFormat::Rgba64 | PixelFormat::Rgb48 => Pixel::GBRP16LE,
+            PixelFormat::P010 => Pixel::YUV420P10LE,
+            PixelFormat::Yuv422p10 => Pixel::YUV422P10LE,
+            PixelFormat::Yuv444p16 => Pixel::YUV444P16LE,
+            _ => Pixel::YUV420P,
+        },
+        VideoCodec::Png => match input {
+            PixelFormat::Rgba64 => Pixel::RGBA64BE,
+            PixelFormat::Rgb48 => Pixel::RGB48BE,
+            PixelFormat::Bgra | PixelFormat::Rgba => Pixel::RGBA,
+            _ => Pixel::RGB24,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ffmpeg_next::format::Pixel;
+
+    #[test]
+    fn test_video_config() {
+        let config = VideoEncoderConfig::new(VideoCodec::Mpeg4, 1280, 720, (30000, 1001));
+        assert_eq!(config.time_base(), (1001, 30000));
+        assert_eq!(config.quality, 3);
+        assert_eq!(
+            VideoEncoderConfig::new(VideoCodec::Ffv1, 64, 64, (25, 1)).quality,
+            0
+        );
+        assert_eq!(VideoCodec::ProRes.encoder_name(), "prores_ks");
+    }
+
+    #[test]
+    fn test_encoder_pixel_format() {
+        let mut config = VideoEncoderConfig::new(VideoCodec::ProRes, 1920, 1080, (25, 1));
+        assert_eq!(
+            encoder_pixel_format(&config, PixelFormat::Bgra),
+            Pixel::YUV422P10LE
+        );
+        config.prores_profile = ProResProfile::P4444;
+        assert_eq!(
+            encoder_pixel_format(&config, PixelFormat::Bgra),
+            Pixel::YUVA444P10LE
+        );
+
+        config.codec = VideoCodec::Png;
+        assert_eq!(
+            encoder_pixel_format(&config, PixelFormat::Rgba64),
+            Pixel::RGBA64BE
+        );
+        assert_eq!(
+            encoder_pixel_format(&config, PixelFormat::Nv12),
+            Pixel::RGB24
+        );
+    }
+
+    #[test]
+    fn test_audio_codecs() {
+        assert_eq!(
+            AudioCodec::Aac.sample_layout(),
+            (SampleFormat::Float32, true)
+        );
+        assert_eq!(
+            AudioCodec::Pcm24.sample_layout(),
+            (SampleFormat::Int32, false)
+        );
+        assert_eq!(AudioCodec::Aac.default_bit_rate(2), 192_000);
+        assert_eq!(AudioCodec::Flac.default_bit_rate(2), 0);
+    }
+}
@@ -0,0 +1,677 @@
+//! FFmpeg encoder implementation using ffmpeg-next
+//!
+//! Frames are converted to the encoder's pixel or sample format, encoded
+//! and interleaved into the output container.
+
+use std::path::Path;
+
+use ffmpeg_next as ffmpeg;
+use ffmpeg_next::format::context::Output as OutputContext;
+use ffmpeg_next::software::scaling::Context as ScalerContext;
+use ffmpeg_next::util::frame::audio::Audio as AudioFrameFFmpeg;
+use ffmpeg_next::util::frame::video::Video as VideoFrameFFmpeg;
+use ffmpeg_next::{Dictionary, Rational};
+
+use super::config::{
+    encoder_pixel_format, AudioEncoderConfig, EncoderConfig, VideoCodec, VideoEncoderConfig,
+};
+use super::samples::{encode_samples, SampleQueue};
+use crate::decoder::color::matrix;
+use crate::decoder::ffmpeg_decoder::{FFmpegContext, ScalerSetup};
+use crate::decoder::{AudioFrame, PixelFormat, VideoFrame};
+use crate::error::{Error, Result};
+
+/// FFmpeg encoder context
+pub struct FFmpegEncoder {
+    /// Output format context
+    output: OutputContext,
+
+    /// Video stream (None = audio only)
+    video: Option<VideoStream>,
+
+    /// Audio stream (None = video only)
+    audio: Option<AudioStream>,
+
+    /// Whether the trailer has been written
+    finished: bool,
+}
+
+/// Encoder state of the video stream
+struct VideoStream {
+    config: VideoEncoderConfig,
+    encoder: ffmpeg::encoder::Video,
+    stream_index: usize,
+    time_base: Rational,
+
+    /// Matrix coefficients and range of the encoded YUV data
+    matrix: u8,
+    full_range: bool,
+
+    /// Converter from the incoming frames' format and size (created on demand)
+    scaler: Option<ScalerContext>,
+    scaler_setup: Option<ScalerSetup>,
+
+    /// Next presentation timestamp in frames
+    next_pts: i64,
+}
+
+/// Encoder state of the audio stream
+struct AudioStream {
+    config: AudioEncoderConfig,
+    encoder: ffmpeg::encoder::Audio,
+    stream_index: usize,
+    time_base: Rational,
+    format: ffmpeg::format::Sample,
+    channel_layout: ffmpeg::channel_layout::ChannelLayout,
+
+    /// Samples per encoded frame
+    frame_size: usize,
+
+    /// Samples not yet filling a frame
+    queue: SampleQueue,
+
+    /// Next presentation timestamp in samples
+    next_pts: i64,
+}
+
+impl FFmpegEncoder {
+    /// Create the output file, open the configured encoders and write the
+    /// container header
+    pub fn new(path: &Path, config: &EncoderConfig) -> Result<Self> {
+        ffmpeg::init().map_err(|e| Error::FFmpeg {
+            code: -1,
+            message: format!("FFmpeg init failed: {}", e),
+        })?;
+        if config.video.is_none() && config.audio.is_none() {
+            return Err(Error::InvalidFormat(
+                "Encoder needs a video or an audio stream".to_string(),
+            ));
+        }
+
+        let output = match config.format {
+            Some(format) => ffmpeg::format::output_as(path, format.muxer_name()),
+            None => ffmpeg::format::output(path),
+        };
+        let mut output = output.map_err(|e| {
+            Error::InvalidFormat(format!("Failed to create {}: {}", path.display(), e))
+        })?;
+
+        let video = match config.video {
+            Some(ref video) => Some(Self::open_video(&mut output, video)?),
+            None => None,
+        };
+        let audio = match config.audio {
+            Some(ref audio) => Some(Self::open_audio(&mut output, audio)?),
+            None => None,
+        };
+
+        output.write_header().map_err(|e| Error::FFmpeg {
+            code: e.into(),
+            message: format!("Failed to write header: {}", e),
+        })?;
+        log::info!(
+            "Encoding to {} ({})",
+            path.display(),
+            output.format().name()
+        );
+
+        Ok(Self {
+            output,
+            video,
+            audio,
+            finished: false,
+        })
+    }
+
+    /// Find an encoder by name and check that the container can hold it
+    fn find_codec(output: &OutputContext, name: &str) -> Result<ffmpeg::Codec> {
+        let codec = ffmpeg::encoder::find_by_name(name).ok_or_else(|| {
+            Error::CodecNotSupported(format!("Encoder {} is not available", name))
+        })?;
+        let supported = unsafe {
+            // 0 = FF_COMPLIANCE_NORMAL; a negative answer means the muxer decides
+            ffmpeg::ffi::avformat_query_codec((*output.as_ptr()).oformat, codec.id().into(), 0) != 0
+        };
+        if !supported {
+            return Err(Error::CodecNotSupported(format!(
+                "{} cannot be stored in {}",
+                name,
+                output.format().name()
+            )));
+        }
+        Ok(codec)
+    }
+
+    /// Add the video stream and open its encoder
+    fn open_video(output: &mut OutputContext, config: &VideoEncoderConfig) -> Result<VideoStream> {
+        if config.width == 0 || config.height == 0 {
+            return Err(Error::InvalidFormat(format!(
+                "Invalid video size {}x{}",
+                config.width, config.height
+            )));
+        }
+        if config.frame_rate.0 <= 0 || config.frame_rate.1 <= 0 {
+            return Err(Error::InvalidFormat(format!(
+                "Invalid frame rate {}/{}",
+                config.frame_rate.0, config.frame_rate.1
+            )));
+        }
+
+        let codec = Self::find_codec(output, config.codec.encoder_name())?;
+        let global_header = output
+            .format()
+            .flags()
+            .contains(ffmpeg::format::Flags::GLOBAL_HEADER);
+        let mut stream = output
+            .add_stream(codec)
+            .map_err(|e| Error::InvalidFormat(format!("Failed to add video stream: {}", e)))?;
+        let stream_index = stream.index();
+
+        let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()
+            .map_err(|e| Error::CodecNotSupported(format!("{}: {}", codec.name(), e)))?;
+
+        let format = encoder_pixel_format(config, config.input_format);
+        let matrix = if config.height > 576 {
+            matrix::BT709
+        } else {
+            matrix::SMPTE170M
+        };
+        let full_range = config.codec == VideoCodec::Mjpeg;
+        let time_base = Rational::new(config.time_base().0, config.time_base().1);
+
+        encoder.set_width(config.width);
+        encoder.set_height(config.height);
+        encoder.set_format(format);
+        encoder.set_time_base(time_base);
+        encoder.set_frame_rate(Some(Rational::new(
+            config.frame_rate.0,
+            config.frame_rate.1,
+        )));
+        encoder.set_colorspace(ffmpeg::color::Space::from(Self::color_space(matrix)));
+        encoder.set_color_range(if full_range {
+            ffmpeg::color::Range::JPEG
+        } else {
+            ffmpeg::color::Range::MPEG
+        });
+        if config.bit_rate > 0 {
+            encoder.set_bit_rate(config.bit_rate as usize);
+        }
+        if config.gop_size > 0 {
+            encoder.set_gop(config.gop_size);
+        }
+        let mut flags = ffmpeg::codec::Flags::empty();
+        if config.codec.uses_quantizer() && config.quality > 0 {
+            flags |= ffmpeg::codec::Flags::QSCALE;
+            encoder.set_global_quality(Self::quantizer_lambda(config.quality));
+        }
+        if global_header {
+            flags |= ffmpeg::codec::Flags::GLOBAL_HEADER;
+        }
+        encoder.set_flags(flags);
+
+        let mut options = Dictionary::new();
+        if config.codec == VideoCodec::ProRes {
+            options.set("profile", &(config.prores_profile as u8).to_string());
+        }
+        let encoder = encoder.open_as_with(codec, options).map_err(|e| {
+            Error::CodecNotSupported(format!("Failed to open {}: {}", codec.name(), e))
+        })?;
+        stream.set_parameters(&encoder);
+        stream.set_time_base(time_base);
+        stream.set_avg_frame_rate(Rational::new(config.frame_rate.0, config.frame_rate.1));
+
+        log::info!(
+            "Video encoder: {} {}x{} {:?} @ {}/{} fps",
+            codec.name(),
+            config.width,
+            config.height,
+            format,
+            config.frame_rate.0,
+            config.frame_rate.1
+        );
+
+        Ok(VideoStream {
+            config: config.clone(),
+            encoder,
+            stream_index,
+            time_base,
+            matrix,
+            full_range,
+            scaler: None,
+            scaler_setup: None,
+            next_pts: 0,
+        })
+    }
+
+    /// Add the audio stream and open its encoder
+    fn open_audio(output: &mut OutputContext, config: &AudioEncoderConfig) -> Result<AudioStream> {
+        if config.sample_rate == 0 || config.channels == 0 {
+            return Err(Error::InvalidFormat(format!(
+                "Invalid audio format {} Hz, {} channels",
+                config.sample_rate, config.channels
+            )));
+        }
+
+        let codec = Self::find_codec(output, config.codec.encoder_name())?;
+        let global_header = output
+            .format()
+            .flags()
+            .contains(ffmpeg::format::Flags::GLOBAL_HEADER);
+        let mut stream = output
+            .add_stream(codec)
+            .map_err(|e| Error::InvalidFormat(format!("Failed to add audio stream: {}", e)))?;
+        let stream_index = stream.index();
+
+        let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .audio()
+            .map_err(|e| Error::CodecNotSupported(format!("{}: {}", codec.name(), e)))?;
+
+        let (sample_format, planar) = config.codec.sample_layout();
+        let format = FFmpegContext::sample_format_to_ffmpeg(sample_format, planar);
+        let channel_layout = ffmpeg::channel_layout::ChannelLayout::default(config.channels as i32);
+        let time_base = Rational::new(1, config.sample_rate as i32);
+
+        encoder.set_rate(config.sample_rate as i32);
+        encoder.set_channel_layout(channel_layout);
+        encoder.set_format(format);
+        encoder.set_time_base(time_base);
+        let bit_rate = if config.bit_rate > 0 {
+            config.bit_rate
+        } else {
+            config.codec.default_bit_rate(config.channels)
+        };
+        if bit_rate > 0 {
+            encoder.set_bit_rate(bit_rate as usize);
+        }
+        if global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+
+        let encoder = encoder.open_as(codec).map_err(|e| {
+            Error::CodecNotSupported(format!("Failed to open {}: {}", codec.name(), e))
+        })?;
+        stream.set_parameters(&encoder);
+        stream.set_time_base(time_base);
+
+        // PCM takes frames of any size
+        let frame_size = match encoder.frame_size() {
+            0 => 1024,
+            size => size as usize,
+        };
+
+        log::info!(
+            "Audio encoder: {} {} Hz, {} channels, {} samples per frame",
+            codec.name(),
+            config.sample_rate,
+            config.channels,
+            frame_size
+        );
+
+        Ok(AudioStream {
+            config: *config,
+            encoder,
+            stream_index,
+            time_base,
+            format,
+            channel_layout,
+            frame_size,
+            queue: SampleQueue::new(config.channels as usize),
+            next_pts: 0,
+        })
+    }
+
+    /// Encode a video frame (scaled and converted to the encoder's format).
+    ///
+    /// Frames get consecutive timestamps at the configured frame rate.
+    pub fn write_video_frame(&mut self, frame: &VideoFrame) -> Result<()> {
+        self.check_open()?;
+        let video = self
+            .video
+            .as_mut()
+            .ok_or_else(|| Error::InvalidFormat("Encoder has no video stream".to_string()))?;
+
+        let source = Self::video_frame_to_ffmpeg(frame)?;
+
+        let (src_matrix, src_full_range) =
+            FFmpegContext::effective_source_color(&frame.color, source.format(), frame.height);
+        let setup = ScalerSetup {
+            format: source.format(),
+            width: frame.width,
+            height: frame.height,
+            out_format: video.encoder.format(),
+            out_width: video.config.width,
+            out_height: video.config.height,
+            src_matrix,
+            src_full_range,
+            dst_matrix: video.matrix,
+            dst_full_range: video.full_range,
+        };
+
+        let mut converted = if setup.format == setup.out_format
+            && (setup.width, setup.height) == (setup.out_width, setup.out_height)
+        {
+            source
+        } else {
+            let scaler = FFmpegContext::prepare_scaler(
+                &mut video.scaler,
+                &mut video.scaler_setup,
+                setup,
+                video.config.scaling_algorithm,
+            )?;
+            let mut converted = VideoFrameFFmpeg::empty();
+            scaler
+                .run(&source, &mut converted)
+                .map_err(|e| Error::DecodeFailed(format!("Failed to scale frame: {}", e)))?;
+            converted
+        };
+
+        converted.set_pts(Some(video.next_pts));
+        if video.config.codec.uses_quantizer() && video.config.quality > 0 {
+            unsafe {
+                (*converted.as_mut_ptr()).quality = Self::quantizer_lambda(video.config.quality);
+            }
+        }
+        video.next_pts += 1;
+
+        video
+            .encoder
+            .send_frame(&converted)
+            .map_err(|e| Error::FFmpeg {
+                code: e.into(),
+                message: format!("Failed to encode video frame: {}", e),
+            })?;
+        Self::write_packets(
+            &mut self.output,
+            &mut video.encoder,
+            video.stream_index,
+            video.time_base,
+        )
+    }
+
+    /// Queue audio samples and encode every full encoder frame.
+    ///
+    /// The frame's sample rate and channel count must match the configuration.
+    pub fn write_audio_frame(&mut self, frame: &AudioFrame) -> Result<()> {
+        self.check_open()?;
+        let audio = self
+            .audio
+            .as_mut()
+            .ok_or_else(|| Error::InvalidFormat("Encoder has no audio stream".to_string()))?;
+        if frame.sample_rate != audio.config.sample_rate {
+            return Err(Error::InvalidFormat(format!(
+                "Audio frame has {} Hz, encoder expects {} Hz",
+                frame.sample_rate, audio.config.sample_rate
+            )));
+        }
+
+        audio.queue.push(frame)?;
+        while audio.queue.len() >= audio.frame_size {
+            Self::encode_queued_audio(&mut self.output, audio)?;
+        }
+        Ok(())
+    }
+
+    /// Encode up to one frame of queued samples
+    fn encode_queued_audio(output: &mut OutputContext, audio: &mut AudioStream) -> Result<()> {
+        let channels = audio.queue.pop(audio.frame_size);
+        let count = channels.first().map_or(0, Vec::len);
+        let (sample_format, planar) = audio.config.codec.sample_layout();
+
+        let mut frame = AudioFrameFFmpeg::new(audio.format, count, audio.channel_layout);
+        frame.set_rate(audio.config.sample_rate);
+        for (plane, bytes) in encode_samples(&channels, sample_format, planar)
+            .iter()
+            .enumerate()
+        {
+            frame.data_mut(plane)[..bytes.len()].copy_from_slice(bytes);
+        }
+        frame.set_pts(Some(audio.next_pts));
+        audio.next_pts += count as i64;
+
+        audio
+            .encoder
+            .send_frame(&frame)
+            .map_err(|e| Error::FFmpeg {
+                code: e.into(),
+                message: format!("Failed to encode audio frame: {}", e),
+            })?;
+        Self::write_packets(
+            output,
+            &mut audio.encoder,
+            audio.stream_index,
+            audio.time_base,
+        )
+    }
+
+    /// Write the packets an encoder has ready to the container
+    fn write_packets(
+        output: &mut OutputContext,
+        encoder: &mut ffmpeg::encoder::Encoder,
+        stream_index: usize,
+        time_base: Rational,
+    ) -> Result<()> {
+        let stream_time_base = output
+            .stream(stream_index)
+            .map(|stream| stream.time_base())
+            .unwrap_or(time_base);
+
+        let mut packet = ffmpeg::Packet::empty();
+        while encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(stream_index);
+            packet.rescale_ts(time_base, stream_time_base);
+            packet
+                .write_interleaved(output)
+                .map_err(|e| Error::FFmpeg {
+                    code: e.into(),
+                    message: format!("Failed to write packet: {}", e),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Flush the encoders and write the container trailer.
+    ///
+    /// Queued audio samples are encoded as a final, shorter frame.
+    pub fn finish(&mut self) -> Result<()> {
+        self.check_open()?;
+        self.finished = true;
+
+        if let Some(ref mut video) = self.video {
+            video.encoder.send_eof().map_err(|e| Error::FFmpeg {
+                code: e.into(),
+                message: format!("Failed to flush video encoder: {}", e),
+            })?;
+            Self::write_packets(
+                &mut self.output,
+                &mut video.encoder,
+                video.stream_index,
+                video.time_base,
+            )?;
+        }
+        if let Some(ref mut audio) = self.audio {
+            while !audio.queue.is_empty() {
+                Self::encode_queued_audio(&mut self.output, audio)?;
+            }
+            audio.encoder.send_eof().map_err(|e| Error::FFmpeg {
+                code: e.into(),
+                message: format!("Failed to flush audio encoder: {}", e),
+            })?;
+            Self::write_packets(
+                &mut self.output,
+                &mut audio.encoder,
+                audio.stream_index,
+                audio.time_base,
+            )?;
+        }
+
+        self.output.write_trailer().map_err(|e| Error::FFmpeg {
+            code: e.into(),
+            message: format!("Failed to write trailer: {}", e),
+        })?;
+        log::info!(
+            "Encoding finished: {} video frames, {} audio samples",
+            self.video_frame_count(),
+            self.audio_sample_count()
+        );
+        Ok(())
+    }
+
+    /// Number of video frames written
+    pub fn video_frame_count(&self) -> i64 {
+        self.video.as_ref().map_or(0, |video| video.next_pts)
+    }
+
+    /// Number of audio samples (per channel) encoded
+    pub fn audio_sample_count(&self) -> i64 {
+        self.audio.as_ref().map_or(0, |audio| audio.next_pts)
+    }
+
+    /// Whether the trailer has been written
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn check_open(&self) -> Result<()> {
+        if self.finished {
+            return Err(Error::InvalidFormat("Encoder already finished".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Copy a frame's packed planes into an FFmpeg frame
+    fn video_frame_to_ffmpeg(frame: &VideoFrame) -> Result<VideoFrameFFmpeg> {
+        if frame.pixel_format == PixelFormat::Native {
+            return Err(Error::InvalidFormat(
+                "Frames must have a concrete pixel format".to_string(),
+            ));
+        }
+
+        let format = FFmpegContext::pixel_format_to_ffmpeg(frame.pixel_format);
+        let mut output = VideoFrameFFmpeg::new(format, frame.width, frame.height);
+        let mut offset = 0;
+        for plane in 0..frame.pixel_format.plane_count() {
+            let (row_size, rows) =
+                match frame
+                    .pixel_format
+                    .plane_layout(plane, frame.stride, frame.height)
+                {
+                    Some((row_size, rows)) => (row_size as usize, rows as usize),
+                    None => break,
+                };
+            let source = frame
+                .data
+                .get(offset..offset + row_size * rows)
+                .ok_or_else(|| {
+                    Error::InvalidFormat(format!(
+                        "Frame data ({} bytes) is smaller than its layout",
+                        frame.data.len()
+                    ))
+                })?;
+            offset += row_size * rows;
+
+            let stride = output.stride(plane);
+            let copy = row_size.min(stride);
+            let destination = output.data_mut(plane);
+            for row in 0..rows {
+                destination[row * stride..row * stride + copy]
+                    .copy_from_slice(&source[row * row_size..row * row_size + copy]);
+            }
+        }
+        Ok(output)
+    }
+
+    /// FFmpeg lambda for a quantizer (FF_QP2LAMBDA = 118)
+    fn quantizer_lambda(quality: u32) -> i32 {
+        quality.clamp(1, 31) as i32 * 118
+    }
+
+    /// AVColorSpace for matrix coefficients of our ColorInfo
+    fn color_space(matrix: u8) -> ffmpeg::ffi::AVColorSpace {
+        match matrix {
+            matrix::SMPTE170M => ffmpeg::ffi::AVColorSpace::AVCOL_SPC_SMPTE170M,
+            matrix::BT470BG => ffmpeg::ffi::AVColorSpace::AVCOL_SPC_BT470BG,
+            matrix::BT2020_NCL => ffmpeg::ffi::AVColorSpace::AVCOL_SPC_BT2020_NCL,
+            _ => ffmpeg::ffi::AVColorSpace::AVCOL_SPC_BT709,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::ColorInfo;
+
+    #[test]
+    fn test_video_frame_to_ffmpeg() {
+        // 4x2 NV12 with a padded stride of 8
+        let mut data = vec![0u8; 8 * 2 + 8];
+        data[0] = 16;
+        data[8 + 3] = 235;
+        data[16] = 128;
+        let frame = VideoFrame::new(data, 4, 2, 8, 0, 0, true, 0, PixelFormat::Nv12);
+
+        let converted = FFmpegEncoder::video_frame_to_ffmpeg(&frame).unwrap();
+        assert_eq!(converted.format(), ffmpeg::format::Pixel::NV12);
+        assert_eq!(converted.data(0)[0], 16);
+        assert_eq!(converted.data(0)[converted.stride(0) + 3], 235);
+        assert_eq!(converted.data(1)[0], 128);
+
+        let mut short = frame.clone();
+        short.data.truncate(10);
+        assert!(FFmpegEncoder::video_frame_to_ffmpeg(&short).is_err());
+    }
+
+    #[test]
+    fn test_lossless_bit_depth() {
+        use crate::decoder::ExportFormat;
+
+        for (input, expected) in [
+            (PixelFormat::Rgba64, ffmpeg::format::Pixel::GBRP16LE),
+            (PixelFormat::P010, ffmpeg::format::Pixel::YUV420P10LE),
+        ] {
+            let path = std::env::temp_dir().join(format!(
+                "cyb_ffv1_{:?}_{}.mkv",
+                input,
+                std::process::id()
+            ));
+            let mut video = VideoEncoderConfig::new(VideoCodec::Ffv1, 16, 16, (25, 1));
+            video.input_format = input;
+            let config = EncoderConfig {
+                video: Some(video),
+                audio: None,
+                format: Some(ExportFormat::Mkv),
+            };
+
+            let data = vec![0x40; VideoFrame::expected_size(16, 16, input)];
+            let stride = 16 * input.bytes_per_pixel();
+            let frame = VideoFrame::new(data, 16, 16, stride, 0, 40_000, true, 0, input);
+            let mut encoder = FFmpegEncoder::new(&path, &config).unwrap();
+            encoder.write_video_frame(&frame).unwrap();
+            encoder.finish().unwrap();
+
+            let written = ffmpeg::format::input(&path).unwrap();
+            let stream = written.streams().best(ffmpeg::media::Type::Video).unwrap();
+            let decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+                .unwrap()
+                .decoder()
+                .video()
+                .unwrap();
+            assert_eq!(decoder.format(), expected);
+            drop(written);
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_quantizer_lambda() {
+        assert_eq!(FFmpegEncoder::quantizer_lambda(2), 236);
+        assert_eq!(FFmpegEncoder::quantizer_lambda(0), 118);
+        assert_eq!(
+            FFmpegEncoder::color_space(ColorInfo::default().matrix),
+            ffmpeg::ffi::AVColorSpace::AVCOL_SPC_BT709
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Video encoder module using ffmpeg-next
+//!
+//! This module renders decoded (or generated) frames into a new file:
+//! frames are converted to the encoder's format, encoded with one of the
+//! codecs available in LGPL builds of FFmpeg and muxed into the container.
+
+use std::path::Path;
+
+use crate::decoder::{AudioFrame, VideoFrame};
+use crate::error::Result;
+
+mod config;
+mod ffmpeg_encoder;
+mod samples;
+
+pub use config::{
+    AudioCodec, AudioEncoderConfig, EncoderConfig, ProResProfile, VideoCodec, VideoEncoderConfig,
+};
+
+use ffmpeg_encoder::FFmpegEncoder;
+
+/// Main encoder struct
+///
+/// The file is only complete after `finish()`; dropping the encoder before
+/// that leaves it without a trailer (unplayable for MP4/MOV).
+pub struct Encoder {
+    /// FFmpeg encoder context
+    ctx: FFmpegEncoder,
+}
+
+impl Encoder {
+    /// Create the output file and write its header
+    pub fn new<P: AsRef<Path>>(path: P, config: EncoderConfig) -> Result<Self> {
+        let ctx = FFmpegEncoder::new(path.as_ref(), &config)?;
+        Ok(Self { ctx })
+    }
+
+    /// Encode the next video frame.
+    ///
+    /// Frames are written at the configured constant frame rate in the order
+    /// they are passed; their timestamps are ignored. Frames of another size
+    /// or pixel format are converted.
+    pub fn write_video_frame(&mut self, frame: &VideoFrame) -> Result<()> {
+        self.ctx.write_video_frame(frame)
+    }
+
+    /// Encode audio samples (any sample format and layout; the sample rate
+    /// and channel count must match the configuration)
+    pub fn write_audio_frame(&mut self, frame: &AudioFrame) -> Result<()> {
+        self.ctx.write_audio_frame(frame)
+    }
+
+    /// Flush the encoders and finalize the container
+    pub fn finish(&mut self) -> Result<()> {
+        self.ctx.finish()
+    }
+
+    /// Number of video frames written so far
+    pub fn video_frame_count(&self) -> i64 {
+        self.ctx.video_frame_count()
+    }
+
+    /// Number of audio samples (per channel) encoded so far
+    pub fn audio_sample_count(&self) -> i64 {
+        self.ctx.audio_sample_count()
+    }
+
+    /// Check if the file has been finalized
+    pub fn is_finished(&self) -> bool {
+        self.ctx.is_finished()
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        if !self.ctx.is_finished() {
+            log::warn!("Encoder dropped without finish(); the output file is incomplete");
+        }
+    }
+}
+
+// Make Encoder Send safe (used from one thread at a time)
+unsafe impl Send for Encoder {}
@@ -0,0 +1,178 @@
+//! Sample buffering between incoming audio frames and encoder frames
+//!
+//! Audio frames arrive with any sample count, format and layout while most
+//! encoders take frames of a fixed size, so samples are queued per channel as
+//! floats and converted to the encoder's format when a frame is full.
+
+use std::collections::VecDeque;
+
+use crate::decoder::{AudioFrame, SampleFormat};
+use crate::error::{Error, Result};
+
+/// Per-channel float samples waiting to fill encoder frames
+pub(crate) struct SampleQueue {
+    channels: Vec<VecDeque<f32>>,
+}
+
+impl SampleQueue {
+    /// Create an empty queue for `channels` channels
+    pub fn new(channels: usize) -> Self {
+        Self {
+            channels: vec![VecDeque::new(); channels],
+        }
+    }
+
+    /// Number of queued samples per channel
+    pub fn len(&self) -> usize {
+        self.channels.first().map_or(0, VecDeque::len)
+    }
+
+    /// Check if no samples are queued
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Queue the samples of a frame (any format, planar or interleaved)
+    pub fn push(&mut self, frame: &AudioFrame) -> Result<()> {
+        let channels = self.channels.len();
+        if frame.channels as usize != channels {
+            return Err(Error::InvalidFormat(format!(
+                "Audio frame has {} channels, encoder expects {}",
+                frame.channels, channels
+            )));
+        }
+        let count = frame.sample_count as usize;
+        if frame.data.len() < count * channels {
+            return Err(Error::InvalidFormat(format!(
+                "Audio frame holds {} samples, {} expected",
+                frame.data.len(),
+                count * channels
+            )));
+        }
+
+        for (channel, queue) in self.channels.iter_mut().enumerate() {
+            queue.extend((0..count).map(|n| {
+                if frame.planar {
+                    frame.data.sample_f32(channel * count + n)
+                } else {
+                    frame.data.sample_f32(n * channels + channel)
+                }
+            }));
+        }
+        Ok(())
+    }
+
+    /// Take up to `count` samples per channel
+    pub fn pop(&mut self, count: usize) -> Vec<Vec<f32>> {
+        let count = count.min(self.len());
+        self.channels
+            .iter_mut()
+            .map(|queue| queue.drain(..count).collect())
+            .collect()
+    }
+}
+
+/// Convert per-channel float samples to raw bytes of `format`: one buffer
+/// per channel if `planar`, else a single interleaved buffer
+pub(crate) fn encode_samples(
+    channels: &[Vec<f32>],
+    format: SampleFormat,
+    planar: bool,
+) -> Vec<Vec<u8>> {
+    let count = channels.first().map_or(0, Vec::len);
+    let mut buffers = if planar {
+        vec![Vec::with_capacity(count * format.bytes_per_sample()); channels.len()]
+    } else {
+        vec![Vec::with_capacity(
+            count * channels.len() * format.bytes_per_sample(),
+        )]
+    };
+
+    for n in 0..count {
+        for (channel, samples) in channels.iter().enumerate() {
+            let buffer = &mut buffers[if planar { channel } else { 0 }];
+            let value = samples[n].clamp(-1.0, 1.0);
+            match format {
+                SampleFormat::Float32 => buffer.extend_from_slice(&value.to_le_bytes()),
+                SampleFormat::Int16 => {
+                    buffer.extend_from_slice(&((value * 32767.0).round() as i16).to_le_bytes())
+                }
+                SampleFormat::Int32 => buffer.extend_from_slice(
+                    &((value as f64 * 2_147_483_647.0).round() as i32).to_le_bytes(),
+                ),
+            }
+        }
+    }
+    buffers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::AudioSamples;
+
+    #[test]
+    fn test_queue_frames() {
+        let mut queue = SampleQueue::new(2);
+        // Interleaved int16: L = 0.5, R = -0.5
+        let interleaved = AudioFrame::new(
+            AudioSamples::Int16(vec![16384, -16384, 16384, -16384]),
+            false,
+            2,
+            2,
+            48000,
+            0,
+            0,
+            0,
+        );
+        queue.push(&interleaved).unwrap();
+        // Planar float: L = 0.25, R = 1.0
+        let planar = AudioFrame::new(
+            AudioSamples::Float32(vec![0.25, 0.25, 0.25, 1.0, 1.0, 1.0]),
+            true,
+            3,
+            2,
+            48000,
+            0,
+            0,
+            0,
+        );
+        queue.push(&planar).unwrap();
+        assert_eq!(queue.len(), 5);
+
+        let chunk = queue.pop(3);
+        assert_eq!(chunk[0], vec![0.5, 0.5, 0.25]);
+        assert_eq!(chunk[1], vec![-0.5, -0.5, 1.0]);
+        assert_eq!(queue.pop(10)[1].len(), 2);
+        assert!(queue.is_empty());
+
+        let mono = AudioFrame::new(
+            AudioSamples::Float32(vec![0.0]),
+            false,
+            1,
+            1,
+            48000,
+            0,
+            0,
+            0,
+        );
+        assert!(queue.push(&mono).is_err());
+    }
+
+    #[test]
+    fn test_encode_samples() {
+        let channels = vec![vec![1.0, -2.0], vec![0.0, 0.5]];
+
+        let interleaved = encode_samples(&channels, SampleFormat::Int16, false);
+        assert_eq!(interleaved.len(), 1);
+        let values: Vec<i16> = interleaved[0]
+            .chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        assert_eq!(values, vec![32767, 0, -32767, 16384]);
+
+        let planar = encode_samples(&channels, SampleFormat::Float32, true);
+        assert_eq!(planar.len(), 2);
+        assert_eq!(planar[1][4..8], 0.5f32.to_le_bytes());
+    }
+}
@@ -29,6 +29,14 @@ pub enum Error {
     #[error("Seek failed at {0} microseconds")]
     SeekFailed(i64),
 
+    /// Invalid filter graph description
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    /// Invalid or out-of-range timecode
+    #[error("Invalid timecode: {0}")]
+    InvalidTimecode(String),
+
     /// Memory allocation error
     #[error("Memory allocation failed")]
     Memory,
@@ -41,6 +49,10 @@ pub enum Error {
     #[error("Decoder not prepared")]
     NotPrepared,
 
+    /// Operation cancelled by the caller
+    #[error("Operation cancelled")]
+    Cancelled,
+
     /// FFmpeg error with code
     #[error("FFmpeg error {code}: {message}")]
     FFmpeg { code: i32, message: String },
@@ -71,9 +83,12 @@ impl Error {
             Error::CodecNotSupported(_) => 3,
             Error::DecodeFailed(_) => 4,
             Error::SeekFailed(_) => 5,
+            Error::InvalidFilter(_) => 2,
+            Error::InvalidTimecode(_) => 2,
             Error::Memory => 6,
             Error::InvalidHandle => 7,
             Error::NotPrepared => 8,
+            Error::Cancelled => 9,
             Error::FFmpeg { code, .. } => *code,
             Error::Io(_) => 1,
             Error::LockPoisoned => 6,
@@ -130,6 +145,7 @@ mod tests {
         assert_eq!(Error::FileNotFound(PathBuf::new()).to_ffi_code(), 1);
         assert_eq!(Error::Memory.to_ffi_code(), 6);
         assert_eq!(Error::NotPrepared.to_ffi_code(), 8);
+        assert_eq!(Error::Cancelled.to_ffi_code(), 9);
     }
 
     #[test]
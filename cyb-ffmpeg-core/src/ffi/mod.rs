@@ -3,13 +3,26 @@
 //! All functions in this module are exported with `#[no_mangle]`
 //! and use C-compatible types for cross-language interop.
 
-use std::ffi::{c_char, CStr, CString};
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::ptr;
 
 use parking_lot::Mutex;
 
 use crate::cache::CacheStatistics;
-use crate::decoder::{AudioFrame, Decoder, DecoderConfig, MediaInfo, PixelFormat, VideoFrame};
+use crate::decoder::{
+    color, AttachmentData, AudioFrame, AudioOutputConfig, CaptionChannel, ColorInfo, ColorRange,
+    ContactSheetLayout, Decoder, DecoderConfig, DeinterlaceMode, DownmixMode, ExportFormat,
+    ExportOptions, HdrMetadata, LoudnessOptions, LoudnessPoint, LoudnessReport, MediaInfo,
+    OutputSize, Packet, PixelFormat, SampleFormat, ScaleMode, ScalingAlgorithm, SceneCut,
+    SceneDetectionMethod, SceneDetectionOptions, StreamDisposition, StreamSelector, SubtitleEvent,
+    ThumbnailOptions, Timecode, ToneMapConfig, ToneMapOperator, VideoFrame, Waveform,
+    WaveformBucket, WaveformOptions,
+};
+use crate::encoder::{
+    AudioCodec, AudioEncoderConfig, Encoder, EncoderConfig, ProResProfile, VideoCodec,
+    VideoEncoderConfig,
+};
 use crate::error::Error;
 
 // Thread-local error storage
@@ -23,6 +36,15 @@ fn set_last_error(msg: &str) {
     });
 }
 
+/// Pointer to byte data, null when empty
+fn data_ptr(data: &[u8]) -> *const u8 {
+    if data.is_empty() {
+        ptr::null()
+    } else {
+        data.as_ptr()
+    }
+}
+
 // =============================================================================
 // Result Type
 // =============================================================================
@@ -39,6 +61,7 @@ pub enum CybResult {
     ErrorMemory = 6,
     ErrorInvalidHandle = 7,
     ErrorNotPrepared = 8,
+    ErrorCancelled = 9,
     ErrorUnknown = 99,
 }
 
@@ -47,13 +70,16 @@ impl From<Error> for CybResult {
         set_last_error(&e.to_string());
         match e {
             Error::FileNotFound(_) => CybResult::ErrorFileNotFound,
-            Error::InvalidFormat(_) => CybResult::ErrorInvalidFormat,
+            Error::InvalidFormat(_) | Error::InvalidFilter(_) | Error::InvalidTimecode(_) => {
+                CybResult::ErrorInvalidFormat
+            }
             Error::CodecNotSupported(_) => CybResult::ErrorCodecNotSupported,
             Error::DecodeFailed(_) => CybResult::ErrorDecodeFailed,
             Error::SeekFailed(_) => CybResult::ErrorSeekFailed,
             Error::Memory => CybResult::ErrorMemory,
             Error::InvalidHandle => CybResult::ErrorInvalidHandle,
             Error::NotPrepared => CybResult::ErrorNotPrepared,
+            Error::Cancelled => CybResult::ErrorCancelled,
             _ => CybResult::ErrorUnknown,
         }
     }
@@ -115,29 +141,298 @@ pub struct CybCacheConfig {
     pub enable_prefetch: bool,
 }
 
+/// Stream selection for FFI (unknown codes fail decoder creation)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CybStreamSelector {
+    /// 0=Best, 1=Index, 2=Language, 3=Disposition
+    pub kind: u8,
+    /// Stream index (kind 1)
+    pub index: i32,
+    /// Language code, e.g. "eng" (kind 2)
+    pub language: *const c_char,
+    /// 0=Default, 1=Original, 2=Dub, 3=Commentary, 4=Forced,
+    /// 5=HearingImpaired, 6=VisualImpaired (kind 3)
+    pub disposition: u8,
+}
+
+impl TryFrom<&CybStreamSelector> for StreamSelector {
+    type Error = Error;
+
+    /// Fails on unknown kind and disposition codes
+    fn try_from(s: &CybStreamSelector) -> Result<Self, Error> {
+        Ok(match s.kind {
+            1 if s.index >= 0 => StreamSelector::Index(s.index as usize),
+            2 if !s.language.is_null() => {
+                let language = unsafe { CStr::from_ptr(s.language) }
+                    .to_string_lossy()
+                    .into_owned();
+                StreamSelector::Language(language)
+            }
+            3 => StreamSelector::Disposition(match s.disposition {
+                0 => StreamDisposition::Default,
+                1 => StreamDisposition::Original,
+                2 => StreamDisposition::Dub,
+                3 => StreamDisposition::Commentary,
+                4 => StreamDisposition::Forced,
+                5 => StreamDisposition::HearingImpaired,
+                6 => StreamDisposition::VisualImpaired,
+                code => {
+                    return Err(Error::InvalidFormat(format!(
+                        "Unknown stream disposition {}",
+                        code
+                    )))
+                }
+            }),
+            // Without an index or language the best stream is selected
+            0..=2 => StreamSelector::Best,
+            kind => {
+                return Err(Error::InvalidFormat(format!(
+                    "Unknown stream selector kind {}",
+                    kind
+                )))
+            }
+        })
+    }
+}
+
+/// Audio output format for FFI
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CybAudioOutputConfig {
+    /// Output sample rate in Hz (0 = source rate)
+    pub sample_rate: u32,
+    /// Output channel count (0 = source channel count)
+    pub channels: u32,
+    /// FFmpeg channel mask (0 = default layout for the channel count)
+    pub channel_layout: u64,
+    /// 0=Float32, 1=Int16, 2=Int32
+    pub sample_format: u8,
+    /// Planar (one block per channel) instead of interleaved output
+    pub planar: bool,
+    /// 0=Default, 1=ITU (LFE dropped), 2=ITU with LFE
+    pub downmix: u8,
+}
+
+impl From<&CybAudioOutputConfig> for AudioOutputConfig {
+    fn from(c: &CybAudioOutputConfig) -> Self {
+        AudioOutputConfig {
+            sample_rate: c.sample_rate,
+            channels: c.channels,
+            channel_layout: c.channel_layout,
+            sample_format: match c.sample_format {
+                1 => SampleFormat::Int16,
+                2 => SampleFormat::Int32,
+                _ => SampleFormat::Float32,
+            },
+            planar: c.planar,
+            downmix: match c.downmix {
+                1 => DownmixMode::Itu,
+                2 => DownmixMode::ItuWithLfe,
+                _ => DownmixMode::Default,
+            },
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct CybDecoderConfig {
     pub prefer_hardware_decoding: bool,
     pub cache_config: CybCacheConfig,
     pub thread_count: u32,
-    pub output_pixel_format: u8, // 0=BGRA, 1=NV12, 2=YUV420P
+    /// 0=BGRA, 1=NV12, 2=YUV420P, 3=RGBA, 4=P010, 5=YUV422P10, 6=YUV444P16, 7=RGBA64, 8=RGB48, 9=native
+    pub output_pixel_format: u8,
+    pub video_stream: CybStreamSelector,
+    pub audio_stream: CybStreamSelector,
+    pub subtitle_stream: CybStreamSelector,
+    pub audio_output: CybAudioOutputConfig,
+    /// Output size; 0x0 keeps the source size, one 0 dimension keeps the aspect ratio
+    pub output_width: u32,
+    pub output_height: u32,
+    pub scale_mode: u8,        // 0=Fit, 1=Fill, 2=Stretch
+    pub scaling_algorithm: u8, // 0=FastBilinear, 1=Bilinear, 2=Bicubic, 3=Point, 4=Area, 5=Lanczos, 6=Spline
+    /// Matrix coefficients of YUV output (see CybColorInfo; 0 or 2=unspecified keeps the source
+    /// matrix, as the identity matrix 0 does not apply to YUV output)
+    pub output_color_matrix: u8,
+    /// Range of YUV output (0=source, 1=limited, 2=full)
+    pub output_color_range: u8,
+    /// HDR→SDR tone mapping operator (0=none, 1=Hable, 2=BT.2390)
+    pub tone_map_operator: u8,
+    /// Peak luminance of the SDR target in cd/m² (typically 100)
+    pub tone_map_target_peak: f32,
+    /// Rotate frames upright and correct non-square pixels during conversion
+    pub apply_orientation: bool,
+    /// Deinterlacing of interlaced frames (0=none, 1=bob at double rate, 2=yadif)
+    pub deinterlace_mode: u8,
+    /// libavfilter graph for video frames, e.g. "crop=1280:720" (null = none)
+    pub video_filter: *const c_char,
+    /// libavfilter graph for audio frames, e.g. "volume=0.5" (null = none)
+    pub audio_filter: *const c_char,
+}
+
+/// Copy an optional C string (null or empty = None)
+fn optional_string(value: *const c_char) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    let value = unsafe { CStr::from_ptr(value) }.to_string_lossy();
+    (!value.is_empty()).then(|| value.into_owned())
+}
+
+/// Convert an FFI pixel format code (unknown codes map to YUV420P)
+fn pixel_format_from_u8(value: u8) -> PixelFormat {
+    match value {
+        0 => PixelFormat::Bgra,
+        1 => PixelFormat::Nv12,
+        3 => PixelFormat::Rgba,
+        4 => PixelFormat::P010,
+        5 => PixelFormat::Yuv422p10,
+        6 => PixelFormat::Yuv444p16,
+        7 => PixelFormat::Rgba64,
+        8 => PixelFormat::Rgb48,
+        9 => PixelFormat::Native,
+        _ => PixelFormat::Yuv420p,
+    }
+}
+
+fn scaling_algorithm_from_u8(value: u8) -> ScalingAlgorithm {
+    match value {
+        0 => ScalingAlgorithm::FastBilinear,
+        2 => ScalingAlgorithm::Bicubic,
+        3 => ScalingAlgorithm::Point,
+        4 => ScalingAlgorithm::Area,
+        5 => ScalingAlgorithm::Lanczos,
+        6 => ScalingAlgorithm::Spline,
+        _ => ScalingAlgorithm::Bilinear,
+    }
+}
+
+/// Convert FFI output size parameters (0x0 = source size)
+fn output_size_from_ffi(width: u32, height: u32, scale_mode: u8) -> Option<OutputSize> {
+    if width == 0 && height == 0 {
+        return None;
+    }
+    let mode = match scale_mode {
+        1 => ScaleMode::Fill,
+        2 => ScaleMode::Stretch,
+        _ => ScaleMode::Fit,
+    };
+    Some(OutputSize::new(width, height, mode))
 }
 
-impl From<&CybDecoderConfig> for DecoderConfig {
-    fn from(c: &CybDecoderConfig) -> Self {
-        DecoderConfig {
+impl TryFrom<&CybDecoderConfig> for DecoderConfig {
+    type Error = Error;
+
+    /// Fails on invalid stream selectors
+    fn try_from(c: &CybDecoderConfig) -> Result<Self, Error> {
+        Ok(DecoderConfig {
             prefer_hardware_decoding: c.prefer_hardware_decoding,
             l1_cache_capacity: c.cache_config.l1_capacity,
             l2_cache_capacity: c.cache_config.l2_capacity,
             l3_cache_capacity: c.cache_config.l3_capacity,
             enable_prefetch: c.cache_config.enable_prefetch,
             thread_count: c.thread_count,
-            output_pixel_format: match c.output_pixel_format {
-                0 => PixelFormat::Bgra,
-                1 => PixelFormat::Nv12,
-                _ => PixelFormat::Yuv420p,
+            output_pixel_format: pixel_format_from_u8(c.output_pixel_format),
+            video_stream: StreamSelector::try_from(&c.video_stream)?,
+            audio_stream: StreamSelector::try_from(&c.audio_stream)?,
+            subtitle_stream: StreamSelector::try_from(&c.subtitle_stream)?,
+            audio_output: AudioOutputConfig::from(&c.audio_output),
+            output_size: output_size_from_ffi(c.output_width, c.output_height, c.scale_mode),
+            scaling_algorithm: scaling_algorithm_from_u8(c.scaling_algorithm),
+            output_color_matrix: match c.output_color_matrix {
+                color::matrix::RGB | color::matrix::UNSPECIFIED => None,
+                matrix => Some(matrix),
+            },
+            output_color_range: match ColorRange::from_raw(c.output_color_range) {
+                ColorRange::Unspecified => None,
+                range => Some(range),
+            },
+            tone_mapping: ToneMapConfig {
+                operator: match c.tone_map_operator {
+                    1 => ToneMapOperator::Hable,
+                    2 => ToneMapOperator::Bt2390,
+                    _ => ToneMapOperator::None,
+                },
+                target_peak: c.tone_map_target_peak as f64,
+            },
+            apply_orientation: c.apply_orientation,
+            deinterlace: match c.deinterlace_mode {
+                1 => DeinterlaceMode::Bob,
+                2 => DeinterlaceMode::Yadif,
+                _ => DeinterlaceMode::None,
             },
+            video_filter: optional_string(c.video_filter),
+            audio_filter: optional_string(c.audio_filter),
+        })
+    }
+}
+
+// =============================================================================
+// Custom I/O
+// =============================================================================
+
+type IoReadFn = extern "C" fn(*mut c_void, *mut u8, i32) -> i32;
+type IoSeekFn = extern "C" fn(*mut c_void, i64, i32) -> i64;
+type IoReleaseFn = extern "C" fn(*mut c_void);
+
+/// Caller-supplied I/O callbacks.
+///
+/// Callbacks may be invoked from decoder and prefetch threads but never
+/// concurrently.
+#[repr(C)]
+pub struct CybIoCallbacks {
+    /// User data passed to every callback
+    pub opaque: *mut c_void,
+    /// Copy up to `size` bytes into `buf`; return bytes read, 0 at end of data, negative on error
+    pub read: Option<extern "C" fn(opaque: *mut c_void, buf: *mut u8, size: i32) -> i32>,
+    /// Seek with `whence` 0 (SET), 1 (CUR) or 2 (END); return new position, negative on error
+    pub seek: Option<extern "C" fn(opaque: *mut c_void, offset: i64, whence: i32) -> i64>,
+    /// Called once when `opaque` is no longer used (optional)
+    pub release: Option<extern "C" fn(opaque: *mut c_void)>,
+}
+
+/// `Read + Seek` adapter over FFI callbacks
+struct CallbackReader {
+    opaque: *mut c_void,
+    read: IoReadFn,
+    seek: IoSeekFn,
+    release: Option<IoReleaseFn>,
+}
+
+// Callers guarantee the callbacks can be used from any thread
+unsafe impl Send for CallbackReader {}
+
+impl Read for CallbackReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = buf.len().min(i32::MAX as usize) as i32;
+        let read = (self.read)(self.opaque, buf.as_mut_ptr(), size);
+        if read < 0 {
+            return Err(io::Error::other(format!("read callback failed: {}", read)));
+        }
+        Ok((read as usize).min(buf.len()))
+    }
+}
+
+impl Seek for CallbackReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+        let position = (self.seek)(self.opaque, offset, whence);
+        if position < 0 {
+            return Err(io::Error::other(format!("seek callback failed: {}", position)));
+        }
+        Ok(position as u64)
+    }
+}
+
+impl Drop for CallbackReader {
+    fn drop(&mut self) {
+        if let Some(release) = self.release {
+            release(self.opaque);
         }
     }
 }
@@ -167,13 +462,75 @@ pub extern "C" fn cyb_decoder_create(
         }
     };
 
-    let decoder_config = if config.is_null() {
-        DecoderConfig::default()
-    } else {
-        unsafe { DecoderConfig::from(&*config) }
+    into_decoder_handle(
+        decoder_config_from_ptr(config).and_then(|config| Decoder::new(path_str, config)),
+    )
+}
+
+/// Create decoder for media held in memory (the data is copied)
+#[no_mangle]
+pub extern "C" fn cyb_decoder_create_from_memory(
+    data: *const u8,
+    size: usize,
+    config: *const CybDecoderConfig,
+) -> *mut CybDecoderHandle {
+    if data.is_null() || size == 0 {
+        set_last_error("Data is null or empty");
+        return ptr::null_mut();
+    }
+
+    let bytes = unsafe { std::slice::from_raw_parts(data, size) }.to_vec();
+
+    into_decoder_handle(
+        decoder_config_from_ptr(config).and_then(|config| Decoder::from_bytes(bytes, config)),
+    )
+}
+
+/// Create decoder reading through caller-supplied I/O callbacks
+///
+/// `release` (if set) is called once the decoder and all prefetch workers
+/// are done with `opaque`.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_create_with_io(
+    callbacks: *const CybIoCallbacks,
+    config: *const CybDecoderConfig,
+) -> *mut CybDecoderHandle {
+    if callbacks.is_null() {
+        set_last_error("Callbacks are null");
+        return ptr::null_mut();
+    }
+
+    let callbacks = unsafe { &*callbacks };
+    let (read, seek) = match (callbacks.read, callbacks.seek) {
+        (Some(read), Some(seek)) => (read, seek),
+        _ => {
+            set_last_error("Read and seek callbacks are required");
+            return ptr::null_mut();
+        }
     };
 
-    match Decoder::new(path_str, decoder_config) {
+    let reader = CallbackReader {
+        opaque: callbacks.opaque,
+        read,
+        seek,
+        release: callbacks.release,
+    };
+
+    into_decoder_handle(
+        decoder_config_from_ptr(config).and_then(|config| Decoder::from_reader(reader, config)),
+    )
+}
+
+fn decoder_config_from_ptr(config: *const CybDecoderConfig) -> crate::error::Result<DecoderConfig> {
+    if config.is_null() {
+        Ok(DecoderConfig::default())
+    } else {
+        unsafe { DecoderConfig::try_from(&*config) }
+    }
+}
+
+fn into_decoder_handle(result: crate::error::Result<Decoder>) -> *mut CybDecoderHandle {
+    match result {
         Ok(decoder) => Box::into_raw(Box::new(CybDecoderHandle {
             decoder: Mutex::new(decoder),
         })),
@@ -373,6 +730,139 @@ pub extern "C" fn cyb_decoder_seek_precise(
     }
 }
 
+/// Seek precisely to the start of a chapter
+#[no_mangle]
+pub extern "C" fn cyb_decoder_seek_to_chapter(
+    handle: *mut CybDecoderHandle,
+    index: i32,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    if index < 0 {
+        set_last_error("Chapter index must be non-negative");
+        return CybResult::ErrorUnknown;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.seek_to_chapter(index as usize) {
+        Ok(Some(frame)) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_frame = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Seek precisely to a frame number (0-based); out_frame is null if there is no such frame
+#[no_mangle]
+pub extern "C" fn cyb_decoder_seek_to_frame(
+    handle: *mut CybDecoderHandle,
+    frame_number: i64,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.seek_to_frame(frame_number) {
+        Ok(Some(frame)) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_frame = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get a frame by number (0-based), from the cache or by seeking
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_frame_by_index(
+    handle: *mut CybDecoderHandle,
+    frame_number: i64,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.get_frame_by_index(frame_number) {
+        Ok(Some(frame)) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_frame = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Seek precisely to the frame labelled with a SMPTE timecode ("HH:MM:SS:FF" or "HH:MM:SS;FF")
+#[no_mangle]
+pub extern "C" fn cyb_decoder_seek_to_timecode(
+    handle: *mut CybDecoderHandle,
+    timecode: *const c_char,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || timecode.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let timecode = unsafe { CStr::from_ptr(timecode) }.to_string_lossy();
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.seek_to_timecode(&timecode) {
+        Ok(Some(frame)) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_frame = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
 /// Prime audio decoder after seek.
 /// Call this after seek and before reading audio frames to ensure
 /// audio packets are pre-loaded into the queue for immediate decoding.
@@ -462,6 +952,116 @@ pub extern "C" fn cyb_decoder_clear_cache(handle: *mut CybDecoderHandle) -> CybR
 // Frame Types
 // =============================================================================
 
+/// Color description for FFI (ISO/IEC 23091-4 code points, as used by FFmpeg)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CybColorInfo {
+    /// Matrix coefficients (0=RGB, 1=BT.709, 2=unspecified, 6=BT.601, 9=BT.2020 NCL, ...)
+    pub matrix: u8,
+    /// Color primaries (1=BT.709, 2=unspecified, 9=BT.2020, 12=Display P3, ...)
+    pub primaries: u8,
+    /// Transfer characteristic (1=BT.709, 2=unspecified, 16=PQ, 18=HLG, ...)
+    pub transfer: u8,
+    /// Range (0=unspecified, 1=limited, 2=full)
+    pub range: u8,
+    /// Chroma location (0=unspecified, 1=left, 2=center, 3=top left, 4=top, 5=bottom left, 6=bottom)
+    pub chroma_location: u8,
+}
+
+impl From<&ColorInfo> for CybColorInfo {
+    fn from(c: &ColorInfo) -> Self {
+        CybColorInfo {
+            matrix: c.matrix,
+            primaries: c.primaries,
+            transfer: c.transfer,
+            range: c.range as u8,
+            chroma_location: c.chroma_location as u8,
+        }
+    }
+}
+
+/// HDR metadata for FFI
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CybHdrMetadata {
+    pub has_mastering_display: bool,
+    /// Red, green, blue primaries as CIE 1931 xy (rx, ry, gx, gy, bx, by)
+    pub display_primaries: [f32; 6],
+    /// White point as CIE 1931 xy
+    pub white_point: [f32; 2],
+    /// Mastering display luminance range in cd/m²
+    pub min_luminance: f32,
+    pub max_luminance: f32,
+    pub has_content_light_level: bool,
+    /// Maximum content / frame-average light level in cd/m²
+    pub max_cll: u32,
+    pub max_fall: u32,
+    /// HDR10+ dynamic metadata present
+    pub has_hdr10_plus: bool,
+    /// Dolby Vision configuration or RPU present
+    pub has_dolby_vision: bool,
+}
+
+impl From<&HdrMetadata> for CybHdrMetadata {
+    fn from(h: &HdrMetadata) -> Self {
+        let mut out = CybHdrMetadata {
+            has_hdr10_plus: h.has_hdr10_plus,
+            has_dolby_vision: h.has_dolby_vision,
+            ..Default::default()
+        };
+        if let Some(md) = &h.mastering_display {
+            out.has_mastering_display = true;
+            for (i, xy) in md.primaries.iter().enumerate() {
+                out.display_primaries[i * 2] = xy[0] as f32;
+                out.display_primaries[i * 2 + 1] = xy[1] as f32;
+            }
+            out.white_point = md.white_point.map(|v| v as f32);
+            out.min_luminance = md.min_luminance as f32;
+            out.max_luminance = md.max_luminance as f32;
+        }
+        if let Some(cll) = &h.content_light_level {
+            out.has_content_light_level = true;
+            out.max_cll = cll.max_cll;
+            out.max_fall = cll.max_fall;
+        }
+        out
+    }
+}
+
+/// SMPTE timecode for FFI
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CybTimecode {
+    pub is_valid: bool,
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u16,
+    /// Drop-frame counting (displayed as HH:MM:SS;FF)
+    pub drop_frame: bool,
+    /// Frame rate the timecode counts at
+    pub rate_num: i32,
+    pub rate_den: i32,
+}
+
+impl From<Option<Timecode>> for CybTimecode {
+    fn from(timecode: Option<Timecode>) -> Self {
+        match timecode {
+            Some(tc) => CybTimecode {
+                is_valid: true,
+                hours: tc.hours as u8,
+                minutes: tc.minutes as u8,
+                seconds: tc.seconds as u8,
+                frames: tc.frames as u16,
+                drop_frame: tc.drop_frame,
+                rate_num: tc.rate.0,
+                rate_den: tc.rate.1,
+            },
+            None => CybTimecode::default(),
+        }
+    }
+}
+
 /// Video frame data for FFI
 #[repr(C)]
 pub struct CybVideoFrame {
@@ -483,8 +1083,30 @@ pub struct CybVideoFrame {
     pub is_keyframe: bool,
     /// Sequential frame number
     pub frame_number: i64,
-    /// Pixel format (0=BGRA, 1=NV12, 2=YUV420P)
+    /// Pixel format (0=BGRA, 1=NV12, 2=YUV420P, 3=RGBA, 4=P010, 5=YUV422P10, 6=YUV444P16, 7=RGBA64, 8=RGB48)
     pub pixel_format: u8,
+    /// Number of planes in `data`
+    pub plane_count: u32,
+    /// Byte offset of each plane within `data` (unused entries are 0)
+    pub plane_offsets: [usize; 3],
+    /// Bytes per row of each plane (unused entries are 0)
+    pub plane_strides: [u32; 3],
+    /// Color description of the pixel data
+    pub color: CybColorInfo,
+    /// HDR metadata of the source frame
+    pub hdr: CybHdrMetadata,
+    /// Clockwise rotation still needed for upright display (0 once applied)
+    pub rotation: u32,
+    /// Whether the decoded frame was interlaced (still set after deinterlacing)
+    pub is_interlaced: bool,
+    /// Whether the top field is displayed first
+    pub top_field_first: bool,
+    /// SMPTE timecode (is_valid = false if the source carries none)
+    pub timecode: CybTimecode,
+    /// A/53 closed caption data, 3-byte cc_data triplets (null if none)
+    pub caption_data: *const u8,
+    /// Closed caption data size in bytes
+    pub caption_data_size: usize,
 }
 
 /// Opaque frame handle (owns the data)
@@ -535,20 +1157,33 @@ pub extern "C" fn cyb_decoder_get_frame_at(
     }
 }
 
-/// Get next frame in sequence
+/// Get frame at specific time, scaled to the given size (bypasses the frame cache)
 #[no_mangle]
-pub extern "C" fn cyb_decoder_get_next_frame(
+pub extern "C" fn cyb_decoder_get_frame_at_size(
     handle: *mut CybDecoderHandle,
+    time_us: i64,
+    tolerance_us: i64,
+    width: u32,
+    height: u32,
+    scale_mode: u8,
     out_frame: *mut *mut CybFrameHandle,
 ) -> CybResult {
     if handle.is_null() || out_frame.is_null() {
         return CybResult::ErrorInvalidHandle;
     }
 
+    let size = match output_size_from_ffi(width, height, scale_mode) {
+        Some(size) => size,
+        None => {
+            set_last_error("Output width and height are both 0");
+            return CybResult::ErrorUnknown;
+        }
+    };
+
     let handle = unsafe { &*handle };
-    let mut decoder = handle.decoder.lock();
+    let decoder = handle.decoder.lock();
 
-    match decoder.get_next_frame() {
+    match decoder.get_frame_at_size(time_us, tolerance_us, size) {
         Ok(Some(frame)) => {
             let frame_handle = Box::new(CybFrameHandle { frame });
             unsafe {
@@ -566,17 +1201,90 @@ pub extern "C" fn cyb_decoder_get_next_frame(
     }
 }
 
-/// Get frame data from handle
+/// Get frame at specific time, run through a libavfilter graph (bypasses the frame cache)
 #[no_mangle]
-pub extern "C" fn cyb_frame_get_data(
-    frame_handle: *const CybFrameHandle,
-    out_frame: *mut CybVideoFrame,
-) {
-    if frame_handle.is_null() || out_frame.is_null() {
-        return;
+pub extern "C" fn cyb_decoder_get_frame_with_filter(
+    handle: *mut CybDecoderHandle,
+    time_us: i64,
+    tolerance_us: i64,
+    filter: *const c_char,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
     }
 
-    let frame_handle = unsafe { &*frame_handle };
+    let filter = match optional_string(filter) {
+        Some(filter) => filter,
+        None => {
+            set_last_error("Filter is null or empty");
+            return CybResult::ErrorUnknown;
+        }
+    };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.get_frame_with_filter(time_us, tolerance_us, &filter) {
+        Ok(Some(frame)) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_frame = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get next frame in sequence
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_next_frame(
+    handle: *mut CybDecoderHandle,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let mut decoder = handle.decoder.lock();
+
+    match decoder.get_next_frame() {
+        Ok(Some(frame)) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_frame = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get frame data from handle
+#[no_mangle]
+pub extern "C" fn cyb_frame_get_data(
+    frame_handle: *const CybFrameHandle,
+    out_frame: *mut CybVideoFrame,
+) {
+    if frame_handle.is_null() || out_frame.is_null() {
+        return;
+    }
+
+    let frame_handle = unsafe { &*frame_handle };
     let frame = &frame_handle.frame;
 
     unsafe {
@@ -590,6 +1298,21 @@ pub extern "C" fn cyb_frame_get_data(
         (*out_frame).is_keyframe = frame.is_keyframe;
         (*out_frame).frame_number = frame.frame_number;
         (*out_frame).pixel_format = frame.pixel_format as u8;
+        let plane_count = frame.pixel_format.plane_count();
+        (*out_frame).plane_count = plane_count as u32;
+        for plane in 0..3 {
+            let used = plane < plane_count;
+            (*out_frame).plane_offsets[plane] = if used { frame.plane_offset(plane) } else { 0 };
+            (*out_frame).plane_strides[plane] = frame.plane_stride(plane);
+        }
+        (*out_frame).color = CybColorInfo::from(&frame.color);
+        (*out_frame).hdr = CybHdrMetadata::from(&frame.hdr);
+        (*out_frame).rotation = frame.rotation;
+        (*out_frame).is_interlaced = frame.is_interlaced;
+        (*out_frame).top_field_first = frame.top_field_first;
+        (*out_frame).timecode = frame.timecode.into();
+        (*out_frame).caption_data = data_ptr(&frame.caption_data);
+        (*out_frame).caption_data_size = frame.caption_data.len();
     }
 }
 
@@ -618,6 +1341,24 @@ pub struct CybVideoTrack {
     pub frame_rate: f64,
     pub bit_rate: i64,
     pub is_hardware_decodable: bool,
+    pub color: CybColorInfo,
+    pub hdr: CybHdrMetadata,
+    /// Clockwise rotation needed for upright display (0, 90, 180 or 270)
+    pub rotation: i32,
+    /// Sample aspect ratio (1:1 when unknown)
+    pub sar_num: i32,
+    pub sar_den: i32,
+    /// Dimensions after SAR correction and rotation
+    pub display_width: i32,
+    pub display_height: i32,
+    /// 0=unknown, 1=progressive, 2=top first, 3=bottom first,
+    /// 4=top coded/bottom displayed first, 5=bottom coded/top displayed first
+    pub field_order: u8,
+    /// Total number of frames
+    pub frame_count: i64,
+    /// Codec extradata (null if none)
+    pub extradata: *const u8,
+    pub extradata_size: usize,
 }
 
 /// Audio track info for FFI
@@ -628,7 +1369,69 @@ pub struct CybAudioTrack {
     pub codec_long_name: *const c_char,
     pub sample_rate: i32,
     pub channels: i32,
+    /// Channel layout description, e.g. "5.1(side)" (null if unknown)
+    pub channel_layout: *const c_char,
     pub bit_rate: i64,
+    /// Codec extradata (null if none)
+    pub extradata: *const u8,
+    pub extradata_size: usize,
+}
+
+/// Subtitle track info for FFI
+#[repr(C)]
+pub struct CybSubtitleTrack {
+    pub index: i32,
+    pub codec_name: *const c_char,
+    pub codec_long_name: *const c_char,
+    /// Language code (null if unknown)
+    pub language_code: *const c_char,
+    /// Track title (null if unknown)
+    pub title: *const c_char,
+    pub is_bitmap: bool,
+    pub is_default: bool,
+    pub is_forced: bool,
+    /// Codec extradata (null if none)
+    pub extradata: *const u8,
+    pub extradata_size: usize,
+}
+
+/// Chapter info for FFI
+#[repr(C)]
+pub struct CybChapter {
+    pub id: i64,
+    /// Chapter title (null if untitled)
+    pub title: *const c_char,
+    pub start_us: i64,
+    pub end_us: i64,
+    /// Start and end in time_base units
+    pub start: i64,
+    pub end: i64,
+    pub time_base_num: i32,
+    pub time_base_den: i32,
+}
+
+/// Attachment info for FFI
+#[repr(C)]
+pub struct CybAttachment {
+    /// Stream index
+    pub index: i32,
+    /// 0=Picture (cover art), 1=File
+    pub kind: u8,
+    pub codec_name: *const c_char,
+    /// File name (null if unknown)
+    pub filename: *const c_char,
+    /// MIME type (null if unknown)
+    pub mime_type: *const c_char,
+    /// Description (null if none)
+    pub title: *const c_char,
+    /// Picture type, e.g. "Cover (front)" (null if none)
+    pub picture_type: *const c_char,
+    /// Data size in bytes
+    pub size: usize,
+    /// Picture dimensions (0 for files)
+    pub width: i32,
+    pub height: i32,
+    pub is_font: bool,
 }
 
 /// Media info for FFI
@@ -638,6 +1441,11 @@ pub struct CybMediaInfo {
     pub container_format: *const c_char,
     pub video_track_count: i32,
     pub audio_track_count: i32,
+    pub subtitle_track_count: i32,
+    /// Start timecode (is_valid = false if the media has none)
+    pub start_timecode: CybTimecode,
+    pub chapter_count: i32,
+    pub attachment_count: i32,
 }
 
 /// Opaque media info handle
@@ -646,6 +1454,20 @@ pub struct CybMediaInfoHandle {
     container_format_cstr: CString,
     codec_names: Vec<CString>,
     codec_long_names: Vec<CString>,
+    audio_layouts: Vec<Option<CString>>,
+    subtitle_languages: Vec<Option<CString>>,
+    subtitle_titles: Vec<Option<CString>>,
+    chapter_titles: Vec<Option<CString>>,
+    attachment_strings: Vec<AttachmentStrings>,
+}
+
+/// C strings of an attachment
+struct AttachmentStrings {
+    codec_name: CString,
+    filename: Option<CString>,
+    mime_type: Option<CString>,
+    title: Option<CString>,
+    picture_type: Option<CString>,
 }
 
 /// Get media info
@@ -681,6 +1503,8 @@ pub extern "C" fn cyb_decoder_get_media_info(
                 );
             }
 
+            let mut audio_layouts = Vec::new();
+
             for track in &info.audio_tracks {
                 codec_names.push(
                     CString::new(track.codec.name.clone())
@@ -690,13 +1514,76 @@ pub extern "C" fn cyb_decoder_get_media_info(
                     CString::new(track.codec.long_name.clone())
                         .unwrap_or_else(|_| CString::new("").unwrap()),
                 );
+                audio_layouts.push(
+                    track
+                        .channel_layout
+                        .as_ref()
+                        .and_then(|s| CString::new(s.clone()).ok()),
+                );
+            }
+
+            let mut subtitle_languages = Vec::new();
+            let mut subtitle_titles = Vec::new();
+
+            for track in &info.subtitle_tracks {
+                codec_names.push(
+                    CString::new(track.codec.name.clone())
+                        .unwrap_or_else(|_| CString::new("").unwrap()),
+                );
+                codec_long_names.push(
+                    CString::new(track.codec.long_name.clone())
+                        .unwrap_or_else(|_| CString::new("").unwrap()),
+                );
+                subtitle_languages.push(
+                    track
+                        .language_code
+                        .as_ref()
+                        .and_then(|s| CString::new(s.clone()).ok()),
+                );
+                subtitle_titles.push(
+                    track
+                        .title
+                        .as_ref()
+                        .and_then(|s| CString::new(s.clone()).ok()),
+                );
             }
 
+            let chapter_titles = info
+                .chapters
+                .iter()
+                .map(|chapter| {
+                    chapter
+                        .title
+                        .as_ref()
+                        .and_then(|s| CString::new(s.clone()).ok())
+                })
+                .collect();
+
+            let to_cstring =
+                |s: &Option<String>| s.as_ref().and_then(|s| CString::new(s.clone()).ok());
+            let attachment_strings = info
+                .attachments
+                .iter()
+                .map(|attachment| AttachmentStrings {
+                    codec_name: CString::new(attachment.codec_name.clone())
+                        .unwrap_or_else(|_| CString::new("").unwrap()),
+                    filename: to_cstring(&attachment.filename),
+                    mime_type: to_cstring(&attachment.mime_type),
+                    title: to_cstring(&attachment.title),
+                    picture_type: to_cstring(&attachment.picture_type),
+                })
+                .collect();
+
             let info_handle = Box::new(CybMediaInfoHandle {
                 info,
                 container_format_cstr,
                 codec_names,
                 codec_long_names,
+                audio_layouts,
+                subtitle_languages,
+                subtitle_titles,
+                chapter_titles,
+                attachment_strings,
             });
 
             unsafe {
@@ -729,6 +1616,10 @@ pub extern "C" fn cyb_media_info_get_details(
         (*out_info).container_format = info_handle.container_format_cstr.as_ptr();
         (*out_info).video_track_count = info.video_tracks.len() as i32;
         (*out_info).audio_track_count = info.audio_tracks.len() as i32;
+        (*out_info).subtitle_track_count = info.subtitle_tracks.len() as i32;
+        (*out_info).start_timecode = info.start_timecode.into();
+        (*out_info).chapter_count = info.chapters.len() as i32;
+        (*out_info).attachment_count = info.attachments.len() as i32;
     }
 }
 
@@ -762,6 +1653,17 @@ pub extern "C" fn cyb_media_info_get_video_track(
         (*out_track).frame_rate = track.frame_rate;
         (*out_track).bit_rate = track.bit_rate;
         (*out_track).is_hardware_decodable = track.is_hardware_decodable;
+        (*out_track).color = CybColorInfo::from(&track.color);
+        (*out_track).hdr = CybHdrMetadata::from(&track.hdr);
+        (*out_track).rotation = track.rotation;
+        (*out_track).sar_num = track.sample_aspect_ratio.0;
+        (*out_track).sar_den = track.sample_aspect_ratio.1;
+        (*out_track).display_width = track.display_width;
+        (*out_track).display_height = track.display_height;
+        (*out_track).field_order = track.field_order as u8;
+        (*out_track).frame_count = track.frame_count;
+        (*out_track).extradata = data_ptr(&track.extradata);
+        (*out_track).extradata_size = track.extradata.len();
     }
 
     CybResult::Success
@@ -792,10 +1694,140 @@ pub extern "C" fn cyb_media_info_get_audio_track(
     unsafe {
         (*out_track).index = track.index;
         (*out_track).codec_name = info_handle.codec_names[offset + index as usize].as_ptr();
-        (*out_track).codec_long_name = info_handle.codec_long_names[offset + index as usize].as_ptr();
+        (*out_track).codec_long_name =
+            info_handle.codec_long_names[offset + index as usize].as_ptr();
         (*out_track).sample_rate = track.sample_rate;
         (*out_track).channels = track.channels;
+        (*out_track).channel_layout = info_handle.audio_layouts[index as usize]
+            .as_ref()
+            .map(|s| s.as_ptr())
+            .unwrap_or(ptr::null());
         (*out_track).bit_rate = track.bit_rate;
+        (*out_track).extradata = data_ptr(&track.extradata);
+        (*out_track).extradata_size = track.extradata.len();
+    }
+
+    CybResult::Success
+}
+
+/// Get subtitle track info
+#[no_mangle]
+pub extern "C" fn cyb_media_info_get_subtitle_track(
+    info_handle: *const CybMediaInfoHandle,
+    index: i32,
+    out_track: *mut CybSubtitleTrack,
+) -> CybResult {
+    if info_handle.is_null() || out_track.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let info_handle = unsafe { &*info_handle };
+    let info = &info_handle.info;
+
+    if index < 0 || index as usize >= info.subtitle_tracks.len() {
+        set_last_error("Subtitle track index out of bounds");
+        return CybResult::ErrorUnknown;
+    }
+
+    let track = &info.subtitle_tracks[index as usize];
+    let offset = info.video_tracks.len() + info.audio_tracks.len();
+
+    unsafe {
+        (*out_track).index = track.index;
+        (*out_track).codec_name = info_handle.codec_names[offset + index as usize].as_ptr();
+        (*out_track).codec_long_name =
+            info_handle.codec_long_names[offset + index as usize].as_ptr();
+        (*out_track).language_code = info_handle.subtitle_languages[index as usize]
+            .as_ref()
+            .map(|s| s.as_ptr())
+            .unwrap_or(ptr::null());
+        (*out_track).title = info_handle.subtitle_titles[index as usize]
+            .as_ref()
+            .map(|s| s.as_ptr())
+            .unwrap_or(ptr::null());
+        (*out_track).is_bitmap = track.is_bitmap;
+        (*out_track).is_default = track.is_default;
+        (*out_track).is_forced = track.is_forced;
+        (*out_track).extradata = data_ptr(&track.extradata);
+        (*out_track).extradata_size = track.extradata.len();
+    }
+
+    CybResult::Success
+}
+
+/// Get chapter info
+#[no_mangle]
+pub extern "C" fn cyb_media_info_get_chapter(
+    info_handle: *const CybMediaInfoHandle,
+    index: i32,
+    out_chapter: *mut CybChapter,
+) -> CybResult {
+    if info_handle.is_null() || out_chapter.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let info_handle = unsafe { &*info_handle };
+    let info = &info_handle.info;
+
+    if index < 0 || index as usize >= info.chapters.len() {
+        set_last_error("Chapter index out of bounds");
+        return CybResult::ErrorUnknown;
+    }
+
+    let chapter = &info.chapters[index as usize];
+
+    unsafe {
+        (*out_chapter).id = chapter.id;
+        (*out_chapter).title = info_handle.chapter_titles[index as usize]
+            .as_ref()
+            .map(|s| s.as_ptr())
+            .unwrap_or(ptr::null());
+        (*out_chapter).start_us = chapter.start_us;
+        (*out_chapter).end_us = chapter.end_us;
+        (*out_chapter).start = chapter.start;
+        (*out_chapter).end = chapter.end;
+        (*out_chapter).time_base_num = chapter.time_base.0;
+        (*out_chapter).time_base_den = chapter.time_base.1;
+    }
+
+    CybResult::Success
+}
+
+/// Get attachment info
+#[no_mangle]
+pub extern "C" fn cyb_media_info_get_attachment(
+    info_handle: *const CybMediaInfoHandle,
+    index: i32,
+    out_attachment: *mut CybAttachment,
+) -> CybResult {
+    if info_handle.is_null() || out_attachment.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let info_handle = unsafe { &*info_handle };
+    let info = &info_handle.info;
+
+    if index < 0 || index as usize >= info.attachments.len() {
+        set_last_error("Attachment index out of bounds");
+        return CybResult::ErrorUnknown;
+    }
+
+    let attachment = &info.attachments[index as usize];
+    let strings = &info_handle.attachment_strings[index as usize];
+    let as_ptr = |s: &Option<CString>| s.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null());
+
+    unsafe {
+        (*out_attachment).index = attachment.index;
+        (*out_attachment).kind = attachment.kind as u8;
+        (*out_attachment).codec_name = strings.codec_name.as_ptr();
+        (*out_attachment).filename = as_ptr(&strings.filename);
+        (*out_attachment).mime_type = as_ptr(&strings.mime_type);
+        (*out_attachment).title = as_ptr(&strings.title);
+        (*out_attachment).picture_type = as_ptr(&strings.picture_type);
+        (*out_attachment).size = attachment.size;
+        (*out_attachment).width = attachment.width;
+        (*out_attachment).height = attachment.height;
+        (*out_attachment).is_font = attachment.is_font();
     }
 
     CybResult::Success
@@ -832,8 +1864,14 @@ pub extern "C" fn cyb_decoder_is_decoding(handle: *const CybDecoderHandle) -> bo
 /// Audio frame data for FFI
 #[repr(C)]
 pub struct CybAudioFrame {
-    /// Raw sample data pointer (interleaved float32)
-    pub data: *const f32,
+    /// Raw sample data pointer (layout given by `sample_format` and `is_planar`)
+    pub data: *const u8,
+    /// Sample data size in bytes
+    pub data_size: usize,
+    /// 0=Float32, 1=Int16, 2=Int32
+    pub sample_format: u8,
+    /// Channels stored one after another instead of interleaved
+    pub is_planar: bool,
     /// Number of samples per channel
     pub sample_count: u32,
     /// Number of audio channels
@@ -899,6 +1937,9 @@ pub extern "C" fn cyb_audio_frame_get_data(
 
     unsafe {
         (*out_frame).data = frame.data_ptr();
+        (*out_frame).data_size = frame.data_size();
+        (*out_frame).sample_format = frame.sample_format() as u8;
+        (*out_frame).is_planar = frame.planar;
         (*out_frame).sample_count = frame.sample_count;
         (*out_frame).channels = frame.channels;
         (*out_frame).sample_rate = frame.sample_rate;
@@ -948,19 +1989,1804 @@ pub extern "C" fn cyb_decoder_get_audio_channels(handle: *const CybDecoderHandle
     handle.decoder.lock().audio_channels()
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// =============================================================================
+// Track Selection
+// =============================================================================
 
-    #[test]
-    fn test_null_handle() {
-        assert_eq!(cyb_decoder_prepare(ptr::null_mut()), CybResult::ErrorInvalidHandle);
-        assert_eq!(cyb_decoder_is_prepared(ptr::null()), false);
+/// Switch the active audio track (container stream index)
+#[no_mangle]
+pub extern "C" fn cyb_decoder_select_audio_track(
+    handle: *mut CybDecoderHandle,
+    stream_index: i32,
+) -> CybResult {
+    if handle.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    if stream_index < 0 {
+        set_last_error("Stream index must be non-negative");
+        return CybResult::ErrorUnknown;
     }
+    let handle = unsafe { &*handle };
+    handle
+        .decoder
+        .lock()
+        .select_audio_track(stream_index as usize)
+        .into()
+}
 
-    #[test]
-    fn test_version() {
-        let version = cyb_get_version();
-        assert!(!version.is_null());
+/// Replace the audio filter graph, e.g. "volume=0.5,atempo=1.25" (null = no filtering)
+#[no_mangle]
+pub extern "C" fn cyb_decoder_set_audio_filter(
+    handle: *mut CybDecoderHandle,
+    filter: *const c_char,
+) -> CybResult {
+    if handle.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    let filter = optional_string(filter);
+    let handle = unsafe { &*handle };
+    handle
+        .decoder
+        .lock()
+        .set_audio_filter(filter.as_deref())
+        .into()
+}
+
+/// Get the stream index of the active video track (-1 if none)
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_active_video_track(handle: *const CybDecoderHandle) -> i32 {
+    if handle.is_null() {
+        return -1;
+    }
+    let handle = unsafe { &*handle };
+    handle
+        .decoder
+        .lock()
+        .active_video_track()
+        .map(|i| i as i32)
+        .unwrap_or(-1)
+}
+
+/// Switch the active subtitle track (container stream index)
+#[no_mangle]
+pub extern "C" fn cyb_decoder_select_subtitle_track(
+    handle: *mut CybDecoderHandle,
+    stream_index: i32,
+) -> CybResult {
+    if handle.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    if stream_index < 0 {
+        set_last_error("Stream index must be non-negative");
+        return CybResult::ErrorUnknown;
+    }
+    let handle = unsafe { &*handle };
+    handle
+        .decoder
+        .lock()
+        .select_subtitle_track(stream_index as usize)
+        .into()
+}
+
+/// Get the stream index of the active subtitle track (-1 if none)
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_active_subtitle_track(handle: *const CybDecoderHandle) -> i32 {
+    if handle.is_null() {
+        return -1;
+    }
+    let handle = unsafe { &*handle };
+    handle
+        .decoder
+        .lock()
+        .active_subtitle_track()
+        .map(|i| i as i32)
+        .unwrap_or(-1)
+}
+
+/// Get the stream index of the active audio track (-1 if none)
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_active_audio_track(handle: *const CybDecoderHandle) -> i32 {
+    if handle.is_null() {
+        return -1;
+    }
+    let handle = unsafe { &*handle };
+    handle
+        .decoder
+        .lock()
+        .active_audio_track()
+        .map(|i| i as i32)
+        .unwrap_or(-1)
+}
+
+// =============================================================================
+// Attachments
+// =============================================================================
+
+/// Decode the cover art (front cover if tagged, else the first attached picture)
+///
+/// `out_frame` is set to null if the media has no attached picture.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_cover_art(
+    handle: *const CybDecoderHandle,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.get_cover_art() {
+        Ok(Some(frame)) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_frame = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Decode the attached picture of a stream
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_attached_picture(
+    handle: *const CybDecoderHandle,
+    stream_index: i32,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    if stream_index < 0 {
+        set_last_error("Stream index must be non-negative");
+        return CybResult::ErrorUnknown;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.get_attached_picture(stream_index as usize) {
+        Ok(frame) => {
+            let frame_handle = Box::new(CybFrameHandle { frame });
+            unsafe {
+                *out_frame = Box::into_raw(frame_handle);
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Attachment contents for FFI
+#[repr(C)]
+pub struct CybAttachmentData {
+    /// Raw bytes (the encoded image for pictures)
+    pub data: *const u8,
+    pub data_size: usize,
+    /// File name (null if unknown)
+    pub filename: *const c_char,
+    /// MIME type (null if unknown)
+    pub mime_type: *const c_char,
+}
+
+/// Opaque attachment handle (owns the data)
+pub struct CybAttachmentHandle {
+    attachment: AttachmentData,
+    filename: Option<CString>,
+    mime_type: Option<CString>,
+}
+
+/// Get the raw contents of an attachment or attached picture stream
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_attachment(
+    handle: *const CybDecoderHandle,
+    stream_index: i32,
+    out_attachment: *mut *mut CybAttachmentHandle,
+) -> CybResult {
+    if handle.is_null() || out_attachment.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    if stream_index < 0 {
+        set_last_error("Stream index must be non-negative");
+        return CybResult::ErrorUnknown;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.get_attachment(stream_index as usize) {
+        Ok(attachment) => {
+            let to_cstring =
+                |s: &Option<String>| s.as_ref().and_then(|s| CString::new(s.clone()).ok());
+            let attachment_handle = Box::new(CybAttachmentHandle {
+                filename: to_cstring(&attachment.filename),
+                mime_type: to_cstring(&attachment.mime_type),
+                attachment,
+            });
+            unsafe {
+                *out_attachment = Box::into_raw(attachment_handle);
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get attachment contents from handle
+#[no_mangle]
+pub extern "C" fn cyb_attachment_get_data(
+    attachment_handle: *const CybAttachmentHandle,
+    out_data: *mut CybAttachmentData,
+) {
+    if attachment_handle.is_null() || out_data.is_null() {
+        return;
+    }
+
+    let attachment_handle = unsafe { &*attachment_handle };
+    let data = &attachment_handle.attachment.data;
+
+    unsafe {
+        (*out_data).data = data.as_ptr();
+        (*out_data).data_size = data.len();
+        (*out_data).filename = attachment_handle
+            .filename
+            .as_ref()
+            .map(|s| s.as_ptr())
+            .unwrap_or(ptr::null());
+        (*out_data).mime_type = attachment_handle
+            .mime_type
+            .as_ref()
+            .map(|s| s.as_ptr())
+            .unwrap_or(ptr::null());
+    }
+}
+
+/// Release attachment handle
+#[no_mangle]
+pub extern "C" fn cyb_attachment_release(attachment_handle: *mut CybAttachmentHandle) {
+    if !attachment_handle.is_null() {
+        unsafe {
+            drop(Box::from_raw(attachment_handle));
+        }
+    }
+}
+
+// =============================================================================
+// Subtitles
+// =============================================================================
+
+/// Subtitle event data for FFI
+#[repr(C)]
+pub struct CybSubtitleEvent {
+    /// Container stream index
+    pub stream_index: i32,
+    /// Start time in microseconds
+    pub start_us: i64,
+    /// End time in microseconds (INT64_MAX if not known yet)
+    pub end_us: i64,
+    /// Payload kind (0=Text, 1=ASS, 2=Bitmap)
+    pub kind: u8,
+    /// Plain text (UTF-8, empty for bitmaps)
+    pub text: *const c_char,
+    /// Raw ASS dialogue line (null unless kind is ASS)
+    pub ass: *const c_char,
+    /// Bitmap left position
+    pub bitmap_x: i32,
+    /// Bitmap top position
+    pub bitmap_y: i32,
+    /// Bitmap width (0 if no bitmap)
+    pub bitmap_width: u32,
+    /// Bitmap height (0 if no bitmap)
+    pub bitmap_height: u32,
+    /// Bitmap stride in bytes
+    pub bitmap_stride: u32,
+    /// RGBA bitmap data (null if no bitmap)
+    pub bitmap_data: *const u8,
+    /// Bitmap data size in bytes
+    pub bitmap_data_size: usize,
+}
+
+/// Opaque subtitle event list handle (owns the data)
+pub struct CybSubtitleListHandle {
+    events: Vec<SubtitleEvent>,
+    texts: Vec<CString>,
+    asses: Vec<Option<CString>>,
+}
+
+impl CybSubtitleListHandle {
+    fn new(events: Vec<SubtitleEvent>) -> Self {
+        let texts = events
+            .iter()
+            .map(|e| {
+                CString::new(e.text.replace('\0', "")).unwrap_or_else(|_| CString::new("").unwrap())
+            })
+            .collect();
+        let asses = events
+            .iter()
+            .map(|e| e.ass.as_ref().and_then(|a| CString::new(a.clone()).ok()))
+            .collect();
+
+        Self {
+            events,
+            texts,
+            asses,
+        }
+    }
+}
+
+/// Get subtitle events active at a specific time
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_subtitles_at(
+    handle: *const CybDecoderHandle,
+    time_us: i64,
+    out_list: *mut *mut CybSubtitleListHandle,
+) -> CybResult {
+    if handle.is_null() || out_list.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.subtitles_at(time_us) {
+        Ok(events) => {
+            let list = Box::new(CybSubtitleListHandle::new(events));
+            unsafe {
+                *out_list = Box::into_raw(list);
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Decode the whole active subtitle track up front
+#[no_mangle]
+pub extern "C" fn cyb_decoder_load_subtitles(handle: *mut CybDecoderHandle) -> CybResult {
+    if handle.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    let handle = unsafe { &*handle };
+    handle.decoder.lock().load_subtitles().into()
+}
+
+/// Get number of events in a subtitle list
+#[no_mangle]
+pub extern "C" fn cyb_subtitle_list_get_count(list: *const CybSubtitleListHandle) -> i32 {
+    if list.is_null() {
+        return 0;
+    }
+    let list = unsafe { &*list };
+    list.events.len() as i32
+}
+
+/// Get subtitle event from a list
+#[no_mangle]
+pub extern "C" fn cyb_subtitle_list_get_event(
+    list: *const CybSubtitleListHandle,
+    index: i32,
+    out_event: *mut CybSubtitleEvent,
+) -> CybResult {
+    if list.is_null() || out_event.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let list = unsafe { &*list };
+
+    if index < 0 || index as usize >= list.events.len() {
+        set_last_error("Subtitle event index out of bounds");
+        return CybResult::ErrorUnknown;
+    }
+
+    let event = &list.events[index as usize];
+    let bitmap = event.bitmap.as_ref();
+
+    unsafe {
+        (*out_event).stream_index = event.stream_index;
+        (*out_event).start_us = event.start_us;
+        (*out_event).end_us = event.end_us;
+        (*out_event).kind = event.kind as u8;
+        (*out_event).text = list.texts[index as usize].as_ptr();
+        (*out_event).ass = list.asses[index as usize]
+            .as_ref()
+            .map(|s| s.as_ptr())
+            .unwrap_or(ptr::null());
+        (*out_event).bitmap_x = bitmap.map(|b| b.x).unwrap_or(0);
+        (*out_event).bitmap_y = bitmap.map(|b| b.y).unwrap_or(0);
+        (*out_event).bitmap_width = bitmap.map(|b| b.width).unwrap_or(0);
+        (*out_event).bitmap_height = bitmap.map(|b| b.height).unwrap_or(0);
+        (*out_event).bitmap_stride = bitmap.map(|b| b.stride()).unwrap_or(0);
+        (*out_event).bitmap_data = bitmap.map(|b| b.data.as_ptr()).unwrap_or(ptr::null());
+        (*out_event).bitmap_data_size = bitmap.map(|b| b.data.len()).unwrap_or(0);
+    }
+
+    CybResult::Success
+}
+
+/// Release subtitle list handle
+#[no_mangle]
+pub extern "C" fn cyb_subtitle_list_release(list: *mut CybSubtitleListHandle) {
+    if !list.is_null() {
+        unsafe {
+            drop(Box::from_raw(list));
+        }
+    }
+}
+
+// =============================================================================
+// Packets
+// =============================================================================
+
+/// Compressed packet for FFI
+#[repr(C)]
+pub struct CybPacket {
+    pub stream_index: i32,
+    /// Timestamps in stream time base units (valid if has_pts / has_dts)
+    pub pts: i64,
+    pub dts: i64,
+    pub has_pts: bool,
+    pub has_dts: bool,
+    /// Duration in time base units (0 if unknown)
+    pub duration: i64,
+    pub time_base_num: i32,
+    pub time_base_den: i32,
+    /// Timestamps and duration in microseconds
+    pub pts_us: i64,
+    pub dts_us: i64,
+    pub duration_us: i64,
+    pub is_keyframe: bool,
+    pub is_corrupt: bool,
+    /// Byte position in the file (-1 if unknown)
+    pub position: i64,
+    /// Packet payload
+    pub data: *const u8,
+    pub data_size: usize,
+}
+
+/// Opaque packet handle (owns the data)
+pub struct CybPacketHandle {
+    packet: Packet,
+}
+
+/// Read the next compressed packet of any stream, without decoding
+///
+/// `out_packet` is set to null at the end of the input. Packets read this way
+/// are not decoded; seek before resuming playback.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_read_packet(
+    handle: *mut CybDecoderHandle,
+    out_packet: *mut *mut CybPacketHandle,
+) -> CybResult {
+    if handle.is_null() || out_packet.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.read_packet() {
+        Ok(Some(packet)) => {
+            let packet_handle = Box::new(CybPacketHandle { packet });
+            unsafe {
+                *out_packet = Box::into_raw(packet_handle);
+            }
+            CybResult::Success
+        }
+        Ok(None) => {
+            unsafe {
+                *out_packet = ptr::null_mut();
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get packet data from handle
+#[no_mangle]
+pub extern "C" fn cyb_packet_get_data(
+    packet_handle: *const CybPacketHandle,
+    out_packet: *mut CybPacket,
+) {
+    if packet_handle.is_null() || out_packet.is_null() {
+        return;
+    }
+
+    let packet = unsafe { &(*packet_handle).packet };
+
+    unsafe {
+        (*out_packet).stream_index = packet.stream_index;
+        (*out_packet).pts = packet.pts.unwrap_or(0);
+        (*out_packet).dts = packet.dts.unwrap_or(0);
+        (*out_packet).has_pts = packet.pts.is_some();
+        (*out_packet).has_dts = packet.dts.is_some();
+        (*out_packet).duration = packet.duration;
+        (*out_packet).time_base_num = packet.time_base.0;
+        (*out_packet).time_base_den = packet.time_base.1;
+        (*out_packet).pts_us = packet.pts_us.unwrap_or(0);
+        (*out_packet).dts_us = packet.dts_us.unwrap_or(0);
+        (*out_packet).duration_us = packet.duration_us;
+        (*out_packet).is_keyframe = packet.is_keyframe;
+        (*out_packet).is_corrupt = packet.is_corrupt;
+        (*out_packet).position = packet.position.unwrap_or(-1);
+        (*out_packet).data = data_ptr(&packet.data);
+        (*out_packet).data_size = packet.data.len();
+    }
+}
+
+/// Release packet handle
+#[no_mangle]
+pub extern "C" fn cyb_packet_release(packet_handle: *mut CybPacketHandle) {
+    if !packet_handle.is_null() {
+        unsafe {
+            drop(Box::from_raw(packet_handle));
+        }
+    }
+}
+
+// =============================================================================
+// Export
+// =============================================================================
+
+/// Lossless export options for FFI
+#[repr(C)]
+pub struct CybExportOptions {
+    /// In point in microseconds (snaps back to the preceding video keyframe)
+    pub start_us: i64,
+    /// Out point in microseconds (negative = end of media)
+    pub end_us: i64,
+    /// Stream indices to copy (null or 0 count = all supported tracks)
+    pub tracks: *const i32,
+    pub track_count: usize,
+    /// Container: 0 = MP4, 1 = MOV, 2 = MKV, negative = from the file extension
+    pub format: i32,
+    pub copy_metadata: bool,
+    pub copy_chapters: bool,
+}
+
+/// Result of a finished export
+#[repr(C)]
+pub struct CybExportSummary {
+    /// Exported range in source time, after keyframe snapping
+    pub start_us: i64,
+    pub end_us: i64,
+    pub track_count: i32,
+    pub packet_count: u64,
+    pub byte_count: u64,
+}
+
+/// Export progress callback: receives the completed fraction (0.0-1.0) and
+/// returns false to cancel
+pub type CybExportProgressFn = extern "C" fn(user_data: *mut c_void, fraction: f64) -> bool;
+
+/// Export a time range without re-encoding (stream copy) to MP4, MOV or MKV
+///
+/// Blocks until the export finishes, and holds the decoder handle meanwhile:
+/// export from a separate handle to keep playing. `progress` is called on the
+/// calling thread; returning false cancels with CYB_RESULT_ERROR_CANCELLED and
+/// removes the partial file.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_export(
+    handle: *mut CybDecoderHandle,
+    path: *const c_char,
+    options: *const CybExportOptions,
+    progress: Option<CybExportProgressFn>,
+    user_data: *mut c_void,
+    out_summary: *mut CybExportSummary,
+) -> CybResult {
+    if handle.is_null() || path.is_null() || options.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => path,
+        Err(_) => {
+            set_last_error("Invalid UTF-8 in path");
+            return CybResult::ErrorInvalidFormat;
+        }
+    };
+
+    let c = unsafe { &*options };
+    let tracks = if c.tracks.is_null() || c.track_count == 0 {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(c.tracks, c.track_count) }
+            .iter()
+            .map(|&index| index.max(0) as usize)
+            .collect()
+    };
+    let options = ExportOptions {
+        start_us: c.start_us,
+        end_us: (c.end_us >= 0).then_some(c.end_us),
+        tracks,
+        format: match c.format {
+            0 => Some(ExportFormat::Mp4),
+            1 => Some(ExportFormat::Mov),
+            2 => Some(ExportFormat::Mkv),
+            _ => None,
+        },
+        copy_metadata: c.copy_metadata,
+        copy_chapters: c.copy_chapters,
+    };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    let result = decoder.export(path, &options, |fraction| {
+        progress.map_or(true, |callback| callback(user_data, fraction))
+    });
+    match result {
+        Ok(summary) => {
+            if !out_summary.is_null() {
+                unsafe {
+                    (*out_summary).start_us = summary.start_us;
+                    (*out_summary).end_us = summary.end_us;
+                    (*out_summary).track_count = summary.tracks.len() as i32;
+                    (*out_summary).packet_count = summary.packet_count;
+                    (*out_summary).byte_count = summary.byte_count;
+                }
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+// =============================================================================
+// Closed Captions
+// =============================================================================
+
+/// Get closed caption events of a channel active at a specific time
+///
+/// `channel` is 1-4 for CEA-608 CC1-CC4 and 101-163 for CEA-708 services 1-63.
+/// Events are returned as a subtitle list (kind Text).
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_captions_at(
+    handle: *const CybDecoderHandle,
+    channel: i32,
+    time_us: i64,
+    out_list: *mut *mut CybSubtitleListHandle,
+) -> CybResult {
+    if handle.is_null() || out_list.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    let channel = match CaptionChannel::from_id(channel) {
+        Some(channel) => channel,
+        None => {
+            set_last_error("Invalid caption channel");
+            return CybResult::ErrorUnknown;
+        }
+    };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.captions_at(channel, time_us) {
+        Ok(events) => {
+            let list = Box::new(CybSubtitleListHandle::new(events));
+            unsafe {
+                *out_list = Box::into_raw(list);
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get the caption channels that produced events so far
+///
+/// Writes up to `max_count` channel ids to `out_channels` and returns the
+/// total number of channels.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_caption_channels(
+    handle: *const CybDecoderHandle,
+    out_channels: *mut i32,
+    max_count: i32,
+) -> i32 {
+    if handle.is_null() {
+        return 0;
+    }
+
+    let handle = unsafe { &*handle };
+    let channels = handle.decoder.lock().caption_channels();
+
+    if !out_channels.is_null() {
+        for (i, channel) in channels.iter().take(max_count.max(0) as usize).enumerate() {
+            unsafe {
+                *out_channels.add(i) = channel.id();
+            }
+        }
+    }
+    channels.len() as i32
+}
+
+// =============================================================================
+// Thumbnails
+// =============================================================================
+
+/// Thumbnail strip options for FFI
+#[repr(C)]
+pub struct CybThumbnailOptions {
+    /// Range in microseconds (negative end = end of media)
+    pub start_us: i64,
+    pub end_us: i64,
+    /// Number of evenly spaced thumbnails (ignored when interval_us > 0)
+    pub count: u32,
+    /// Distance between thumbnails in microseconds (0 = derived from count)
+    pub interval_us: i64,
+    /// Thumbnail size (0 on one axis = keep aspect ratio)
+    pub width: u32,
+    pub height: u32,
+    pub scale_mode: u8, // 0=Fit, 1=Fill, 2=Stretch
+    /// Decode the exact frame at each time instead of the nearest keyframe
+    pub exact: bool,
+}
+
+impl CybThumbnailOptions {
+    fn to_options(&self) -> Option<ThumbnailOptions> {
+        Some(ThumbnailOptions {
+            start_us: self.start_us,
+            end_us: (self.end_us >= 0).then_some(self.end_us),
+            count: self.count,
+            interval_us: (self.interval_us > 0).then_some(self.interval_us),
+            size: output_size_from_ffi(self.width, self.height, self.scale_mode)?,
+            exact: self.exact,
+        })
+    }
+}
+
+/// Thumbnail callback: receives the thumbnail's position, requested time and
+/// frame (owned by the callee, release with cyb_frame_release); returns false
+/// to cancel
+pub type CybThumbnailFn = extern "C" fn(
+    user_data: *mut c_void,
+    index: i32,
+    time_us: i64,
+    frame: *mut CybFrameHandle,
+) -> bool;
+
+/// Generate evenly spaced thumbnails, streaming each one to `callback` as it
+/// completes (bypasses the frame cache and keeps the playhead)
+///
+/// Blocks until done and holds the decoder handle meanwhile. `callback` is
+/// called on the calling thread; returning false cancels with
+/// CYB_RESULT_ERROR_CANCELLED. `out_count` (optional) receives the number of
+/// thumbnails delivered.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_generate_thumbnails(
+    handle: *mut CybDecoderHandle,
+    options: *const CybThumbnailOptions,
+    callback: Option<CybThumbnailFn>,
+    user_data: *mut c_void,
+    out_count: *mut i32,
+) -> CybResult {
+    if handle.is_null() || options.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    let callback = match callback {
+        Some(callback) => callback,
+        None => {
+            set_last_error("Thumbnail callback is null");
+            return CybResult::ErrorUnknown;
+        }
+    };
+    let options = match unsafe { &*options }.to_options() {
+        Some(options) => options,
+        None => {
+            set_last_error("Thumbnail width and height are both 0");
+            return CybResult::ErrorUnknown;
+        }
+    };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    let result = decoder.thumbnails(&options, |thumbnail| {
+        let index = thumbnail.index as i32;
+        let frame = Box::into_raw(Box::new(CybFrameHandle {
+            frame: thumbnail.frame,
+        }));
+        callback(user_data, index, thumbnail.time_us, frame)
+    });
+    match result {
+        Ok(count) => {
+            if !out_count.is_null() {
+                unsafe {
+                    *out_count = count as i32;
+                }
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Generate thumbnails and composite them into one contact sheet image
+///
+/// `columns` = 0 picks a near-square grid; `spacing` is the gap in pixels.
+/// The sheet is packed RGB (the configured format, or BGRA).
+#[no_mangle]
+pub extern "C" fn cyb_decoder_get_contact_sheet(
+    handle: *mut CybDecoderHandle,
+    options: *const CybThumbnailOptions,
+    columns: u32,
+    spacing: u32,
+    out_frame: *mut *mut CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || options.is_null() || out_frame.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+    let options = match unsafe { &*options }.to_options() {
+        Some(options) => options,
+        None => {
+            set_last_error("Thumbnail width and height are both 0");
+            return CybResult::ErrorUnknown;
+        }
+    };
+    let layout = ContactSheetLayout { columns, spacing };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    match decoder.contact_sheet(&options, &layout) {
+        Ok(frame) => {
+            unsafe {
+                *out_frame = Box::into_raw(Box::new(CybFrameHandle { frame }));
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+// =============================================================================
+// Waveforms
+// =============================================================================
+
+/// Waveform options for FFI
+#[repr(C)]
+pub struct CybWaveformOptions {
+    /// Range in microseconds (negative end = end of media)
+    pub start_us: i64,
+    pub end_us: i64,
+    /// Samples per channel summarized by one bucket
+    pub samples_per_bucket: u32,
+    /// Audio stream index (negative = active audio track)
+    pub track: i32,
+}
+
+/// Level summary of one bucket of samples (-1.0 to 1.0)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CybWaveformBucket {
+    pub min: f32,
+    pub max: f32,
+    pub rms: f32,
+}
+
+impl From<&WaveformBucket> for CybWaveformBucket {
+    fn from(bucket: &WaveformBucket) -> Self {
+        Self {
+            min: bucket.min,
+            max: bucket.max,
+            rms: bucket.rms,
+        }
+    }
+}
+
+/// Waveform description for FFI
+#[repr(C)]
+pub struct CybWaveformInfo {
+    pub sample_rate: u32,
+    pub channels: u32,
+    pub samples_per_bucket: u32,
+    pub bucket_count: i32,
+    /// Time of the first bucket
+    pub start_us: i64,
+    pub bucket_duration_us: f64,
+}
+
+/// Opaque waveform handle (owns the buckets)
+pub struct CybWaveformHandle {
+    waveform: Waveform,
+}
+
+/// Waveform progress callback: receives the buckets completed since the
+/// previous call, starting at `first_bucket` (bucket after bucket, channels
+/// interleaved, `bucket_count * channels` entries valid during the call), and
+/// the completed fraction (0.0-1.0); returns false to cancel
+pub type CybWaveformProgressFn = extern "C" fn(
+    user_data: *mut c_void,
+    first_bucket: i32,
+    bucket_count: i32,
+    channels: u32,
+    buckets: *const CybWaveformBucket,
+    fraction: f64,
+) -> bool;
+
+/// Compute per-channel min/max/RMS waveform buckets of an audio track
+///
+/// Blocks until done and holds the decoder handle meanwhile. `progress`
+/// (optional) streams partial results on the calling thread; returning false
+/// cancels with CYB_RESULT_ERROR_CANCELLED. Release the result with
+/// cyb_waveform_release.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_generate_waveform(
+    handle: *mut CybDecoderHandle,
+    options: *const CybWaveformOptions,
+    progress: Option<CybWaveformProgressFn>,
+    user_data: *mut c_void,
+    out_waveform: *mut *mut CybWaveformHandle,
+) -> CybResult {
+    if handle.is_null() || options.is_null() || out_waveform.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let c = unsafe { &*options };
+    let options = WaveformOptions {
+        start_us: c.start_us,
+        end_us: (c.end_us >= 0).then_some(c.end_us),
+        samples_per_bucket: c.samples_per_bucket.max(1),
+        track: (c.track >= 0).then_some(c.track as usize),
+    };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    let mut reported = 0;
+    let result = decoder.waveform(&options, |waveform, fraction| {
+        let callback = match progress {
+            Some(callback) => callback,
+            None => return true,
+        };
+        let count = waveform.bucket_count();
+        let buckets: Vec<CybWaveformBucket> = (reported..count)
+            .flat_map(|bucket| waveform.channels.iter().map(move |c| (&c[bucket]).into()))
+            .collect();
+        let first = reported as i32;
+        reported = count;
+        callback(
+            user_data,
+            first,
+            (count as i32) - first,
+            waveform.channel_count() as u32,
+            buckets.as_ptr(),
+            fraction,
+        )
+    });
+    match result {
+        Ok(waveform) => {
+            unsafe {
+                *out_waveform = Box::into_raw(Box::new(CybWaveformHandle { waveform }));
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get the description of a waveform
+#[no_mangle]
+pub extern "C" fn cyb_waveform_get_info(
+    waveform_handle: *const CybWaveformHandle,
+    out_info: *mut CybWaveformInfo,
+) -> CybResult {
+    if waveform_handle.is_null() || out_info.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let waveform = unsafe { &(*waveform_handle).waveform };
+    unsafe {
+        *out_info = CybWaveformInfo {
+            sample_rate: waveform.sample_rate,
+            channels: waveform.channel_count() as u32,
+            samples_per_bucket: waveform.samples_per_bucket,
+            bucket_count: waveform.bucket_count() as i32,
+            start_us: waveform.start_us,
+            bucket_duration_us: waveform.bucket_duration_us(),
+        };
+    }
+    CybResult::Success
+}
+
+/// Copy up to `max_count` buckets of a channel, starting at `first_bucket`
+///
+/// Returns the number of buckets copied.
+#[no_mangle]
+pub extern "C" fn cyb_waveform_get_buckets(
+    waveform_handle: *const CybWaveformHandle,
+    channel: i32,
+    first_bucket: i32,
+    out_buckets: *mut CybWaveformBucket,
+    max_count: i32,
+) -> i32 {
+    if waveform_handle.is_null() || out_buckets.is_null() || channel < 0 || first_bucket < 0 {
+        return 0;
+    }
+
+    let waveform = unsafe { &(*waveform_handle).waveform };
+    let buckets = match waveform.channels.get(channel as usize) {
+        Some(buckets) => buckets,
+        None => return 0,
+    };
+    let first = (first_bucket as usize).min(buckets.len());
+    let count = (buckets.len() - first).min(max_count.max(0) as usize);
+
+    for (i, bucket) in buckets[first..first + count].iter().enumerate() {
+        unsafe {
+            *out_buckets.add(i) = bucket.into();
+        }
+    }
+    count as i32
+}
+
+/// Save a waveform to a file (compact binary format)
+#[no_mangle]
+pub extern "C" fn cyb_waveform_save(
+    waveform_handle: *const CybWaveformHandle,
+    path: *const c_char,
+) -> CybResult {
+    if waveform_handle.is_null() || path.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => path,
+        Err(_) => {
+            set_last_error("Invalid UTF-8 in path");
+            return CybResult::ErrorInvalidFormat;
+        }
+    };
+
+    let waveform = unsafe { &(*waveform_handle).waveform };
+    waveform.save(path).into()
+}
+
+/// Load a waveform saved with cyb_waveform_save
+#[no_mangle]
+pub extern "C" fn cyb_waveform_load(
+    path: *const c_char,
+    out_waveform: *mut *mut CybWaveformHandle,
+) -> CybResult {
+    if path.is_null() || out_waveform.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => path,
+        Err(_) => {
+            set_last_error("Invalid UTF-8 in path");
+            return CybResult::ErrorInvalidFormat;
+        }
+    };
+
+    match Waveform::load(path) {
+        Ok(waveform) => {
+            unsafe {
+                *out_waveform = Box::into_raw(Box::new(CybWaveformHandle { waveform }));
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Release waveform
+#[no_mangle]
+pub extern "C" fn cyb_waveform_release(waveform_handle: *mut CybWaveformHandle) {
+    if !waveform_handle.is_null() {
+        unsafe {
+            drop(Box::from_raw(waveform_handle));
+        }
+    }
+}
+
+// =============================================================================
+// Loudness
+// =============================================================================
+
+/// Loudness measurement options for FFI
+#[repr(C)]
+pub struct CybLoudnessOptions {
+    /// Range in microseconds (negative end = end of media)
+    pub start_us: i64,
+    pub end_us: i64,
+    /// Audio stream index (negative = active audio track)
+    pub track: i32,
+}
+
+/// Loudness of a whole range (-INFINITY for silence)
+#[repr(C)]
+pub struct CybLoudnessSummary {
+    pub integrated_lufs: f64,
+    pub loudness_range_lu: f64,
+    pub true_peak_dbtp: f64,
+    pub sample_peak_dbfs: f64,
+    pub max_momentary_lufs: f64,
+    pub max_short_term_lufs: f64,
+    /// Number of points in the time series
+    pub point_count: i32,
+}
+
+/// Momentary and short-term loudness at one point in time
+/// (-INFINITY for silence or a window not yet filled)
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CybLoudnessPoint {
+    pub time_us: i64,
+    pub momentary_lufs: f64,
+    pub short_term_lufs: f64,
+}
+
+impl From<&LoudnessPoint> for CybLoudnessPoint {
+    fn from(point: &LoudnessPoint) -> Self {
+        Self {
+            time_us: point.time_us,
+            momentary_lufs: point.momentary_lufs,
+            short_term_lufs: point.short_term_lufs,
+        }
+    }
+}
+
+/// Opaque loudness report handle (owns the time series)
+pub struct CybLoudnessHandle {
+    report: LoudnessReport,
+}
+
+/// Loudness progress callback: receives the points measured since the
+/// previous call, starting at `first_point` (valid during the call), and the
+/// completed fraction (0.0-1.0); returns false to cancel
+pub type CybLoudnessProgressFn = extern "C" fn(
+    user_data: *mut c_void,
+    first_point: i32,
+    point_count: i32,
+    points: *const CybLoudnessPoint,
+    fraction: f64,
+) -> bool;
+
+/// Measure the loudness (EBU R128) of an audio track
+///
+/// Blocks until done and holds the decoder handle meanwhile. `progress`
+/// (optional) streams the time series on the calling thread; returning false
+/// cancels with CYB_RESULT_ERROR_CANCELLED. Release the result with
+/// cyb_loudness_release.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_measure_loudness(
+    handle: *mut CybDecoderHandle,
+    options: *const CybLoudnessOptions,
+    progress: Option<CybLoudnessProgressFn>,
+    user_data: *mut c_void,
+    out_report: *mut *mut CybLoudnessHandle,
+) -> CybResult {
+    if handle.is_null() || options.is_null() || out_report.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let c = unsafe { &*options };
+    let options = LoudnessOptions {
+        start_us: c.start_us,
+        end_us: (c.end_us >= 0).then_some(c.end_us),
+        track: (c.track >= 0).then_some(c.track as usize),
+    };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    let mut reported = 0;
+    let result = decoder.loudness(&options, |series, fraction| {
+        let callback = match progress {
+            Some(callback) => callback,
+            None => return true,
+        };
+        let points: Vec<CybLoudnessPoint> = series[reported..].iter().map(Into::into).collect();
+        let first = reported as i32;
+        reported = series.len();
+        callback(
+            user_data,
+            first,
+            points.len() as i32,
+            points.as_ptr(),
+            fraction,
+        )
+    });
+    match result {
+        Ok(report) => {
+            unsafe {
+                *out_report = Box::into_raw(Box::new(CybLoudnessHandle { report }));
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get the summary of a loudness report
+#[no_mangle]
+pub extern "C" fn cyb_loudness_get_summary(
+    report_handle: *const CybLoudnessHandle,
+    out_summary: *mut CybLoudnessSummary,
+) -> CybResult {
+    if report_handle.is_null() || out_summary.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let report = unsafe { &(*report_handle).report };
+    let summary = &report.summary;
+    unsafe {
+        *out_summary = CybLoudnessSummary {
+            integrated_lufs: summary.integrated_lufs,
+            loudness_range_lu: summary.loudness_range_lu,
+            true_peak_dbtp: summary.true_peak_dbtp,
+            sample_peak_dbfs: summary.sample_peak_dbfs,
+            max_momentary_lufs: summary.max_momentary_lufs,
+            max_short_term_lufs: summary.max_short_term_lufs,
+            point_count: report.series.len() as i32,
+        };
+    }
+    CybResult::Success
+}
+
+/// Copy up to `max_count` points of the time series, starting at `first_point`
+///
+/// Returns the number of points copied.
+#[no_mangle]
+pub extern "C" fn cyb_loudness_get_points(
+    report_handle: *const CybLoudnessHandle,
+    first_point: i32,
+    out_points: *mut CybLoudnessPoint,
+    max_count: i32,
+) -> i32 {
+    if report_handle.is_null() || out_points.is_null() || first_point < 0 {
+        return 0;
+    }
+
+    let series = unsafe { &(*report_handle).report.series };
+    let first = (first_point as usize).min(series.len());
+    let count = (series.len() - first).min(max_count.max(0) as usize);
+
+    for (i, point) in series[first..first + count].iter().enumerate() {
+        unsafe {
+            *out_points.add(i) = point.into();
+        }
+    }
+    count as i32
+}
+
+/// Release loudness report
+#[no_mangle]
+pub extern "C" fn cyb_loudness_release(report_handle: *mut CybLoudnessHandle) {
+    if !report_handle.is_null() {
+        unsafe {
+            drop(Box::from_raw(report_handle));
+        }
+    }
+}
+
+// =============================================================================
+// Scene Detection
+// =============================================================================
+
+/// Scene detection options for FFI
+#[repr(C)]
+pub struct CybSceneDetectionOptions {
+    /// Range in microseconds (negative end = end of media)
+    pub start_us: i64,
+    pub end_us: i64,
+    /// 0 = histogram, 1 = luma difference
+    pub method: u8,
+    /// Difference (0.0-1.0) from which two frames are a cut
+    pub threshold: f64,
+    /// Shortest shot in microseconds
+    pub min_shot_us: i64,
+    /// Analysis size (0x0 = decoded size)
+    pub width: u32,
+    pub height: u32,
+    /// 0 = fit, 1 = fill, 2 = stretch
+    pub scale_mode: u8,
+}
+
+/// A detected cut for FFI
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CybSceneCut {
+    /// Time of the first frame of the new shot (its pts_us)
+    pub time_us: i64,
+    pub frame_number: i64,
+    pub score: f64,
+    pub confidence: f64,
+}
+
+impl From<&SceneCut> for CybSceneCut {
+    fn from(cut: &SceneCut) -> Self {
+        Self {
+            time_us: cut.time_us,
+            frame_number: cut.frame_number,
+            score: cut.score,
+            confidence: cut.confidence,
+        }
+    }
+}
+
+/// Opaque scene cut list handle (owns the data)
+pub struct CybSceneListHandle {
+    cuts: Vec<SceneCut>,
+}
+
+/// Scene detection progress callback: receives the cuts found since the
+/// previous call, starting at `first_cut` (valid during the call), and the
+/// completed fraction (0.0-1.0); returns false to cancel
+pub type CybSceneProgressFn = extern "C" fn(
+    user_data: *mut c_void,
+    first_cut: i32,
+    cut_count: i32,
+    cuts: *const CybSceneCut,
+    fraction: f64,
+) -> bool;
+
+/// Find the cuts between shots of the active video track
+///
+/// Blocks until done and holds the decoder handle meanwhile. `progress`
+/// (optional) streams the cuts on the calling thread; returning false
+/// cancels with CYB_RESULT_ERROR_CANCELLED. Release the result with
+/// cyb_scene_list_release.
+#[no_mangle]
+pub extern "C" fn cyb_decoder_detect_scenes(
+    handle: *mut CybDecoderHandle,
+    options: *const CybSceneDetectionOptions,
+    progress: Option<CybSceneProgressFn>,
+    user_data: *mut c_void,
+    out_list: *mut *mut CybSceneListHandle,
+) -> CybResult {
+    if handle.is_null() || options.is_null() || out_list.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let c = unsafe { &*options };
+    let options = SceneDetectionOptions {
+        start_us: c.start_us,
+        end_us: (c.end_us >= 0).then_some(c.end_us),
+        method: match c.method {
+            1 => SceneDetectionMethod::Difference,
+            _ => SceneDetectionMethod::Histogram,
+        },
+        threshold: c.threshold,
+        min_shot_us: c.min_shot_us.max(0),
+        analysis_size: output_size_from_ffi(c.width, c.height, c.scale_mode),
+    };
+
+    let handle = unsafe { &*handle };
+    let decoder = handle.decoder.lock();
+
+    let mut reported = 0;
+    let result = decoder.detect_scenes(&options, |cuts, fraction| {
+        let callback = match progress {
+            Some(callback) => callback,
+            None => return true,
+        };
+        let new_cuts: Vec<CybSceneCut> = cuts[reported..].iter().map(Into::into).collect();
+        let first = reported as i32;
+        reported = cuts.len();
+        callback(
+            user_data,
+            first,
+            new_cuts.len() as i32,
+            new_cuts.as_ptr(),
+            fraction,
+        )
+    });
+    match result {
+        Ok(cuts) => {
+            unsafe {
+                *out_list = Box::into_raw(Box::new(CybSceneListHandle { cuts }));
+            }
+            CybResult::Success
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Get number of cuts in a scene list
+#[no_mangle]
+pub extern "C" fn cyb_scene_list_get_count(list: *const CybSceneListHandle) -> i32 {
+    if list.is_null() {
+        return 0;
+    }
+    let list = unsafe { &*list };
+    list.cuts.len() as i32
+}
+
+/// Get cut from a scene list
+#[no_mangle]
+pub extern "C" fn cyb_scene_list_get_cut(
+    list: *const CybSceneListHandle,
+    index: i32,
+    out_cut: *mut CybSceneCut,
+) -> CybResult {
+    if list.is_null() || out_cut.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let list = unsafe { &*list };
+
+    if index < 0 || index as usize >= list.cuts.len() {
+        set_last_error("Scene cut index out of bounds");
+        return CybResult::ErrorUnknown;
+    }
+
+    unsafe {
+        *out_cut = (&list.cuts[index as usize]).into();
+    }
+    CybResult::Success
+}
+
+/// Release scene list
+#[no_mangle]
+pub extern "C" fn cyb_scene_list_release(list: *mut CybSceneListHandle) {
+    if !list.is_null() {
+        unsafe {
+            drop(Box::from_raw(list));
+        }
+    }
+}
+
+// =============================================================================
+// Encoder
+// =============================================================================
+
+/// Encoder configuration for FFI
+#[repr(C)]
+pub struct CybEncoderConfig {
+    pub has_video: bool,
+    pub video_codec: u8, // 0=MPEG-4, 1=FFV1, 2=ProRes, 3=PNG, 4=MJPEG
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate_num: i32,
+    pub frame_rate_den: i32,
+    /// Bits per second (0 = codec default / quantizer)
+    pub video_bit_rate: u64,
+    /// Quantizer for MPEG-4 and MJPEG (1-31; 0 = use the bit rate, or the
+    /// default quantizer when no bit rate is set)
+    pub quality: u32,
+    /// Keyframe interval in frames (0 = codec default)
+    pub gop_size: u32,
+    pub prores_profile: u8,    // 0=Proxy, 1=LT, 2=422, 3=HQ, 4=4444, 5=4444 XQ
+    pub scaling_algorithm: u8, // Same values as CybDecoderConfig
+    /// Pixel format of the written frames, same values as
+    /// CybDecoderConfig.output_pixel_format (0 = BGRA)
+    pub input_pixel_format: u8,
+
+    pub has_audio: bool,
+    pub audio_codec: u8, // 0=PCM 16-bit, 1=PCM 24-bit, 2=PCM float, 3=AAC, 4=FLAC
+    pub sample_rate: u32,
+    pub channels: u32,
+    /// Bits per second (0 = codec default)
+    pub audio_bit_rate: u64,
+
+    /// Container: 0 = from the file extension, 1 = MP4, 2 = MOV, 3 = MKV
+    pub format: i32,
+}
+
+impl From<&CybEncoderConfig> for EncoderConfig {
+    fn from(c: &CybEncoderConfig) -> Self {
+        let video = c.has_video.then(|| {
+            let codec = match c.video_codec {
+                1 => VideoCodec::Ffv1,
+                2 => VideoCodec::ProRes,
+                3 => VideoCodec::Png,
+                4 => VideoCodec::Mjpeg,
+                _ => VideoCodec::Mpeg4,
+            };
+            let mut video = VideoEncoderConfig::new(
+                codec,
+                c.width,
+                c.height,
+                (c.frame_rate_num, c.frame_rate_den),
+            );
+            video.bit_rate = c.video_bit_rate;
+            if c.quality > 0 || c.video_bit_rate > 0 {
+                video.quality = c.quality;
+            }
+            video.gop_size = c.gop_size;
+            video.prores_profile = match c.prores_profile {
+                0 => ProResProfile::Proxy,
+                1 => ProResProfile::Lt,
+                2 => ProResProfile::Standard,
+                4 => ProResProfile::P4444,
+                5 => ProResProfile::P4444Xq,
+                _ => ProResProfile::Hq,
+            };
+            video.scaling_algorithm = scaling_algorithm_from_u8(c.scaling_algorithm);
+            video.input_format = pixel_format_from_u8(c.input_pixel_format);
+            video
+        });
+        let audio = c.has_audio.then(|| {
+            let codec = match c.audio_codec {
+                1 => AudioCodec::Pcm24,
+                2 => AudioCodec::PcmFloat,
+                3 => AudioCodec::Aac,
+                4 => AudioCodec::Flac,
+                _ => AudioCodec::Pcm16,
+            };
+            let mut audio = AudioEncoderConfig::new(codec, c.sample_rate, c.channels);
+            audio.bit_rate = c.audio_bit_rate;
+            audio
+        });
+
+        EncoderConfig {
+            video,
+            audio,
+            format: match c.format {
+                1 => Some(ExportFormat::Mp4),
+                2 => Some(ExportFormat::Mov),
+                3 => Some(ExportFormat::Mkv),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Opaque encoder handle
+pub struct CybEncoderHandle {
+    encoder: Mutex<Encoder>,
+}
+
+/// Create an encoder writing to `path` (the header is written immediately)
+///
+/// Returns null on failure; the reason is available from cyb_get_last_error.
+#[no_mangle]
+pub extern "C" fn cyb_encoder_create(
+    path: *const c_char,
+    config: *const CybEncoderConfig,
+) -> *mut CybEncoderHandle {
+    if path.is_null() || config.is_null() {
+        set_last_error("Path or config is null");
+        return ptr::null_mut();
+    }
+
+    let path_str = unsafe {
+        match CStr::from_ptr(path).to_str() {
+            Ok(s) => s,
+            Err(_) => {
+                set_last_error("Invalid UTF-8 in path");
+                return ptr::null_mut();
+            }
+        }
+    };
+
+    let config = unsafe { EncoderConfig::from(&*config) };
+    match Encoder::new(path_str, config) {
+        Ok(encoder) => Box::into_raw(Box::new(CybEncoderHandle {
+            encoder: Mutex::new(encoder),
+        })),
+        Err(e) => {
+            set_last_error(&e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Encode a video frame (written at the configured frame rate; frames of
+/// another size or pixel format are converted)
+#[no_mangle]
+pub extern "C" fn cyb_encoder_write_video_frame(
+    handle: *mut CybEncoderHandle,
+    frame_handle: *const CybFrameHandle,
+) -> CybResult {
+    if handle.is_null() || frame_handle.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let frame = unsafe { &(*frame_handle).frame };
+    handle.encoder.lock().write_video_frame(frame).into()
+}
+
+/// Encode an audio frame (sample rate and channel count must match the config)
+#[no_mangle]
+pub extern "C" fn cyb_encoder_write_audio_frame(
+    handle: *mut CybEncoderHandle,
+    frame_handle: *const CybAudioFrameHandle,
+) -> CybResult {
+    if handle.is_null() || frame_handle.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    let frame = unsafe { &(*frame_handle).frame };
+    handle.encoder.lock().write_audio_frame(frame).into()
+}
+
+/// Flush the encoders and finalize the file
+///
+/// Must be called before cyb_encoder_destroy, or the file is incomplete.
+#[no_mangle]
+pub extern "C" fn cyb_encoder_finish(handle: *mut CybEncoderHandle) -> CybResult {
+    if handle.is_null() {
+        return CybResult::ErrorInvalidHandle;
+    }
+
+    let handle = unsafe { &*handle };
+    handle.encoder.lock().finish().into()
+}
+
+/// Destroy encoder
+#[no_mangle]
+pub extern "C" fn cyb_encoder_destroy(handle: *mut CybEncoderHandle) {
+    if !handle.is_null() {
+        unsafe {
+            drop(Box::from_raw(handle));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_null_handle() {
+        assert_eq!(cyb_decoder_prepare(ptr::null_mut()), CybResult::ErrorInvalidHandle);
+        assert_eq!(cyb_decoder_is_prepared(ptr::null()), false);
+    }
+
+    #[test]
+    fn test_callback_reader() {
+        extern "C" fn read(opaque: *mut c_void, buf: *mut u8, size: i32) -> i32 {
+            let cursor = unsafe { &mut *(opaque as *mut io::Cursor<Vec<u8>>) };
+            let buf = unsafe { std::slice::from_raw_parts_mut(buf, size as usize) };
+            cursor.read(buf).map(|n| n as i32).unwrap_or(-1)
+        }
+        extern "C" fn seek(opaque: *mut c_void, offset: i64, whence: i32) -> i64 {
+            let cursor = unsafe { &mut *(opaque as *mut io::Cursor<Vec<u8>>) };
+            let pos = match whence {
+                0 => SeekFrom::Start(offset as u64),
+                1 => SeekFrom::Current(offset),
+                _ => SeekFrom::End(offset),
+            };
+            cursor.seek(pos).map(|p| p as i64).unwrap_or(-1)
+        }
+
+        let mut cursor = io::Cursor::new(vec![1u8, 2, 3, 4]);
+        let mut reader = CallbackReader {
+            opaque: &mut cursor as *mut _ as *mut c_void,
+            read,
+            seek,
+            release: None,
+        };
+
+        assert_eq!(reader.seek(SeekFrom::End(-2)).unwrap(), 2);
+        let mut buf = [0u8; 4];
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], &[3, 4]);
+        assert!(reader.seek(SeekFrom::Current(-10)).is_err());
+    }
+
+    #[test]
+    fn test_version() {
+        let version = cyb_get_version();
+        assert!(!version.is_null());
+    }
+
+    #[test]
+    fn test_stream_selector_conversion() {
+        let language = CString::new("jpn").unwrap();
+        let selector = CybStreamSelector {
+            kind: 2,
+            index: -1,
+            language: language.as_ptr(),
+            disposition: 0,
+        };
+        assert_eq!(
+            StreamSelector::try_from(&selector).unwrap(),
+            StreamSelector::Language("jpn".to_string())
+        );
+
+        let selector = CybStreamSelector {
+            kind: 1,
+            index: -1,
+            language: ptr::null(),
+            disposition: 0,
+        };
+        assert_eq!(
+            StreamSelector::try_from(&selector).unwrap(),
+            StreamSelector::Best
+        );
+
+        // Unknown codes are rejected rather than selecting another track
+        for (kind, disposition) in [(3, 7), (4, 0)] {
+            let selector = CybStreamSelector {
+                kind,
+                disposition,
+                ..selector
+            };
+            assert!(StreamSelector::try_from(&selector).is_err());
+        }
+    }
+
+    #[test]
+    fn test_audio_output_conversion() {
+        let output = AudioOutputConfig::from(&CybAudioOutputConfig {
+            sample_rate: 44100,
+            channels: 6,
+            channel_layout: 0,
+            sample_format: 1,
+            planar: true,
+            downmix: 2,
+        });
+        assert_eq!(output.sample_rate, 44100);
+        assert_eq!(output.channels, 6);
+        assert_eq!(output.sample_format, SampleFormat::Int16);
+        assert!(output.planar);
+        assert_eq!(output.downmix, DownmixMode::ItuWithLfe);
+    }
+
+    #[test]
+    fn test_color_info_conversion() {
+        use crate::decoder::ChromaLocation;
+
+        let color = CybColorInfo::from(&ColorInfo {
+            matrix: 9,
+            primaries: 9,
+            transfer: 16,
+            range: ColorRange::Limited,
+            chroma_location: ChromaLocation::TopLeft,
+        });
+        assert_eq!(color.transfer, 16);
+        assert_eq!(color.range, 1);
+        assert_eq!(color.chroma_location, 3);
+    }
+
+    #[test]
+    fn test_hdr_metadata_conversion() {
+        use crate::decoder::{ContentLightLevel, MasteringDisplay};
+
+        let hdr = CybHdrMetadata::from(&HdrMetadata {
+            mastering_display: Some(MasteringDisplay {
+                primaries: [[0.708, 0.292], [0.17, 0.797], [0.131, 0.046]],
+                white_point: [0.3127, 0.329],
+                min_luminance: 0.005,
+                max_luminance: 1000.0,
+            }),
+            content_light_level: Some(ContentLightLevel {
+                max_cll: 800,
+                max_fall: 300,
+            }),
+            has_hdr10_plus: true,
+            has_dolby_vision: false,
+        });
+        assert!(hdr.has_mastering_display);
+        assert_eq!(hdr.display_primaries[2], 0.17);
+        assert_eq!(hdr.max_luminance, 1000.0);
+        assert_eq!(hdr.max_cll, 800);
+        assert!(hdr.has_hdr10_plus);
+    }
+
+    #[test]
+    fn test_pixel_format_from_u8() {
+        assert_eq!(pixel_format_from_u8(4), PixelFormat::P010);
+        assert_eq!(pixel_format_from_u8(9), PixelFormat::Native);
+        assert_eq!(pixel_format_from_u8(200), PixelFormat::Yuv420p);
+    }
+
+    #[test]
+    fn test_output_size_from_ffi() {
+        assert_eq!(output_size_from_ffi(0, 0, 1), None);
+
+        let size = output_size_from_ffi(320, 0, 1).unwrap();
+        assert_eq!(size.width, 320);
+        assert_eq!(size.height, 0);
+        assert_eq!(size.mode, ScaleMode::Fill);
+        assert_eq!(
+            output_size_from_ffi(320, 180, 9).unwrap().mode,
+            ScaleMode::Fit
+        );
     }
 }
@@ -4,6 +4,7 @@
 //! - FFmpeg wrapper using ffmpeg-next
 //! - Multi-tier frame caching (L1/L2/L3)
 //! - Parallel decoding and prefetching
+//! - Encoding decoded frames to new files
 //! - VideoToolbox hardware acceleration
 //!
 //! # Architecture
@@ -35,6 +36,7 @@
 
 pub mod cache;
 pub mod decoder;
+pub mod encoder;
 pub mod error;
 pub mod ffi;
 pub mod threading;
@@ -42,6 +44,7 @@ pub mod threading;
 // Re-export main types
 pub use cache::{Cache, CacheConfig, CacheStatistics};
 pub use decoder::{Decoder, DecoderConfig, MediaInfo, VideoFrame};
+pub use encoder::{Encoder, EncoderConfig};
 pub use error::{Error, Result};
 
 /// Library version
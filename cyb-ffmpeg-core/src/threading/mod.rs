@@ -14,6 +14,7 @@ use parking_lot::Mutex;
 use crate::cache::Cache;
 use crate::decoder::config::DecoderConfig;
 use crate::decoder::ffmpeg_decoder::FFmpegContext;
+use crate::decoder::MediaSource;
 
 /// Prefetch command
 #[derive(Debug, Clone)]
@@ -43,8 +44,8 @@ pub enum PrefetchResult {
 
 /// Context required by prefetch workers
 pub struct PrefetchContext {
-    /// Path to media file
-    pub path: String,
+    /// Media source (each worker opens its own reader on it)
+    pub source: MediaSource,
 
     /// Decoder configuration
     pub config: DecoderConfig,
@@ -65,7 +66,7 @@ pub struct PrefetchContext {
 impl PrefetchContext {
     /// Create a new prefetch context
     pub fn new(
-        path: String,
+        source: MediaSource,
         config: DecoderConfig,
         cache: Arc<Cache>,
         current_time_us: Arc<AtomicI64>,
@@ -73,7 +74,7 @@ impl PrefetchContext {
         duration_us: i64,
     ) -> Self {
         Self {
-            path,
+            source,
             config,
             cache,
             current_time_us,
@@ -252,7 +253,7 @@ impl PrefetchManager {
                         Some(pctx) => {
                             // Create or reuse FFmpegContext
                             if ffmpeg_ctx.is_none() {
-                                match FFmpegContext::new(&pctx.path, &pctx.config) {
+                                match FFmpegContext::from_source(&pctx.source, &pctx.config) {
                                     Ok(ctx) => {
                                         ffmpeg_ctx = Some(ctx);
                                     }